@@ -18,6 +18,7 @@ fn main() -> anyhow::Result<()> {
         title: "CCPlayer Window Test".to_string(),
         always_on_top: false,
         start_minimized: false,
+        position: None,
     };
     
     // Create the window
@@ -5,7 +5,7 @@
 
 use crate::audio::{
     AudioDevice, AudioDeviceType, AudioEventHandler, AudioFormat, AudioOutput, 
-    AudioProcessingOptions, AudioStats, ChannelLayout, SampleFormat,
+    AudioProcessingOptions, AudioStats, ChannelLayout, ChannelMixer, DeviceCapabilities, MixConfig, SampleFormat,
 };
 use crate::decoder::AudioSamples;
 use crate::utils::error::{CCPlayerError, IntoPlayerError, Result};
@@ -31,6 +31,9 @@ const MIN_FILL_RATIO: f32 = 0.25;
 /// Volume ramp duration for smooth transitions (in samples)
 const VOLUME_RAMP_SAMPLES: usize = 512;
 
+/// Device buffer size in frames unless configured otherwise (low latency)
+const DEFAULT_BUFFER_FRAMES: u32 = 256;
+
 /// CPAL audio output implementation
 pub struct CpalAudioOutput {
     /// Audio format
@@ -42,6 +45,9 @@ pub struct CpalAudioOutput {
     /// Current audio device
     device: Option<Device>,
     
+    /// Name of the device streams are opened on (None for the system default)
+    device_name: Option<String>,
+    
     /// Audio stream
     stream: Option<Stream>,
     
@@ -66,9 +72,15 @@ pub struct CpalAudioOutput {
     /// Processing options
     processing_options: Arc<RwLock<AudioProcessingOptions>>,
     
+    /// Channel mixer for samples that do not match the output layout
+    mixer: ChannelMixer,
+    
     /// Device monitor thread handle
     device_monitor: Option<thread::JoinHandle<()>>,
     
+    /// Device buffer size in frames (0 for the device default)
+    buffer_frames: u32,
+    
     /// Shutdown flag
     shutdown: Arc<AtomicBool>,
 }
@@ -207,6 +219,39 @@ impl AudioClock {
     }
 }
 
+impl CpalAudioOutput {
+    /// Set the device buffer size used when the stream is next initialized
+    /// 
+    /// # Arguments
+    /// 
+    /// * `frames` - Buffer size in frames, 0 for the device default
+    pub fn set_buffer_size(&mut self, frames: u32) {
+        self.buffer_frames = frames;
+    }
+    
+    /// Set the output device used when the stream is next initialized
+    /// 
+    /// # Arguments
+    /// 
+    /// * `name` - Device name as listed by [`AudioOutput::get_devices`], None for the system default
+    pub fn set_device_name(&mut self, name: Option<String>) {
+        self.device_name = name;
+    }
+    
+    /// Find the chosen output device, or the system default
+    fn output_device(&self) -> Result<Device> {
+        let Some(wanted) = &self.device_name else {
+            return self.host.default_output_device()
+                .ok_or_else(|| CCPlayerError::Audio("No default output device found".to_string()));
+        };
+        
+        self.host.output_devices()
+            .audio_err("Failed to enumerate output devices")?
+            .find(|device| device.name().is_ok_and(|name| &name == wanted))
+            .ok_or_else(|| CCPlayerError::Audio(format!("Audio device not found: {}", wanted)))
+    }
+}
+
 impl AudioOutput for CpalAudioOutput {
     fn new() -> Result<Self> where Self: Sized {
         let host = cpal::default_host();
@@ -215,6 +260,7 @@ impl AudioOutput for CpalAudioOutput {
             format: None,
             host,
             device: None,
+            device_name: None,
             stream: None,
             producer: None,
             volume: Arc::new(RwLock::new(VolumeControl::new())),
@@ -223,7 +269,9 @@ impl AudioOutput for CpalAudioOutput {
             stats: Arc::new(RwLock::new(AudioStats::default())),
             event_handler: None,
             processing_options: Arc::new(RwLock::new(AudioProcessingOptions::default())),
+            mixer: ChannelMixer::default(),
             device_monitor: None,
+            buffer_frames: DEFAULT_BUFFER_FRAMES,
             shutdown: Arc::new(AtomicBool::new(false)),
         })
     }
@@ -234,15 +282,17 @@ impl AudioOutput for CpalAudioOutput {
             drop(stream);
         }
         
-        // Select default output device
-        let device = self.host.default_output_device()
-            .ok_or_else(|| CCPlayerError::Audio("No default output device found".to_string()))?;
+        // Select the chosen or default output device
+        let device = self.output_device()?;
         
         // Create stream config
         let config = StreamConfig {
             channels: format.channels,
             sample_rate: SampleRate(format.sample_rate),
-            buffer_size: cpal::BufferSize::Fixed(256), // Low latency buffer
+            buffer_size: match self.buffer_frames {
+                0 => cpal::BufferSize::Default,
+                frames => cpal::BufferSize::Fixed(frames),
+            },
         };
         
         // Create ring buffer
@@ -343,13 +393,14 @@ impl AudioOutput for CpalAudioOutput {
         let format = self.format.as_ref()
             .ok_or_else(|| CCPlayerError::Audio("Audio format not set".to_string()))?;
         
-        // Validate sample format
-        if samples.channels != format.channels as usize {
-            return Err(CCPlayerError::Audio(format!(
-                "Channel count mismatch: expected {}, got {}",
-                format.channels, samples.channels
-            )));
-        }
+        // Fold or spread channels to the output layout
+        let mixed;
+        let samples = if samples.channels != format.channels as usize {
+            mixed = self.mixer.process(samples, format.channel_layout);
+            &mixed
+        } else {
+            samples
+        };
         
         // Resample if necessary
         let resampled = if samples.sample_rate != format.sample_rate {
@@ -429,6 +480,15 @@ impl AudioOutput for CpalAudioOutput {
         Ok(())
     }
     
+    fn set_mix_config(&mut self, config: MixConfig) {
+        self.mixer.set_config(config);
+    }
+    
+    fn negotiate_format(&self, requested: AudioFormat) -> Result<AudioFormat> {
+        let capabilities = DeviceCapabilities::from_device(&self.output_device()?)?;
+        Ok(capabilities.negotiate(requested))
+    }
+    
     fn get_volume(&self) -> f32 {
         self.volume.read().target
     }
@@ -473,11 +533,12 @@ impl AudioOutput for CpalAudioOutput {
         for dev in devices {
             if let Ok(name) = dev.name() {
                 if name == device.name || device.id == name {
-                    // Re-initialize with new device
+                    // Kept for later streams; an open stream is re-initialized on it
+                    self.device_name = Some(name);
                     if let Some(format) = self.format {
-                        self.device = Some(dev);
                         return self.initialize(format);
                     }
+                    return Ok(());
                 }
             }
         }
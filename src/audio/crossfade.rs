@@ -0,0 +1,243 @@
+//! Crossfading between playlist items
+//!
+//! Mixes the end of the outgoing track with the start of the incoming one,
+//! each shaped by its own volume ramp.
+
+use crate::audio::{RampType, VolumeRamp};
+use crate::decoder::{AudioSamples, MediaInfo};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Crossfade settings
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct CrossfadeConfig {
+    /// Length of the overlap
+    pub duration: Duration,
+
+    /// Curve the outgoing track fades out with
+    pub fade_out: RampType,
+
+    /// Curve the incoming track fades in with
+    pub fade_in: RampType,
+}
+
+impl Default for CrossfadeConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(5),
+            fade_out: RampType::Exponential,
+            fade_in: RampType::Exponential,
+        }
+    }
+}
+
+impl CrossfadeConfig {
+    /// Check whether two consecutive items should be crossfaded
+    ///
+    /// Only audio-only items are crossfaded. With `prefer_gapless`, tracks
+    /// of the same album are joined gaplessly instead.
+    pub fn applies_to(&self, current: &MediaInfo, next: &MediaInfo, prefer_gapless: bool) -> bool {
+        if self.duration.is_zero() || !current.video_streams.is_empty() || !next.video_streams.is_empty() {
+            return false;
+        }
+
+        let same_album = match (&current.metadata.album, &next.metadata.album) {
+            (Some(current), Some(next)) => !current.is_empty() && current == next,
+            _ => false,
+        };
+        !(prefer_gapless && same_album)
+    }
+}
+
+/// Mixer for the overlap of two tracks
+///
+/// Both tracks must have the same sample rate and channel count.
+pub struct Crossfader {
+    /// Gain of the outgoing track
+    fade_out: VolumeRamp,
+
+    /// Gain of the incoming track
+    fade_in: VolumeRamp,
+
+    /// Incoming samples decoded but not mixed yet (interleaved)
+    pending: VecDeque<f32>,
+
+    /// Timestamp of the first pending sample, in microseconds
+    pending_pts: i64,
+
+    /// Sample rate of both tracks
+    sample_rate: u32,
+
+    /// Channel count of both tracks
+    channels: usize,
+}
+
+impl Crossfader {
+    /// Create a crossfader for tracks in the given format
+    pub fn new(config: &CrossfadeConfig, sample_rate: u32, channels: usize) -> Self {
+        let frames = (config.duration.as_secs_f64() * sample_rate as f64) as usize;
+
+        Self {
+            fade_out: VolumeRamp::fade(1.0, 0.0, frames, config.fade_out),
+            fade_in: VolumeRamp::fade(0.0, 1.0, frames, config.fade_in),
+            pending: VecDeque::new(),
+            pending_pts: 0,
+            sample_rate,
+            channels: channels.max(1),
+        }
+    }
+
+    /// Check whether more incoming audio is needed to mix `frames` outgoing frames
+    pub fn wants_incoming(&self, frames: usize) -> bool {
+        self.pending.len() < frames * self.channels
+    }
+
+    /// Queue decoded audio of the incoming track
+    pub fn push_incoming(&mut self, samples: &AudioSamples) {
+        if self.pending.is_empty() {
+            self.pending_pts = samples.pts;
+        }
+        self.pending.extend(samples.data.iter().copied());
+    }
+
+    /// Mix outgoing audio with the queued incoming audio
+    ///
+    /// Missing incoming audio is treated as silence. The result keeps the
+    /// outgoing track's timestamp.
+    pub fn mix(&mut self, outgoing: AudioSamples) -> AudioSamples {
+        let mut data = outgoing.data;
+        let frames = data.len() / self.channels;
+
+        for frame in data.chunks_exact_mut(self.channels) {
+            let out_gain = self.fade_out.next_value();
+            let in_gain = if self.pending.is_empty() { 0.0 } else { self.fade_in.next_value() };
+
+            for sample in frame.iter_mut() {
+                let incoming = self.pending.pop_front().unwrap_or(0.0);
+                *sample = *sample * out_gain + incoming * in_gain;
+            }
+        }
+        self.advance_pending_pts(frames);
+
+        AudioSamples { data, ..outgoing }
+    }
+
+    /// The outgoing track ended; fade in whatever incoming audio is queued
+    pub fn finish(&mut self) -> Option<AudioSamples> {
+        if self.pending.is_empty() {
+            return None;
+        }
+
+        let frames = self.pending.len() / self.channels;
+        let pts = self.pending_pts;
+        let data: Vec<f32> = self.pending.drain(..frames * self.channels).collect();
+        self.pending.clear();
+
+        Some(self.fade_in(AudioSamples {
+            data,
+            sample_count: frames,
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            pts,
+        }))
+    }
+
+    /// Apply the rest of the fade-in to incoming audio
+    pub fn fade_in(&mut self, mut incoming: AudioSamples) -> AudioSamples {
+        for frame in incoming.data.chunks_exact_mut(self.channels) {
+            let gain = self.fade_in.next_value();
+            frame.iter_mut().for_each(|sample| *sample *= gain);
+        }
+        incoming
+    }
+
+    /// Check whether the incoming track has reached full volume
+    pub fn is_complete(&self) -> bool {
+        self.fade_in.is_complete() && self.pending.is_empty()
+    }
+
+    /// Move the pending timestamp past consumed frames
+    fn advance_pending_pts(&mut self, frames: usize) {
+        self.pending_pts += (frames as u64 * 1_000_000 / self.sample_rate.max(1) as u64) as i64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::MediaMetadata;
+
+    /// Audio-only item from the given album
+    fn track(album: &str) -> MediaInfo {
+        MediaInfo {
+            source: "track.flac".to_string(),
+            duration: Duration::from_secs(180),
+            video_streams: Vec::new(),
+            audio_streams: Vec::new(),
+            subtitle_streams: Vec::new(),
+            format: "flac".to_string(),
+            file_size: None,
+            bitrate: None,
+            metadata: MediaMetadata {
+                album: Some(album.to_string()),
+                ..Default::default()
+            },
+            variants: Vec::new(),
+            current_variant: None,
+            is_live: false,
+            is_still: false,
+        }
+    }
+
+    /// Stereo samples at 100 Hz
+    fn samples(value: f32, frames: usize, pts: i64) -> AudioSamples {
+        AudioSamples {
+            data: vec![value; frames * 2],
+            sample_count: frames,
+            channels: 2,
+            sample_rate: 100,
+            pts,
+        }
+    }
+
+    #[test]
+    fn test_crossfade_mix() {
+        let config = CrossfadeConfig {
+            duration: Duration::from_secs(1),
+            fade_out: RampType::Linear,
+            fade_in: RampType::Linear,
+        };
+        let mut fader = Crossfader::new(&config, 100, 2);
+
+        assert!(fader.wants_incoming(50));
+        fader.push_incoming(&samples(1.0, 80, 0));
+        assert!(!fader.wants_incoming(50));
+
+        // Halfway through, the linear curves sum to full volume
+        let mixed = fader.mix(samples(1.0, 50, 9_000_000));
+        assert_eq!(mixed.pts, 9_000_000);
+        assert!((mixed.data[0] - 1.0).abs() < 1e-6);
+        assert!((mixed.data[98] - 1.0).abs() < 1e-6);
+
+        // The outgoing track ends early: the rest fades in on its own
+        let rest = fader.finish().unwrap();
+        assert_eq!(rest.sample_count, 30);
+        assert_eq!(rest.pts, 500_000);
+        assert!(rest.data[0] > 0.45 && rest.data[0] < 0.55);
+        assert!(!fader.is_complete());
+
+        let incoming = fader.fade_in(samples(1.0, 40, 800_000));
+        assert!(incoming.data[79] > 0.95);
+        assert!(fader.is_complete());
+    }
+
+    #[test]
+    fn test_crossfade_skips_same_album() {
+        let current = track("Abbey Road");
+        let config = CrossfadeConfig::default();
+
+        assert!(!config.applies_to(&current, &track("Abbey Road"), true));
+        assert!(config.applies_to(&current, &track("Abbey Road"), false));
+        assert!(config.applies_to(&current, &track("Let It Be"), true));
+    }
+}
@@ -3,7 +3,7 @@
 //! This module handles audio device enumeration, selection,
 //! and hot-plug detection.
 
-use crate::audio::{AudioDevice, AudioDeviceType};
+use crate::audio::{AudioDevice, AudioDeviceType, AudioFormat, ChannelLayout};
 use crate::utils::error::{CCPlayerError, IntoPlayerError, Result};
 use cpal::traits::{DeviceTrait, HostTrait};
 use crossbeam::channel::{bounded, Receiver, Sender};
@@ -213,6 +213,22 @@ impl DeviceManager {
         
         Err(CCPlayerError::Audio(format!("Device not found: {}", device_id)))
     }
+    
+    /// Negotiate the output format with the default device
+    /// 
+    /// # Arguments
+    /// 
+    /// * `source` - Native format of the audio being played
+    /// 
+    /// # Returns
+    /// 
+    /// The format to open the device with
+    pub fn negotiate_format(&self, source: AudioFormat) -> Result<AudioFormat> {
+        let device = self.get_default_device()
+            .ok_or_else(|| CCPlayerError::Audio("No default output device found".to_string()))?;
+        
+        Ok(self.get_device_capabilities(&device.id)?.negotiate(source))
+    }
 }
 
 impl Drop for DeviceManager {
@@ -266,7 +282,8 @@ pub struct DeviceCapabilities {
 }
 
 impl DeviceCapabilities {
-    fn from_device(device: &cpal::Device) -> Result<Self> {
+    /// Read what an output device supports
+    pub(crate) fn from_device(device: &cpal::Device) -> Result<Self> {
         let mut sample_rates = Vec::new();
         let mut min_channels = u16::MAX;
         let mut max_channels = 0u16;
@@ -309,6 +326,42 @@ impl DeviceCapabilities {
             exclusive_mode: cfg!(windows), // Available on Windows
         })
     }
+    
+    /// Pick the supported format closest to the source
+    /// 
+    /// The source rate and channel count are kept whenever the device
+    /// supports them, so audio is only resampled or remixed when needed.
+    pub fn negotiate(&self, source: AudioFormat) -> AudioFormat {
+        let sample_rate = if self.sample_rates.is_empty() || self.sample_rates.contains(&source.sample_rate) {
+            source.sample_rate
+        } else {
+            // Next rate up loses nothing; otherwise the highest available
+            self.sample_rates.iter()
+                .copied()
+                .find(|&rate| rate > source.sample_rate)
+                .or_else(|| self.sample_rates.last().copied())
+                .unwrap_or(source.sample_rate)
+        };
+        
+        let channels = if self.max_channels == 0 {
+            source.channels
+        } else {
+            source.channels.clamp(self.min_channels.min(self.max_channels), self.max_channels)
+        };
+        
+        let channel_layout = if channels == source.channels {
+            source.channel_layout
+        } else {
+            ChannelLayout::from_channel_count(channels)
+        };
+        
+        AudioFormat {
+            sample_rate,
+            channels,
+            sample_format: source.sample_format,
+            channel_layout,
+        }
+    }
 }
 
 /// Device monitor thread function
@@ -444,6 +497,37 @@ mod tests {
         assert!(manager.get_default_device().is_some());
     }
     
+    #[test]
+    fn test_format_negotiation() {
+        let capabilities = DeviceCapabilities {
+            sample_rates: vec![44100, 48000, 96000],
+            min_channels: 1,
+            max_channels: 2,
+            sample_formats: vec![cpal::SampleFormat::F32],
+            buffer_size_range: None,
+            exclusive_mode: false,
+        };
+        let format = |sample_rate, channels| AudioFormat {
+            sample_rate,
+            channels,
+            channel_layout: ChannelLayout::from_channel_count(channels),
+            ..AudioFormat::default()
+        };
+        
+        // Supported formats pass through untouched
+        assert_eq!(capabilities.negotiate(format(44100, 2)), format(44100, 2));
+        assert_eq!(capabilities.negotiate(format(48000, 1)), format(48000, 1));
+        
+        // 5.1 on a stereo device is remixed, 88.2 kHz goes up to 96 kHz
+        assert_eq!(capabilities.negotiate(format(48000, 6)), format(48000, 2));
+        assert_eq!(capabilities.negotiate(format(88200, 2)), format(96000, 2));
+        assert_eq!(capabilities.negotiate(format(192000, 2)), format(96000, 2));
+        
+        // A 5.1 device keeps all channels
+        let surround = DeviceCapabilities { max_channels: 8, ..capabilities };
+        assert_eq!(surround.negotiate(format(48000, 6)), format(48000, 6));
+    }
+    
     #[test]
     fn test_device_type_detection() {
         assert_eq!(guess_device_type("Realtek HD Audio"), AudioDeviceType::Speakers);
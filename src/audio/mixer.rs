@@ -0,0 +1,364 @@
+//! Channel mixing for CCPlayer
+//!
+//! Folds multichannel audio down to devices with fewer speakers and
+//! optionally spreads stereo over a surround setup. Channels are expected
+//! in FFmpeg's default order: FL FR FC LFE (BL BR) SL SR.
+
+use crate::audio::ChannelLayout;
+use crate::decoder::AudioSamples;
+use std::f32::consts::FRAC_1_SQRT_2;
+
+/// Front level of the nightmode mix
+const NIGHTMODE_FRONT_LEVEL: f32 = 0.3;
+
+/// Surround level of the nightmode mix
+const NIGHTMODE_SURROUND_LEVEL: f32 = 0.3;
+
+/// Pro Logic II coefficient of a surround channel on its own side
+const PL2_SAME_SIDE: f32 = 0.8660;
+
+/// Pro Logic II coefficient of a surround channel on the opposite side
+const PL2_OPPOSITE_SIDE: f32 = 0.5;
+
+/// How surround channels are folded into stereo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub enum DownmixMode {
+    /// ITU-R BS.775 downmix using the configured levels
+    Itu,
+
+    /// Dolby Pro Logic II matrix encoding, decodable back to surround
+    ProLogic2,
+
+    /// Center (dialogue) at full level with quiet fronts and surrounds
+    Nightmode,
+}
+
+/// Channel mixing settings
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct MixConfig {
+    /// Downmix matrix
+    pub downmix: DownmixMode,
+
+    /// Level of the center channel in the front speakers
+    pub center_level: f32,
+
+    /// Level of the surround channels in the front speakers
+    pub surround_level: f32,
+
+    /// Level of the LFE channel in the front speakers
+    pub lfe_level: f32,
+
+    /// Spread stereo over all speakers of a surround device
+    pub upmix: bool,
+}
+
+impl Default for MixConfig {
+    fn default() -> Self {
+        Self {
+            downmix: DownmixMode::Itu,
+            center_level: FRAC_1_SQRT_2,
+            surround_level: FRAC_1_SQRT_2,
+            lfe_level: 0.0,
+            upmix: false,
+        }
+    }
+}
+
+/// Speaker position of a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Speaker {
+    FrontLeft,
+    FrontRight,
+    Center,
+    Lfe,
+    BackLeft,
+    BackRight,
+    SideLeft,
+    SideRight,
+}
+
+impl Speaker {
+    /// Whether the speaker is on the left
+    fn is_left(self) -> bool {
+        matches!(self, Self::FrontLeft | Self::BackLeft | Self::SideLeft)
+    }
+
+    /// Whether the speaker is a surround channel
+    fn is_surround(self) -> bool {
+        matches!(self, Self::BackLeft | Self::BackRight | Self::SideLeft | Self::SideRight)
+    }
+}
+
+/// Speaker positions of a layout, in channel order
+fn speakers(layout: ChannelLayout) -> Option<&'static [Speaker]> {
+    use Speaker::*;
+
+    match layout {
+        ChannelLayout::Mono => Some(&[Center]),
+        ChannelLayout::Stereo => Some(&[FrontLeft, FrontRight]),
+        ChannelLayout::Surround21 => Some(&[FrontLeft, FrontRight, Lfe]),
+        ChannelLayout::Surround30 => Some(&[FrontLeft, FrontRight, Center]),
+        ChannelLayout::Surround51 => Some(&[FrontLeft, FrontRight, Center, Lfe, SideLeft, SideRight]),
+        ChannelLayout::Surround71 => Some(&[FrontLeft, FrontRight, Center, Lfe, BackLeft, BackRight, SideLeft, SideRight]),
+        ChannelLayout::Custom(_) => None,
+    }
+}
+
+/// Channel mixing stage
+///
+/// Applies a mixing matrix to interleaved samples. The matrix is rebuilt
+/// only when the input or output layout changes.
+#[derive(Debug, Clone)]
+pub struct ChannelMixer {
+    /// Settings
+    config: MixConfig,
+
+    /// Input channel count and output layout `matrix` was built for
+    layouts: Option<(usize, ChannelLayout)>,
+
+    /// Current mixing matrix
+    matrix: Vec<Vec<f32>>,
+}
+
+impl ChannelMixer {
+    /// Create a mixer with the given settings
+    pub fn new(config: MixConfig) -> Self {
+        Self {
+            config,
+            layouts: None,
+            matrix: Vec::new(),
+        }
+    }
+
+    /// Current settings
+    pub fn config(&self) -> MixConfig {
+        self.config
+    }
+
+    /// Replace the settings
+    pub fn set_config(&mut self, config: MixConfig) {
+        self.config = config;
+        self.layouts = None;
+    }
+
+    /// Mix samples into the output layout
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Interleaved input samples
+    /// * `output` - Layout to mix into
+    ///
+    /// # Returns
+    ///
+    /// Interleaved samples with the output layout's channel count
+    pub fn process(&mut self, samples: &AudioSamples, output: ChannelLayout) -> AudioSamples {
+        if self.layouts != Some((samples.channels, output)) {
+            let input = ChannelLayout::from_channel_count(samples.channels as u16);
+            self.matrix = self.matrix(input, output);
+            self.layouts = Some((samples.channels, output));
+        }
+        let matrix = &self.matrix;
+
+        let mut data = Vec::with_capacity(samples.sample_count * matrix.len());
+        for frame in samples.data.chunks_exact(samples.channels.max(1)) {
+            for row in matrix {
+                data.push(row.iter().zip(frame).map(|(gain, sample)| gain * sample).sum());
+            }
+        }
+
+        AudioSamples {
+            data,
+            sample_count: samples.sample_count,
+            channels: matrix.len(),
+            sample_rate: samples.sample_rate,
+            pts: samples.pts,
+        }
+    }
+
+    /// Build the mixing matrix between two layouts
+    ///
+    /// # Returns
+    ///
+    /// One row of input gains per output channel
+    pub fn matrix(&self, input: ChannelLayout, output: ChannelLayout) -> Vec<Vec<f32>> {
+        let in_count = input.channel_count() as usize;
+        let out_count = output.channel_count() as usize;
+
+        // Unknown layouts: pass matching channels straight through
+        let (Some(input), Some(output)) = (speakers(input), speakers(output)) else {
+            return (0..out_count)
+                .map(|row| (0..in_count).map(|col| if row == col { 1.0 } else { 0.0 }).collect())
+                .collect();
+        };
+
+        let mut matrix = if output == [Speaker::Center] && input.len() > 1 {
+            // Mono: fold to stereo first, then sum both sides
+            let stereo = self.fold(input, &[Speaker::FrontLeft, Speaker::FrontRight]);
+            vec![stereo[0].iter().zip(&stereo[1]).map(|(left, right)| 0.5 * (left + right)).collect()]
+        } else {
+            self.fold(input, output)
+        };
+
+        // Scale down so no output channel can clip
+        let peak = matrix
+            .iter()
+            .map(|row| row.iter().map(|gain| gain.abs()).sum::<f32>())
+            .fold(0.0, f32::max);
+        if peak > 1.0 {
+            for gain in matrix.iter_mut().flatten() {
+                *gain /= peak;
+            }
+        }
+
+        matrix
+    }
+
+    /// Unnormalized matrix from input to output speakers
+    fn fold(&self, input: &[Speaker], output: &[Speaker]) -> Vec<Vec<f32>> {
+        use Speaker::*;
+
+        let mut matrix = vec![vec![0.0; input.len()]; output.len()];
+        let row = |speaker: Speaker| output.iter().position(|&out| out == speaker);
+        let downmix = input.len() > output.len();
+
+        // Levels of the selected downmix
+        let (front, center, surround, lfe) = match self.config.downmix {
+            DownmixMode::Nightmode if downmix => (NIGHTMODE_FRONT_LEVEL, 1.0, NIGHTMODE_SURROUND_LEVEL, 0.0),
+            _ => (1.0, self.config.center_level, self.config.surround_level, self.config.lfe_level),
+        };
+        let pro_logic = downmix && self.config.downmix == DownmixMode::ProLogic2;
+
+        for (col, &speaker) in input.iter().enumerate() {
+            let mut add = |target: Speaker, gain: f32| {
+                if let Some(row) = row(target) {
+                    matrix[row][col] += gain;
+                }
+            };
+
+            match speaker {
+                FrontLeft | FrontRight if row(speaker).is_some() => add(speaker, front),
+                FrontLeft | FrontRight => add(Center, 1.0),
+                Center | Lfe if row(speaker).is_some() => add(speaker, 1.0),
+                Center => {
+                    add(FrontLeft, center);
+                    add(FrontRight, center);
+                }
+                Lfe => {
+                    add(FrontLeft, lfe);
+                    add(FrontRight, lfe);
+                }
+                _ if row(speaker).is_some() => add(speaker, 1.0),
+                _ => {
+                    let left = speaker.is_left();
+                    let (side, back) = if left { (SideLeft, BackLeft) } else { (SideRight, BackRight) };
+                    let front_speaker = if left { FrontLeft } else { FrontRight };
+
+                    if row(side).is_some() {
+                        add(side, surround);
+                    } else if row(back).is_some() {
+                        add(back, surround);
+                    } else if pro_logic {
+                        // Surrounds go out of phase into both fronts
+                        let (same, opposite) = if left { (-PL2_SAME_SIDE, PL2_OPPOSITE_SIDE) } else { (PL2_SAME_SIDE, -PL2_OPPOSITE_SIDE) };
+                        add(FrontLeft, if left { same } else { opposite });
+                        add(FrontRight, if left { opposite } else { same });
+                    } else {
+                        add(front_speaker, surround);
+                    }
+                }
+            }
+        }
+
+        // Optional stereo upmix: derive center and surrounds from the fronts
+        if self.config.upmix && input == [FrontLeft, FrontRight] {
+            if let Some(index) = row(Center) {
+                matrix[index] = vec![0.5, 0.5];
+            }
+            for &speaker in output.iter().filter(|speaker| speaker.is_surround()) {
+                let index = row(speaker).unwrap();
+                matrix[index] = if speaker.is_left() { vec![self.config.surround_level, 0.0] } else { vec![0.0, self.config.surround_level] };
+            }
+        }
+
+        matrix
+    }
+}
+
+impl Default for ChannelMixer {
+    fn default() -> Self {
+        Self::new(MixConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matrix(actual: Vec<Vec<f32>>, expected: &[&[f32]]) {
+        assert_eq!(actual.len(), expected.len());
+        for (row, expected) in actual.iter().zip(expected) {
+            for (gain, expected) in row.iter().zip(expected.iter()) {
+                assert!((gain - expected).abs() < 1e-3, "{:?} != {:?}", actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_itu_downmix() {
+        let mixer = ChannelMixer::default();
+        let matrix = mixer.matrix(ChannelLayout::Surround51, ChannelLayout::Stereo);
+
+        // L = FL + 0.707 C + 0.707 SL, normalized by 2.414
+        let (main, side) = (1.0 / 2.414, FRAC_1_SQRT_2 / 2.414);
+        assert_matrix(matrix, &[&[main, 0.0, side, 0.0, side, 0.0], &[0.0, main, side, 0.0, 0.0, side]]);
+
+        // Three channels are 3.0, so the centre goes into both sides
+        let matrix = mixer.matrix(ChannelLayout::from_channel_count(3), ChannelLayout::Stereo);
+        let (main, side) = (1.0 / 1.707, FRAC_1_SQRT_2 / 1.707);
+        assert_matrix(matrix, &[&[main, 0.0, side], &[0.0, main, side]]);
+
+        // Identical layouts are left alone
+        let identity = mixer.matrix(ChannelLayout::Stereo, ChannelLayout::Stereo);
+        assert_matrix(identity, &[&[1.0, 0.0], &[0.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_pro_logic_and_nightmode() {
+        let pro_logic = ChannelMixer::new(MixConfig { downmix: DownmixMode::ProLogic2, ..MixConfig::default() });
+        let matrix = pro_logic.matrix(ChannelLayout::Surround51, ChannelLayout::Stereo);
+        // Surrounds are negative on the left and positive on the right
+        assert!(matrix[0][4] < 0.0 && matrix[0][5] < 0.0);
+        assert!(matrix[1][4] > 0.0 && matrix[1][5] > 0.0);
+        assert!((matrix[0][4].abs() - matrix[1][5]).abs() < 1e-6);
+
+        let night = ChannelMixer::new(MixConfig { downmix: DownmixMode::Nightmode, ..MixConfig::default() });
+        let matrix = night.matrix(ChannelLayout::Surround51, ChannelLayout::Stereo);
+        assert!(matrix[0][2] > 3.0 * matrix[0][0]);
+        assert_eq!(matrix[0][3], 0.0);
+    }
+
+    #[test]
+    fn test_process_and_upmix() {
+        let mut mixer = ChannelMixer::default();
+        let samples = AudioSamples {
+            data: vec![1.0, 0.0, 0.0, 1.0],
+            sample_count: 2,
+            channels: 2,
+            sample_rate: 48000,
+            pts: 0,
+        };
+
+        // Without upmix the extra speakers stay silent
+        let mixed = mixer.process(&samples, ChannelLayout::Surround51);
+        assert_eq!(mixed.channels, 6);
+        assert_eq!(mixed.data, vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+
+        mixer.set_config(MixConfig { upmix: true, ..MixConfig::default() });
+        let mixed = mixer.process(&samples, ChannelLayout::Surround51);
+        assert_eq!(&mixed.data[..6], &[1.0, 0.0, 0.5, 0.0, FRAC_1_SQRT_2, 0.0]);
+
+        // Mono sums both sides
+        let mono = mixer.process(&samples, ChannelLayout::Mono);
+        assert_eq!(mono.data, vec![0.5, 0.5]);
+    }
+}
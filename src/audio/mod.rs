@@ -5,14 +5,18 @@
 //! and audio device selection.
 
 mod cpal_output;
+mod crossfade;
 mod device;
+mod mixer;
 mod sync;
 mod volume;
 
 pub use cpal_output::CpalAudioOutput;
+pub use crossfade::{CrossfadeConfig, Crossfader};
 pub use device::{DeviceManager, DeviceChangeListener, DeviceCapabilities};
+pub use mixer::{ChannelMixer, DownmixMode, MixConfig};
 pub use sync::{AVSyncController, SyncMode, FrameAction, MasterClock, VideoClock, SyncStats};
-pub use volume::{VolumeController, RampType};
+pub use volume::{VolumeController, VolumeRamp, RampType};
 
 use crate::utils::error::Result;
 use crate::decoder::AudioSamples;
@@ -57,6 +61,23 @@ pub trait AudioOutput: Send + Sync {
     /// * `volume` - Volume level (0.0 to 1.0)
     fn set_volume(&mut self, volume: f32) -> Result<()>;
     
+    /// Set how samples are mixed when their channels differ from the output
+    /// 
+    /// # Arguments
+    /// 
+    /// * `config` - Downmix and upmix settings
+    fn set_mix_config(&mut self, config: MixConfig);
+    
+    /// Choose the format to open the output with
+    /// 
+    /// The format is checked against the device the output plays on, as
+    /// chosen with `set_device`, not necessarily the default one.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `requested` - Format the player wants to play
+    fn negotiate_format(&self, requested: AudioFormat) -> Result<AudioFormat>;
+    
     /// Get current volume level
     fn get_volume(&self) -> f32;
     
@@ -156,6 +177,9 @@ pub enum ChannelLayout {
     /// 2.1 (3 channels)
     Surround21,
     
+    /// 3.0 (3 channels: left, right, centre)
+    Surround30,
+    
     /// 5.1 (6 channels)
     Surround51,
     
@@ -172,12 +196,24 @@ impl ChannelLayout {
         match self {
             Self::Mono => 1,
             Self::Stereo => 2,
-            Self::Surround21 => 3,
+            Self::Surround21 | Self::Surround30 => 3,
             Self::Surround51 => 6,
             Self::Surround71 => 8,
             Self::Custom(count) => *count,
         }
     }
+    
+    /// Get the standard layout for a channel count
+    pub fn from_channel_count(channels: u16) -> Self {
+        match channels {
+            1 => Self::Mono,
+            2 => Self::Stereo,
+            3 => Self::Surround30,
+            6 => Self::Surround51,
+            8 => Self::Surround71,
+            n => Self::Custom(n),
+        }
+    }
 }
 
 /// Audio device information
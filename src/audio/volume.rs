@@ -101,7 +101,7 @@ pub struct VolumeRamp {
 }
 
 /// Volume ramp type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub enum RampType {
     /// Linear ramp
     Linear,
@@ -414,6 +414,22 @@ impl VolumeRamp {
         }
     }
     
+    /// Create a one-off fade between two volumes
+    pub fn fade(from: f32, to: f32, duration_samples: usize, ramp_type: RampType) -> Self {
+        Self {
+            current: from,
+            target: to,
+            duration_samples,
+            samples_processed: 0,
+            ramp_type,
+        }
+    }
+    
+    /// Check whether the ramp has reached its target
+    pub fn is_complete(&self) -> bool {
+        self.samples_processed >= self.duration_samples
+    }
+    
     /// Set target volume
     fn set_target(&mut self, target: f32) {
         if (target - self.current).abs() > 0.001 {
@@ -423,7 +439,7 @@ impl VolumeRamp {
     }
     
     /// Get next ramped value
-    fn next_value(&mut self) -> f32 {
+    pub fn next_value(&mut self) -> f32 {
         if self.samples_processed >= self.duration_samples {
             self.current = self.target;
             return self.current;
@@ -0,0 +1,196 @@
+//! `ccplayer analyze`: packet-level stream analysis
+//!
+//! Reads every packet of a file without decoding and reports bitrate per
+//! second, keyframes, GOP lengths and timestamp anomalies of each stream,
+//! as JSON or CSV. A short summary goes to stderr.
+
+use super::probe::format_size;
+use crate::decoder::{AnalysisReport, FFmpegDecoder};
+use crate::utils::error::{CCPlayerError, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Output format of the `analyze` subcommand
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One JSON document with every result
+    Json,
+
+    /// One row per bitrate second, keyframe, GOP and anomaly
+    Csv,
+}
+
+/// Arguments of the `analyze` subcommand
+#[derive(clap::Args, Debug)]
+pub struct AnalyzeArgs {
+    /// Media file
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub format: OutputFormat,
+
+    /// Write the results to a file instead of stdout
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Pause between packets of a stream reported as a gap, in seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = 1.0)]
+    pub gap: f64,
+}
+
+/// Run the `analyze` subcommand
+pub fn run(args: &AnalyzeArgs) -> Result<()> {
+    if !args.file.exists() {
+        return Err(CCPlayerError::NotFound(args.file.display().to_string()));
+    }
+    let gap = Duration::try_from_secs_f64(args.gap)
+        .map_err(|_| CCPlayerError::InvalidInput(format!("Invalid gap threshold: {}", args.gap)))?;
+
+    let report = FFmpegDecoder::analyze(&args.file, gap)?;
+
+    let output = match args.format {
+        OutputFormat::Json => serde_json::to_string_pretty(&report)
+            .map_err(|e| CCPlayerError::Internal(format!("Failed to serialize analysis: {}", e)))?,
+        OutputFormat::Csv => format_csv(&report),
+    };
+
+    match &args.output {
+        Some(path) => std::fs::write(path, &output)?,
+        None => println!("{}", output.trim_end()),
+    }
+    eprint!("{}", format_summary(&report));
+
+    Ok(())
+}
+
+/// Format the results as CSV
+///
+/// Columns are `stream,kind,record,time,value,detail`; `record` is one of
+/// `bitrate` (bits in that second), `keyframe`, `gop` (packets from that
+/// keyframe to the next) and `anomaly` (offending step in seconds, with
+/// the anomaly kind as detail).
+pub fn format_csv(report: &AnalysisReport) -> String {
+    let mut out = String::from("stream,kind,record,time,value,detail\n");
+
+    // Writing to a String cannot fail
+    for stream in &report.streams {
+        let prefix = format!("{},{}", stream.index, stream.kind);
+
+        for (second, bits) in stream.bitrate_timeline.iter().enumerate() {
+            let _ = writeln!(out, "{},bitrate,{:.3},{},", prefix, report.start_time + second as f64, bits);
+        }
+        for time in &stream.keyframes {
+            let _ = writeln!(out, "{},keyframe,{:.6},,", prefix, time);
+        }
+        for (time, length) in stream.keyframes.iter().zip(&stream.gop_lengths) {
+            let _ = writeln!(out, "{},gop,{:.6},{},", prefix, time, length);
+        }
+        for anomaly in &stream.anomalies {
+            let _ = writeln!(out, "{},anomaly,{:.6},{:.6},{}", prefix, anomaly.time, anomaly.delta, anomaly.kind.name());
+        }
+    }
+
+    out
+}
+
+/// Format a short human-readable summary
+pub fn format_summary(report: &AnalysisReport) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "{}", report.source);
+    for stream in &report.streams {
+        let _ = writeln!(
+            out,
+            "  #{:<3}{} {}: {} packets, {}, {:.1} s, avg {} kb/s, peak {} kb/s",
+            stream.index,
+            stream.kind,
+            stream.codec,
+            stream.packets,
+            format_size(stream.bytes),
+            stream.duration,
+            stream.average_bitrate / 1000,
+            stream.peak_bitrate / 1000
+        );
+
+        if let Some((min, max, mean)) = stream.gop_summary() {
+            let _ = writeln!(
+                out,
+                "       {} keyframes, GOP {}-{} packets (mean {:.1})",
+                stream.keyframes.len(),
+                min,
+                max,
+                mean
+            );
+        }
+
+        if !stream.anomalies.is_empty() {
+            let mut counts = BTreeMap::new();
+            for anomaly in &stream.anomalies {
+                *counts.entry(anomaly.kind.name()).or_insert(0) += 1;
+            }
+            let counts: Vec<_> = counts.into_iter().map(|(kind, count)| format!("{} {}", count, kind)).collect();
+            let _ = writeln!(out, "       anomalies: {}", counts.join(", "));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::{Anomaly, AnomalyKind, StreamAnalysis};
+
+    /// Results for a short clip with one timestamp gap
+    fn sample() -> AnalysisReport {
+        AnalysisReport {
+            source: "clip.ts".to_string(),
+            start_time: 1.0,
+            streams: vec![StreamAnalysis {
+                index: 0,
+                kind: "video".to_string(),
+                codec: "h264".to_string(),
+                packets: 100,
+                bytes: 500_000,
+                duration: 2.0,
+                average_bitrate: 2_000_000,
+                peak_bitrate: 2_400_000,
+                bitrate_timeline: vec![1_600_000, 2_400_000],
+                keyframes: vec![1.0, 2.0],
+                gop_lengths: vec![50],
+                anomalies: vec![Anomaly {
+                    packet: 60,
+                    time: 2.2,
+                    kind: AnomalyKind::Gap,
+                    delta: 0.5,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_format_csv() {
+        let csv = format_csv(&sample());
+        let lines: Vec<_> = csv.lines().collect();
+
+        assert_eq!(lines[0], "stream,kind,record,time,value,detail");
+        assert_eq!(lines[1], "0,video,bitrate,1.000,1600000,");
+        assert_eq!(lines[3], "0,video,keyframe,1.000000,,");
+        assert_eq!(lines[5], "0,video,gop,1.000000,50,");
+        assert_eq!(lines[6], "0,video,anomaly,2.200000,0.500000,gap");
+        assert_eq!(lines.len(), 7);
+    }
+
+    #[test]
+    fn test_format_summary() {
+        let summary = format_summary(&sample());
+
+        assert!(summary.contains("#0  video h264: 100 packets, 488.3 KiB, 2.0 s, avg 2000 kb/s, peak 2400 kb/s"));
+        assert!(summary.contains("2 keyframes, GOP 50-50 packets (mean 50.0)"));
+        assert!(summary.contains("anomalies: 1 gap"));
+    }
+}
@@ -0,0 +1,154 @@
+//! `ccplayer config`: configuration tools
+//!
+//! `dump` prints the effective configuration with the layer each value
+//! comes from, `check` validates a config file and points out keys that
+//! are not settings, and `schema` prints a JSON Schema of the config file
+//! for editor completion.
+
+use super::options::PlayerOptions;
+use crate::utils::config::{Config, ConfigSource};
+use crate::utils::error::{CCPlayerError, Result};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Longest `key = value` the source comments of `config dump` are aligned after
+const MAX_ALIGN: usize = 56;
+
+/// Arguments of the `config` subcommand
+#[derive(clap::Args, Debug)]
+pub struct ConfigArgs {
+    /// Configuration tool to run
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+/// Subcommands of `ccplayer config`
+#[derive(clap::Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective configuration and the source of every value
+    Dump(PlayerOptions),
+
+    /// Validate a config file and report unknown keys
+    Check {
+        /// Config file to check
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+    },
+
+    /// Print a JSON Schema of the config file
+    Schema,
+}
+
+/// Run the `config` subcommand
+pub fn run(args: &ConfigArgs) -> Result<()> {
+    match &args.command {
+        ConfigCommand::Dump(options) => dump(options),
+        ConfigCommand::Check { file } => check(file),
+        ConfigCommand::Schema => {
+            let json = serde_json::to_string_pretty(&Config::schema())
+                .map_err(|e| CCPlayerError::Internal(format!("Failed to serialize config schema: {}", e)))?;
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+/// Print the effective configuration, then validate it
+fn dump(options: &PlayerOptions) -> Result<()> {
+    let traced = options.traced_config()?;
+
+    // Printed before validating so the offending value can be found
+    print!("{}", format_dump(&traced.entries()));
+    traced.config.validate()
+}
+
+/// Check a config file applied on top of the defaults, and each of its profiles
+///
+/// Every problem is printed; the error only counts them.
+fn check(file: &Path) -> Result<()> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| CCPlayerError::Config(format!("Failed to read {}: {}", file.display(), e)))?;
+
+    let mut problems: Vec<String> = Config::unknown_keys(&contents)?
+        .into_iter()
+        .map(|(line, key)| format!("{}:{}: unknown key {}", file.display(), line, key))
+        .collect();
+
+    let mut config = Config::default();
+    match config.merge_from_file(file).and_then(|()| config.validate()) {
+        Ok(()) => {
+            for name in config.profile.keys() {
+                let mut profiled = config.clone();
+                if let Err(e) = profiled.apply_profile(name).and_then(|()| profiled.validate()) {
+                    problems.push(format!("{}: profile {}: {}", file.display(), name, e));
+                }
+            }
+        }
+        Err(e) => problems.push(format!("{}: {}", file.display(), e)),
+    }
+
+    if problems.is_empty() {
+        println!("{}: OK", file.display());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    Err(CCPlayerError::Config(format!("{} problem(s) in {}", problems.len(), file.display())))
+}
+
+/// Format configuration values as TOML dotted keys, each followed by its source
+///
+/// # Arguments
+///
+/// * `entries` - Dotted path, value and source of every value
+pub fn format_dump(entries: &[(String, toml::Value, &ConfigSource)]) -> String {
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|(path, value, _)| format!("{} = {}", path, format_value(value)))
+        .collect();
+    let width = lines.iter().map(String::len).filter(|&len| len <= MAX_ALIGN).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for (line, (_, _, source)) in lines.iter().zip(entries) {
+        let _ = writeln!(out, "{:<width$}  # {}", line, source, width = width);
+    }
+    out
+}
+
+/// Format a value as TOML, showing settings stored as `f32` without the widening noise
+fn format_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Float(float) if *float as f32 as f64 == *float => {
+            let short: f64 = (*float as f32).to_string().parse().unwrap_or(*float);
+            toml::Value::Float(short).to_string()
+        }
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_dump() {
+        let user = ConfigSource::User(PathBuf::from("/home/me/.config/ccplayer/config.toml"));
+        let entries = vec![
+            ("audio.volume".to_string(), toml::Value::Float(0.4f32.into()), &ConfigSource::Cli),
+            ("general.log_level".to_string(), toml::Value::String("info".to_string()), &ConfigSource::Default),
+            ("keybindings.\"ctrl+q\"".to_string(), toml::Value::String("quit".to_string()), &user),
+        ];
+
+        let dump = format_dump(&entries);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines[0], "audio.volume = 0.4             # cli");
+        assert_eq!(lines[1], "general.log_level = \"info\"     # default");
+        assert_eq!(lines[2], "keybindings.\"ctrl+q\" = \"quit\"  # user /home/me/.config/ccplayer/config.toml");
+
+        // The whole dump is itself a valid config document
+        let document: toml::Table = toml::from_str(&dump).unwrap();
+        assert_eq!(document["keybindings"]["ctrl+q"].as_str(), Some("quit"));
+    }
+}
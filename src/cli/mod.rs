@@ -0,0 +1,35 @@
+//! Command-line subcommands for CCPlayer
+//!
+//! Tools that inspect media or the configuration without opening a
+//! player window, and the options shared with playback.
+
+pub mod analyze;
+pub mod config;
+pub mod options;
+pub mod probe;
+
+use crate::utils::error::Result;
+
+/// Subcommands of the `ccplayer` binary
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Print stream and metadata information without playing
+    Probe(probe::ProbeArgs),
+
+    /// Scan every packet for bitrate, GOP structure and timestamp problems
+    Analyze(analyze::AnalyzeArgs),
+
+    /// Show the effective configuration, check a config file or print its schema
+    Config(config::ConfigArgs),
+}
+
+impl Command {
+    /// Run the subcommand
+    pub fn run(&self) -> Result<()> {
+        match self {
+            Command::Probe(args) => probe::run(args),
+            Command::Analyze(args) => analyze::run(args),
+            Command::Config(args) => config::run(args),
+        }
+    }
+}
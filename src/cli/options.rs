@@ -0,0 +1,407 @@
+//! Command-line options for playback
+//!
+//! [`PlayerOptions`] change the configuration. They are accepted both when
+//! playing and by `ccplayer config dump`, and form the last configuration
+//! layer, on top of the config files, the environment and any `--profile`.
+//!
+//! [`PlaybackArgs`] only apply to the current run: where items start and
+//! stop, tracks, subtitles and what goes into the playlist.
+
+use crate::player::{PlaybackOptions, PlaylistItem};
+use crate::utils::config::{ConfigSource, TracedConfig};
+use crate::utils::{CCPlayerError, Config, Result};
+use clap::ArgAction;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Command-line options that override config settings
+#[derive(clap::Args, Debug)]
+pub struct PlayerOptions {
+    /// Start in fullscreen mode
+    #[arg(short, long)]
+    pub fullscreen: bool,
+
+    /// Set initial volume (0-100)
+    #[arg(short, long, value_name = "VOLUME", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub volume: Option<u8>,
+
+    /// Disable hardware acceleration
+    #[arg(long = "no-hw-accel", action = ArgAction::SetTrue)]
+    pub no_hardware_accel: bool,
+
+    /// Enable debug logging
+    #[arg(short, long)]
+    pub debug: bool,
+
+    /// Window width
+    #[arg(long)]
+    pub width: Option<u32>,
+
+    /// Window height
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// Window size and position, e.g. "1280x720", "1280x720+100+50" or "+0+0"
+    #[arg(long, value_name = "[WxH][+X+Y]", value_parser = parse_geometry)]
+    pub geometry: Option<Geometry>,
+
+    /// Keep the window above other windows
+    #[arg(long)]
+    pub ontop: bool,
+
+    /// Window title
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Load media paused instead of playing it
+    #[arg(long)]
+    pub pause: bool,
+
+    /// Repeat the playlist: N plays in all, or forever without N
+    #[arg(
+        long = "loop",
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub loop_count: Option<Option<u32>>,
+
+    /// Audio output device ("help" lists the devices)
+    #[arg(long, value_name = "NAME")]
+    pub audio_device: Option<String>,
+
+    /// Folder screenshots are saved in
+    #[arg(long, value_name = "DIR")]
+    pub screenshot_dir: Option<PathBuf>,
+
+    /// Apply a profile from the config file (repeatable)
+    #[arg(long, value_name = "NAME")]
+    pub profile: Vec<String>,
+
+    /// FFmpeg video filter chain (e.g. "crop=1280:720,eq=contrast=1.1")
+    #[arg(long = "vf", value_name = "FILTERGRAPH")]
+    pub video_filters: Option<String>,
+
+    /// FFmpeg audio filter chain (e.g. "highpass=f=200,loudnorm")
+    #[arg(long = "af", value_name = "FILTERGRAPH")]
+    pub audio_filters: Option<String>,
+}
+
+impl PlayerOptions {
+    /// Configuration from all layers: defaults, config files and environment,
+    /// then profiles and these options
+    pub fn config(&self) -> Result<Config> {
+        let config = self.traced_config()?.config;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Configuration from all layers with the source of every value, not yet validated
+    pub fn traced_config(&self) -> Result<TracedConfig> {
+        let mut traced = TracedConfig::load()?;
+        for profile in &self.profile {
+            traced.apply_profile(profile)?;
+        }
+        traced.update(ConfigSource::Cli, |config| self.apply(config));
+        Ok(traced)
+    }
+
+    /// Apply the options to a configuration
+    pub fn apply(&self, config: &mut Config) {
+        if self.fullscreen {
+            config.window.fullscreen = true;
+        }
+        if let Some(volume) = self.volume {
+            config.audio.volume = volume as f32 / 100.0;
+        }
+        if self.no_hardware_accel {
+            config.decoder.hardware_acceleration = false;
+        }
+        if self.debug {
+            config.general.log_level = "debug".to_string();
+        }
+        if let Some(width) = self.width {
+            config.window.width = width;
+        }
+        if let Some(height) = self.height {
+            config.window.height = height;
+        }
+        if let Some(geometry) = self.geometry {
+            if let Some((width, height)) = geometry.size {
+                config.window.width = width;
+                config.window.height = height;
+            }
+            if geometry.position.is_some() {
+                config.window.position = geometry.position;
+            }
+        }
+        if self.ontop {
+            config.window.always_on_top = true;
+        }
+        if let Some(title) = &self.title {
+            config.window.title = title.clone();
+        }
+        if self.pause {
+            config.general.auto_play = false;
+        }
+        if let Some(count) = self.loop_count {
+            config.player.loop_playback = true;
+            config.player.loop_count = count;
+        }
+        if let Some(device) = &self.audio_device {
+            config.audio.device = Some(device.clone());
+        }
+        if let Some(dir) = &self.screenshot_dir {
+            config.player.screenshot_dir = Some(dir.clone());
+        }
+        if let Some(filters) = &self.video_filters {
+            config.decoder.video_filters = Some(filters.clone());
+        }
+        if let Some(filters) = &self.audio_filters {
+            config.audio.filters = Some(filters.clone());
+        }
+    }
+}
+
+/// Command-line options for this run only
+#[derive(clap::Args, Debug)]
+pub struct PlaybackArgs {
+    /// Start each item at this position ([[HH:]MM:]SS[.ms])
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub start: Option<Duration>,
+
+    /// Stop each item at this position ([[HH:]MM:]SS[.ms])
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub end: Option<Duration>,
+
+    /// Playback speed (up to 4.0)
+    #[arg(long, value_parser = parse_speed)]
+    pub speed: Option<f32>,
+
+    /// Audio stream to play, by the index `ccplayer probe` shows
+    #[arg(long, value_name = "INDEX")]
+    pub aid: Option<usize>,
+
+    /// Text subtitle stream of local files to show, by the index `ccplayer probe` shows
+    #[arg(long, value_name = "INDEX")]
+    pub sid: Option<usize>,
+
+    /// Show subtitles from an SRT or WebVTT file
+    #[arg(long, value_name = "FILE", conflicts_with = "sid")]
+    pub sub_file: Option<PathBuf>,
+
+    /// Start muted
+    #[arg(long)]
+    pub mute: bool,
+
+    /// Add the entries of an M3U or plain-text playlist, after any FILE
+    #[arg(long, value_name = "FILE")]
+    pub playlist: Option<PathBuf>,
+
+    /// Play the playlist in random order
+    #[arg(long)]
+    pub shuffle: bool,
+}
+
+impl PlaybackArgs {
+    /// Start and end positions, tracks and subtitles for the player
+    pub fn playback_options(&self) -> PlaybackOptions {
+        PlaybackOptions {
+            start: self.start,
+            end: self.end,
+            audio_track: self.aid,
+            subtitle_track: self.sid,
+            subtitle_file: self.sub_file.clone(),
+        }
+    }
+
+    /// Playlist of the given files followed by the entries of `--playlist`
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - Files and URLs given on the command line
+    pub fn playlist_items(&self, files: &[PathBuf]) -> Result<Vec<PlaylistItem>> {
+        let mut items: Vec<PlaylistItem> = files.iter().map(|file| playlist_item(file.to_string_lossy().into_owned())).collect();
+
+        if let Some(path) = &self.playlist {
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| CCPlayerError::InvalidInput(format!("Failed to read playlist {}: {}", path.display(), e)))?;
+            let base = path.parent().unwrap_or(Path::new(""));
+            items.extend(parse_playlist(&contents, base));
+        }
+
+        Ok(items)
+    }
+}
+
+/// Window size and position given with `--geometry`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    /// Width and height in pixels
+    pub size: Option<(u32, u32)>,
+
+    /// Position of the top-left corner in pixels
+    pub position: Option<[i32; 2]>,
+}
+
+/// Parse `[WxH][+X+Y]`; negative positions are written `+-X`
+fn parse_geometry(value: &str) -> std::result::Result<Geometry, String> {
+    let (size, position) = match value.split_once('+') {
+        Some((size, position)) => (size, Some(position)),
+        None => (value, None),
+    };
+
+    let size = match size {
+        "" => None,
+        size => {
+            let (width, height) = size.split_once(['x', 'X']).ok_or("expected WxH")?;
+            let dimension = |value: &str| match value.parse::<u32>() {
+                Ok(0) | Err(_) => Err(format!("invalid window size '{}'", size)),
+                Ok(value) => Ok(value),
+            };
+            Some((dimension(width)?, dimension(height)?))
+        }
+    };
+
+    let position = match position {
+        Some(position) => {
+            let invalid = || format!("invalid window position '+{}'", position);
+            let (x, y) = position.split_once('+').ok_or_else(invalid)?;
+            Some([x.parse().map_err(|_| invalid())?, y.parse().map_err(|_| invalid())?])
+        }
+        None => None,
+    };
+
+    if size.is_none() && position.is_none() {
+        return Err("expected [WxH][+X+Y]".to_string());
+    }
+    Ok(Geometry { size, position })
+}
+
+/// Parse a position given as seconds, `MM:SS` or `HH:MM:SS`, with optional fractions
+fn parse_time(value: &str) -> std::result::Result<Duration, String> {
+    let invalid = || format!("invalid time '{}', expected [[HH:]MM:]SS[.ms]", value);
+
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+
+    let (seconds, clock) = parts.split_last().ok_or_else(invalid)?;
+    let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+    let mut total = 0u64;
+    for part in clock {
+        total = total * 60 + part.parse::<u64>().map_err(|_| invalid())?;
+    }
+
+    if !seconds.is_finite() || seconds < 0.0 || (!clock.is_empty() && seconds >= 60.0) {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total * 60) + Duration::from_secs_f64(seconds))
+}
+
+/// Parse a playback speed the player accepts
+fn parse_speed(value: &str) -> std::result::Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(speed) if speed > 0.0 && speed <= 4.0 => Ok(speed),
+        _ => Err(format!("invalid speed '{}', expected a number above 0 and up to 4", value)),
+    }
+}
+
+/// Read the entries of an M3U or plain-text playlist
+///
+/// `#EXTINF` lines give the title and duration of the entry that follows;
+/// other `#` lines are comments. Relative paths are resolved against the
+/// playlist's folder.
+///
+/// # Arguments
+///
+/// * `contents` - Text of the playlist
+/// * `base` - Folder the playlist is in
+fn parse_playlist(contents: &str, base: &Path) -> Vec<PlaylistItem> {
+    let mut items = Vec::new();
+    let mut info: Option<(Option<Duration>, Option<String>)> = None;
+
+    for line in contents.trim_start_matches('\u{feff}').lines().map(str::trim) {
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            let (seconds, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+            let duration = seconds.trim().parse::<f64>().ok()
+                .filter(|seconds| *seconds >= 0.0)
+                .map(Duration::from_secs_f64);
+            let title = Some(title.trim().to_string()).filter(|title| !title.is_empty());
+            info = Some((duration, title));
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let path = if line.contains("://") || Path::new(line).is_absolute() {
+            line.to_string()
+        } else {
+            base.join(line).to_string_lossy().into_owned()
+        };
+
+        let (duration, title) = info.take().unwrap_or_default();
+        items.push(PlaylistItem { path, title, duration });
+    }
+
+    items
+}
+
+/// Playlist item for a path or URL without a known title or duration
+fn playlist_item(path: String) -> PlaylistItem {
+    PlaylistItem { path, title: None, duration: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_geometry() {
+        let geometry = |size, position| Ok(Geometry { size, position });
+        assert_eq!(parse_geometry("1280x720"), geometry(Some((1280, 720)), None));
+        assert_eq!(parse_geometry("640X360+100+50"), geometry(Some((640, 360)), Some([100, 50])));
+        assert_eq!(parse_geometry("+-1920+0"), geometry(None, Some([-1920, 0])));
+
+        assert!(parse_geometry("").is_err());
+        assert!(parse_geometry("0x720").is_err());
+        assert!(parse_geometry("1280").is_err());
+        assert!(parse_geometry("1280x720+100").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_time("1:30"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_time("01:02:03.5"), Ok(Duration::from_millis(3_723_500)));
+        assert_eq!(parse_time("2.25"), Ok(Duration::from_millis(2250)));
+
+        assert!(parse_time("1:75").is_err());
+        assert!(parse_time("-5").is_err());
+        assert!(parse_time("1:2:3:4").is_err());
+        assert!(parse_time("soon").is_err());
+    }
+
+    #[test]
+    fn test_parse_playlist() {
+        let m3u = "#EXTM3U\n#EXTINF:215,Artist - Song\nmusic/song.flac\n\n# a comment\n\
+                   https://example.com/live.m3u8\n#EXTINF:-1,\n/srv/media/clip.mp4\n";
+        let items = parse_playlist(m3u, Path::new("/home/me/lists"));
+
+        let paths: Vec<&str> = items.iter().map(|item| item.path.as_str()).collect();
+        assert_eq!(paths, [
+            Path::new("/home/me/lists").join("music/song.flac").to_str().unwrap(),
+            "https://example.com/live.m3u8",
+            "/srv/media/clip.mp4",
+        ]);
+        assert_eq!(items[0].title.as_deref(), Some("Artist - Song"));
+        assert_eq!(items[0].duration, Some(Duration::from_secs(215)));
+
+        // Info only belongs to the entry right after it
+        assert_eq!(items[1].title, None);
+        assert_eq!((items[2].title.as_deref(), items[2].duration), (None, None));
+    }
+}
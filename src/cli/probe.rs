@@ -0,0 +1,273 @@
+//! `ccplayer probe`: media report without playback
+//!
+//! Opens a file, archive entry or URL with the decoder alone (no window or
+//! audio device) and prints what CCPlayer sees in it, as a table or JSON.
+
+use crate::decoder::{ArchivePath, Decoder, FFmpegDecoder, MediaInfo};
+use crate::renderer::Rotation;
+use crate::utils::error::{CCPlayerError, Result};
+use crate::utils::format_duration;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Arguments of the `probe` subcommand
+#[derive(clap::Args, Debug)]
+pub struct ProbeArgs {
+    /// Media file, archive entry (`clips.zip!/clip.mp4`) or URL
+    #[arg(value_name = "SOURCE")]
+    pub source: String,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Run the `probe` subcommand
+pub fn run(args: &ProbeArgs) -> Result<()> {
+    let info = probe(&args.source)?;
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&info)
+            .map_err(|e| CCPlayerError::Internal(format!("Failed to serialize media info: {}", e)))?;
+        println!("{}", json);
+    } else {
+        print!("{}", format_report(&info));
+    }
+
+    Ok(())
+}
+
+/// Open a source and read its media information
+///
+/// # Arguments
+///
+/// * `source` - File path, archive entry or URL
+pub fn probe(source: &str) -> Result<MediaInfo> {
+    let mut decoder = FFmpegDecoder::new()?;
+
+    if source.contains("://") {
+        return decoder.open_url(source);
+    }
+
+    let path = Path::new(source);
+    if let Some(archive) = ArchivePath::parse(path) {
+        if archive.entry.is_none() {
+            return Err(CCPlayerError::InvalidInput(format!(
+                "Name an entry inside the archive, e.g. {}!/clip.mp4",
+                archive.archive.display()
+            )));
+        }
+        return decoder.open_source(archive.open_entry()?);
+    }
+
+    if !path.exists() {
+        return Err(CCPlayerError::NotFound(source.to_string()));
+    }
+    decoder.open_file(path)
+}
+
+/// Format media information as a human-readable table
+pub fn format_report(info: &MediaInfo) -> String {
+    let mut out = String::new();
+
+    // Writing to a String cannot fail
+    let _ = writeln!(out, "{:<10}{}", "Source", info.source);
+    let _ = writeln!(out, "{:<10}{}", "Format", info.format);
+    if info.is_live {
+        let _ = writeln!(out, "{:<10}live", "Duration");
+    } else if info.is_still {
+        let _ = writeln!(out, "{:<10}still image", "Duration");
+    } else {
+        let _ = writeln!(out, "{:<10}{}", "Duration", format_duration(info.duration));
+    }
+    if let Some(size) = info.file_size {
+        let _ = writeln!(out, "{:<10}{}", "Size", format_size(size));
+    }
+    if let Some(bitrate) = info.bitrate {
+        let _ = writeln!(out, "{:<10}{} kb/s", "Bitrate", bitrate / 1000);
+    }
+
+    let _ = writeln!(out, "\nStreams");
+    for video in &info.video_streams {
+        let _ = write!(
+            out,
+            "  #{:<3}video     {} {}x{} {:.3} fps {} {:?}",
+            video.index, video.codec, video.width, video.height, video.fps, video.pixel_format, video.color_space
+        );
+        if video.rotation != Rotation::default() {
+            let _ = write!(out, " rotated {}°", video.rotation.degrees);
+            if video.rotation.flip {
+                let _ = write!(out, " mirrored");
+            }
+        }
+        if let Some(hdr) = &video.hdr_metadata {
+            let _ = write!(out, " MaxCLL {} MaxFALL {}", hdr.max_cll, hdr.max_fall);
+        }
+        let _ = writeln!(out);
+    }
+    for audio in &info.audio_streams {
+        let _ = write!(
+            out,
+            "  #{:<3}audio     {} {} Hz {} ch ({}) {}",
+            audio.index, audio.codec, audio.sample_rate, audio.channels, audio.channel_layout, audio.sample_format
+        );
+        if let Some(language) = &audio.language {
+            let _ = write!(out, " [{}]", language);
+        }
+        let _ = writeln!(out);
+    }
+    for subtitle in &info.subtitle_streams {
+        let _ = write!(out, "  #{:<3}subtitle  {}", subtitle.index, subtitle.codec);
+        if let Some(language) = &subtitle.language {
+            let _ = write!(out, " [{}]", language);
+        }
+        if let Some(title) = &subtitle.title {
+            let _ = write!(out, " \"{}\"", title);
+        }
+        if subtitle.forced {
+            let _ = write!(out, " forced");
+        }
+        let _ = writeln!(out);
+    }
+
+    if !info.variants.is_empty() {
+        let _ = writeln!(out, "\nVariants");
+        for variant in &info.variants {
+            let current = if info.current_variant == Some(variant.index) { "*" } else { " " };
+            let _ = write!(out, " {}{:<3}{} kb/s", current, variant.index, variant.bandwidth / 1000);
+            if let Some((width, height)) = variant.resolution {
+                let _ = write!(out, " {}x{}", width, height);
+            }
+            if let Some(codecs) = &variant.codecs {
+                let _ = write!(out, " {}", codecs);
+            }
+            let _ = writeln!(out);
+        }
+    }
+
+    let metadata = &info.metadata;
+    let mut tags: Vec<(&str, String)> = [
+        ("title", metadata.title.clone()),
+        ("artist", metadata.artist.clone()),
+        ("album", metadata.album.clone()),
+        ("year", metadata.year.map(|year| year.to_string())),
+        ("genre", metadata.genre.clone()),
+        ("track", metadata.track.map(|track| track.to_string())),
+        ("comment", metadata.comment.clone()),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| Some((key, value?)))
+    .collect();
+
+    let mut custom: Vec<_> = metadata.custom.iter().collect();
+    custom.sort();
+    tags.extend(custom.into_iter().map(|(key, value)| (key.as_str(), value.clone())));
+
+    if !tags.is_empty() {
+        let _ = writeln!(out, "\nMetadata");
+        for (key, value) in tags {
+            let _ = writeln!(out, "  {:<12}{}", key, value);
+        }
+    }
+
+    out
+}
+
+/// Format a byte count with a binary unit
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", value, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::{AudioStreamInfo, ColorSpace, MediaMetadata, VideoStreamInfo};
+    use std::time::Duration;
+
+    /// Media info of a small phone recording
+    fn sample() -> MediaInfo {
+        MediaInfo {
+            source: "clip.mp4".to_string(),
+            duration: Duration::from_secs(95),
+            video_streams: vec![VideoStreamInfo {
+                index: 0,
+                codec: "h264".to_string(),
+                width: 1920,
+                height: 1080,
+                fps: 29.97,
+                bitrate: Some(8_000_000),
+                pixel_format: "yuv420p".to_string(),
+                color_space: ColorSpace::Sdr,
+                hdr_metadata: None,
+                rotation: Rotation::new(90.0, false),
+            }],
+            audio_streams: vec![AudioStreamInfo {
+                index: 1,
+                codec: "aac".to_string(),
+                sample_rate: 48000,
+                channels: 2,
+                channel_layout: "stereo".to_string(),
+                bitrate: Some(128_000),
+                sample_format: "fltp".to_string(),
+                language: Some("eng".to_string()),
+                encoder_delay: 0,
+                encoder_padding: 0,
+            }],
+            subtitle_streams: Vec::new(),
+            format: "mov,mp4,m4a,3gp,3g2,mj2".to_string(),
+            file_size: Some(96_000_000),
+            bitrate: Some(8_128_000),
+            metadata: MediaMetadata {
+                title: Some("Beach".to_string()),
+                ..Default::default()
+            },
+            variants: Vec::new(),
+            current_variant: None,
+            is_live: false,
+            is_still: false,
+        }
+    }
+
+    #[test]
+    fn test_format_report() {
+        let report = format_report(&sample());
+
+        assert!(report.contains("Duration  01:35"));
+        assert!(report.contains("Size      91.6 MiB"));
+        assert!(report.contains("video     h264 1920x1080 29.970 fps yuv420p Sdr rotated 90°"));
+        assert!(report.contains("audio     aac 48000 Hz 2 ch (stereo) fltp [eng]"));
+        assert!(report.contains("  title       Beach"));
+    }
+
+    #[test]
+    fn test_media_info_json() {
+        let json = serde_json::to_value(sample()).unwrap();
+        assert_eq!(json["video_streams"][0]["rotation"]["degrees"], 90);
+        assert_eq!(json["audio_streams"][0]["language"], "eng");
+
+        let info: MediaInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(info.duration, Duration::from_secs(95));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
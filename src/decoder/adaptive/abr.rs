@@ -0,0 +1,278 @@
+//! Adaptive bitrate logic
+//!
+//! Estimates network throughput from segment downloads and picks the
+//! variant that fits into it.
+
+use crate::decoder::{QualitySelection, VariantInfo};
+use std::time::Duration;
+
+/// Downloads smaller than this are dominated by latency and ignored
+const MIN_SAMPLE_BYTES: usize = 8 * 1024;
+
+/// Half-life of the fast-reacting average in seconds of download time
+const FAST_HALF_LIFE: f64 = 2.0;
+
+/// Half-life of the slow-reacting average in seconds of download time
+const SLOW_HALF_LIFE: f64 = 5.0;
+
+/// Switching up requires this much extra headroom over switching down
+const UPSWITCH_HEADROOM: f64 = 0.85;
+
+/// Exponentially weighted moving average, weighted by download time
+#[derive(Debug, Clone)]
+struct Ewma {
+    /// Decay per second of weight
+    alpha: f64,
+
+    /// Biased estimate
+    estimate: f64,
+
+    /// Total weight seen so far
+    total_weight: f64,
+}
+
+impl Ewma {
+    /// Create an average with the given half-life
+    fn new(half_life: f64) -> Self {
+        Self {
+            alpha: (0.5f64.ln() / half_life).exp(),
+            estimate: 0.0,
+            total_weight: 0.0,
+        }
+    }
+
+    /// Add a value with the given weight
+    fn sample(&mut self, weight: f64, value: f64) {
+        let decay = self.alpha.powf(weight);
+        self.estimate = value * (1.0 - decay) + decay * self.estimate;
+        self.total_weight += weight;
+    }
+
+    /// Current estimate, corrected for the zero initial value
+    fn estimate(&self) -> f64 {
+        let zero_factor = 1.0 - self.alpha.powf(self.total_weight);
+        self.estimate / zero_factor
+    }
+}
+
+/// Network throughput estimator
+///
+/// Keeps a fast and a slow moving average and reports the lower of the
+/// two, so drops are picked up quickly while spikes are not trusted.
+#[derive(Debug, Clone)]
+pub struct ThroughputEstimator {
+    /// Fast-reacting average
+    fast: Ewma,
+
+    /// Slow-reacting average
+    slow: Ewma,
+
+    /// Number of samples taken
+    samples: usize,
+}
+
+impl ThroughputEstimator {
+    /// Create a new estimator
+    pub fn new() -> Self {
+        Self {
+            fast: Ewma::new(FAST_HALF_LIFE),
+            slow: Ewma::new(SLOW_HALF_LIFE),
+            samples: 0,
+        }
+    }
+
+    /// Record a completed download
+    pub fn add_sample(&mut self, bytes: usize, elapsed: Duration) {
+        if bytes < MIN_SAMPLE_BYTES {
+            return;
+        }
+
+        let seconds = elapsed.as_secs_f64().max(0.001);
+        let bits_per_second = bytes as f64 * 8.0 / seconds;
+
+        self.fast.sample(seconds, bits_per_second);
+        self.slow.sample(seconds, bits_per_second);
+        self.samples += 1;
+    }
+
+    /// Estimated throughput in bits per second
+    pub fn estimate(&self) -> Option<f64> {
+        if self.samples == 0 {
+            return None;
+        }
+
+        Some(self.fast.estimate().min(self.slow.estimate()))
+    }
+
+    /// Number of samples taken
+    pub fn sample_count(&self) -> usize {
+        self.samples
+    }
+}
+
+impl Default for ThroughputEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Throughput-based variant selection
+#[derive(Debug, Clone)]
+pub struct VariantSelector {
+    /// Fraction of the estimated throughput a variant may use
+    safety_factor: f64,
+}
+
+impl VariantSelector {
+    /// Create a selector with the given safety factor
+    pub fn new(safety_factor: f64) -> Self {
+        Self {
+            safety_factor: safety_factor.clamp(0.1, 1.0),
+        }
+    }
+
+    /// Pick a variant index
+    ///
+    /// # Arguments
+    ///
+    /// * `variants` - Available variants
+    /// * `current` - Variant currently playing
+    /// * `estimate` - Throughput estimate in bits per second
+    pub fn select(&self, variants: &[VariantInfo], current: Option<usize>, estimate: Option<f64>) -> usize {
+        let Some(estimate) = estimate else {
+            // No measurements yet: keep going, or start with the first listed variant
+            return current.unwrap_or(0);
+        };
+
+        let budget = estimate * self.safety_factor;
+        let best_within = |limit: f64| {
+            variants
+                .iter()
+                .filter(|v| v.bandwidth as f64 <= limit)
+                .max_by_key(|v| v.bandwidth)
+        };
+
+        let lowest = variants.iter().min_by_key(|v| v.bandwidth).map_or(0, |v| v.index);
+        let down = best_within(budget).map_or(lowest, |v| v.index);
+
+        match current.and_then(|c| variants.get(c)) {
+            // Only switch up when there is extra headroom
+            Some(current) if variants[down].bandwidth > current.bandwidth => best_within(budget * UPSWITCH_HEADROOM)
+                .filter(|v| v.bandwidth > current.bandwidth)
+                .map_or(current.index, |v| v.index),
+            _ => down,
+        }
+    }
+
+    /// Pick a variant index under the user's quality selection
+    ///
+    /// A pinned variant that does not exist falls back to automatic
+    /// selection.
+    ///
+    /// # Arguments
+    ///
+    /// * `selection` - Automatic selection or a pinned variant index
+    /// * `variants` - Available variants
+    /// * `current` - Variant currently playing
+    /// * `estimate` - Throughput estimate in bits per second
+    pub fn choose(
+        &self,
+        selection: QualitySelection,
+        variants: &[VariantInfo],
+        current: Option<usize>,
+        estimate: Option<f64>,
+    ) -> usize {
+        match selection {
+            QualitySelection::Pinned(index) if index < variants.len() => index,
+            _ => self.select(variants, current, estimate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variants() -> Vec<VariantInfo> {
+        [400_000, 1_500_000, 5_000_000]
+            .iter()
+            .enumerate()
+            .map(|(index, &bandwidth)| VariantInfo {
+                index,
+                bandwidth,
+                resolution: None,
+                frame_rate: None,
+                codecs: None,
+                name: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_estimator_converges() {
+        let mut estimator = ThroughputEstimator::new();
+        assert!(estimator.estimate().is_none());
+
+        // 1 MB per second = 8 Mbit/s
+        for _ in 0..10 {
+            estimator.add_sample(1_000_000, Duration::from_secs(1));
+        }
+
+        let estimate = estimator.estimate().unwrap();
+        assert!((estimate - 8_000_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_estimator_reacts_to_drops() {
+        let mut estimator = ThroughputEstimator::new();
+        for _ in 0..10 {
+            estimator.add_sample(1_000_000, Duration::from_secs(1));
+        }
+
+        estimator.add_sample(100_000, Duration::from_secs(2));
+        assert!(estimator.estimate().unwrap() < 5_000_000.0);
+
+        // Tiny downloads do not count
+        estimator.add_sample(100, Duration::from_secs(10));
+        assert_eq!(estimator.sample_count(), 11);
+    }
+
+    #[test]
+    fn test_selector_budget() {
+        let selector = VariantSelector::new(0.8);
+        let variants = variants();
+
+        assert_eq!(selector.select(&variants, None, None), 0);
+        assert_eq!(selector.select(&variants, None, Some(2_000_000.0)), 1);
+        assert_eq!(selector.select(&variants, None, Some(10_000_000.0)), 2);
+        assert_eq!(selector.select(&variants, Some(2), Some(100_000.0)), 0);
+    }
+
+    #[test]
+    fn test_selector_upswitch_hysteresis() {
+        let selector = VariantSelector::new(0.8);
+        let variants = variants();
+
+        // 6.5 Mbit/s fits variant 2 for staying, but not for switching up
+        assert_eq!(selector.select(&variants, Some(1), Some(6_500_000.0)), 1);
+        assert_eq!(selector.select(&variants, Some(2), Some(6_500_000.0)), 2);
+        assert_eq!(selector.select(&variants, Some(1), Some(8_000_000.0)), 2);
+    }
+
+    #[test]
+    fn test_quality_selection() {
+        let selector = VariantSelector::new(0.8);
+        let variants = variants();
+
+        // A pinned variant is kept whatever the throughput
+        assert_eq!(selector.choose(QualitySelection::Pinned(2), &variants, Some(2), Some(100_000.0)), 2);
+        assert_eq!(selector.choose(QualitySelection::Pinned(0), &variants, Some(0), Some(10_000_000.0)), 0);
+
+        // Automatic selection stays within the bandwidth budget
+        assert_eq!(selector.choose(QualitySelection::Auto, &variants, Some(2), Some(2_000_000.0)), 1);
+        assert_eq!(selector.choose(QualitySelection::Auto, &variants, None, Some(10_000_000.0)), 2);
+
+        // Pinning a variant that does not exist selects automatically
+        assert_eq!(selector.choose(QualitySelection::Pinned(7), &variants, None, Some(2_000_000.0)), 1);
+    }
+}
@@ -0,0 +1,556 @@
+//! DASH manifest parsing
+//!
+//! Turns an MPD into per-representation segment lists. Supports
+//! SegmentTemplate (with or without SegmentTimeline), SegmentList and
+//! single-file representations. Only the first period is played.
+
+use super::{ByteRange, InitSection, Segment, SegmentList};
+use crate::utils::error::{CCPlayerError, Result};
+use roxmltree::Node;
+use url::Url;
+
+/// Content type of an adaptation set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentKind {
+    Video,
+    Audio,
+    Other,
+}
+
+/// A representation with its resolved segments
+#[derive(Debug, Clone)]
+pub(crate) struct DashRepresentation {
+    /// Representation ID
+    pub id: String,
+
+    /// Content type
+    pub kind: ContentKind,
+
+    /// Bandwidth in bits per second
+    pub bandwidth: u64,
+
+    /// Video resolution
+    pub resolution: Option<(u32, u32)>,
+
+    /// Frame rate
+    pub frame_rate: Option<f32>,
+
+    /// Codec string
+    pub codecs: Option<String>,
+
+    /// Language of the adaptation set
+    pub language: Option<String>,
+
+    /// Segment list
+    pub segments: SegmentList,
+}
+
+/// Parsed MPD
+#[derive(Debug, Clone)]
+pub(crate) struct DashManifest {
+    /// Representations of the first period
+    pub representations: Vec<DashRepresentation>,
+
+    /// Whether the MPD is dynamic (live)
+    pub dynamic: bool,
+
+    /// Suggested refresh interval for dynamic MPDs in seconds
+    pub minimum_update_period: Option<f64>,
+}
+
+impl DashManifest {
+    /// Representations of one content type
+    pub fn representations_of(&self, kind: ContentKind) -> impl Iterator<Item = &DashRepresentation> {
+        self.representations.iter().filter(move |r| r.kind == kind)
+    }
+}
+
+/// Segment template attributes, inherited from adaptation set to representation
+#[derive(Debug, Clone, Default)]
+struct SegmentTemplate {
+    media: Option<String>,
+    initialization: Option<String>,
+    start_number: Option<u64>,
+    timescale: Option<u64>,
+    duration: Option<u64>,
+    timeline: Vec<TimelineEntry>,
+}
+
+/// `<S t d r>` entry of a SegmentTimeline
+#[derive(Debug, Clone, Copy)]
+struct TimelineEntry {
+    time: Option<u64>,
+    duration: u64,
+    repeat: i64,
+}
+
+impl SegmentTemplate {
+    /// Read a SegmentTemplate child element, if any
+    fn from_parent(node: Node) -> Option<Self> {
+        let template = child(node, "SegmentTemplate")?;
+
+        let timeline = child(template, "SegmentTimeline")
+            .map(|timeline| {
+                timeline
+                    .children()
+                    .filter(|n| n.tag_name().name() == "S")
+                    .filter_map(|s| {
+                        Some(TimelineEntry {
+                            time: s.attribute("t").and_then(|v| v.parse().ok()),
+                            duration: s.attribute("d")?.parse().ok()?,
+                            repeat: s.attribute("r").and_then(|v| v.parse().ok()).unwrap_or(0),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            media: template.attribute("media").map(str::to_string),
+            initialization: template.attribute("initialization").map(str::to_string),
+            start_number: template.attribute("startNumber").and_then(|v| v.parse().ok()),
+            timescale: template.attribute("timescale").and_then(|v| v.parse().ok()),
+            duration: template.attribute("duration").and_then(|v| v.parse().ok()),
+            timeline,
+        })
+    }
+
+    /// Merge with a more specific template (its values win)
+    fn merged(&self, other: &SegmentTemplate) -> SegmentTemplate {
+        SegmentTemplate {
+            media: other.media.clone().or_else(|| self.media.clone()),
+            initialization: other.initialization.clone().or_else(|| self.initialization.clone()),
+            start_number: other.start_number.or(self.start_number),
+            timescale: other.timescale.or(self.timescale),
+            duration: other.duration.or(self.duration),
+            timeline: if other.timeline.is_empty() {
+                self.timeline.clone()
+            } else {
+                other.timeline.clone()
+            },
+        }
+    }
+}
+
+/// Check whether manifest text is an MPD
+pub(crate) fn is_mpd(text: &str) -> bool {
+    text.contains("<MPD")
+}
+
+/// Parse an MPD
+pub(crate) fn parse_mpd(text: &str, mpd_url: &Url) -> Result<DashManifest> {
+    let document = roxmltree::Document::parse(text)
+        .map_err(|e| CCPlayerError::InvalidInput(format!("Invalid MPD: {}", e)))?;
+
+    let mpd = document.root_element();
+    if mpd.tag_name().name() != "MPD" {
+        return Err(CCPlayerError::UnsupportedFormat("Not a DASH manifest".to_string()));
+    }
+
+    let dynamic = mpd.attribute("type") == Some("dynamic");
+    let presentation_duration = mpd.attribute("mediaPresentationDuration").and_then(parse_duration);
+    let mpd_base = base_url(mpd, mpd_url)?;
+
+    let period = child(mpd, "Period")
+        .ok_or_else(|| CCPlayerError::InvalidInput("MPD has no Period".to_string()))?;
+
+    if mpd.children().filter(|n| n.tag_name().name() == "Period").count() > 1 {
+        log::warn!("Multi-period MPD; only the first period will be played");
+    }
+
+    let period_duration = period
+        .attribute("duration")
+        .and_then(parse_duration)
+        .or(presentation_duration);
+    let period_base = base_url(period, &mpd_base)?;
+
+    let mut representations = Vec::new();
+
+    for set in period.children().filter(|n| n.tag_name().name() == "AdaptationSet") {
+        let set_base = base_url(set, &period_base)?;
+        let set_template = SegmentTemplate::from_parent(set).unwrap_or_default();
+
+        for rep in set.children().filter(|n| n.tag_name().name() == "Representation") {
+            let id = rep
+                .attribute("id")
+                .ok_or_else(|| CCPlayerError::InvalidInput("Representation without id".to_string()))?
+                .to_string();
+            let bandwidth = rep.attribute("bandwidth").and_then(|v| v.parse().ok()).unwrap_or(0);
+            let rep_base = base_url(rep, &set_base)?;
+
+            let segments = if let Some(template) = SegmentTemplate::from_parent(rep)
+                .map(|t| set_template.merged(&t))
+                .or_else(|| SegmentTemplate::from_parent(set))
+            {
+                template_segments(&template, &id, bandwidth, &rep_base, period_duration, dynamic)?
+            } else if let Some(list) = child(rep, "SegmentList").or_else(|| child(set, "SegmentList")) {
+                list_segments(list, &rep_base)?
+            } else {
+                single_segment(&rep_base, period_duration)?
+            };
+
+            let width = attribute(rep, set, "width").and_then(|v| v.parse().ok());
+            let height = attribute(rep, set, "height").and_then(|v| v.parse().ok());
+
+            representations.push(DashRepresentation {
+                kind: content_kind(set, rep),
+                bandwidth,
+                resolution: width.zip(height),
+                frame_rate: attribute(rep, set, "frameRate").and_then(parse_frame_rate),
+                codecs: attribute(rep, set, "codecs").map(str::to_string),
+                language: set.attribute("lang").map(str::to_string),
+                segments: SegmentList {
+                    complete: !dynamic,
+                    ..segments
+                },
+                id,
+            });
+        }
+    }
+
+    Ok(DashManifest {
+        representations,
+        dynamic,
+        minimum_update_period: mpd.attribute("minimumUpdatePeriod").and_then(parse_duration),
+    })
+}
+
+/// Build segments from a SegmentTemplate
+fn template_segments(
+    template: &SegmentTemplate,
+    id: &str,
+    bandwidth: u64,
+    base: &Url,
+    period_duration: Option<f64>,
+    dynamic: bool,
+) -> Result<SegmentList> {
+    let media = template
+        .media
+        .as_deref()
+        .ok_or_else(|| CCPlayerError::InvalidInput("SegmentTemplate without media".to_string()))?;
+    let timescale = template.timescale.unwrap_or(1).max(1) as f64;
+    let start_number = template.start_number.unwrap_or(1);
+
+    let init = match &template.initialization {
+        Some(pattern) => Some(InitSection {
+            url: resolve(base, &expand_template(pattern, id, bandwidth, 0, 0))?,
+            byte_range: None,
+        }),
+        None => None,
+    };
+
+    let mut list = SegmentList::default();
+    let mut push = |number: u64, time: u64, duration: u64| -> Result<()> {
+        list.segments.push(Segment {
+            url: resolve(base, &expand_template(media, id, bandwidth, number, time))?,
+            byte_range: None,
+            duration: duration as f64 / timescale,
+            start: time as f64 / timescale,
+            sequence: number,
+            init: init.clone(),
+            discontinuity: false,
+        });
+        Ok(())
+    };
+
+    if !template.timeline.is_empty() {
+        let mut time = 0u64;
+        let mut number = start_number;
+        let end = period_duration.map(|d| (d * timescale) as u64);
+
+        for entry in &template.timeline {
+            time = entry.time.unwrap_or(time);
+
+            // A negative repeat count runs until the end of the period
+            let repeat = if entry.repeat < 0 {
+                match end {
+                    Some(end) => end.saturating_sub(time).div_ceil(entry.duration.max(1)).saturating_sub(1),
+                    None => 0,
+                }
+            } else {
+                entry.repeat as u64
+            };
+
+            for _ in 0..=repeat {
+                push(number, time, entry.duration)?;
+                time += entry.duration;
+                number += 1;
+            }
+        }
+    } else if let Some(duration) = template.duration {
+        let total = period_duration.ok_or_else(|| {
+            let kind = if dynamic { "Live" } else { "Static" };
+            CCPlayerError::UnsupportedFormat(format!("{} MPD without duration or SegmentTimeline", kind))
+        })?;
+        let count = (total * timescale / duration as f64).ceil() as u64;
+
+        for i in 0..count {
+            push(start_number + i, i * duration, duration)?;
+        }
+    } else {
+        return Err(CCPlayerError::InvalidInput("SegmentTemplate without duration or timeline".to_string()));
+    }
+
+    list.target_duration = list.segments.iter().map(|s| s.duration).fold(0.0, f64::max);
+    Ok(list)
+}
+
+/// Build segments from a SegmentList
+fn list_segments(node: Node, base: &Url) -> Result<SegmentList> {
+    let timescale = node.attribute("timescale").and_then(|v| v.parse::<f64>().ok()).unwrap_or(1.0);
+    let duration = node.attribute("duration").and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0) / timescale;
+
+    let init = match child(node, "Initialization") {
+        Some(init) => Some(InitSection {
+            url: match init.attribute("sourceURL") {
+                Some(url) => resolve(base, url)?,
+                None => base.to_string(),
+            },
+            byte_range: init.attribute("range").and_then(parse_range),
+        }),
+        None => None,
+    };
+
+    let mut list = SegmentList {
+        target_duration: duration,
+        ..SegmentList::default()
+    };
+
+    for (i, url) in node.children().filter(|n| n.tag_name().name() == "SegmentURL").enumerate() {
+        list.segments.push(Segment {
+            url: match url.attribute("media") {
+                Some(media) => resolve(base, media)?,
+                None => base.to_string(),
+            },
+            byte_range: url.attribute("mediaRange").and_then(parse_range),
+            duration,
+            start: i as f64 * duration,
+            sequence: i as u64,
+            init: init.clone(),
+            discontinuity: false,
+        });
+    }
+
+    Ok(list)
+}
+
+/// A representation that is one file at its BaseURL
+///
+/// The file carries its own initialization data, so no init section is
+/// needed and the whole file is a single segment.
+fn single_segment(base: &Url, period_duration: Option<f64>) -> Result<SegmentList> {
+    let duration = period_duration.unwrap_or(0.0);
+
+    Ok(SegmentList {
+        segments: vec![Segment {
+            url: base.to_string(),
+            byte_range: None,
+            duration,
+            start: 0.0,
+            sequence: 0,
+            init: None,
+            discontinuity: false,
+        }],
+        complete: true,
+        target_duration: duration,
+    })
+}
+
+/// Substitute `$RepresentationID$`, `$Number$`, `$Bandwidth$` and `$Time$`
+fn expand_template(pattern: &str, id: &str, bandwidth: u64, number: u64, time: u64) -> String {
+    let mut output = String::with_capacity(pattern.len());
+    let mut parts = pattern.split('$');
+
+    if let Some(first) = parts.next() {
+        output.push_str(first);
+    }
+
+    let mut in_identifier = true;
+    for part in parts {
+        if in_identifier {
+            let (name, width) = match part.split_once('%') {
+                Some((name, format)) => (name, format.trim_start_matches('0').trim_end_matches('d').parse().ok()),
+                None => (part, None),
+            };
+
+            let value = match name {
+                "" => "$".to_string(),
+                "RepresentationID" => id.to_string(),
+                "Number" => number.to_string(),
+                "Bandwidth" => bandwidth.to_string(),
+                "Time" => time.to_string(),
+                other => format!("${}$", other),
+            };
+
+            match width {
+                Some(width) => output.push_str(&format!("{:0>width$}", value, width = width)),
+                None => output.push_str(&value),
+            }
+        } else {
+            output.push_str(part);
+        }
+
+        in_identifier = !in_identifier;
+    }
+
+    output
+}
+
+/// Parse an ISO 8601 duration (`PT1H2M3.5S`, `P1DT2H`)
+fn parse_duration(text: &str) -> Option<f64> {
+    let rest = text.strip_prefix('P')?;
+    let (date, time) = rest.split_once('T').unwrap_or((rest, ""));
+
+    let mut seconds = 0.0;
+    let mut number = String::new();
+
+    for (part, is_time) in [(date, false), (time, true)] {
+        for c in part.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                number.push(c);
+                continue;
+            }
+
+            let value: f64 = number.parse().ok()?;
+            number.clear();
+
+            seconds += value * match (c, is_time) {
+                ('D', false) => 86_400.0,
+                ('H', true) => 3_600.0,
+                ('M', true) => 60.0,
+                ('S', true) => 1.0,
+                _ => return None,
+            };
+        }
+    }
+
+    Some(seconds)
+}
+
+/// Parse a frame rate (`30`, `30000/1001`)
+fn parse_frame_rate(text: &str) -> Option<f32> {
+    match text.split_once('/') {
+        Some((num, den)) => {
+            let den: f32 = den.parse().ok()?;
+            (den > 0.0).then(|| num.parse::<f32>().ok().map(|n| n / den)).flatten()
+        }
+        None => text.parse().ok(),
+    }
+}
+
+/// Parse a `first-last` byte range
+fn parse_range(text: &str) -> Option<ByteRange> {
+    let (first, last) = text.split_once('-')?;
+    let first: u64 = first.parse().ok()?;
+    let last: u64 = last.parse().ok()?;
+
+    (last >= first).then_some(ByteRange {
+        offset: first,
+        length: last - first + 1,
+    })
+}
+
+/// Determine the content type of a representation
+fn content_kind(set: Node, rep: Node) -> ContentKind {
+    let kind = set
+        .attribute("contentType")
+        .or_else(|| attribute(rep, set, "mimeType"))
+        .unwrap_or("");
+
+    if kind.starts_with("video") {
+        ContentKind::Video
+    } else if kind.starts_with("audio") {
+        ContentKind::Audio
+    } else {
+        ContentKind::Other
+    }
+}
+
+/// Attribute of a representation, inherited from its adaptation set
+fn attribute<'a>(rep: Node<'a, 'a>, set: Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    rep.attribute(name).or_else(|| set.attribute(name))
+}
+
+/// First child element with the given local name
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+/// Resolve the BaseURL of an element against its parent's
+fn base_url(node: Node, parent: &Url) -> Result<Url> {
+    match child(node, "BaseURL").and_then(|n| n.text()) {
+        Some(text) => parent
+            .join(text.trim())
+            .map_err(|e| CCPlayerError::InvalidInput(format!("Invalid BaseURL {}: {}", text, e))),
+        None => Ok(parent.clone()),
+    }
+}
+
+/// Resolve a segment URL
+fn resolve(base: &Url, url: &str) -> Result<String> {
+    base.join(url)
+        .map(|u| u.to_string())
+        .map_err(|e| CCPlayerError::InvalidInput(format!("Invalid segment URL {}: {}", url, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE_MPD: &str = r#"<?xml version="1.0"?>
+<MPD xmlns="urn:mpeg:dash:schema:mpd:2011" type="static" mediaPresentationDuration="PT10S">
+  <Period>
+    <AdaptationSet contentType="video" mimeType="video/mp4">
+      <SegmentTemplate media="$RepresentationID$/seg-$Number%03d$.m4s" initialization="$RepresentationID$/init.mp4" duration="4" timescale="1" startNumber="1"/>
+      <Representation id="360p" bandwidth="800000" width="640" height="360" frameRate="30000/1001"/>
+      <Representation id="1080p" bandwidth="5000000" width="1920" height="1080"/>
+    </AdaptationSet>
+    <AdaptationSet contentType="audio" lang="en">
+      <Representation id="audio" bandwidth="128000" codecs="mp4a.40.2">
+        <SegmentTemplate media="audio/$Time$.m4s" initialization="audio/init.mp4" timescale="1000">
+          <SegmentTimeline>
+            <S t="0" d="5000" r="1"/>
+          </SegmentTimeline>
+        </SegmentTemplate>
+      </Representation>
+    </AdaptationSet>
+  </Period>
+</MPD>"#;
+
+    #[test]
+    fn test_parse_template_mpd() {
+        let url = Url::parse("https://cdn.example.com/vod/manifest.mpd").unwrap();
+        let manifest = parse_mpd(TEMPLATE_MPD, &url).unwrap();
+        assert!(!manifest.dynamic);
+
+        let video: Vec<_> = manifest.representations_of(ContentKind::Video).collect();
+        assert_eq!(video.len(), 2);
+        assert_eq!(video[0].resolution, Some((640, 360)));
+        assert!((video[0].frame_rate.unwrap() - 29.97).abs() < 0.01);
+
+        let segments = &video[1].segments.segments;
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].url, "https://cdn.example.com/vod/1080p/seg-001.m4s");
+        assert_eq!(segments[0].init.as_ref().unwrap().url, "https://cdn.example.com/vod/1080p/init.mp4");
+
+        let audio: Vec<_> = manifest.representations_of(ContentKind::Audio).collect();
+        assert_eq!(audio[0].segments.segments.len(), 2);
+        assert_eq!(audio[0].segments.segments[1].url, "https://cdn.example.com/vod/audio/5000.m4s");
+        assert_eq!(audio[0].language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn test_expand_template() {
+        assert_eq!(expand_template("$RepresentationID$_$Number$.m4s", "v1", 0, 7, 0), "v1_7.m4s");
+        assert_eq!(expand_template("seg$Number%05d$.m4s", "v1", 0, 42, 0), "seg00042.m4s");
+        assert_eq!(expand_template("$Bandwidth$/$Time$$$.m4s", "v1", 800, 0, 9000), "800/9000$.m4s");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("PT10S"), Some(10.0));
+        assert_eq!(parse_duration("PT1H2M3.5S"), Some(3723.5));
+        assert_eq!(parse_duration("P1DT1S"), Some(86401.0));
+        assert_eq!(parse_duration("10S"), None);
+    }
+}
@@ -0,0 +1,360 @@
+//! HLS playlist parsing
+//!
+//! Parses master playlists (variant streams and alternative renditions)
+//! and media playlists (segment lists) as described in RFC 8216.
+
+use super::{ByteRange, InitSection, Segment, SegmentList};
+use crate::utils::error::{CCPlayerError, Result};
+use std::collections::HashMap;
+use url::Url;
+
+/// Variant stream from an `#EXT-X-STREAM-INF` tag
+#[derive(Debug, Clone)]
+pub(crate) struct HlsVariant {
+    /// Media playlist URL
+    pub uri: Url,
+
+    /// Peak bandwidth in bits per second
+    pub bandwidth: u64,
+
+    /// Video resolution
+    pub resolution: Option<(u32, u32)>,
+
+    /// Frame rate
+    pub frame_rate: Option<f32>,
+
+    /// Codec string
+    pub codecs: Option<String>,
+
+    /// Audio rendition group
+    pub audio_group: Option<String>,
+}
+
+/// Alternative rendition from an `#EXT-X-MEDIA` tag
+#[derive(Debug, Clone)]
+pub(crate) struct HlsRendition {
+    /// Rendition type (AUDIO, VIDEO, SUBTITLES, CLOSED-CAPTIONS)
+    pub media_type: String,
+
+    /// Group the rendition belongs to
+    pub group_id: String,
+
+    /// Human-readable name
+    pub name: String,
+
+    /// Language tag
+    pub language: Option<String>,
+
+    /// Media playlist URL (None if carried in the variant stream)
+    pub uri: Option<Url>,
+
+    /// Whether this is the group's default rendition
+    pub default: bool,
+}
+
+/// Parsed master playlist
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MasterPlaylist {
+    /// Variant streams in playlist order
+    pub variants: Vec<HlsVariant>,
+
+    /// Alternative renditions
+    pub renditions: Vec<HlsRendition>,
+}
+
+impl MasterPlaylist {
+    /// Pick the audio rendition of a group (default first)
+    pub fn audio_rendition(&self, group_id: &str) -> Option<&HlsRendition> {
+        let mut group = self
+            .renditions
+            .iter()
+            .filter(|r| r.media_type == "AUDIO" && r.group_id == group_id);
+
+        let first = group.clone().next();
+        group.find(|r| r.default).or(first)
+    }
+}
+
+/// Check whether playlist text is a master playlist
+pub(crate) fn is_master_playlist(text: &str) -> bool {
+    text.lines().any(|line| line.trim_start().starts_with("#EXT-X-STREAM-INF"))
+}
+
+/// Parse a master playlist
+pub(crate) fn parse_master_playlist(text: &str, base: &Url) -> Result<MasterPlaylist> {
+    check_header(text)?;
+
+    let mut playlist = MasterPlaylist::default();
+    let mut pending: Option<HashMap<String, String>> = None;
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(attrs) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            pending = Some(parse_attributes(attrs));
+        } else if let Some(attrs) = line.strip_prefix("#EXT-X-MEDIA:") {
+            let attrs = parse_attributes(attrs);
+            playlist.renditions.push(HlsRendition {
+                media_type: attrs.get("TYPE").cloned().unwrap_or_default(),
+                group_id: attrs.get("GROUP-ID").cloned().unwrap_or_default(),
+                name: attrs.get("NAME").cloned().unwrap_or_default(),
+                language: attrs.get("LANGUAGE").cloned(),
+                uri: match attrs.get("URI") {
+                    Some(uri) => Some(resolve(base, uri)?),
+                    None => None,
+                },
+                default: attrs.get("DEFAULT").is_some_and(|v| v == "YES"),
+            });
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some(attrs) = pending.take() {
+            playlist.variants.push(HlsVariant {
+                uri: resolve(base, line)?,
+                bandwidth: attrs
+                    .get("BANDWIDTH")
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| CCPlayerError::InvalidInput("EXT-X-STREAM-INF without BANDWIDTH".to_string()))?,
+                resolution: attrs.get("RESOLUTION").and_then(|v| parse_resolution(v)),
+                frame_rate: attrs.get("FRAME-RATE").and_then(|v| v.parse().ok()),
+                codecs: attrs.get("CODECS").cloned(),
+                audio_group: attrs.get("AUDIO").cloned(),
+            });
+        }
+    }
+
+    if playlist.variants.is_empty() {
+        return Err(CCPlayerError::InvalidInput("Master playlist has no variant streams".to_string()));
+    }
+
+    Ok(playlist)
+}
+
+/// Parse a media playlist into a segment list
+pub(crate) fn parse_media_playlist(text: &str, base: &Url) -> Result<SegmentList> {
+    check_header(text)?;
+
+    let mut list = SegmentList::default();
+    let mut sequence = 0u64;
+    let mut start = 0.0f64;
+    let mut duration: Option<f64> = None;
+    let mut byte_range: Option<ByteRange> = None;
+    let mut init: Option<InitSection> = None;
+    let mut discontinuity = false;
+    let mut last_range_end: HashMap<String, u64> = HashMap::new();
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(value) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            list.target_duration = value.parse().unwrap_or(0.0);
+        } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            sequence = value
+                .parse()
+                .map_err(|_| CCPlayerError::InvalidInput(format!("Invalid media sequence: {}", value)))?;
+        } else if let Some(value) = line.strip_prefix("#EXTINF:") {
+            let value = value.split(',').next().unwrap_or("");
+            duration = Some(
+                value
+                    .parse()
+                    .map_err(|_| CCPlayerError::InvalidInput(format!("Invalid EXTINF duration: {}", value)))?,
+            );
+        } else if let Some(value) = line.strip_prefix("#EXT-X-BYTERANGE:") {
+            byte_range = Some(parse_byte_range(value, None)?);
+        } else if let Some(attrs) = line.strip_prefix("#EXT-X-MAP:") {
+            let attrs = parse_attributes(attrs);
+            let uri = attrs
+                .get("URI")
+                .ok_or_else(|| CCPlayerError::InvalidInput("EXT-X-MAP without URI".to_string()))?;
+            init = Some(InitSection {
+                url: resolve(base, uri)?.to_string(),
+                byte_range: match attrs.get("BYTERANGE") {
+                    Some(range) => Some(parse_byte_range(range, Some(0))?),
+                    None => None,
+                },
+            });
+        } else if let Some(attrs) = line.strip_prefix("#EXT-X-KEY:") {
+            let attrs = parse_attributes(attrs);
+            if attrs.get("METHOD").is_some_and(|m| m != "NONE") {
+                return Err(CCPlayerError::UnsupportedFormat("Encrypted HLS segments are not supported".to_string()));
+            }
+        } else if line == "#EXT-X-DISCONTINUITY" {
+            discontinuity = true;
+        } else if line == "#EXT-X-ENDLIST" {
+            list.complete = true;
+        } else if line.starts_with('#') {
+            continue;
+        } else {
+            let url = resolve(base, line)?.to_string();
+            let segment_duration = duration.take().unwrap_or(list.target_duration);
+
+            // A byte range without offset continues where the previous one ended
+            let range = byte_range.take().map(|mut range| {
+                if range.offset == u64::MAX {
+                    range.offset = last_range_end.get(&url).copied().unwrap_or(0);
+                }
+                last_range_end.insert(url.clone(), range.offset + range.length);
+                range
+            });
+
+            list.segments.push(Segment {
+                url,
+                byte_range: range,
+                duration: segment_duration,
+                start,
+                sequence,
+                init: init.clone(),
+                discontinuity,
+            });
+
+            start += segment_duration;
+            sequence += 1;
+            discontinuity = false;
+        }
+    }
+
+    Ok(list)
+}
+
+/// Verify the `#EXTM3U` header
+fn check_header(text: &str) -> Result<()> {
+    if text.trim_start().starts_with("#EXTM3U") {
+        Ok(())
+    } else {
+        Err(CCPlayerError::UnsupportedFormat("Not an HLS playlist (missing #EXTM3U)".to_string()))
+    }
+}
+
+/// Resolve a playlist URI against the playlist URL
+fn resolve(base: &Url, uri: &str) -> Result<Url> {
+    base.join(uri)
+        .map_err(|e| CCPlayerError::InvalidInput(format!("Invalid playlist URI {}: {}", uri, e)))
+}
+
+/// Parse an attribute list (`KEY=value,KEY="quoted, value"`)
+fn parse_attributes(text: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        let Some(eq) = rest.find('=') else { break };
+        let key = rest[..eq].trim().to_string();
+        rest = &rest[eq + 1..];
+
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let value = quoted[..end].to_string();
+            rest = quoted.get(end + 1..).unwrap_or("");
+            value
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = rest[..end].trim().to_string();
+            rest = &rest[end..];
+            value
+        };
+
+        attributes.insert(key, value);
+        rest = rest.trim_start_matches(',').trim_start();
+    }
+
+    attributes
+}
+
+/// Parse `WIDTHxHEIGHT`
+fn parse_resolution(text: &str) -> Option<(u32, u32)> {
+    let (width, height) = text.split_once(['x', 'X'])?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Parse `length[@offset]`; a missing offset is `default_offset` or "continue"
+fn parse_byte_range(text: &str, default_offset: Option<u64>) -> Result<ByteRange> {
+    let invalid = || CCPlayerError::InvalidInput(format!("Invalid byte range: {}", text));
+
+    let (length, offset) = match text.split_once('@') {
+        Some((length, offset)) => (length, Some(offset.parse().map_err(|_| invalid())?)),
+        None => (text, default_offset),
+    };
+
+    Ok(ByteRange {
+        offset: offset.unwrap_or(u64::MAX),
+        length: length.parse().map_err(|_| invalid())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://cdn.example.com/show/master.m3u8").unwrap()
+    }
+
+    #[test]
+    fn test_parse_master_playlist() {
+        let text = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,URI=\"audio/en.m3u8\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,CODECS=\"avc1.4d401e,mp4a.40.2\",AUDIO=\"aac\"\n\
+            low/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080,FRAME-RATE=29.970,AUDIO=\"aac\"\n\
+            https://other.example.com/high/index.m3u8\n";
+
+        let master = parse_master_playlist(text, &base()).unwrap();
+        assert_eq!(master.variants.len(), 2);
+        assert_eq!(master.variants[0].uri.as_str(), "https://cdn.example.com/show/low/index.m3u8");
+        assert_eq!(master.variants[0].codecs.as_deref(), Some("avc1.4d401e,mp4a.40.2"));
+        assert_eq!(master.variants[1].resolution, Some((1920, 1080)));
+        assert_eq!(master.variants[1].uri.host_str(), Some("other.example.com"));
+
+        let audio = master.audio_rendition("aac").unwrap();
+        assert_eq!(audio.language.as_deref(), Some("en"));
+        assert_eq!(audio.uri.as_ref().unwrap().as_str(), "https://cdn.example.com/show/audio/en.m3u8");
+    }
+
+    #[test]
+    fn test_parse_media_playlist() {
+        let text = "#EXTM3U\n\
+            #EXT-X-TARGETDURATION:6\n\
+            #EXT-X-MEDIA-SEQUENCE:100\n\
+            #EXTINF:6.0,\n\
+            seg100.ts\n\
+            #EXTINF:5.5,\n\
+            seg101.ts\n\
+            #EXT-X-DISCONTINUITY\n\
+            #EXTINF:4.0,\n\
+            seg102.ts\n";
+
+        let list = parse_media_playlist(text, &base()).unwrap();
+        assert!(!list.complete);
+        assert_eq!(list.target_duration, 6.0);
+        assert_eq!(list.segments.len(), 3);
+        assert_eq!(list.segments[2].sequence, 102);
+        assert_eq!(list.segments[2].start, 11.5);
+        assert!(list.segments[2].discontinuity);
+        assert!(!list.segments[1].discontinuity);
+    }
+
+    #[test]
+    fn test_parse_byte_ranges_and_map() {
+        let text = "#EXTM3U\n\
+            #EXT-X-TARGETDURATION:4\n\
+            #EXT-X-MAP:URI=\"video.mp4\",BYTERANGE=\"720@0\"\n\
+            #EXTINF:4,\n\
+            #EXT-X-BYTERANGE:1000@720\n\
+            video.mp4\n\
+            #EXTINF:4,\n\
+            #EXT-X-BYTERANGE:2000\n\
+            video.mp4\n\
+            #EXT-X-ENDLIST\n";
+
+        let list = parse_media_playlist(text, &base()).unwrap();
+        assert!(list.complete);
+
+        let init = list.segments[0].init.as_ref().unwrap();
+        assert_eq!(init.byte_range, Some(ByteRange { offset: 0, length: 720 }));
+        assert_eq!(list.segments[1].byte_range, Some(ByteRange { offset: 1720, length: 2000 }));
+    }
+
+    #[test]
+    fn test_encrypted_playlist_rejected() {
+        let text = "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"\n#EXTINF:4,\nseg.ts\n";
+        assert!(matches!(
+            parse_media_playlist(text, &base()),
+            Err(CCPlayerError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,56 @@
+//! HTTP client for manifests and segments
+//!
+//! Thin blocking wrapper around ureq that maps failures to network errors.
+
+use super::ByteRange;
+use crate::utils::error::{IntoPlayerError, Result};
+use std::io::Read;
+use std::time::Duration;
+
+/// Blocking HTTP client shared by a download session
+#[derive(Clone)]
+pub(crate) struct HttpClient {
+    /// Connection-pooling agent
+    agent: ureq::Agent,
+}
+
+impl HttpClient {
+    /// Create a client with the given connect/read timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(timeout)
+                .timeout_read(timeout)
+                .user_agent(concat!("CCPlayer/", env!("CARGO_PKG_VERSION")))
+                .build(),
+        }
+    }
+
+    /// Fetch a text resource (playlist or manifest)
+    pub fn get_text(&self, url: &str) -> Result<String> {
+        self.agent
+            .get(url)
+            .call()
+            .network_err(&format!("GET {}", url))?
+            .into_string()
+            .network_err(&format!("Failed to read {}", url))
+    }
+
+    /// Fetch a binary resource, optionally a byte range of it
+    pub fn get_bytes(&self, url: &str, range: Option<ByteRange>) -> Result<Vec<u8>> {
+        let mut request = self.agent.get(url);
+        if let Some(range) = range {
+            request = request.set("Range", &range.header_value());
+        }
+
+        let response = request.call().network_err(&format!("GET {}", url))?;
+
+        let mut data = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut data)
+            .network_err(&format!("Failed to read {}", url))?;
+
+        Ok(data)
+    }
+}
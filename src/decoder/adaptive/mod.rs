@@ -0,0 +1,1006 @@
+//! Adaptive streaming (HLS and DASH)
+//!
+//! Parses HLS master playlists and DASH MPDs, downloads media segments
+//! itself and hands them to FFmpeg as one continuous byte stream. Variant
+//! switches happen at segment boundaries based on measured throughput,
+//! unless the user pins a quality.
+
+mod abr;
+mod dash;
+mod hls;
+mod http;
+
+pub use abr::{ThroughputEstimator, VariantSelector};
+
+use crate::decoder::{QualitySelection, VariantInfo};
+use crate::utils::error::{CCPlayerError, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError, SendTimeoutError, Sender};
+use parking_lot::Mutex;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use self::http::HttpClient;
+
+/// Interval at which blocked threads re-check for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of segments from the live edge where live playback starts
+const LIVE_EDGE_SEGMENTS: usize = 3;
+
+/// Check whether a URL points at an HLS playlist or DASH manifest
+pub fn is_adaptive_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_ascii_lowercase();
+    path.ends_with(".m3u8") || path.ends_with(".m3u") || path.ends_with(".mpd")
+}
+
+/// Adaptive streaming configuration
+#[derive(Debug, Clone)]
+pub struct AdaptiveConfig {
+    /// Number of downloaded segments buffered ahead of the demuxer
+    pub prefetch_segments: usize,
+
+    /// Initial quality selection
+    pub quality: QualitySelection,
+
+    /// Fraction of the measured throughput a variant may use
+    pub safety_factor: f64,
+
+    /// Timeout for individual HTTP requests
+    pub request_timeout: Duration,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        Self {
+            prefetch_segments: 3,
+            quality: QualitySelection::Auto,
+            safety_factor: 0.8,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Byte range within a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ByteRange {
+    /// Offset of the first byte
+    pub offset: u64,
+
+    /// Number of bytes
+    pub length: u64,
+}
+
+impl ByteRange {
+    /// Value for an HTTP `Range` header
+    pub fn header_value(&self) -> String {
+        format!("bytes={}-{}", self.offset, self.offset + self.length.saturating_sub(1))
+    }
+}
+
+/// Initialization section (fMP4 `moov`) that precedes media segments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InitSection {
+    /// Resource URL
+    pub url: String,
+
+    /// Byte range within the resource
+    pub byte_range: Option<ByteRange>,
+}
+
+/// A downloadable media segment
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Segment {
+    /// Resource URL
+    pub url: String,
+
+    /// Byte range within the resource
+    pub byte_range: Option<ByteRange>,
+
+    /// Duration in seconds
+    pub duration: f64,
+
+    /// Start time in seconds from the start of the list
+    pub start: f64,
+
+    /// Sequence number (HLS media sequence or DASH segment number)
+    pub sequence: u64,
+
+    /// Initialization section this segment depends on
+    pub init: Option<InitSection>,
+
+    /// Whether a timestamp discontinuity precedes this segment
+    pub discontinuity: bool,
+}
+
+/// Segment list of a single variant at one point in time
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SegmentList {
+    /// Segments in playback order
+    pub segments: Vec<Segment>,
+
+    /// Whether the list is final (VOD) or grows over time (live)
+    pub complete: bool,
+
+    /// Maximum segment duration in seconds
+    pub target_duration: f64,
+}
+
+impl SegmentList {
+    /// Find a segment by sequence number
+    pub fn find(&self, sequence: u64) -> Option<&Segment> {
+        self.segments.iter().find(|s| s.sequence == sequence)
+    }
+
+    /// Find the segment that contains the given time
+    pub fn segment_at(&self, time: f64) -> Option<&Segment> {
+        self.segments
+            .iter()
+            .find(|s| time < s.start + s.duration)
+    }
+
+    /// Sequence number where playback of this list starts
+    pub fn initial_sequence(&self) -> Option<u64> {
+        let index = if self.complete {
+            0
+        } else {
+            self.segments.len().saturating_sub(LIVE_EDGE_SEGMENTS)
+        };
+
+        self.segments.get(index).map(|s| s.sequence)
+    }
+
+    /// Total duration of all segments in seconds
+    pub fn duration(&self) -> f64 {
+        self.segments.iter().map(|s| s.duration).sum()
+    }
+
+    /// Start and end time of the list in seconds
+    pub fn time_range(&self) -> Option<(f64, f64)> {
+        let first = self.segments.first()?;
+        let last = self.segments.last()?;
+        Some((first.start, last.start + last.duration))
+    }
+
+    /// Shift start times so segments shared with `previous` keep theirs
+    ///
+    /// Live playlists count time from their first segment, which moves on
+    /// every reload; aligning keeps a single timeline for the session.
+    pub fn align_to(&mut self, previous: &SegmentList) {
+        let shared = self
+            .segments
+            .iter()
+            .find_map(|s| previous.find(s.sequence).map(|p| p.start - s.start));
+
+        let shift = match shared {
+            Some(shift) => shift,
+            // The list moved past everything we knew: continue after it
+            None => match (previous.segments.last(), self.segments.first()) {
+                (Some(last), Some(first)) if first.sequence > last.sequence => last.start + last.duration - first.start,
+                _ => return,
+            },
+        };
+
+        for segment in &mut self.segments {
+            segment.start += shift;
+        }
+    }
+}
+
+/// Where a variant's segment list comes from
+#[derive(Debug, Clone)]
+enum VariantSource {
+    /// HLS media playlist
+    HlsPlaylist(Url),
+
+    /// DASH representation (re-read from the MPD when dynamic)
+    DashRepresentation {
+        mpd_url: Url,
+        id: String,
+        segments: SegmentList,
+    },
+}
+
+/// A variant together with its segment source
+#[derive(Debug, Clone)]
+struct Variant {
+    /// Public description
+    info: VariantInfo,
+
+    /// Segment source
+    source: VariantSource,
+}
+
+impl Variant {
+    /// Fetch (or reuse) the current segment list
+    fn load_segments(&self, http: &HttpClient) -> Result<SegmentList> {
+        match &self.source {
+            VariantSource::HlsPlaylist(url) => {
+                let text = http.get_text(url.as_str())?;
+                hls::parse_media_playlist(&text, url)
+            }
+            VariantSource::DashRepresentation { mpd_url, id, segments } => {
+                if segments.complete {
+                    return Ok(segments.clone());
+                }
+
+                let text = http.get_text(mpd_url.as_str())?;
+                let manifest = dash::parse_mpd(&text, mpd_url)?;
+                manifest
+                    .representations
+                    .into_iter()
+                    .find(|r| &r.id == id)
+                    .map(|r| r.segments)
+                    .ok_or_else(|| CCPlayerError::NotFound(format!("Representation {} vanished from MPD", id)))
+            }
+        }
+    }
+}
+
+/// Data passed from the download thread to the reader
+enum Chunk {
+    /// Media bytes
+    Data(Vec<u8>),
+
+    /// A new initialization section follows; the demuxer must be reopened
+    Reinit,
+
+    /// The stream ended
+    End,
+
+    /// Downloading failed
+    Error(String),
+}
+
+/// State shared between a session, its reader and its download thread
+struct SessionShared {
+    /// Public variant descriptions
+    variants: Vec<VariantInfo>,
+
+    /// Variant currently being downloaded
+    current: Mutex<Option<usize>>,
+
+    /// Requested quality
+    selection: Mutex<QualitySelection>,
+
+    /// Throughput measurements
+    estimator: Mutex<ThroughputEstimator>,
+
+    /// Total duration in seconds (known once a complete list was loaded)
+    duration: Mutex<Option<f64>>,
+
+    /// Time range of the current list in seconds (live lists only)
+    live_window: Mutex<Option<(f64, f64)>>,
+
+    /// Seek generation and start time of the first segment it delivered
+    stream_start: Mutex<Option<(u64, f64)>>,
+
+    /// Pending seek target in seconds
+    seek_request: Mutex<Option<f64>>,
+
+    /// Incremented on every seek; chunks from older generations are discarded
+    generation: AtomicU64,
+
+    /// Set when the reader hit a reinit marker
+    reopen_pending: AtomicBool,
+
+    /// Set when the session is dropped
+    shutdown: AtomicBool,
+}
+
+/// One adaptive download session (video+audio or a single rendition)
+pub struct AdaptiveSession {
+    /// Shared state
+    shared: Arc<SessionShared>,
+
+    /// Receiving end of the segment channel
+    receiver: Receiver<(u64, Chunk)>,
+
+    /// Download thread
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl AdaptiveSession {
+    /// Start downloading segments of the given variants
+    fn start(variants: Vec<Variant>, config: &AdaptiveConfig) -> Result<Self> {
+        if variants.is_empty() {
+            return Err(CCPlayerError::UnsupportedFormat("Manifest contains no playable variants".to_string()));
+        }
+
+        let shared = Arc::new(SessionShared {
+            variants: variants.iter().map(|v| v.info.clone()).collect(),
+            current: Mutex::new(None),
+            selection: Mutex::new(config.quality),
+            estimator: Mutex::new(ThroughputEstimator::new()),
+            duration: Mutex::new(None),
+            live_window: Mutex::new(None),
+            stream_start: Mutex::new(None),
+            seek_request: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            reopen_pending: AtomicBool::new(false),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let (sender, receiver) = crossbeam_channel::bounded(config.prefetch_segments.max(1));
+
+        let worker = SegmentWorker {
+            variants,
+            shared: shared.clone(),
+            http: HttpClient::new(config.request_timeout),
+            selector: VariantSelector::new(config.safety_factor),
+            sender,
+            active: None,
+            segments: None,
+            next_sequence: None,
+            position: 0.0,
+            sent_init: None,
+            generation: 0,
+        };
+
+        let worker = thread::Builder::new()
+            .name("adaptive-download".to_string())
+            .spawn(move || worker.run())
+            .map_err(|e| CCPlayerError::Internal(format!("Failed to spawn download thread: {}", e)))?;
+
+        Ok(Self {
+            shared,
+            receiver,
+            worker: Some(worker),
+        })
+    }
+
+    /// Create a reader that yields the downloaded byte stream
+    pub fn reader(&self) -> AdaptiveReader {
+        self.shared.reopen_pending.store(false, Ordering::Release);
+
+        AdaptiveReader {
+            receiver: self.receiver.clone(),
+            shared: self.shared.clone(),
+            generation: self.shared.generation.load(Ordering::Acquire),
+            buffer: Vec::new(),
+            offset: 0,
+            finished: false,
+        }
+    }
+
+    /// Available quality variants
+    pub fn variants(&self) -> &[VariantInfo] {
+        &self.shared.variants
+    }
+
+    /// Variant currently being downloaded
+    pub fn current_variant(&self) -> Option<usize> {
+        *self.shared.current.lock()
+    }
+
+    /// Current quality selection
+    pub fn quality(&self) -> QualitySelection {
+        *self.shared.selection.lock()
+    }
+
+    /// Change the quality selection; takes effect at the next segment
+    pub fn set_quality(&self, selection: QualitySelection) -> Result<()> {
+        if let QualitySelection::Pinned(index) = selection {
+            if index >= self.shared.variants.len() {
+                return Err(CCPlayerError::InvalidInput(format!(
+                    "Variant {} out of range (0..{})",
+                    index,
+                    self.shared.variants.len()
+                )));
+            }
+        }
+
+        *self.shared.selection.lock() = selection;
+        Ok(())
+    }
+
+    /// Total duration of on-demand streams
+    pub fn duration(&self) -> Option<Duration> {
+        self.shared.duration.lock().map(Duration::from_secs_f64)
+    }
+
+    /// Whether the stream is live (its list keeps growing)
+    pub fn is_live(&self) -> bool {
+        self.shared.live_window.lock().is_some()
+    }
+
+    /// Time range currently listed by a live stream (the DVR window)
+    pub fn live_window(&self) -> Option<(Duration, Duration)> {
+        self.shared
+            .live_window
+            .lock()
+            .map(|(start, end)| (Duration::from_secs_f64(start.max(0.0)), Duration::from_secs_f64(end.max(0.0))))
+    }
+
+    /// Timeline position of the first data delivered since the last seek
+    ///
+    /// Relates the demuxer's timestamps to `live_window` and `seek`.
+    pub fn stream_start(&self) -> Option<Duration> {
+        let generation = self.shared.generation.load(Ordering::Acquire);
+        self.shared
+            .stream_start
+            .lock()
+            .filter(|(started, _)| *started == generation)
+            .map(|(_, start)| Duration::from_secs_f64(start.max(0.0)))
+    }
+
+    /// Estimated throughput in bits per second
+    pub fn throughput(&self) -> Option<f64> {
+        self.shared.estimator.lock().estimate()
+    }
+
+    /// Restart downloading at the given position
+    ///
+    /// Readers created before the seek stop receiving data; create a new
+    /// reader afterwards.
+    pub fn seek(&self, position: Duration) {
+        let mut request = self.shared.seek_request.lock();
+        self.shared.generation.fetch_add(1, Ordering::AcqRel);
+        *request = Some(position.as_secs_f64());
+    }
+
+    /// Check (and clear) whether the demuxer has to be reopened
+    pub fn take_reopen_request(&self) -> bool {
+        self.shared.reopen_pending.swap(false, Ordering::AcqRel)
+    }
+}
+
+impl Drop for AdaptiveSession {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Sessions for an opened manifest
+pub struct AdaptiveSource {
+    /// Video (or muxed audio+video) session
+    pub main: AdaptiveSession,
+
+    /// Separate audio rendition, if the manifest has one
+    pub audio: Option<AdaptiveSession>,
+}
+
+impl AdaptiveSource {
+    /// Download and parse a manifest and start the download sessions
+    pub fn open(url: &str, config: AdaptiveConfig) -> Result<Self> {
+        let manifest_url = Url::parse(url)
+            .map_err(|e| CCPlayerError::InvalidInput(format!("Invalid URL {}: {}", url, e)))?;
+
+        let http = HttpClient::new(config.request_timeout);
+        let text = http.get_text(url)?;
+
+        let (main, audio) = if dash::is_mpd(&text) {
+            Self::dash_variants(&text, &manifest_url)?
+        } else {
+            Self::hls_variants(&text, &manifest_url)?
+        };
+
+        log::info!(
+            "Opened adaptive stream {} with {} variant(s){}",
+            url,
+            main.len(),
+            if audio.is_some() { " and a separate audio rendition" } else { "" }
+        );
+
+        let audio_config = AdaptiveConfig {
+            quality: QualitySelection::Pinned(0),
+            ..config.clone()
+        };
+
+        Ok(Self {
+            main: AdaptiveSession::start(main, &config)?,
+            audio: match audio {
+                Some(variant) => Some(AdaptiveSession::start(vec![variant], &audio_config)?),
+                None => None,
+            },
+        })
+    }
+
+    /// Build variants from an HLS master or media playlist
+    fn hls_variants(text: &str, url: &Url) -> Result<(Vec<Variant>, Option<Variant>)> {
+        if !hls::is_master_playlist(text) {
+            // A plain media playlist is a single variant
+            let variant = Variant {
+                info: VariantInfo {
+                    index: 0,
+                    bandwidth: 0,
+                    resolution: None,
+                    frame_rate: None,
+                    codecs: None,
+                    name: None,
+                },
+                source: VariantSource::HlsPlaylist(url.clone()),
+            };
+            return Ok((vec![variant], None));
+        }
+
+        let master = hls::parse_master_playlist(text, url)?;
+
+        let variants = master
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(index, v)| Variant {
+                info: VariantInfo {
+                    index,
+                    bandwidth: v.bandwidth,
+                    resolution: v.resolution,
+                    frame_rate: v.frame_rate,
+                    codecs: v.codecs.clone(),
+                    name: None,
+                },
+                source: VariantSource::HlsPlaylist(v.uri.clone()),
+            })
+            .collect();
+
+        // Audio renditions with their own URI are downloaded separately
+        let audio = master
+            .variants
+            .first()
+            .and_then(|v| v.audio_group.as_ref())
+            .and_then(|group| master.audio_rendition(group))
+            .and_then(|rendition| {
+                rendition.uri.clone().map(|uri| Variant {
+                    info: VariantInfo {
+                        index: 0,
+                        bandwidth: 0,
+                        resolution: None,
+                        frame_rate: None,
+                        codecs: None,
+                        name: Some(rendition.name.clone()),
+                    },
+                    source: VariantSource::HlsPlaylist(uri),
+                })
+            });
+
+        Ok((variants, audio))
+    }
+
+    /// Build variants from a DASH MPD
+    fn dash_variants(text: &str, url: &Url) -> Result<(Vec<Variant>, Option<Variant>)> {
+        let manifest = dash::parse_mpd(text, url)?;
+
+        let to_variant = |index: usize, rep: &dash::DashRepresentation| Variant {
+            info: VariantInfo {
+                index,
+                bandwidth: rep.bandwidth,
+                resolution: rep.resolution,
+                frame_rate: rep.frame_rate,
+                codecs: rep.codecs.clone(),
+                name: Some(rep.id.clone()),
+            },
+            source: VariantSource::DashRepresentation {
+                mpd_url: url.clone(),
+                id: rep.id.clone(),
+                segments: rep.segments.clone(),
+            },
+        };
+
+        let video: Vec<_> = manifest.representations_of(dash::ContentKind::Video).collect();
+        let audio: Vec<_> = manifest.representations_of(dash::ContentKind::Audio).collect();
+
+        if video.is_empty() {
+            // Audio-only presentation: adapt between the audio representations
+            let variants = audio.iter().enumerate().map(|(i, r)| to_variant(i, r)).collect();
+            return Ok((variants, None));
+        }
+
+        let variants = video.iter().enumerate().map(|(i, r)| to_variant(i, r)).collect();
+        let audio = audio.first().map(|r| to_variant(0, r));
+
+        Ok((variants, audio))
+    }
+}
+
+/// Byte stream over downloaded segments
+///
+/// Returns end-of-file at the end of the stream and when the demuxer has to
+/// be reopened for a new initialization section.
+pub struct AdaptiveReader {
+    /// Receiving end of the segment channel
+    receiver: Receiver<(u64, Chunk)>,
+
+    /// Shared session state
+    shared: Arc<SessionShared>,
+
+    /// Seek generation this reader belongs to
+    generation: u64,
+
+    /// Current chunk
+    buffer: Vec<u8>,
+
+    /// Read position within the current chunk
+    offset: usize,
+
+    /// No more data will be returned
+    finished: bool,
+}
+
+impl Read for AdaptiveReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.offset < self.buffer.len() {
+                let count = buf.len().min(self.buffer.len() - self.offset);
+                buf[..count].copy_from_slice(&self.buffer[self.offset..self.offset + count]);
+                self.offset += count;
+                return Ok(count);
+            }
+
+            if self.finished || self.shared.shutdown.load(Ordering::Acquire) {
+                return Ok(0);
+            }
+
+            match self.receiver.recv_timeout(POLL_INTERVAL) {
+                Ok((generation, _)) if generation != self.generation => continue,
+                Ok((_, Chunk::Data(data))) => {
+                    self.buffer = data;
+                    self.offset = 0;
+                }
+                Ok((_, Chunk::Reinit)) => {
+                    self.finished = true;
+                    self.shared.reopen_pending.store(true, Ordering::Release);
+                    return Ok(0);
+                }
+                Ok((_, Chunk::End)) => {
+                    self.finished = true;
+                    return Ok(0);
+                }
+                Ok((_, Chunk::Error(message))) => {
+                    self.finished = true;
+                    return Err(io::Error::other(message));
+                }
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.finished = true;
+                    return Ok(0);
+                }
+            }
+        }
+    }
+}
+
+/// Download thread state
+struct SegmentWorker {
+    /// All variants of this session
+    variants: Vec<Variant>,
+
+    /// Shared session state
+    shared: Arc<SessionShared>,
+
+    /// HTTP client
+    http: HttpClient,
+
+    /// Throughput-based variant selection
+    selector: VariantSelector,
+
+    /// Sending end of the segment channel
+    sender: Sender<(u64, Chunk)>,
+
+    /// Variant whose segment list is loaded
+    active: Option<usize>,
+
+    /// Segment list of the active variant
+    segments: Option<SegmentList>,
+
+    /// Sequence number of the next segment to download
+    next_sequence: Option<u64>,
+
+    /// Presentation time of the next segment in seconds
+    position: f64,
+
+    /// Initialization section last sent downstream
+    sent_init: Option<InitSection>,
+
+    /// Seek generation of the data being downloaded
+    generation: u64,
+}
+
+impl SegmentWorker {
+    /// Thread entry point
+    fn run(mut self) {
+        if let Err(e) = self.download_loop() {
+            if !self.is_shutdown() {
+                log::warn!("Adaptive download failed: {}", e);
+                let _ = self.send(Chunk::Error(e.to_string()));
+            }
+        }
+    }
+
+    /// Download segments until the stream ends or the session is dropped
+    fn download_loop(&mut self) -> Result<()> {
+        while !self.is_shutdown() {
+            {
+                // Generation and seek target change together under this lock
+                let mut request = self.shared.seek_request.lock();
+                if let Some(position) = request.take() {
+                    self.position = position;
+                    self.next_sequence = self
+                        .segments
+                        .as_ref()
+                        .and_then(|list| list.segment_at(position))
+                        .map(|s| s.sequence);
+                    self.sent_init = None;
+                }
+                self.generation = self.shared.generation.load(Ordering::Acquire);
+            }
+
+            let target = self.choose_variant();
+            if self.active != Some(target) {
+                self.switch_to(target)?;
+            }
+
+            let segment = match self.next_segment() {
+                Some(segment) => segment,
+                None => {
+                    let list = self.segments.as_ref().expect("segment list loaded");
+                    if list.complete {
+                        self.send(Chunk::End)?;
+
+                        // Stay alive so a later seek can restart downloading
+                        while !self.is_shutdown() && self.shared.seek_request.lock().is_none() {
+                            thread::sleep(POLL_INTERVAL);
+                        }
+                        continue;
+                    }
+
+                    // Live list ran dry: wait for the playlist to grow
+                    let refresh = Duration::from_secs_f64((list.target_duration / 2.0).max(0.5));
+                    self.sleep(refresh);
+                    self.reload()?;
+                    continue;
+                }
+            };
+
+            self.fetch_segment(&segment)?;
+        }
+
+        Ok(())
+    }
+
+    /// Decide which variant the next segment comes from
+    fn choose_variant(&self) -> usize {
+        let selection = *self.shared.selection.lock();
+        let estimate = self.shared.estimator.lock().estimate();
+        self.selector.choose(selection, &self.shared.variants, self.active, estimate)
+    }
+
+    /// Switch to another variant at the current segment boundary
+    fn switch_to(&mut self, target: usize) -> Result<()> {
+        let mut list = self.variants[target].load_segments(&self.http)?;
+        if let Some(previous) = &self.segments {
+            list.align_to(previous);
+        }
+
+        self.next_sequence = match self.next_sequence {
+            // Variants normally share sequence numbers; fall back to time
+            Some(sequence) if list.find(sequence).is_some() => Some(sequence),
+            Some(_) => list.segment_at(self.position).map(|s| s.sequence),
+            None if self.position > 0.0 => list.segment_at(self.position).map(|s| s.sequence),
+            None => list.initial_sequence(),
+        };
+
+        if let Some(previous) = self.active {
+            log::info!(
+                "Switching variant {} -> {} ({} bps)",
+                previous,
+                target,
+                self.variants[target].info.bandwidth
+            );
+        }
+
+        if list.complete {
+            *self.shared.duration.lock() = Some(list.duration());
+        }
+
+        self.active = Some(target);
+        self.set_segments(list);
+        *self.shared.current.lock() = Some(target);
+
+        Ok(())
+    }
+
+    /// Install a new segment list and publish its live window
+    fn set_segments(&mut self, list: SegmentList) {
+        *self.shared.live_window.lock() = if list.complete { None } else { list.time_range() };
+        self.segments = Some(list);
+    }
+
+    /// Re-read the segment list of the active variant (live streams)
+    fn reload(&mut self) -> Result<()> {
+        if let Some(active) = self.active {
+            let mut list = self.variants[active].load_segments(&self.http)?;
+            if let Some(previous) = &self.segments {
+                list.align_to(previous);
+            }
+
+            if self.next_sequence.is_none() {
+                self.next_sequence = list.initial_sequence();
+            }
+
+            self.set_segments(list);
+        }
+
+        Ok(())
+    }
+
+    /// The next segment to download, if the list already contains it
+    fn next_segment(&self) -> Option<Segment> {
+        let list = self.segments.as_ref()?;
+        let sequence = self.next_sequence?;
+
+        // Live lists may have moved past a stale position
+        let first = list.segments.first()?.sequence;
+        list.find(sequence.max(first)).cloned()
+    }
+
+    /// Download a segment (and its init section) and pass it on
+    fn fetch_segment(&mut self, segment: &Segment) -> Result<()> {
+        if segment.init != self.sent_init {
+            if let Some(init) = &segment.init {
+                let data = self.http.get_bytes(&init.url, init.byte_range)?;
+
+                // fMP4 cannot change its init section mid-stream
+                if self.sent_init.is_some() {
+                    self.send(Chunk::Reinit)?;
+                }
+
+                self.send(Chunk::Data(data))?;
+            }
+
+            self.sent_init = segment.init.clone();
+        } else if segment.discontinuity && self.sent_media() {
+            // Timestamps start over (ad breaks, stitched streams); a fresh
+            // demuxer picks up the new ones
+            self.send(Chunk::Reinit)?;
+        }
+
+        let started = Instant::now();
+        let data = self.http.get_bytes(&segment.url, segment.byte_range)?;
+        self.shared.estimator.lock().add_sample(data.len(), started.elapsed());
+
+        {
+            let mut stream_start = self.shared.stream_start.lock();
+            if stream_start.is_none_or(|(generation, _)| generation != self.generation) {
+                *stream_start = Some((self.generation, segment.start));
+            }
+        }
+
+        self.next_sequence = Some(segment.sequence + 1);
+        self.position = segment.start + segment.duration;
+
+        self.send(Chunk::Data(data))
+    }
+
+    /// Whether media of the current seek generation was sent downstream
+    fn sent_media(&self) -> bool {
+        self.shared.stream_start.lock().is_some_and(|(generation, _)| generation == self.generation)
+    }
+
+    /// Send a chunk, waiting while the channel is full
+    fn send(&self, chunk: Chunk) -> Result<()> {
+        let mut message = (self.generation, chunk);
+
+        loop {
+            if self.is_shutdown() {
+                return Ok(());
+            }
+
+            match self.sender.send_timeout(message, POLL_INTERVAL) {
+                Ok(()) => return Ok(()),
+                Err(SendTimeoutError::Timeout(returned)) => {
+                    // A seek makes queued data worthless
+                    if self.shared.seek_request.lock().is_some() {
+                        return Ok(());
+                    }
+                    message = returned;
+                }
+                Err(SendTimeoutError::Disconnected(_)) => {
+                    return Err(CCPlayerError::Internal("Adaptive reader disconnected".to_string()));
+                }
+            }
+        }
+    }
+
+    /// Sleep, waking early on shutdown or seek
+    fn sleep(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline && !self.is_shutdown() && self.shared.seek_request.lock().is_none() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Check whether the session was dropped
+    fn is_shutdown(&self) -> bool {
+        self.shared.shutdown.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(sequence: u64, start: f64) -> Segment {
+        Segment {
+            url: format!("http://example.com/{}.ts", sequence),
+            byte_range: None,
+            duration: 4.0,
+            start,
+            sequence,
+            init: None,
+            discontinuity: false,
+        }
+    }
+
+    #[test]
+    fn test_is_adaptive_url() {
+        assert!(is_adaptive_url("https://example.com/live/master.m3u8"));
+        assert!(is_adaptive_url("https://example.com/vod/manifest.MPD?token=abc"));
+        assert!(!is_adaptive_url("https://example.com/video.mp4"));
+        assert!(!is_adaptive_url("rtsp://camera.local/stream"));
+    }
+
+    #[test]
+    fn test_byte_range_header() {
+        let range = ByteRange { offset: 100, length: 50 };
+        assert_eq!(range.header_value(), "bytes=100-149");
+    }
+
+    #[test]
+    fn test_segment_list_lookup() {
+        let list = SegmentList {
+            segments: (0..5).map(|i| segment(10 + i, i as f64 * 4.0)).collect(),
+            complete: true,
+            target_duration: 4.0,
+        };
+
+        assert_eq!(list.find(12).map(|s| s.start), Some(8.0));
+        assert_eq!(list.segment_at(9.5).map(|s| s.sequence), Some(12));
+        assert!(list.segment_at(25.0).is_none());
+        assert_eq!(list.initial_sequence(), Some(10));
+        assert_eq!(list.duration(), 20.0);
+    }
+
+    #[test]
+    fn test_live_list_starts_near_edge() {
+        let list = SegmentList {
+            segments: (0..6).map(|i| segment(i, i as f64 * 4.0)).collect(),
+            complete: false,
+            target_duration: 4.0,
+        };
+
+        assert_eq!(list.initial_sequence(), Some(3));
+    }
+
+    #[test]
+    fn test_live_list_alignment() {
+        let previous = SegmentList {
+            segments: (0..4).map(|i| segment(100 + i, i as f64 * 4.0)).collect(),
+            complete: false,
+            target_duration: 4.0,
+        };
+
+        // Reloaded list dropped two segments and gained two, counting from 0 again
+        let mut reloaded = SegmentList {
+            segments: (0..4).map(|i| segment(102 + i, i as f64 * 4.0)).collect(),
+            ..previous.clone()
+        };
+        reloaded.align_to(&previous);
+        assert_eq!(reloaded.time_range(), Some((8.0, 24.0)));
+        assert_eq!(reloaded.segment_at(13.0).map(|s| s.sequence), Some(103));
+
+        // A list that skipped ahead continues after the previous one
+        let mut skipped = SegmentList {
+            segments: (0..2).map(|i| segment(110 + i, i as f64 * 4.0)).collect(),
+            ..previous.clone()
+        };
+        skipped.align_to(&previous);
+        assert_eq!(skipped.time_range(), Some((16.0, 24.0)));
+    }
+}
@@ -0,0 +1,493 @@
+//! Packet-level stream analysis
+//!
+//! Looks at demuxed packets only, without decoding: bitrate over time,
+//! keyframe positions and GOP structure, and timestamp problems such as
+//! non-monotonic DTS or gaps. The same byte counting gives the live
+//! bitrate figures during playback.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+/// A demuxed packet, reduced to what the analysis needs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PacketInfo {
+    /// Stream index
+    pub stream: usize,
+
+    /// Payload size in bytes
+    pub size: usize,
+
+    /// Presentation timestamp in microseconds
+    pub pts: Option<i64>,
+
+    /// Decoding timestamp in microseconds
+    pub dts: Option<i64>,
+
+    /// Duration in microseconds (0 if unknown)
+    pub duration: i64,
+
+    /// Whether the packet starts a keyframe
+    pub keyframe: bool,
+}
+
+impl PacketInfo {
+    /// Timestamp used for ordering: DTS, or PTS when there is none
+    pub fn time(&self) -> Option<i64> {
+        self.dts.or(self.pts)
+    }
+}
+
+/// Kind of timestamp problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// DTS did not increase
+    NonMonotonicDts,
+
+    /// PTS went backwards in a stream without frame reordering
+    NonMonotonicPts,
+
+    /// PTS earlier than DTS
+    PtsBeforeDts,
+
+    /// Packet without any timestamp
+    MissingTimestamp,
+
+    /// Time between packets longer than the gap threshold
+    Gap,
+}
+
+impl AnomalyKind {
+    /// Name as used in reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnomalyKind::NonMonotonicDts => "non_monotonic_dts",
+            AnomalyKind::NonMonotonicPts => "non_monotonic_pts",
+            AnomalyKind::PtsBeforeDts => "pts_before_dts",
+            AnomalyKind::MissingTimestamp => "missing_timestamp",
+            AnomalyKind::Gap => "gap",
+        }
+    }
+}
+
+/// A timestamp problem at one packet
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Anomaly {
+    /// Packet number within its stream
+    pub packet: u64,
+
+    /// Packet time in seconds (previous packet's time if it has none)
+    pub time: f64,
+
+    /// Kind of problem
+    pub kind: AnomalyKind,
+
+    /// Offending step in seconds (backwards jump or gap length)
+    pub delta: f64,
+}
+
+/// Analysis of one stream
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StreamAnalysis {
+    /// Stream index
+    pub index: usize,
+
+    /// Media type ("video", "audio", "subtitle", ...)
+    pub kind: String,
+
+    /// Codec name
+    pub codec: String,
+
+    /// Number of packets
+    pub packets: u64,
+
+    /// Total payload in bytes
+    pub bytes: u64,
+
+    /// Time from the first packet to the end of the last, in seconds
+    pub duration: f64,
+
+    /// Average bitrate in bits per second
+    pub average_bitrate: u64,
+
+    /// Highest one-second bitrate in bits per second
+    pub peak_bitrate: u64,
+
+    /// Bits in each second of the file, from the first second of any stream
+    pub bitrate_timeline: Vec<u64>,
+
+    /// Keyframe times in seconds
+    pub keyframes: Vec<f64>,
+
+    /// Packets from each keyframe to the next (video only)
+    pub gop_lengths: Vec<u32>,
+
+    /// Timestamp problems
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl StreamAnalysis {
+    /// Shortest, longest and mean GOP length
+    pub fn gop_summary(&self) -> Option<(u32, u32, f64)> {
+        let min = *self.gop_lengths.iter().min()?;
+        let max = *self.gop_lengths.iter().max()?;
+        let mean = self.gop_lengths.iter().map(|length| *length as f64).sum::<f64>() / self.gop_lengths.len() as f64;
+        Some((min, max, mean))
+    }
+}
+
+/// Analysis of a whole file
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AnalysisReport {
+    /// File path or URL
+    pub source: String,
+
+    /// Time of the first bitrate timeline entry, in seconds
+    pub start_time: f64,
+
+    /// Per-stream results, by stream index
+    pub streams: Vec<StreamAnalysis>,
+}
+
+/// Running state of one stream
+struct StreamState {
+    /// Results collected so far
+    analysis: StreamAnalysis,
+
+    /// Whether PTS may go backwards (frame reordering)
+    reorders: bool,
+
+    /// Bytes per whole second of media time
+    seconds: BTreeMap<i64, u64>,
+
+    /// First packet time
+    first_time: Option<i64>,
+
+    /// DTS of the previous packet
+    last_dts: Option<i64>,
+
+    /// PTS of the previous packet
+    last_pts: Option<i64>,
+
+    /// Time the previous packet ends at
+    last_end: Option<i64>,
+
+    /// Packets since the last keyframe
+    gop: u32,
+
+    /// Whether a keyframe has been seen
+    seen_keyframe: bool,
+}
+
+/// Collects packet statistics for every stream of a file
+pub struct PacketAnalyzer {
+    /// Per-stream state, by stream index
+    streams: Vec<StreamState>,
+
+    /// Pauses between packets reported as gaps
+    gap_threshold: Duration,
+}
+
+impl PacketAnalyzer {
+    /// Create an analyzer
+    ///
+    /// # Arguments
+    ///
+    /// * `streams` - (media type, codec) of each stream, by index
+    /// * `gap_threshold` - Pauses between packets reported as gaps
+    pub fn new(streams: Vec<(String, String)>, gap_threshold: Duration) -> Self {
+        let streams = streams
+            .into_iter()
+            .enumerate()
+            .map(|(index, (kind, codec))| StreamState {
+                reorders: kind == "video",
+                analysis: StreamAnalysis {
+                    index,
+                    kind,
+                    codec,
+                    packets: 0,
+                    bytes: 0,
+                    duration: 0.0,
+                    average_bitrate: 0,
+                    peak_bitrate: 0,
+                    bitrate_timeline: Vec::new(),
+                    keyframes: Vec::new(),
+                    gop_lengths: Vec::new(),
+                    anomalies: Vec::new(),
+                },
+                seconds: BTreeMap::new(),
+                first_time: None,
+                last_dts: None,
+                last_pts: None,
+                last_end: None,
+                gop: 0,
+                seen_keyframe: false,
+            })
+            .collect();
+
+        Self { streams, gap_threshold }
+    }
+
+    /// Account for one packet
+    pub fn add(&mut self, packet: &PacketInfo) {
+        let gap_threshold = self.gap_threshold.as_micros() as i64;
+        let Some(state) = self.streams.get_mut(packet.stream) else {
+            return;
+        };
+
+        let number = state.analysis.packets;
+        state.analysis.packets += 1;
+        state.analysis.bytes += packet.size as u64;
+
+        let time = packet.time();
+        let mut report = |kind, at: Option<i64>, delta: i64| {
+            state.analysis.anomalies.push(Anomaly {
+                packet: number,
+                time: seconds(at.or(state.last_dts).unwrap_or(0)),
+                kind,
+                delta: seconds(delta),
+            });
+        };
+
+        match (packet.pts, packet.dts) {
+            (None, None) => report(AnomalyKind::MissingTimestamp, None, 0),
+            (Some(pts), Some(dts)) if pts < dts => report(AnomalyKind::PtsBeforeDts, Some(dts), dts - pts),
+            _ => {}
+        }
+        if let (Some(dts), Some(last)) = (packet.dts, state.last_dts) {
+            if dts <= last {
+                report(AnomalyKind::NonMonotonicDts, Some(dts), last - dts);
+            }
+        }
+        if let (Some(pts), Some(last), false) = (packet.pts, state.last_pts, state.reorders) {
+            if pts < last {
+                report(AnomalyKind::NonMonotonicPts, Some(pts), last - pts);
+            }
+        }
+        if let (Some(time), Some(end)) = (time, state.last_end) {
+            if time - end > gap_threshold {
+                report(AnomalyKind::Gap, Some(end), time - end);
+            }
+        }
+
+        if let Some(time) = time {
+            state.first_time.get_or_insert(time);
+            *state.seconds.entry(time.div_euclid(1_000_000)).or_default() += packet.size as u64;
+            state.last_end = Some(state.last_end.map_or(time, |end| end.max(time)) + packet.duration.max(0));
+        }
+        state.last_dts = packet.dts.or(state.last_dts);
+        state.last_pts = packet.pts.or(state.last_pts);
+
+        if packet.keyframe {
+            state.analysis.keyframes.push(seconds(packet.pts.or(time).unwrap_or(0)));
+            if state.seen_keyframe && state.reorders {
+                state.analysis.gop_lengths.push(state.gop);
+            }
+            state.seen_keyframe = true;
+            state.gop = 0;
+        }
+        state.gop += 1;
+    }
+
+    /// Finish the analysis
+    pub fn finish(self, source: &str) -> AnalysisReport {
+        let origin = self
+            .streams
+            .iter()
+            .filter_map(|state| state.seconds.keys().next().copied())
+            .min()
+            .unwrap_or(0);
+
+        let streams = self
+            .streams
+            .into_iter()
+            .map(|mut state| {
+                let analysis = &mut state.analysis;
+
+                if let (Some(first), Some(end)) = (state.first_time, state.last_end) {
+                    analysis.duration = seconds(end - first);
+                }
+                if analysis.duration > 0.0 {
+                    analysis.average_bitrate = (analysis.bytes as f64 * 8.0 / analysis.duration) as u64;
+                }
+
+                if let Some(last) = state.seconds.keys().next_back() {
+                    let mut timeline = vec![0; (last - origin + 1) as usize];
+                    for (second, bytes) in &state.seconds {
+                        timeline[(second - origin) as usize] = bytes * 8;
+                    }
+                    analysis.peak_bitrate = timeline.iter().copied().max().unwrap_or(0);
+                    analysis.bitrate_timeline = timeline;
+                }
+
+                state.analysis
+            })
+            .collect();
+
+        AnalysisReport {
+            source: source.to_string(),
+            start_time: origin as f64,
+            streams,
+        }
+    }
+}
+
+/// Bitrate over a sliding window of media time
+#[derive(Debug, Clone)]
+pub struct BitrateMeter {
+    /// Length of the window
+    window: Duration,
+
+    /// (time in microseconds, bytes) of the packets in the window
+    samples: VecDeque<(i64, usize)>,
+
+    /// Bytes in the window
+    bytes: usize,
+}
+
+impl BitrateMeter {
+    /// Create a meter averaging over `window`
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Account for a packet
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Packet timestamp in microseconds
+    /// * `size` - Packet size in bytes
+    pub fn add(&mut self, time: i64, size: usize) {
+        // A jump backwards (seek, loop) starts over
+        if self.samples.back().is_some_and(|(last, _)| time < *last) {
+            self.reset();
+        }
+
+        self.samples.push_back((time, size));
+        self.bytes += size;
+
+        let start = time - self.window.as_micros() as i64;
+        while let Some((oldest, bytes)) = self.samples.front().copied() {
+            if oldest > start {
+                break;
+            }
+            self.samples.pop_front();
+            self.bytes -= bytes;
+        }
+    }
+
+    /// Bitrate in bits per second
+    pub fn bitrate(&self) -> u32 {
+        let window = self.window.as_secs_f64();
+        if window <= 0.0 {
+            return 0;
+        }
+        (self.bytes as f64 * 8.0 / window) as u32
+    }
+
+    /// Forget all packets
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.bytes = 0;
+    }
+}
+
+/// Convert microseconds to seconds
+fn seconds(micros: i64) -> f64 {
+    micros as f64 / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packet with equal PTS and DTS
+    fn packet(stream: usize, time: i64, size: usize, keyframe: bool) -> PacketInfo {
+        PacketInfo {
+            stream,
+            size,
+            pts: Some(time),
+            dts: Some(time),
+            duration: 500_000,
+            keyframe,
+        }
+    }
+
+    #[test]
+    fn test_bitrate_and_gops() {
+        let streams = vec![("video".to_string(), "h264".to_string()), ("audio".to_string(), "aac".to_string())];
+        let mut analyzer = PacketAnalyzer::new(streams, Duration::from_secs(1));
+
+        // Video: keyframe every third packet, two packets per second
+        for n in 0..8 {
+            analyzer.add(&packet(0, 10_000_000 + n * 500_000, 1000, n % 3 == 0));
+        }
+        analyzer.add(&packet(1, 11_000_000, 250, true));
+
+        let report = analyzer.finish("clip.mp4");
+        assert_eq!(report.start_time, 10.0);
+
+        let video = &report.streams[0];
+        assert_eq!(video.packets, 8);
+        assert_eq!(video.bitrate_timeline, vec![16_000; 4]);
+        assert_eq!(video.peak_bitrate, 16_000);
+        assert_eq!(video.average_bitrate, 16_000);
+        assert_eq!(video.keyframes, vec![10.0, 11.5, 13.0]);
+        assert_eq!(video.gop_lengths, vec![3, 3]);
+        assert_eq!(video.gop_summary(), Some((3, 3, 3.0)));
+        assert!(video.anomalies.is_empty());
+
+        // Audio timeline is aligned to the file's first second
+        assert_eq!(report.streams[1].bitrate_timeline, vec![0, 2_000]);
+        assert!(report.streams[1].gop_lengths.is_empty());
+    }
+
+    #[test]
+    fn test_timestamp_anomalies() {
+        let streams = vec![("audio".to_string(), "aac".to_string())];
+        let mut analyzer = PacketAnalyzer::new(streams, Duration::from_secs(1));
+
+        analyzer.add(&packet(0, 0, 100, true));
+        analyzer.add(&packet(0, 500_000, 100, true));
+        analyzer.add(&packet(0, 400_000, 100, true));
+        analyzer.add(&packet(0, 3_000_000, 100, true));
+        analyzer.add(&PacketInfo { pts: None, dts: None, ..packet(0, 0, 100, true) });
+
+        let report = analyzer.finish("radio.aac");
+        let kinds: Vec<_> = report.streams[0].anomalies.iter().map(|anomaly| anomaly.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AnomalyKind::NonMonotonicDts,
+                AnomalyKind::NonMonotonicPts,
+                AnomalyKind::Gap,
+                AnomalyKind::MissingTimestamp,
+            ]
+        );
+
+        let gap = &report.streams[0].anomalies[2];
+        assert_eq!(gap.packet, 3);
+        assert_eq!(gap.time, 1.5);
+        assert_eq!(gap.delta, 1.5);
+    }
+
+    #[test]
+    fn test_bitrate_meter() {
+        let mut meter = BitrateMeter::new(Duration::from_secs(2));
+        for n in 0..10 {
+            meter.add(n * 100_000, 1000);
+        }
+        assert_eq!(meter.bitrate(), 40_000);
+
+        // Only the last two seconds count
+        meter.add(5_000_000, 1000);
+        assert_eq!(meter.bitrate(), 4_000);
+
+        meter.add(0, 500);
+        assert_eq!(meter.bitrate(), 2_000);
+    }
+}
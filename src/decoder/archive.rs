@@ -0,0 +1,428 @@
+//! Archive media sources
+//!
+//! Plays entries of ZIP and TAR archives without extracting them. Stored
+//! entries are read in place and stay seekable; compressed entries are
+//! unpacked into an anonymous temp file first.
+
+use super::source::{MediaSource, ReaderSource};
+use crate::utils::error::{CCPlayerError, Result};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use zip::{CompressionMethod, ZipArchive};
+
+/// Separates the archive path from the entry path (`clips.zip!/a/b.mp4`)
+pub const ENTRY_SEPARATOR: &str = "!/";
+
+/// Entry extensions listed when an archive is opened as a playlist
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "m4v", "mkv", "webm", "mov", "avi", "wmv", "flv", "ts", "m2ts", "mpg", "mpeg", "ogv",
+    "mp3", "m4a", "aac", "flac", "ogg", "opus", "wav",
+];
+
+/// Supported archive formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// ZIP archive
+    Zip,
+
+    /// Uncompressed TAR archive
+    Tar,
+
+    /// Gzip-compressed TAR archive
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Media entry found in an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path inside the archive
+    pub name: String,
+
+    /// Uncompressed size in bytes
+    pub size: u64,
+
+    /// Whether the entry has to be decompressed to be played
+    pub compressed: bool,
+}
+
+/// Path to an archive, optionally pointing at one of its entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivePath {
+    /// Archive file on disk
+    pub archive: PathBuf,
+
+    /// Entry inside the archive, `None` for the archive itself
+    pub entry: Option<String>,
+
+    /// Archive format
+    pub format: ArchiveFormat,
+}
+
+impl ArchivePath {
+    /// Parse `archive.zip` or `archive.zip!/inner/path.mp4`
+    ///
+    /// Returns `None` if the path does not refer to a supported archive.
+    pub fn parse(path: &Path) -> Option<Self> {
+        let text = path.to_str()?;
+
+        // The first separator preceded by an archive name splits the path
+        for (index, _) in text.match_indices(ENTRY_SEPARATOR) {
+            let archive = PathBuf::from(&text[..index]);
+            if let Some(format) = ArchiveFormat::from_path(&archive) {
+                return Some(Self {
+                    archive,
+                    entry: Some(text[index + ENTRY_SEPARATOR.len()..].to_string()),
+                    format,
+                });
+            }
+        }
+
+        ArchiveFormat::from_path(path).map(|format| Self {
+            archive: path.to_path_buf(),
+            entry: None,
+            format,
+        })
+    }
+
+    /// Build the path string of an entry inside `archive`
+    pub fn entry_path(archive: &Path, entry: &str) -> String {
+        format!("{}{}{}", archive.display(), ENTRY_SEPARATOR, entry)
+    }
+
+    /// List the media entries of an archive, sorted by name
+    pub fn list_entries(&self) -> Result<Vec<ArchiveEntry>> {
+        let mut entries = match self.format {
+            ArchiveFormat::Zip => list_zip_entries(&self.archive)?,
+            ArchiveFormat::Tar => list_tar_entries(File::open(&self.archive)?, false)?,
+            ArchiveFormat::TarGz => list_tar_entries(GzDecoder::new(File::open(&self.archive)?), true)?,
+        };
+
+        entries.retain(|entry| is_media_entry(&entry.name));
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(entries)
+    }
+
+    /// Open an archive entry for playback
+    pub fn open_entry(&self) -> Result<Box<dyn MediaSource>> {
+        let entry = self.entry.as_deref().ok_or_else(|| {
+            CCPlayerError::InvalidInput(format!("No archive entry given for {}", self.archive.display()))
+        })?;
+        let name = ArchivePath::entry_path(&self.archive, entry);
+
+        let source = match self.format {
+            ArchiveFormat::Zip => open_zip_entry(&self.archive, entry, name)?,
+            ArchiveFormat::Tar => {
+                let mut archive = tar::Archive::new(File::open(&self.archive)?);
+                match find_tar_entry(&mut archive, entry)? {
+                    Some(found) => {
+                        // TAR never compresses entries, so read them in place
+                        let (start, length) = (found.raw_file_position(), found.size());
+                        let source = EntrySource::new(File::open(&self.archive)?, start, length, name)?;
+                        Some(Box::new(source) as Box<dyn MediaSource>)
+                    }
+                    None => None,
+                }
+            }
+            ArchiveFormat::TarGz => {
+                let mut archive = tar::Archive::new(GzDecoder::new(File::open(&self.archive)?));
+                match find_tar_entry(&mut archive, entry)? {
+                    Some(mut found) => Some(cache_entry(&mut found, name)?),
+                    None => None,
+                }
+            }
+        };
+
+        source.ok_or_else(|| {
+            CCPlayerError::NotFound(format!("{} in {}", entry, self.archive.display()))
+        })
+    }
+}
+
+/// Whether an entry name has a media extension
+fn is_media_entry(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// List the file entries of a ZIP archive
+fn list_zip_entries(archive: &Path) -> Result<Vec<ArchiveEntry>> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    let mut entries = Vec::with_capacity(zip.len());
+
+    for index in 0..zip.len() {
+        // Raw access works for encrypted entries too
+        let file = zip.by_index_raw(index)?;
+        if file.is_file() {
+            entries.push(ArchiveEntry {
+                name: file.name().to_string(),
+                size: file.size(),
+                compressed: file.compression() != CompressionMethod::Stored,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// List the file entries of a TAR stream
+fn list_tar_entries<R: Read>(reader: R, compressed: bool) -> Result<Vec<ArchiveEntry>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            entries.push(ArchiveEntry {
+                name: tar_entry_name(&entry)?,
+                size: entry.size(),
+                compressed,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Open a ZIP entry, in place if it is stored
+fn open_zip_entry(archive: &Path, entry: &str, name: String) -> Result<Option<Box<dyn MediaSource>>> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    if zip.index_for_name(entry).is_none() {
+        return Ok(None);
+    }
+
+    let mut file = zip.by_name(entry)?;
+    if file.compression() == CompressionMethod::Stored {
+        let (start, length) = (file.data_start(), file.size());
+        return Ok(Some(Box::new(EntrySource::new(File::open(archive)?, start, length, name)?)));
+    }
+
+    cache_entry(&mut file, name).map(Some)
+}
+
+/// Find a regular file entry in a TAR archive
+fn find_tar_entry<'a, R: Read>(archive: &'a mut tar::Archive<R>, entry: &str) -> Result<Option<tar::Entry<'a, R>>> {
+    let wanted = entry.trim_start_matches("./");
+
+    for candidate in archive.entries()? {
+        let candidate = candidate?;
+        if candidate.header().entry_type().is_file() && tar_entry_name(&candidate)? == wanted {
+            return Ok(Some(candidate));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Entry path without the `./` prefix many tar tools add
+fn tar_entry_name<R: Read>(entry: &tar::Entry<'_, R>) -> Result<String> {
+    let path = entry.path()?;
+    Ok(path.to_string_lossy().trim_start_matches("./").to_string())
+}
+
+/// Decompress an entry into an anonymous temp file
+fn cache_entry(reader: &mut dyn Read, name: String) -> Result<Box<dyn MediaSource>> {
+    log::debug!("Caching compressed archive entry {}", name);
+
+    let mut cache = tempfile::tempfile()?;
+    io::copy(reader, &mut cache)?;
+    cache.seek(SeekFrom::Start(0))?;
+
+    Ok(Box::new(ReaderSource::new(cache)?.with_name(name)))
+}
+
+/// Seekable view of a byte range of the archive file
+struct EntrySource {
+    /// Archive file
+    file: File,
+
+    /// Offset of the entry data in the archive
+    start: u64,
+
+    /// Entry length
+    length: u64,
+
+    /// Position within the entry
+    position: u64,
+
+    /// Source name
+    name: String,
+}
+
+impl EntrySource {
+    /// Create a view of `length` bytes starting at `start`
+    fn new(mut file: File, start: u64, length: u64, name: String) -> io::Result<Self> {
+        file.seek(SeekFrom::Start(start))?;
+
+        Ok(Self {
+            file,
+            start,
+            length,
+            position: 0,
+            name,
+        })
+    }
+}
+
+impl MediaSource for EntrySource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.length.saturating_sub(self.position);
+        let count = (buf.len() as u64).min(remaining) as usize;
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let read = self.file.read(&mut buf[..count])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<u64> {
+        let position = position.min(self.length);
+        self.file.seek(SeekFrom::Start(self.start + position))?;
+        self.position = position;
+        Ok(position)
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.length)
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn content(seed: u8) -> Vec<u8> {
+        (0..20_000u32).map(|i| (i as u8).wrapping_mul(seed)).collect()
+    }
+
+    fn write_zip(dir: &Path) -> PathBuf {
+        let path = dir.join("clips.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        zip.start_file("b/second.mp4", stored).unwrap();
+        zip.write_all(&content(3)).unwrap();
+        zip.start_file("readme.txt", deflated).unwrap();
+        zip.write_all(b"not media").unwrap();
+        zip.start_file("a/first.mkv", deflated).unwrap();
+        zip.write_all(&content(5)).unwrap();
+        zip.finish().unwrap();
+
+        path
+    }
+
+    fn read_all(source: &mut dyn MediaSource) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            match source.read(&mut buf).unwrap() {
+                0 => return data,
+                count => data.extend_from_slice(&buf[..count]),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_archive_path() {
+        let path = ArchivePath::parse(Path::new("/media/clips.zip!/inner/path.mp4")).unwrap();
+        assert_eq!(path.archive, PathBuf::from("/media/clips.zip"));
+        assert_eq!(path.entry.as_deref(), Some("inner/path.mp4"));
+        assert_eq!(path.format, ArchiveFormat::Zip);
+
+        let path = ArchivePath::parse(Path::new("/media/Backup.TGZ")).unwrap();
+        assert_eq!(path.entry, None);
+        assert_eq!(path.format, ArchiveFormat::TarGz);
+
+        assert!(ArchivePath::parse(Path::new("/media/wow!/clip.mp4")).is_none());
+        assert!(ArchivePath::parse(Path::new("/media/clip.mp4")).is_none());
+    }
+
+    #[test]
+    fn test_zip_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = write_zip(dir.path());
+        let path = ArchivePath::parse(&archive).unwrap();
+
+        let entries = path.list_entries().unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["a/first.mkv", "b/second.mp4"]);
+        assert!(entries[0].compressed);
+        assert!(!entries[1].compressed);
+
+        // Stored entries are read in place and can seek
+        let entry = ArchivePath::parse(Path::new(&ArchivePath::entry_path(&archive, "b/second.mp4"))).unwrap();
+        let mut source = entry.open_entry().unwrap();
+        assert_eq!(source.size(), Some(20_000));
+        assert_eq!(read_all(source.as_mut()), content(3));
+        source.seek(19_990).unwrap();
+        assert_eq!(read_all(source.as_mut()), &content(3)[19_990..]);
+
+        // Compressed entries go through the cache
+        let entry = ArchivePath::parse(Path::new(&ArchivePath::entry_path(&archive, "a/first.mkv"))).unwrap();
+        let mut source = entry.open_entry().unwrap();
+        assert!(source.is_seekable());
+        assert_eq!(read_all(source.as_mut()), content(5));
+
+        let entry = ArchivePath::parse(Path::new(&ArchivePath::entry_path(&archive, "missing.mp4"))).unwrap();
+        assert!(matches!(entry.open_entry(), Err(CCPlayerError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_tar_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("clips.tar");
+
+        let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+        for (name, seed) in [("./one.mp4", 7u8), ("./two.webm", 11u8)] {
+            let data = content(seed);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, data.as_slice()).unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+
+        let path = ArchivePath::parse(&archive).unwrap();
+        let entries = path.list_entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].name, "two.webm");
+
+        let entry = ArchivePath::parse(Path::new(&ArchivePath::entry_path(&archive, "two.webm"))).unwrap();
+        let mut source = entry.open_entry().unwrap();
+        source.seek(100).unwrap();
+        assert_eq!(read_all(source.as_mut()), &content(11)[100..]);
+    }
+}
@@ -0,0 +1,254 @@
+//! Custom FFmpeg I/O
+//!
+//! Lets FFmpeg demux from any `MediaSource` instead of a file path or URL,
+//! through a custom AVIOContext with read and (optional) seek callbacks.
+//! Also opens URLs with an interrupt callback that aborts stalled reads.
+
+use super::network::StallWatchdog;
+use super::source::MediaSource;
+use crate::utils::error::{CCPlayerError, Result};
+use ffmpeg_next as ffmpeg;
+use ffmpeg_next::ffi;
+use std::ffi::CString;
+use std::io;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+/// Size of the AVIOContext read buffer
+const AVIO_BUFFER_SIZE: usize = 64 * 1024;
+
+/// `whence` values passed to the seek callback (from stdio.h)
+const SEEK_SET: c_int = 0;
+const SEEK_CUR: c_int = 1;
+const SEEK_END: c_int = 2;
+
+/// State handed to FFmpeg as the opaque pointer
+struct IoState {
+    /// The source being read
+    source: Box<dyn MediaSource>,
+
+    /// Current byte position
+    position: u64,
+}
+
+/// Owns a custom AVIOContext and the source behind it
+///
+/// The `Input` opened with it must be dropped first.
+pub(crate) struct CustomIo {
+    /// FFmpeg I/O context
+    context: *mut ffi::AVIOContext,
+
+    /// Source state passed as the opaque pointer
+    opaque: *mut IoState,
+}
+
+// The context is only touched through `&mut` of the owning decoder
+unsafe impl Send for CustomIo {}
+unsafe impl Sync for CustomIo {}
+
+impl CustomIo {
+    /// Open a demuxer that reads from `source`
+    pub(crate) fn open_input(source: Box<dyn MediaSource>) -> Result<(ffmpeg::format::context::Input, CustomIo)> {
+        let seekable = source.is_seekable();
+
+        // The name doubles as a probing hint (file extension)
+        let url = CString::new(source.name()).unwrap_or_default();
+
+        unsafe {
+            let buffer = ffi::av_malloc(AVIO_BUFFER_SIZE) as *mut u8;
+            if buffer.is_null() {
+                return Err(CCPlayerError::decoder_error("Failed to allocate I/O buffer"));
+            }
+
+            let opaque = Box::into_raw(Box::new(IoState { source, position: 0 }));
+            let context = ffi::avio_alloc_context(
+                buffer,
+                AVIO_BUFFER_SIZE as c_int,
+                0,
+                opaque as *mut c_void,
+                Some(read_packet),
+                None,
+                if seekable { Some(seek_packet) } else { None },
+            );
+
+            if context.is_null() {
+                ffi::av_free(buffer as *mut c_void);
+                drop(Box::from_raw(opaque));
+                return Err(CCPlayerError::decoder_error("Failed to allocate I/O context"));
+            }
+
+            // From here on the guard frees the context and source
+            let io = CustomIo { context, opaque };
+
+            let mut format_context = ffi::avformat_alloc_context();
+            if format_context.is_null() {
+                return Err(CCPlayerError::decoder_error("Failed to allocate format context"));
+            }
+
+            (*format_context).pb = context;
+            (*format_context).flags |= ffi::AVFMT_FLAG_CUSTOM_IO as c_int;
+
+            // Frees the format context on failure
+            let ret = ffi::avformat_open_input(&mut format_context, url.as_ptr(), ptr::null_mut(), ptr::null_mut());
+            if ret < 0 {
+                return Err(ffmpeg::Error::from(ret).into());
+            }
+
+            let ret = ffi::avformat_find_stream_info(format_context, ptr::null_mut());
+            if ret < 0 {
+                ffi::avformat_close_input(&mut format_context);
+                return Err(ffmpeg::Error::from(ret).into());
+            }
+
+            Ok((ffmpeg::format::context::Input::wrap(format_context), io))
+        }
+    }
+}
+
+impl Drop for CustomIo {
+    fn drop(&mut self) {
+        unsafe {
+            // FFmpeg may have replaced the buffer, so free the one it points at
+            if !self.context.is_null() {
+                ffi::av_freep(&mut (*self.context).buffer as *mut *mut u8 as *mut c_void);
+                ffi::avio_context_free(&mut self.context);
+            }
+
+            drop(Box::from_raw(self.opaque));
+        }
+    }
+}
+
+/// AVIOContext read callback
+unsafe extern "C" fn read_packet(opaque: *mut c_void, buf: *mut u8, buf_size: c_int) -> c_int {
+    let state = &mut *(opaque as *mut IoState);
+    let output = std::slice::from_raw_parts_mut(buf, buf_size.max(0) as usize);
+
+    loop {
+        match state.source.read(output) {
+            Ok(0) => return ffi::AVERROR_EOF,
+            Ok(count) => {
+                state.position += count as u64;
+                return count as c_int;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                log::warn!("Custom I/O read failed: {}", e);
+                return ffi::AVERROR_EXTERNAL;
+            }
+        }
+    }
+}
+
+/// AVIOContext seek callback
+unsafe extern "C" fn seek_packet(opaque: *mut c_void, offset: i64, whence: c_int) -> i64 {
+    let state = &mut *(opaque as *mut IoState);
+
+    if whence & ffi::AVSEEK_SIZE as c_int != 0 {
+        return state.source.size().map_or(-1, |size| size as i64);
+    }
+
+    let base = match whence & !(ffi::AVSEEK_FORCE as c_int) {
+        SEEK_SET => 0,
+        SEEK_CUR => state.position as i64,
+        SEEK_END => match state.source.size() {
+            Some(size) => size as i64,
+            None => return -1,
+        },
+        _ => return -1,
+    };
+
+    let target = base + offset;
+    if target < 0 {
+        return -1;
+    }
+
+    match state.source.seek(target as u64) {
+        Ok(position) => {
+            state.position = position;
+            position as i64
+        }
+        Err(e) => {
+            log::warn!("Custom I/O seek to {} failed: {}", target, e);
+            -1
+        }
+    }
+}
+
+/// Owns the watchdog behind an input's interrupt callback
+///
+/// The `Input` opened with it must be dropped first.
+pub(crate) struct InterruptGuard {
+    /// Watchdog passed as the opaque pointer
+    opaque: *mut StallWatchdog,
+}
+
+// The watchdog itself is thread-safe
+unsafe impl Send for InterruptGuard {}
+unsafe impl Sync for InterruptGuard {}
+
+impl InterruptGuard {
+    /// Watchdog consulted by the interrupt callback
+    pub(crate) fn watchdog(&self) -> &StallWatchdog {
+        unsafe { &*self.opaque }
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(self.opaque));
+        }
+    }
+}
+
+/// Open a URL with FFmpeg's own protocols
+///
+/// Blocking I/O is aborted once `watchdog` has seen no activity for its
+/// timeout, which surfaces as a read error the caller can recover from.
+pub(crate) fn open_url_input(
+    url: &str,
+    options: ffmpeg::Dictionary,
+    watchdog: StallWatchdog,
+) -> Result<(ffmpeg::format::context::Input, InterruptGuard)> {
+    let url = CString::new(url)?;
+
+    watchdog.touch();
+    let guard = InterruptGuard {
+        opaque: Box::into_raw(Box::new(watchdog)),
+    };
+
+    unsafe {
+        let mut format_context = ffi::avformat_alloc_context();
+        if format_context.is_null() {
+            return Err(CCPlayerError::decoder_error("Failed to allocate format context"));
+        }
+
+        (*format_context).interrupt_callback = ffi::AVIOInterruptCB {
+            callback: Some(interrupt_on_stall),
+            opaque: guard.opaque as *mut c_void,
+        };
+
+        // Frees the format context on failure
+        let mut opts = options.disown();
+        let ret = ffi::avformat_open_input(&mut format_context, url.as_ptr(), ptr::null_mut(), &mut opts);
+        ffmpeg::Dictionary::own(opts);
+        if ret < 0 {
+            return Err(ffmpeg::Error::from(ret).into());
+        }
+
+        let ret = ffi::avformat_find_stream_info(format_context, ptr::null_mut());
+        if ret < 0 {
+            ffi::avformat_close_input(&mut format_context);
+            return Err(ffmpeg::Error::from(ret).into());
+        }
+
+        Ok((ffmpeg::format::context::Input::wrap(format_context), guard))
+    }
+}
+
+/// AVIOInterruptCB callback: abort once the stream has stalled
+unsafe extern "C" fn interrupt_on_stall(opaque: *mut c_void) -> c_int {
+    let watchdog = &*(opaque as *const StallWatchdog);
+    watchdog.is_stalled() as c_int
+}
@@ -0,0 +1,520 @@
+//! Network read-ahead cache
+//!
+//! Sits between a network source and the demuxer. Data is downloaded ahead
+//! of the read position on a background thread and kept in a bounded
+//! on-disk cache of fixed-size blocks, so seeking back into media that was
+//! already downloaded needs no new request.
+
+use super::source::MediaSource;
+use crate::utils::error::Result;
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Size of a cache block
+const BLOCK_SIZE: u64 = 256 * 1024;
+
+/// Largest single read from the upstream source
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Byte rate assumed for time-based read-ahead until the real one is known
+const FALLBACK_BYTE_RATE: u64 = 1024 * 1024;
+
+/// How far ahead of the read position to download
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Readahead {
+    /// Amount of media time, converted with the stream's byte rate
+    Duration(Duration),
+
+    /// Fixed number of bytes
+    Bytes(u64),
+}
+
+/// Read-ahead cache settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheConfig {
+    /// Upper bound for the on-disk cache in bytes
+    pub max_size: u64,
+
+    /// How far ahead of the read position to download
+    pub readahead: Readahead,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 256 * 1024 * 1024,
+            readahead: Readahead::Duration(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Read-ahead in bytes for a stream with the given byte rate
+    ///
+    /// Capped at half the cache so the other half keeps data behind the
+    /// read position for backward seeks.
+    pub fn readahead_bytes(&self, byte_rate: Option<u64>) -> u64 {
+        let bytes = match self.readahead {
+            Readahead::Duration(duration) => {
+                (duration.as_secs_f64() * byte_rate.unwrap_or(FALLBACK_BYTE_RATE) as f64) as u64
+            }
+            Readahead::Bytes(bytes) => bytes,
+        };
+
+        bytes.clamp(BLOCK_SIZE, (self.max_size / 2).max(BLOCK_SIZE))
+    }
+
+    /// Number of blocks the cache file may hold
+    fn max_blocks(&self) -> u64 {
+        (self.max_size / BLOCK_SIZE).max(2)
+    }
+}
+
+/// A cached block of the stream
+#[derive(Debug)]
+struct Block {
+    /// Position of the block in the cache file, in blocks
+    slot: u64,
+
+    /// Bytes downloaded from the start of the block
+    filled: u64,
+
+    /// Access counter value at the last read, for eviction
+    last_used: u64,
+}
+
+/// State shared by the reader and the download thread
+struct CacheState {
+    /// Cache settings
+    config: CacheConfig,
+
+    /// Backing file
+    file: File,
+
+    /// Cached blocks by block index
+    blocks: HashMap<u64, Block>,
+
+    /// First slot never used so far
+    next_slot: u64,
+
+    /// Current read position of the demuxer
+    read_pos: u64,
+
+    /// Total size, once known
+    size: Option<u64>,
+
+    /// Average byte rate of the media, for time-based read-ahead
+    byte_rate: Option<u64>,
+
+    /// Last download error, cleared by a seek
+    error: Option<(io::ErrorKind, String)>,
+
+    /// Access counter
+    clock: u64,
+
+    /// Set when the reader is gone
+    closed: bool,
+}
+
+impl CacheState {
+    /// Full length of a block (shorter for the last one)
+    fn block_len(&self, index: u64) -> u64 {
+        match self.size {
+            Some(size) => size.saturating_sub(index * BLOCK_SIZE).min(BLOCK_SIZE),
+            None => BLOCK_SIZE,
+        }
+    }
+
+    /// Bytes downloaded for a block
+    fn filled(&self, index: u64) -> u64 {
+        self.blocks.get(&index).map_or(0, |block| block.filled)
+    }
+
+    /// Next byte offset to download, if read-ahead is not yet satisfied
+    fn next_fetch(&self) -> Option<u64> {
+        let mut end = self.read_pos + self.config.readahead_bytes(self.byte_rate);
+        if let Some(size) = self.size {
+            end = end.min(size);
+        }
+
+        let mut index = self.read_pos / BLOCK_SIZE;
+        while index * BLOCK_SIZE < end {
+            let filled = self.filled(index);
+            if filled < self.block_len(index) {
+                return Some(index * BLOCK_SIZE + filled);
+            }
+            index += 1;
+        }
+
+        None
+    }
+
+    /// Make sure a block has a slot, evicting the least recently used
+    /// block outside `protect` if the cache is full
+    fn reserve(&mut self, index: u64, protect: Range<u64>) -> bool {
+        if self.blocks.contains_key(&index) {
+            return true;
+        }
+
+        let slot = if self.next_slot < self.config.max_blocks() {
+            self.next_slot += 1;
+            self.next_slot - 1
+        } else {
+            let victim = self
+                .blocks
+                .iter()
+                .filter(|(index, _)| !protect.contains(index))
+                .min_by_key(|(_, block)| block.last_used)
+                .map(|(index, _)| *index);
+
+            match victim.and_then(|victim| self.blocks.remove(&victim)) {
+                Some(block) => block.slot,
+                None => return false,
+            }
+        };
+
+        self.blocks.insert(index, Block { slot, filled: 0, last_used: self.clock });
+        true
+    }
+
+    /// Store downloaded bytes at `offset` (the end of the block's data)
+    fn store(&mut self, offset: u64, data: &[u8]) -> io::Result<()> {
+        let index = offset / BLOCK_SIZE;
+        let Some(block) = self.blocks.get_mut(&index) else {
+            return Ok(());
+        };
+
+        self.file.seek(SeekFrom::Start(block.slot * BLOCK_SIZE + offset % BLOCK_SIZE))?;
+        self.file.write_all(data)?;
+        block.filled += data.len() as u64;
+        Ok(())
+    }
+
+    /// Read cached bytes at the read position
+    ///
+    /// # Returns
+    ///
+    /// `None` if the data has not been downloaded yet
+    fn read_cached(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        let index = self.read_pos / BLOCK_SIZE;
+        let offset = self.read_pos % BLOCK_SIZE;
+
+        self.clock += 1;
+        let clock = self.clock;
+        let Some(block) = self.blocks.get_mut(&index).filter(|block| block.filled > offset) else {
+            return Ok(None);
+        };
+        block.last_used = clock;
+
+        let count = ((block.filled - offset) as usize).min(buf.len());
+        self.file.seek(SeekFrom::Start(block.slot * BLOCK_SIZE + offset))?;
+        self.file.read_exact(&mut buf[..count])?;
+        Ok(Some(count))
+    }
+
+    /// Whether the byte at `position` is cached
+    fn is_cached(&self, position: u64) -> bool {
+        self.filled(position / BLOCK_SIZE) > position % BLOCK_SIZE
+    }
+
+    /// Cached byte ranges, merged and sorted
+    fn buffered_ranges(&self) -> Vec<Range<u64>> {
+        let mut indices: Vec<_> = self.blocks.iter().filter(|(_, block)| block.filled > 0).map(|(index, _)| *index).collect();
+        indices.sort_unstable();
+
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        for index in indices {
+            let start = index * BLOCK_SIZE;
+            let end = start + self.filled(index);
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end = end,
+                _ => ranges.push(start..end),
+            }
+        }
+
+        ranges
+    }
+}
+
+/// Lock and condition variable shared with the download thread
+struct Shared {
+    /// Cache state
+    state: Mutex<CacheState>,
+
+    /// Signalled whenever data arrives or the read position moves
+    changed: Condvar,
+}
+
+/// Media source that reads ahead of the demuxer into a disk cache
+pub struct CachedSource {
+    /// State shared with the download thread
+    shared: Arc<Shared>,
+
+    /// Whether the upstream source can seek
+    seekable: bool,
+
+    /// Upstream source name
+    name: String,
+}
+
+impl CachedSource {
+    /// Start caching `upstream`
+    pub fn new(upstream: Box<dyn MediaSource>, config: CacheConfig) -> Result<Self> {
+        let file = tempfile::tempfile()?;
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(CacheState {
+                config,
+                file,
+                blocks: HashMap::new(),
+                next_slot: 0,
+                read_pos: 0,
+                size: upstream.size(),
+                byte_rate: None,
+                error: None,
+                clock: 0,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let seekable = upstream.is_seekable();
+        let name = upstream.name();
+
+        let downloader = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("cache-download".to_string())
+            .spawn(move || download(&downloader, upstream))?;
+
+        Ok(Self { shared, seekable, name })
+    }
+
+    /// Handle for watching the cache from other threads
+    pub fn handle(&self) -> CacheHandle {
+        CacheHandle {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl MediaSource for CachedSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.shared.state.lock();
+        loop {
+            if state.size.is_some_and(|size| state.read_pos >= size) {
+                return Ok(0);
+            }
+
+            if let Some(count) = state.read_cached(buf)? {
+                state.read_pos += count as u64;
+                self.shared.changed.notify_all();
+                return Ok(count);
+            }
+
+            if let Some((kind, message)) = &state.error {
+                return Err(io::Error::new(*kind, message.clone()));
+            }
+
+            self.shared.changed.wait(&mut state);
+        }
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<u64> {
+        let mut state = self.shared.state.lock();
+        if !self.seekable && position != state.read_pos && !state.is_cached(position) {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "position is not cached and the source cannot seek"));
+        }
+
+        state.read_pos = position;
+        state.error = None;
+        self.shared.changed.notify_all();
+        Ok(position)
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.shared.state.lock().size
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.seekable
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Drop for CachedSource {
+    fn drop(&mut self) {
+        // The download thread exits after its current read
+        self.shared.state.lock().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+/// Shared view of a `CachedSource`
+#[derive(Clone)]
+pub struct CacheHandle {
+    /// State shared with the source
+    shared: Arc<Shared>,
+}
+
+impl CacheHandle {
+    /// Byte ranges held in the cache, merged and sorted
+    pub fn buffered_ranges(&self) -> Vec<Range<u64>> {
+        self.shared.state.lock().buffered_ranges()
+    }
+
+    /// Total size of the source, once known
+    pub fn size(&self) -> Option<u64> {
+        self.shared.state.lock().size
+    }
+
+    /// Set the media's average byte rate, used for time-based read-ahead
+    pub fn set_byte_rate(&self, byte_rate: u64) {
+        self.shared.state.lock().byte_rate = Some(byte_rate.max(1));
+        self.shared.changed.notify_all();
+    }
+}
+
+/// Download thread: keep the cache filled ahead of the read position
+fn download(shared: &Shared, mut upstream: Box<dyn MediaSource>) {
+    let mut upstream_pos = 0;
+    let mut buf = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        let offset = {
+            let mut state = shared.state.lock();
+            loop {
+                if state.closed {
+                    return;
+                }
+
+                if state.error.is_none() {
+                    if let Some(offset) = state.next_fetch() {
+                        let index = offset / BLOCK_SIZE;
+                        let protect = state.read_pos / BLOCK_SIZE..index + 1;
+                        if state.reserve(index, protect) {
+                            break offset;
+                        }
+                    }
+                }
+
+                shared.changed.wait(&mut state);
+            }
+        };
+
+        // Network I/O happens without holding the lock
+        let wanted = ((BLOCK_SIZE - offset % BLOCK_SIZE) as usize).min(CHUNK_SIZE);
+        let result = if upstream_pos == offset {
+            upstream.read(&mut buf[..wanted])
+        } else {
+            upstream.seek(offset).and_then(|position| {
+                upstream_pos = position;
+                upstream.read(&mut buf[..wanted])
+            })
+        };
+
+        let mut state = shared.state.lock();
+        match result {
+            Ok(0) => {
+                log::debug!("Cache reached the end of {} at byte {}", upstream.name(), offset);
+                state.size = Some(offset);
+            }
+            Ok(count) => {
+                upstream_pos += count as u64;
+                if let Err(e) = state.store(offset, &buf[..count]) {
+                    log::warn!("Failed to write cache file: {}", e);
+                    state.error = Some((e.kind(), e.to_string()));
+                }
+            }
+            Err(e) => {
+                log::warn!("Cache download from {} failed at byte {}: {}", upstream.name(), offset, e);
+                state.error = Some((e.kind(), e.to_string()));
+            }
+        }
+        shared.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::MemorySource;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn read_exact(source: &mut CachedSource, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        let mut filled = 0;
+        while filled < len {
+            let count = source.read(&mut out[filled..]).unwrap();
+            assert!(count > 0, "unexpected end of stream");
+            filled += count;
+        }
+        out
+    }
+
+    #[test]
+    fn test_readahead_bytes() {
+        let config = CacheConfig {
+            max_size: 64 * 1024 * 1024,
+            readahead: Readahead::Duration(Duration::from_secs(10)),
+        };
+        assert_eq!(config.readahead_bytes(Some(500_000)), 5_000_000);
+
+        // Never more than half the cache
+        assert_eq!(config.readahead_bytes(Some(100_000_000)), 32 * 1024 * 1024);
+
+        let config = CacheConfig {
+            readahead: Readahead::Bytes(1),
+            ..config
+        };
+        assert_eq!(config.readahead_bytes(None), BLOCK_SIZE);
+    }
+
+    #[test]
+    fn test_cached_source_seeks_back() {
+        let content = data(3 * BLOCK_SIZE as usize + 1000);
+        let mut source = CachedSource::new(Box::new(MemorySource::from_bytes(content.clone())), CacheConfig::default()).unwrap();
+        assert_eq!(source.size(), Some(content.len() as u64));
+
+        assert_eq!(read_exact(&mut source, 400_000), &content[..400_000]);
+        source.seek(100).unwrap();
+        assert_eq!(read_exact(&mut source, 1000), &content[100..1100]);
+
+        source.seek(content.len() as u64 - 10).unwrap();
+        assert_eq!(read_exact(&mut source, 10), &content[content.len() - 10..]);
+        assert_eq!(source.read(&mut [0u8; 16]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let content = data(8 * BLOCK_SIZE as usize);
+        let config = CacheConfig {
+            max_size: 4 * BLOCK_SIZE,
+            readahead: Readahead::Bytes(BLOCK_SIZE),
+        };
+        let mut source = CachedSource::new(Box::new(MemorySource::from_bytes(content.clone())), config).unwrap();
+
+        assert_eq!(read_exact(&mut source, content.len()), content);
+
+        let ranges = source.handle().buffered_ranges();
+        let cached: u64 = ranges.iter().map(|range| range.end - range.start).sum();
+        assert!(cached <= config.max_size);
+        assert_eq!(ranges.last().unwrap().end, content.len() as u64);
+        assert!(ranges[0].start > 0);
+    }
+}
@@ -0,0 +1,255 @@
+//! Deinterlacing of interlaced video
+//!
+//! Interlaced frames are recognised by their frame flags and field order
+//! and run through a libavfilter deinterlacer. In double-rate mode every
+//! field becomes a frame of half the duration.
+
+use crate::utils::error::Result;
+use ffmpeg_next as ffmpeg;
+use ffmpeg_next::Rescale;
+
+use super::filter_graph::VideoFilterGraph;
+
+/// Deinterlacing algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub enum DeinterlaceMode {
+    /// Line doubling of each field (cheapest, flickers on fine detail)
+    Bob,
+
+    /// Yet Another DeInterlacing Filter (spatial and temporal)
+    Yadif,
+
+    /// Bob Weaver Deinterlacing Filter (yadif with better interpolation)
+    Bwdif,
+}
+
+/// Deinterlacing settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct DeinterlaceConfig {
+    /// Algorithm to use, or None to show interlaced frames as they are
+    pub mode: Option<DeinterlaceMode>,
+
+    /// Output one frame per field instead of one per frame
+    pub double_rate: bool,
+
+    /// Deinterlace every frame, even frames not flagged as interlaced
+    pub force: bool,
+}
+
+impl Default for DeinterlaceConfig {
+    fn default() -> Self {
+        Self {
+            mode: Some(DeinterlaceMode::Yadif),
+            double_rate: true,
+            force: false,
+        }
+    }
+}
+
+/// Field order of a video frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldOrder {
+    /// Not interlaced
+    Progressive,
+
+    /// Interlaced, top field first
+    TopFirst,
+
+    /// Interlaced, bottom field first
+    BottomFirst,
+}
+
+impl FieldOrder {
+    /// Field order from a frame's interlacing flags
+    pub fn from_flags(interlaced: bool, top_field_first: bool) -> Self {
+        match (interlaced, top_field_first) {
+            (false, _) => Self::Progressive,
+            (true, true) => Self::TopFirst,
+            (true, false) => Self::BottomFirst,
+        }
+    }
+
+    /// Field order of a decoded frame
+    pub fn of(frame: &ffmpeg::frame::Video) -> Self {
+        Self::from_flags(frame.is_interlaced(), frame.is_top_first())
+    }
+}
+
+impl DeinterlaceConfig {
+    /// Check whether frames of the given field order need deinterlacing
+    pub fn applies_to(&self, order: FieldOrder) -> bool {
+        self.mode.is_some() && (self.force || order != FieldOrder::Progressive)
+    }
+
+    /// Frames produced per input frame
+    pub fn rate_factor(&self) -> i64 {
+        if self.double_rate {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Filter graph description for frames of the given field order
+    ///
+    /// # Returns
+    ///
+    /// None when the frames pass through unchanged
+    pub fn filter_spec(&self, order: FieldOrder) -> Option<String> {
+        if !self.applies_to(order) {
+            return None;
+        }
+
+        let parity = match order {
+            FieldOrder::TopFirst => "tff",
+            FieldOrder::BottomFirst => "bff",
+            FieldOrder::Progressive => "auto",
+        };
+        let send = if self.double_rate { "send_field" } else { "send_frame" };
+        // Flagged progressive frames in mixed content are left alone
+        let deint = if self.force { "all" } else { "interlaced" };
+
+        let spec = match self.mode? {
+            DeinterlaceMode::Bob if self.double_rate => {
+                "separatefields,scale=w=iw:h=ih*2:flags=bilinear".to_string()
+            }
+            DeinterlaceMode::Bob => {
+                let field = if order == FieldOrder::BottomFirst { "bottom" } else { "top" };
+                format!("field=type={},scale=w=iw:h=ih*2:flags=bilinear", field)
+            }
+            DeinterlaceMode::Yadif => format!("yadif=mode={}:parity={}:deint={}", send, parity, deint),
+            DeinterlaceMode::Bwdif => format!("bwdif=mode={}:parity={}:deint={}", send, parity, deint),
+        };
+        Some(spec)
+    }
+}
+
+/// libavfilter deinterlacing graph for one video format
+pub struct Deinterlacer {
+    /// Deinterlacing filter graph
+    graph: VideoFilterGraph,
+
+    /// Time base of the decoded frames
+    input_time_base: ffmpeg::Rational,
+
+    /// Frames produced per input frame
+    rate_factor: i64,
+
+    /// Duration of the last input frame, in the input time base
+    input_duration: i64,
+}
+
+impl Deinterlacer {
+    /// Build a deinterlacer for frames like `frame`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Deinterlacing settings
+    /// * `frame` - First frame to deinterlace
+    /// * `time_base` - Time base of the frame's timestamps
+    ///
+    /// # Returns
+    ///
+    /// None when the frame needs no deinterlacing
+    pub fn new(config: &DeinterlaceConfig, frame: &ffmpeg::frame::Video, time_base: ffmpeg::Rational) -> Result<Option<Self>> {
+        let Some(spec) = config.filter_spec(FieldOrder::of(frame)) else {
+            return Ok(None);
+        };
+
+        let graph = VideoFilterGraph::new(&spec, frame, time_base)?;
+        log::info!("Deinterlacing {}x{} video with {}", frame.width(), frame.height(), spec);
+
+        Ok(Some(Self {
+            graph,
+            input_time_base: time_base,
+            rate_factor: config.rate_factor(),
+            input_duration: 0,
+        }))
+    }
+
+    /// Check whether the graph still fits the frame's format and size
+    pub fn matches(&self, frame: &ffmpeg::frame::Video) -> bool {
+        self.graph.matches(frame, self.input_time_base)
+    }
+
+    /// Time base of the deinterlaced frames
+    pub fn time_base(&self) -> ffmpeg::Rational {
+        self.graph.time_base()
+    }
+
+    /// Feed a decoded frame
+    pub fn push(&mut self, frame: &ffmpeg::frame::Video) -> Result<()> {
+        if frame.duration() > 0 {
+            self.input_duration = frame.duration();
+        }
+
+        self.graph.push(frame)
+    }
+
+    /// Signal the end of the stream so buffered fields are released
+    pub fn flush(&mut self) -> Result<()> {
+        self.graph.flush()
+    }
+
+    /// Take the next deinterlaced frame
+    ///
+    /// Frames without a duration get the input duration divided by the
+    /// number of frames each input frame turns into.
+    pub fn pull(&mut self) -> Option<ffmpeg::frame::Video> {
+        let mut frame = self.graph.pull()?;
+
+        if frame.duration() <= 0 && self.input_duration > 0 {
+            let duration = self.input_duration.rescale(self.input_time_base, self.time_base()) / self.rate_factor;
+            // SAFETY: the frame was just filled by the sink and is exclusively ours
+            unsafe {
+                (*frame.as_mut_ptr()).duration = duration;
+            }
+        }
+
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_order_from_flags() {
+        assert_eq!(FieldOrder::from_flags(false, true), FieldOrder::Progressive);
+        assert_eq!(FieldOrder::from_flags(true, true), FieldOrder::TopFirst);
+        assert_eq!(FieldOrder::from_flags(true, false), FieldOrder::BottomFirst);
+    }
+
+    #[test]
+    fn test_filter_spec() {
+        let config = DeinterlaceConfig::default();
+        assert_eq!(config.filter_spec(FieldOrder::Progressive), None);
+        assert_eq!(
+            config.filter_spec(FieldOrder::TopFirst).unwrap(),
+            "yadif=mode=send_field:parity=tff:deint=interlaced"
+        );
+        assert_eq!(config.rate_factor(), 2);
+
+        let bwdif = DeinterlaceConfig {
+            mode: Some(DeinterlaceMode::Bwdif),
+            double_rate: false,
+            force: true,
+        };
+        assert_eq!(
+            bwdif.filter_spec(FieldOrder::Progressive).unwrap(),
+            "bwdif=mode=send_frame:parity=auto:deint=all"
+        );
+        assert_eq!(bwdif.rate_factor(), 1);
+
+        let bob = DeinterlaceConfig {
+            mode: Some(DeinterlaceMode::Bob),
+            double_rate: false,
+            force: false,
+        };
+        assert!(bob.filter_spec(FieldOrder::BottomFirst).unwrap().starts_with("field=type=bottom,"));
+
+        let off = DeinterlaceConfig { mode: None, ..config };
+        assert_eq!(off.filter_spec(FieldOrder::TopFirst), None);
+    }
+}
@@ -4,22 +4,45 @@
 //! support for hardware acceleration and various codecs.
 
 use crate::decoder::{
-    AudioSamples, AudioStreamInfo, ColorSpace, Decoder, HdrMetadata, HwAccelMethod,
-    MasteringDisplay, MediaInfo, MediaMetadata, SubtitleStreamInfo, VideoStreamInfo,
+    ArchivePath, AudioSamples, AudioStreamInfo, ColorSpace, Decoder, HdrMetadata, HwAccelMethod, LiveWindow,
+    MasteringDisplay, MediaInfo, MediaMetadata, QualitySelection, SubtitleStreamInfo, VideoStreamInfo,
 };
 use crate::renderer::{FrameData, VideoFrame};
 use crate::utils::error::{CCPlayerError, Result};
 use ffmpeg_next as ffmpeg;
 use ffmpeg_next::{format, media, util};
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 
+use super::adaptive::{self, AdaptiveConfig, AdaptiveSource};
+use super::analysis::{AnalysisReport, BitrateMeter, PacketAnalyzer, PacketInfo};
+use super::avio::{self, CustomIo, InterruptGuard};
+use super::cache::{CacheConfig, CacheHandle, CachedSource};
+use super::deinterlace::{DeinterlaceConfig, Deinterlacer};
+use super::filter_graph::{AudioFilterGraph, VideoFilterGraph};
+use super::network::{HttpSource, ReconnectPolicy, StallWatchdog};
+use super::source::{MediaSource, StreamSource};
 use super::frame_queue::FrameQueue;
 use super::hw_accel::{HardwareAccelerator, HwAccelConfig};
+use super::still_image::exif_orientation;
 use super::stream_info::StreamInfoExtractor;
+use super::subtitle::{ass_text, SubtitleCue, SubtitleTrack};
+use super::timestamps::{StreamTiming, TimestampNormalizer};
+use super::trim::SampleTrim;
+
+/// Audio buffers decoded ahead when pre-rolling the next playlist item
+const PREROLL_BUFFERS: usize = 8;
+
+/// Media time the live bitrates are averaged over
+const BITRATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Offset keeping the stream ids of a separate audio input apart from the main input's
+const AUDIO_INPUT_STREAMS: usize = 1 << 16;
 
 /// FFmpeg decoder implementation
 pub struct FFmpegDecoder {
@@ -49,6 +72,88 @@ pub struct FFmpegDecoder {
     
     /// Hardware acceleration enabled
     hw_accel_enabled: bool,
+    
+    /// Adaptive streaming sessions (HLS/DASH)
+    adaptive: Option<AdaptiveSource>,
+    
+    /// Custom I/O behind `input_context` (must be dropped after it)
+    custom_io: Option<CustomIo>,
+    
+    /// Separate audio input (adaptive streams with an audio rendition)
+    audio_input_context: Option<format::context::Input>,
+    
+    /// Custom I/O behind `audio_input_context`
+    audio_custom_io: Option<CustomIo>,
+    
+    /// Stall watchdog of a network input opened by FFmpeg (must be dropped after it)
+    interrupt: Option<InterruptGuard>,
+    
+    /// How dropped network streams are recovered
+    reconnect_policy: ReconnectPolicy,
+    
+    /// After a reconnect, output before this PTS (microseconds) was already delivered
+    resume_from: Option<i64>,
+    
+    /// Read-ahead cache settings for network sources
+    cache_config: CacheConfig,
+    
+    /// Read-ahead cache of the current network source
+    cache: Option<CacheHandle>,
+    
+    /// Threads for software video decoding (0 lets FFmpeg decide)
+    thread_count: usize,
+    
+    /// Timeline tracking for live sources
+    live: Option<LiveState>,
+    
+    /// Audio output rate and channel count negotiated with the device
+    audio_output: Option<(u32, u16)>,
+    
+    /// Audio stream chosen by the user (None for FFmpeg's best stream)
+    audio_track: Option<usize>,
+    
+    /// Removes encoder delay and padding from decoded audio
+    audio_trim: Option<SampleTrim>,
+    
+    /// Next playlist item, opened and pre-rolled ahead of time
+    next: Option<Box<FFmpegDecoder>>,
+    
+    /// Thread opening the next playlist item
+    next_opening: Option<thread::JoinHandle<Result<(Box<FFmpegDecoder>, MediaInfo)>>>,
+    
+    /// Audio decoded ahead while pre-rolling
+    preroll: VecDeque<AudioSamples>,
+    
+    /// Deinterlacing settings
+    deinterlace: DeinterlaceConfig,
+    
+    /// User-defined video filtergraph description
+    video_filters: Option<String>,
+    
+    /// User-defined audio filtergraph description
+    audio_filters: Option<String>,
+    
+    /// Decoded audio not delivered yet (filters can release several buffers at once)
+    pending_audio: VecDeque<AudioSamples>,
+    
+    /// Bitrate of the video packets read
+    video_bitrate: BitrateMeter,
+    
+    /// Bitrate of the audio packets read
+    audio_bitrate: BitrateMeter,
+    
+    /// Unwraps packet timestamps and rebases them across discontinuities
+    timestamps: TimestampNormalizer,
+}
+
+/// Timeline of a live source
+#[derive(Debug, Default)]
+struct LiveState {
+    /// Newest timestamp delivered, in microseconds
+    newest_pts: i64,
+    
+    /// Demuxer timestamp minus adaptive timeline position, in microseconds
+    offset: Option<i64>,
 }
 
 /// Video decoder state
@@ -67,6 +172,30 @@ struct VideoDecoder {
     
     /// Target pixel format
     target_format: ffmpeg::format::Pixel,
+    
+    /// Deinterlacer, set up once interlaced frames show up
+    deinterlacer: Option<Deinterlacer>,
+    
+    /// User-defined filter chain, set up at the first frame
+    filters: Option<VideoFilterGraph>,
+    
+    /// Expected PTS of the next frame in microseconds, for frames without one
+    next_pts: Option<i64>,
+    
+    /// Duration of frames without one, from the stream's average frame rate
+    default_duration: i64,
+}
+
+impl VideoDecoder {
+    /// Time base of the frames leaving the deinterlacer
+    fn deinterlaced_time_base(&self) -> ffmpeg::Rational {
+        self.deinterlacer.as_ref().map_or(self.time_base, Deinterlacer::time_base)
+    }
+    
+    /// Time base of the frames leaving the last filter stage
+    fn output_time_base(&self) -> ffmpeg::Rational {
+        self.filters.as_ref().map_or_else(|| self.deinterlaced_time_base(), VideoFilterGraph::time_base)
+    }
 }
 
 /// Audio decoder state
@@ -91,6 +220,12 @@ struct AudioDecoder {
     
     /// Target channel layout
     target_layout: ffmpeg::channel_layout::ChannelLayout,
+    
+    /// User-defined filter chain, set up at the first frame
+    filters: Option<AudioFilterGraph>,
+    
+    /// Expected PTS of the next frame in microseconds, for frames without one
+    next_pts: Option<i64>,
 }
 
 impl FFmpegDecoder {
@@ -102,6 +237,20 @@ impl FFmpegDecoder {
         ffmpeg::log::set_level(ffmpeg::log::Level::Warning);
     }
     
+    /// Set the read-ahead cache used for HTTP sources opened from now on
+    pub fn set_cache_config(&mut self, config: CacheConfig) {
+        self.cache_config = config;
+    }
+    
+    /// Set the number of video decoding threads used for media opened from now on
+    /// 
+    /// # Arguments
+    /// 
+    /// * `count` - Thread count, 0 to match the number of CPU cores
+    pub fn set_thread_count(&mut self, count: usize) {
+        self.thread_count = count;
+    }
+    
     /// Open video stream and create decoder
     fn open_video_stream(
         &mut self,
@@ -109,14 +258,22 @@ impl FFmpegDecoder {
         hw_accel: Option<&Box<dyn HardwareAccelerator>>,
     ) -> Result<()> {
         // Find video stream
-        let stream = input
-            .streams()
-            .best(media::Type::Video)
-            .ok_or_else(|| CCPlayerError::decoder_error("No video stream found"))?;
+        let stream = match input.streams().best(media::Type::Video) {
+            Some(s) => s,
+            None => return Ok(()), // Audio-only media
+        };
         
         let stream_index = stream.index();
         let time_base = stream.time_base();
         
+        // Only an estimate for variable frame rate video; the next frame's PTS settles it
+        let frame_rate = stream.avg_frame_rate();
+        let default_duration = if frame_rate.numerator() > 0 && frame_rate.denominator() > 0 {
+            1_000_000 * frame_rate.denominator() as i64 / frame_rate.numerator() as i64
+        } else {
+            16667 // Assume ~60fps
+        };
+        
         // Get codec parameters
         let codec_params = stream.parameters();
         
@@ -128,7 +285,7 @@ impl FFmpegDecoder {
         let mut context = ffmpeg::codec::context::Context::from_parameters(codec_params)?;
         context.set_threading(ffmpeg::codec::threading::Config {
             kind: ffmpeg::codec::threading::Type::Frame,
-            count: 0, // Auto-detect
+            count: self.thread_count, // 0 = auto-detect
         });
         
         // Configure hardware acceleration if available
@@ -152,6 +309,10 @@ impl FFmpegDecoder {
             time_base,
             converter: None,
             target_format,
+            deinterlacer: None,
+            filters: None,
+            next_pts: None,
+            default_duration,
         });
         
         Ok(())
@@ -159,8 +320,15 @@ impl FFmpegDecoder {
     
     /// Open audio stream and create decoder
     fn open_audio_stream(&mut self, input: &mut format::context::Input) -> Result<()> {
-        // Find audio stream
-        let stream = match input.streams().best(media::Type::Audio) {
+        // Find audio stream: the chosen one if this input has it, else the best one
+        let chosen = self.audio_track.and_then(|index| {
+            let stream = input.stream(index).filter(|stream| stream.parameters().medium() == media::Type::Audio);
+            if stream.is_none() {
+                log::warn!("No audio stream #{}, playing the default one", index);
+            }
+            stream
+        });
+        let stream = match chosen.or_else(|| input.streams().best(media::Type::Audio)) {
             Some(s) => s,
             None => return Ok(()), // No audio stream is OK
         };
@@ -181,10 +349,13 @@ impl FFmpegDecoder {
         // Open decoder
         let decoder = context.decoder().audio()?;
         
-        // Target audio format: 32-bit float, stereo, 48kHz
+        // Target audio format: 32-bit float at the negotiated (or native) rate and layout
         let target_format = ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Planar);
-        let target_rate = 48000;
-        let target_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
+        let native_layout = source_layout(decoder.channel_layout(), decoder.channels());
+        let (target_rate, target_layout) = match self.audio_output {
+            Some((rate, channels)) => (rate, output_layout(native_layout, channels)),
+            None => (decoder.rate(), native_layout),
+        };
         
         self.audio_decoder = Some(AudioDecoder {
             decoder,
@@ -194,9 +365,594 @@ impl FFmpegDecoder {
             target_format,
             target_rate,
             target_layout,
+            filters: None,
+            next_pts: None,
         });
         
-        Ok(())
+        Ok(())
+    }
+    
+    /// Open an HLS or DASH stream through the adaptive download sessions
+    fn open_adaptive(&mut self, url: &str) -> Result<MediaInfo> {
+        self.close_inputs();
+        
+        let source = AdaptiveSource::open(url, AdaptiveConfig::default())?;
+        
+        let (mut input, io) = CustomIo::open_input(Box::new(StreamSource::new(source.main.reader())))?;
+        
+        // Extract media information
+        let extractor = StreamInfoExtractor::new();
+        let mut media_info = extractor.extract_info(&mut input, url.to_string())?;
+        
+        let audio_input = match &source.audio {
+            Some(audio) => {
+                let (mut audio_input, audio_io) = CustomIo::open_input(Box::new(StreamSource::new(audio.reader())))?;
+                let audio_info = extractor.extract_info(&mut audio_input, url.to_string())?;
+                media_info.audio_streams = audio_info.audio_streams;
+                Some((audio_input, audio_io))
+            }
+            None => None,
+        };
+        
+        // Segment containers usually lack a duration; use the manifest's
+        if media_info.duration.is_zero() {
+            if let Some(duration) = source.main.duration() {
+                media_info.duration = duration;
+            }
+        }
+        
+        media_info.variants = source.main.variants().to_vec();
+        media_info.current_variant = source.main.current_variant();
+        
+        // Only the manifest knows whether the stream is live
+        media_info.is_live = source.main.is_live();
+        if media_info.is_live {
+            media_info.duration = Duration::ZERO;
+        }
+        
+        // Setup hardware acceleration if enabled
+        if self.hw_accel_enabled {
+            let hw_config = HwAccelConfig::detect_best_method(&media_info)?;
+            if let Some(config) = hw_config {
+                self.hw_accelerator = Some(super::hw_accel::create_accelerator(config)?);
+            }
+        }
+        
+        // Open video stream
+        let hw_accelerator = self.hw_accelerator.take();
+        let opened = self.open_video_stream(&mut input, hw_accelerator.as_ref());
+        self.hw_accelerator = hw_accelerator;
+        opened?;
+        
+        // Open audio stream from the rendition input if there is one
+        match audio_input {
+            Some((mut audio_input, audio_io)) => {
+                self.open_audio_stream(&mut audio_input)?;
+                self.audio_input_context = Some(audio_input);
+                self.audio_custom_io = Some(audio_io);
+            }
+            None => self.open_audio_stream(&mut input)?,
+        }
+        
+        self.input_context = Some(input);
+        self.custom_io = Some(io);
+        self.adaptive = Some(source);
+        self.media_info = Some(media_info.clone());
+        self.live = media_info.is_live.then(LiveState::default);
+        self.audio_trim = self.stream_trim(&media_info);
+        self.eof = false;
+        self.position = Duration::ZERO;
+        
+        Ok(media_info)
+    }
+    
+    /// Reopen the adaptive main input after a new init section or a seek
+    fn reopen_adaptive_input(&mut self) -> Result<()> {
+        let reader = self.adaptive.as_ref()
+            .map(|source| StreamSource::new(source.main.reader()))
+            .ok_or_else(|| CCPlayerError::decoder_error("No adaptive stream".to_string()))?;
+        
+        // The input has to go before the I/O context it reads from
+        self.input_context = None;
+        self.custom_io = None;
+        
+        let (mut input, io) = CustomIo::open_input(Box::new(reader))?;
+        
+        let hw_accelerator = self.hw_accelerator.take();
+        let opened = self.open_video_stream(&mut input, hw_accelerator.as_ref());
+        self.hw_accelerator = hw_accelerator;
+        opened?;
+        
+        if self.audio_input_context.is_none() {
+            self.open_audio_stream(&mut input)?;
+        }
+        
+        self.input_context = Some(input);
+        self.custom_io = Some(io);
+        
+        Ok(())
+    }
+    
+    /// Reopen the separate adaptive audio input
+    fn reopen_adaptive_audio_input(&mut self) -> Result<()> {
+        let reader = match self.adaptive.as_ref().and_then(|source| source.audio.as_ref()) {
+            Some(audio) => StreamSource::new(audio.reader()),
+            None => return Ok(()),
+        };
+        
+        self.audio_input_context = None;
+        self.audio_custom_io = None;
+        
+        let (mut input, io) = CustomIo::open_input(Box::new(reader))?;
+        self.open_audio_stream(&mut input)?;
+        
+        self.audio_input_context = Some(input);
+        self.audio_custom_io = Some(io);
+        
+        Ok(())
+    }
+    
+    /// Close all inputs, dropping each before its custom I/O
+    fn close_inputs(&mut self) {
+        self.input_context = None;
+        self.custom_io = None;
+        self.interrupt = None;
+        self.audio_input_context = None;
+        self.audio_custom_io = None;
+        self.adaptive = None;
+        self.resume_from = None;
+        self.cache = None;
+        self.live = None;
+        self.audio_trim = None;
+        self.next = None;
+        self.next_opening = None;
+        self.preroll.clear();
+        self.pending_audio.clear();
+        self.video_bitrate.reset();
+        self.audio_bitrate.reset();
+        self.timestamps.reset();
+    }
+    
+    /// Trim for the encoder delay and padding of the audio stream being decoded
+    fn stream_trim(&self, media_info: &MediaInfo) -> Option<SampleTrim> {
+        let index = self.audio_decoder.as_ref()?.stream_index;
+        let audio = media_info.audio_streams.iter().find(|audio| audio.index == index)?;
+        SampleTrim::new(audio.encoder_delay, audio.encoder_padding, audio.sample_rate, media_info.duration)
+    }
+    
+    /// Remove encoder delay and padding from decoded audio
+    fn trim_audio(&self, samples: AudioSamples) -> Option<AudioSamples> {
+        match &self.audio_trim {
+            Some(trim) => trim.apply(samples),
+            None => Some(samples),
+        }
+    }
+    
+    /// Decode the start of freshly opened media ahead of time
+    /// 
+    /// Audio-only media pre-rolls a few audio buffers. Media with video is
+    /// read up to its first frame, and the audio packets on the way are
+    /// decoded too so its audio still starts with the first sample.
+    fn preroll_output(&mut self) -> Result<()> {
+        if self.video_decoder.is_some() {
+            return self.preroll_video();
+        }
+        
+        let mut preroll = VecDeque::with_capacity(PREROLL_BUFFERS);
+        while preroll.len() < PREROLL_BUFFERS {
+            match self.decode_audio()? {
+                Some(samples) => preroll.push_back(samples),
+                None => break,
+            }
+        }
+        self.preroll = preroll;
+        
+        Ok(())
+    }
+    
+    /// Decode up to the first video frame, keeping the audio read before it
+    fn preroll_video(&mut self) -> Result<()> {
+        // A separate audio input is read on its own
+        let shared_input = self.audio_input_context.is_none();
+        
+        while self.frame_queue.lock().is_empty() {
+            let input = self.input_context.as_mut()
+                .ok_or_else(|| CCPlayerError::decoder_error("No input context".to_string()))?;
+            let mut packet = match Self::next_packet(input) {
+                Ok(Some(packet)) => packet,
+                Ok(None) => break,
+                Err(e) => return Err(self.read_error(e)),
+            };
+            normalize_packet(&mut self.timestamps, input, &mut packet, 0);
+            
+            if let Some(video_decoder) = self.video_decoder.as_mut().filter(|video| packet.stream() == video.stream_index) {
+                video_decoder.decoder.send_packet(&packet)?;
+                
+                let mut decoded_frame = ffmpeg::frame::Video::empty();
+                while self.video_decoder.as_mut().is_some_and(|video| video.decoder.receive_frame(&mut decoded_frame).is_ok()) {
+                    for frame in self.filter_video_frame(&decoded_frame)? {
+                        self.frame_queue.lock().push_frame(frame)?;
+                    }
+                }
+            } else if let Some(audio_decoder) = self.audio_decoder.as_mut()
+                .filter(|audio| shared_input && packet.stream() == audio.stream_index)
+            {
+                audio_decoder.decoder.send_packet(&packet)?;
+                
+                // Delivered by decode_audio ahead of anything it reads itself
+                let mut decoded_frame = ffmpeg::frame::Audio::empty();
+                while self.audio_decoder.as_mut().is_some_and(|audio| audio.decoder.receive_frame(&mut decoded_frame).is_ok()) {
+                    let samples = self.filter_audio_frame(&decoded_frame)?;
+                    self.queue_audio(samples);
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Scan every packet of a file without decoding
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - Media file
+    /// * `gap_threshold` - Pauses between packets of a stream reported as gaps
+    pub fn analyze(path: &Path, gap_threshold: Duration) -> Result<AnalysisReport> {
+        Self::init_ffmpeg();
+        
+        let mut input = format::input(path)?;
+        
+        let mut time_bases = Vec::new();
+        let mut streams = Vec::new();
+        for stream in input.streams() {
+            let params = stream.parameters();
+            let kind = match params.medium() {
+                media::Type::Video => "video",
+                media::Type::Audio => "audio",
+                media::Type::Subtitle => "subtitle",
+                media::Type::Data => "data",
+                media::Type::Attachment => "attachment",
+                _ => "unknown",
+            };
+            time_bases.push(stream.time_base());
+            streams.push((kind.to_string(), params.id().name().to_string()));
+        }
+        
+        let mut analyzer = PacketAnalyzer::new(streams, gap_threshold);
+        while let Some(packet) = Self::next_packet(&mut input)
+            .map_err(|e| CCPlayerError::decoder_error(format!("Failed to read packet: {}", e)))?
+        {
+            if let Some(time_base) = time_bases.get(packet.stream()) {
+                analyzer.add(&packet_info(&packet, *time_base));
+            }
+        }
+        
+        Ok(analyzer.finish(&path.to_string_lossy()))
+    }
+    
+    /// Read all cues of a text subtitle stream
+    /// 
+    /// Reads through the whole input once, so call it off the playback
+    /// threads. Image-based subtitles (DVD, PGS) have no text and are rejected.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - Media file
+    /// * `stream_index` - Subtitle stream as listed by `ccplayer probe`
+    /// * `cancel` - Set to stop reading; the cues found so far are dropped
+    pub fn extract_subtitles(path: &Path, stream_index: usize, cancel: &AtomicBool) -> Result<SubtitleTrack> {
+        Self::init_ffmpeg();
+        
+        let mut input = format::input(path)?;
+        let (mut decoder, time_base) = {
+            let stream = input.stream(stream_index)
+                .filter(|stream| stream.parameters().medium() == media::Type::Subtitle)
+                .ok_or_else(|| CCPlayerError::NotFound(format!(
+                    "No subtitle stream #{} in {}", stream_index, path.display()
+                )))?;
+            let context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+            (context.decoder().subtitle()?, stream.time_base())
+        };
+        
+        let to_duration = |micros: i64| Duration::from_micros(micros.max(0) as u64);
+        let mut cues = Vec::new();
+        let mut images = false;
+        while let Some(packet) = Self::next_packet(&mut input)
+            .map_err(|e| CCPlayerError::decoder_error(format!("Failed to read packet: {}", e)))?
+        {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(CCPlayerError::Internal("Subtitle extraction cancelled".to_string()));
+            }
+            if packet.stream() != stream_index {
+                continue;
+            }
+            
+            let mut subtitle = ffmpeg::codec::subtitle::Subtitle::new();
+            if !decoder.decode(&packet, &mut subtitle)? {
+                continue;
+            }
+            
+            // Display times are in milliseconds from the packet's PTS
+            let timing = packet_info(&packet, time_base);
+            let Some(pts) = timing.pts else {
+                continue;
+            };
+            let start = pts + subtitle.start() as i64 * 1000;
+            let end = match subtitle.end() {
+                0 => pts + timing.duration,
+                end => pts + end as i64 * 1000,
+            };
+            
+            for rect in subtitle.rects() {
+                let text = match rect {
+                    ffmpeg::codec::subtitle::Rect::Text(text) => text.get().to_string(),
+                    ffmpeg::codec::subtitle::Rect::Ass(ass) => ass_text(ass.get()),
+                    _ => {
+                        images = true;
+                        continue;
+                    }
+                };
+                cues.push(SubtitleCue { start: to_duration(start), end: to_duration(end), text });
+            }
+        }
+        
+        if cues.is_empty() && images {
+            return Err(CCPlayerError::UnsupportedFormat(format!(
+                "Subtitle stream #{} is image-based, only text subtitles can be shown", stream_index
+            )));
+        }
+        Ok(SubtitleTrack::new(cues))
+    }
+    
+    /// Demuxer options for URLs opened by FFmpeg
+    fn url_options() -> ffmpeg::Dictionary<'static> {
+        let mut options = ffmpeg::Dictionary::new();
+        options.set("rtsp_transport", "tcp");
+        options.set("buffer_size", "1048576"); // 1MB buffer
+        options
+    }
+    
+    /// Read the next packet, telling end of stream apart from read errors
+    fn next_packet(input: &mut format::context::Input) -> std::result::Result<Option<ffmpeg::Packet>, ffmpeg::Error> {
+        let mut packet = ffmpeg::Packet::empty();
+        
+        loop {
+            match packet.read(input) {
+                Ok(()) => return Ok(Some(packet)),
+                Err(ffmpeg::Error::Eof) => return Ok(None),
+                Err(ffmpeg::Error::Other { errno }) if errno == util::error::EAGAIN => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    
+    /// Error for a failed packet read
+    fn read_error(&self, error: ffmpeg::Error) -> CCPlayerError {
+        match &self.media_info {
+            Some(info) if info.source.contains("://") => {
+                CCPlayerError::Network(format!("Lost stream {}: {}", info.source, error))
+            }
+            _ => error.into(),
+        }
+    }
+    
+    /// Reopen a dropped or stalled network input and resume where it broke off
+    fn reconnect(&mut self, cause: ffmpeg::Error) -> Result<()> {
+        let url = self.media_info.as_ref()
+            .map(|info| info.source.clone())
+            .ok_or_else(|| CCPlayerError::decoder_error("No stream to reconnect".to_string()))?;
+        let resume_at = self.position;
+        let policy = self.reconnect_policy;
+        
+        self.input_context = None;
+        self.interrupt = None;
+        
+        let mut last_error = cause.to_string();
+        for attempt in 0..policy.max_attempts {
+            let delay = policy.delay(attempt);
+            log::warn!(
+                "Lost {} ({}), reconnecting in {:?} ({}/{})",
+                url, last_error, delay, attempt + 1, policy.max_attempts
+            );
+            thread::sleep(delay);
+            
+            let watchdog = StallWatchdog::new(policy.stall_timeout);
+            match avio::open_url_input(&url, Self::url_options(), watchdog) {
+                Ok((input, interrupt)) => {
+                    self.input_context = Some(input);
+                    self.interrupt = Some(interrupt);
+                    self.flush()?;
+                    
+                    // Seekable streams resume at the last PTS, live ones at the live edge
+                    if self.media_info.as_ref().is_some_and(|info| !info.duration.is_zero()) {
+                        let file_time = self.timestamps.file_time(resume_at.as_micros() as i64);
+                        if let Err(e) = self.seek_input(Duration::from_micros(file_time.max(0) as u64)) {
+                            log::warn!("Could not resume {} at {:?}: {}", url, resume_at, e);
+                        }
+                    }
+                    
+                    self.resume_from = Some(resume_at.as_micros() as i64);
+                    self.eof = false;
+                    
+                    log::info!("Reconnected to {}, resuming at {:?}", url, resume_at);
+                    return Ok(());
+                }
+                Err(e) => last_error = e.to_string(),
+            }
+        }
+        
+        Err(CCPlayerError::Network(format!(
+            "Gave up on {} after {} reconnect attempts: {}",
+            url, policy.max_attempts, last_error
+        )))
+    }
+    
+    /// Seek the demuxer (not the adaptive sessions) to a timestamp
+    fn seek_input(&mut self, timestamp: Duration) -> Result<()> {
+        let input = self.input_context.as_mut()
+            .ok_or_else(|| CCPlayerError::decoder_error("No input context".to_string()))?;
+        
+        let video_decoder = self.video_decoder.as_ref()
+            .ok_or_else(|| CCPlayerError::decoder_error("No video decoder".to_string()))?;
+        
+        // Convert timestamp to stream time base
+        let stream_timestamp = (timestamp.as_secs_f64() * 
+            video_decoder.time_base.denominator() as f64 / 
+            video_decoder.time_base.numerator() as f64) as i64;
+        
+        // Seek to timestamp
+        input.seek(stream_timestamp, stream_timestamp..stream_timestamp)?;
+        
+        Ok(())
+    }
+    
+    /// Record a delivered timestamp for the live window
+    fn note_live_pts(&mut self, pts: i64) {
+        let stream_start = self.adaptive.as_ref().and_then(|source| source.main.stream_start());
+        let Some(live) = self.live.as_mut() else {
+            return;
+        };
+        
+        live.newest_pts = live.newest_pts.max(pts);
+        
+        // The first output after opening or seeking starts at the first delivered segment
+        if live.offset.is_none() {
+            if let Some(start) = stream_start {
+                live.offset = Some(pts - start.as_micros() as i64);
+            }
+        }
+    }
+    
+    /// Whether output at `pts` was already delivered before a reconnect
+    fn already_delivered(&self, pts: i64) -> bool {
+        self.resume_from.is_some_and(|resume| pts < resume)
+    }
+    
+    /// Run a decoded frame through deinterlacing and the user's filters
+    /// and convert the results
+    /// 
+    /// Either stage may hold frames back or turn one frame into several.
+    fn filter_video_frame(&mut self, frame: &ffmpeg::frame::Video) -> Result<Vec<VideoFrame>> {
+        let deinterlaced = self.deinterlace_frame(frame)?;
+        let stage: Vec<&ffmpeg::frame::Video> = match &deinterlaced {
+            Some(fields) => fields.iter().collect(),
+            None => vec![frame],
+        };
+        
+        let filtered = self.apply_video_filters(&stage)?;
+        let output: Vec<&ffmpeg::frame::Video> = match &filtered {
+            Some(frames) => frames.iter().collect(),
+            None => stage,
+        };
+        
+        output.into_iter().map(|frame| self.convert_video_frame(frame)).collect()
+    }
+    
+    /// Release the frames both filter stages hold back at the end of the stream
+    fn flush_video_filters(&mut self) -> Result<Vec<VideoFrame>> {
+        let mut fields = Vec::new();
+        if let Some(deinterlacer) = self.video_decoder.as_mut().and_then(|video_decoder| video_decoder.deinterlacer.as_mut()) {
+            deinterlacer.flush()?;
+            while let Some(field) = deinterlacer.pull() {
+                fields.push(field);
+            }
+        }
+        
+        let stage: Vec<&ffmpeg::frame::Video> = fields.iter().collect();
+        let mut output = self.apply_video_filters(&stage)?.unwrap_or(fields);
+        if let Some(filters) = self.video_decoder.as_mut().and_then(|video_decoder| video_decoder.filters.as_mut()) {
+            filters.flush()?;
+            while let Some(frame) = filters.pull() {
+                output.push(frame);
+            }
+        }
+        
+        output.iter().map(|frame| self.convert_video_frame(frame)).collect()
+    }
+    
+    /// Deinterlace a decoded frame if needed
+    /// 
+    /// The deinterlacer is set up at the first frame that needs it. From
+    /// then on every frame passes through it so its field history stays
+    /// intact; progressive frames in mixed content are left unchanged.
+    /// 
+    /// # Returns
+    /// 
+    /// The deinterlaced frames, or None if the frame passes through as is
+    fn deinterlace_frame(&mut self, frame: &ffmpeg::frame::Video) -> Result<Option<Vec<ffmpeg::frame::Video>>> {
+        let config = self.deinterlace;
+        let video_decoder = self.video_decoder.as_mut()
+            .ok_or_else(|| CCPlayerError::decoder_error("No video decoder".to_string()))?;
+        
+        // Resolution or format changes need a new graph
+        if video_decoder.deinterlacer.as_ref().is_some_and(|deinterlacer| !deinterlacer.matches(frame)) {
+            video_decoder.deinterlacer = None;
+        }
+        if video_decoder.deinterlacer.is_none() {
+            video_decoder.deinterlacer = Deinterlacer::new(&config, frame, video_decoder.time_base)?;
+        }
+        
+        let Some(deinterlacer) = video_decoder.deinterlacer.as_mut() else {
+            return Ok(None);
+        };
+        deinterlacer.push(frame)?;
+        
+        // Two fields per frame at double rate
+        let mut fields = Vec::new();
+        while let Some(field) = deinterlacer.pull() {
+            fields.push(field);
+        }
+        Ok(Some(fields))
+    }
+    
+    /// Run frames through the user-defined filter chain
+    /// 
+    /// An invalid chain is reported once and dropped so playback continues
+    /// unfiltered.
+    /// 
+    /// # Returns
+    /// 
+    /// The filtered frames, or None if no filters are set
+    fn apply_video_filters(&mut self, frames: &[&ffmpeg::frame::Video]) -> Result<Option<Vec<ffmpeg::frame::Video>>> {
+        let Some(spec) = self.video_filters.clone() else {
+            return Ok(None);
+        };
+        let video_decoder = self.video_decoder.as_mut()
+            .ok_or_else(|| CCPlayerError::decoder_error("No video decoder".to_string()))?;
+        
+        if let Some(first) = frames.first() {
+            // Timestamps arrive in the deinterlacer's time base once it runs
+            let time_base = video_decoder.deinterlaced_time_base();
+            if video_decoder.filters.as_ref().is_some_and(|filters| !filters.matches(first, time_base)) {
+                video_decoder.filters = None;
+            }
+            if video_decoder.filters.is_none() {
+                match VideoFilterGraph::new(&spec, first, time_base) {
+                    Ok(filters) => {
+                        log::info!("Applying video filters: {}", spec);
+                        video_decoder.filters = Some(filters);
+                    }
+                    Err(e) => {
+                        log::error!("Video filter '{}' failed, playing unfiltered: {}", spec, e);
+                        self.video_filters = None;
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+        
+        let Some(filters) = video_decoder.filters.as_mut() else {
+            return Ok(Some(Vec::new()));
+        };
+        for frame in frames {
+            filters.push(frame)?;
+        }
+        
+        let mut output = Vec::new();
+        while let Some(frame) = filters.pull() {
+            output.push(frame);
+        }
+        Ok(Some(output))
     }
     
     /// Convert FFmpeg frame to our VideoFrame format
@@ -204,25 +960,29 @@ impl FFmpegDecoder {
         let video_decoder = self.video_decoder.as_mut()
             .ok_or_else(|| CCPlayerError::decoder_error("No video decoder".to_string()))?;
         
+        // Filtered frames carry the filters' time base (finer at double rate)
+        let time_base = video_decoder.output_time_base();
+        
         // Calculate PTS in microseconds
         let pts = if frame.timestamp().is_some() {
             let pts_seconds = frame.timestamp().unwrap() as f64 * 
-                video_decoder.time_base.numerator() as f64 / 
-                video_decoder.time_base.denominator() as f64;
+                time_base.numerator() as f64 / 
+                time_base.denominator() as f64;
             (pts_seconds * 1_000_000.0) as i64
         } else {
-            0
+            video_decoder.next_pts.unwrap_or(0)
         };
         
         // Get frame duration
         let duration = if frame.duration() > 0 {
             let duration_seconds = frame.duration() as f64 *
-                video_decoder.time_base.numerator() as f64 /
-                video_decoder.time_base.denominator() as f64;
+                time_base.numerator() as f64 /
+                time_base.denominator() as f64;
             (duration_seconds * 1_000_000.0) as i64
         } else {
-            16667 // Default to ~60fps
+            video_decoder.default_duration
         };
+        video_decoder.next_pts = Some(pts + duration);
         
         // Convert pixel format if needed
         let converted_frame = if frame.format() != video_decoder.target_format {
@@ -313,8 +1073,12 @@ impl FFmpegDecoder {
         })
     }
     
-    /// Convert FFmpeg audio frame to our AudioSamples format
-    fn convert_audio_frame(&mut self, frame: &ffmpeg::frame::Audio) -> Result<AudioSamples> {
+    /// Run a decoded audio frame through the user's filters and convert the results
+    /// 
+    /// Filters may hold audio back or release several buffers at once.
+    /// Timestamps of filtered audio are mapped back onto the media timeline,
+    /// so tempo changes keep the audio clock in step with video.
+    fn filter_audio_frame(&mut self, frame: &ffmpeg::frame::Audio) -> Result<Vec<AudioSamples>> {
         let audio_decoder = self.audio_decoder.as_mut()
             .ok_or_else(|| CCPlayerError::decoder_error("No audio decoder".to_string()))?;
         
@@ -325,36 +1089,166 @@ impl FFmpegDecoder {
                 audio_decoder.time_base.denominator() as f64;
             (pts_seconds * 1_000_000.0) as i64
         } else {
-            0
+            audio_decoder.next_pts.unwrap_or(0)
         };
+        audio_decoder.next_pts = Some(pts + frame.samples() as i64 * 1_000_000 / frame.rate().max(1) as i64);
         
-        // Create resampler if needed
-        if audio_decoder.resampler.is_none() ||
-           frame.rate() != audio_decoder.target_rate ||
-           frame.format() != audio_decoder.target_format ||
-           frame.channel_layout() != audio_decoder.target_layout {
-            
-            audio_decoder.resampler = Some(
-                ffmpeg::software::resampling::Context::get(
-                    frame.format(),
-                    frame.channel_layout(),
-                    frame.rate(),
-                    audio_decoder.target_format,
-                    audio_decoder.target_layout,
-                    audio_decoder.target_rate,
-                )?
-            );
+        let Some(spec) = self.audio_filters.clone() else {
+            return Ok(vec![self.convert_audio_frame(frame, pts)?]);
+        };
+        
+        // Format changes need a new graph
+        let layout = source_layout(frame.channel_layout(), frame.channels());
+        if audio_decoder.filters.as_ref().is_some_and(|filters| !filters.matches(frame, layout)) {
+            audio_decoder.filters = None;
+        }
+        if audio_decoder.filters.is_none() {
+            match AudioFilterGraph::new(&spec, frame, layout, audio_decoder.time_base) {
+                Ok(filters) => {
+                    log::info!("Applying audio filters: {}", spec);
+                    audio_decoder.filters = Some(filters);
+                }
+                Err(e) => {
+                    log::error!("Audio filter '{}' failed, playing unfiltered: {}", spec, e);
+                    self.audio_filters = None;
+                    return Ok(vec![self.convert_audio_frame(frame, pts)?]);
+                }
+            }
+        }
+        
+        let Some(filters) = audio_decoder.filters.as_mut() else {
+            return Ok(Vec::new());
+        };
+        filters.push(frame)?;
+        self.drain_audio_filters()
+    }
+    
+    /// Convert the audio the filters have ready
+    fn drain_audio_filters(&mut self) -> Result<Vec<AudioSamples>> {
+        let mut output = Vec::new();
+        while let Some((filtered, pts)) = self.audio_decoder.as_mut()
+            .and_then(|audio_decoder| audio_decoder.filters.as_mut())
+            .and_then(AudioFilterGraph::pull)
+        {
+            output.push(self.convert_audio_frame(&filtered, pts)?);
+        }
+        Ok(output)
+    }
+    
+    /// Release the audio the filters hold back at the end of the stream
+    fn flush_audio_filters(&mut self) -> Result<Vec<AudioSamples>> {
+        match self.audio_decoder.as_mut().and_then(|audio_decoder| audio_decoder.filters.as_mut()) {
+            Some(filters) => {
+                filters.flush()?;
+                self.drain_audio_filters()
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+    
+    /// Trim decoded audio and queue it for delivery
+    fn queue_audio(&mut self, samples: Vec<AudioSamples>) {
+        for samples in samples {
+            let Some(samples) = self.trim_audio(samples) else {
+                continue;
+            };
+            if !self.already_delivered(samples.pts) {
+                self.note_live_pts(samples.pts);
+                self.pending_audio.push_back(samples);
+            }
+        }
+    }
+    
+    /// Flush the video decoder and its filters into the frame queue at the end of the input
+    fn drain_video_decoder(&mut self) -> Result<()> {
+        let Some(video_decoder) = self.video_decoder.as_mut() else {
+            return Ok(());
+        };
+        video_decoder.decoder.send_eof()?;
+        
+        let mut decoded_frame = ffmpeg::frame::Video::empty();
+        while self.video_decoder.as_mut().is_some_and(|video| video.decoder.receive_frame(&mut decoded_frame).is_ok()) {
+            for frame in self.filter_video_frame(&decoded_frame)? {
+                self.frame_queue.lock().push_frame(frame)?;
+            }
+        }
+        
+        // The filters hold back their last frames until the end
+        for frame in self.flush_video_filters()? {
+            self.frame_queue.lock().push_frame(frame)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Flush the audio decoder and its filters into the pending audio at the end of the input
+    fn drain_audio_decoder(&mut self) -> Result<()> {
+        let Some(audio_decoder) = self.audio_decoder.as_mut() else {
+            return Ok(());
+        };
+        audio_decoder.decoder.send_eof()?;
+        
+        let mut decoded_frame = ffmpeg::frame::Audio::empty();
+        while self.audio_decoder.as_mut().is_some_and(|audio| audio.decoder.receive_frame(&mut decoded_frame).is_ok()) {
+            let samples = self.filter_audio_frame(&decoded_frame)?;
+            self.queue_audio(samples);
         }
         
-        // Resample audio
+        // Filters like loudnorm hold back audio until the end
+        let samples = self.flush_audio_filters()?;
+        self.queue_audio(samples);
+        
+        Ok(())
+    }
+    
+    /// Convert FFmpeg audio frame to our AudioSamples format
+    /// 
+    /// # Arguments
+    /// 
+    /// * `frame` - Decoded or filtered audio
+    /// * `pts` - Media timestamp of the frame in microseconds
+    fn convert_audio_frame(&mut self, frame: &ffmpeg::frame::Audio, pts: i64) -> Result<AudioSamples> {
+        let audio_decoder = self.audio_decoder.as_mut()
+            .ok_or_else(|| CCPlayerError::decoder_error("No audio decoder".to_string()))?;
+        
+        // Frames already in the output format are used as they are
+        let layout = source_layout(frame.channel_layout(), frame.channels());
+        let passthrough = frame.format() == audio_decoder.target_format
+            && frame.rate() == audio_decoder.target_rate
+            && layout == audio_decoder.target_layout;
+        
         let mut resampled = ffmpeg::frame::Audio::empty();
-        let delay = audio_decoder.resampler.as_ref().unwrap()
-            .run(frame, &mut resampled)?
-            .unwrap_or(0);
+        let resampled = if passthrough {
+            frame
+        } else {
+            // (Re)create the resampler when the source format changes
+            let stale = match &audio_decoder.resampler {
+                Some(resampler) => {
+                    let input = resampler.input();
+                    input.format != frame.format() || input.channel_layout != layout || input.rate != frame.rate()
+                }
+                None => true,
+            };
+            if stale {
+                audio_decoder.resampler = Some(
+                    ffmpeg::software::resampling::Context::get(
+                        frame.format(),
+                        layout,
+                        frame.rate(),
+                        audio_decoder.target_format,
+                        audio_decoder.target_layout,
+                        audio_decoder.target_rate,
+                    )?
+                );
+            }
+            
+            audio_decoder.resampler.as_mut().unwrap().run(frame, &mut resampled)?;
+            &resampled
+        };
         
         // Convert to f32 samples
         let sample_count = resampled.samples();
-        let channels = resampled.channel_layout().channels() as usize;
+        let channels = resampled.channels() as usize;
         let mut data = Vec::with_capacity(sample_count * channels);
         
         // Extract samples (assuming planar f32 format)
@@ -389,16 +1283,50 @@ impl Decoder for FFmpegDecoder {
             position: Duration::ZERO,
             eof: false,
             hw_accel_enabled: true,
+            adaptive: None,
+            custom_io: None,
+            audio_input_context: None,
+            audio_custom_io: None,
+            interrupt: None,
+            reconnect_policy: ReconnectPolicy::default(),
+            resume_from: None,
+            cache_config: CacheConfig::default(),
+            cache: None,
+            thread_count: 0,
+            live: None,
+            audio_output: None,
+            audio_track: None,
+            audio_trim: None,
+            next: None,
+            next_opening: None,
+            preroll: VecDeque::new(),
+            deinterlace: DeinterlaceConfig::default(),
+            video_filters: None,
+            audio_filters: None,
+            pending_audio: VecDeque::new(),
+            video_bitrate: BitrateMeter::new(BITRATE_WINDOW),
+            audio_bitrate: BitrateMeter::new(BITRATE_WINDOW),
+            timestamps: TimestampNormalizer::default(),
         })
     }
     
     fn open_file(&mut self, path: &Path) -> Result<MediaInfo> {
+        self.close_inputs();
+        
         // Open input file
         let mut input = format::input(path)?;
         
         // Extract media information
         let extractor = StreamInfoExtractor::new();
-        let media_info = extractor.extract_info(&mut input, path.to_string_lossy())?;
+        let mut media_info = extractor.extract_info(&mut input, path.to_string_lossy())?;
+        
+        // Photos keep their orientation in EXIF rather than a display matrix
+        if media_info.is_still {
+            let orientation = std::fs::read(path).ok().and_then(|data| exif_orientation(&data));
+            if let (Some(rotation), Some(video)) = (orientation, media_info.video_streams.first_mut()) {
+                video.rotation = rotation;
+            }
+        }
         
         // Setup hardware acceleration if enabled
         if self.hw_accel_enabled {
@@ -416,6 +1344,8 @@ impl Decoder for FFmpegDecoder {
         
         self.input_context = Some(input);
         self.media_info = Some(media_info.clone());
+        self.live = media_info.is_live.then(LiveState::default);
+        self.audio_trim = self.stream_trim(&media_info);
         self.eof = false;
         self.position = Duration::ZERO;
         
@@ -423,12 +1353,31 @@ impl Decoder for FFmpegDecoder {
     }
     
     fn open_url(&mut self, url: &str) -> Result<MediaInfo> {
-        // Open input URL
-        let mut options = ffmpeg::Dictionary::new();
-        options.set("rtsp_transport", "tcp");
-        options.set("buffer_size", "1048576"); // 1MB buffer
+        // HLS and DASH are downloaded by our own sessions so we can adapt
+        if adaptive::is_adaptive_url(url) {
+            return self.open_adaptive(url);
+        }
+        
+        // Plain HTTP goes through our own source so dropped connections resume,
+        // with a read-ahead cache in front so backward seeks stay local
+        if url.starts_with("http://") || url.starts_with("https://") {
+            let source = HttpSource::open(url, self.reconnect_policy)?;
+            let cached = CachedSource::new(Box::new(source), self.cache_config)?;
+            let cache = cached.handle();
+            
+            let media_info = self.open_source(Box::new(cached))?;
+            if let Some(bitrate) = media_info.bitrate {
+                cache.set_byte_rate(bitrate as u64 / 8);
+            }
+            self.cache = Some(cache);
+            return Ok(media_info);
+        }
+        
+        self.close_inputs();
         
-        let mut input = format::input_with_dictionary(&url, options)?;
+        // Open input URL, aborting reads that stall
+        let watchdog = StallWatchdog::new(self.reconnect_policy.stall_timeout);
+        let (mut input, interrupt) = avio::open_url_input(url, Self::url_options(), watchdog)?;
         
         // Extract media information
         let extractor = StreamInfoExtractor::new();
@@ -449,25 +1398,58 @@ impl Decoder for FFmpegDecoder {
         self.open_audio_stream(&mut input)?;
         
         self.input_context = Some(input);
+        self.interrupt = Some(interrupt);
         self.media_info = Some(media_info.clone());
+        self.live = media_info.is_live.then(LiveState::default);
+        self.audio_trim = self.stream_trim(&media_info);
         self.eof = false;
         self.position = Duration::ZERO;
         
         Ok(media_info)
     }
     
-    fn decode_frame(&mut self) -> Result<Option<VideoFrame>> {
-        if self.eof {
-            return Ok(None);
+    fn open_source(&mut self, source: Box<dyn MediaSource>) -> Result<MediaInfo> {
+        self.close_inputs();
+        
+        let name = source.name();
+        let size = source.size();
+        let (mut input, io) = CustomIo::open_input(source)?;
+        
+        // Extract media information
+        let extractor = StreamInfoExtractor::new();
+        let mut media_info = extractor.extract_info(&mut input, name)?;
+        media_info.file_size = media_info.file_size.or(size);
+        
+        // Setup hardware acceleration if enabled
+        if self.hw_accel_enabled {
+            let hw_config = HwAccelConfig::detect_best_method(&media_info)?;
+            if let Some(config) = hw_config {
+                self.hw_accelerator = Some(super::hw_accel::create_accelerator(config)?);
+            }
         }
         
-        let input = self.input_context.as_mut()
-            .ok_or_else(|| CCPlayerError::decoder_error("No input context".to_string()))?;
+        // Open video stream
+        let hw_accelerator = self.hw_accelerator.take();
+        let opened = self.open_video_stream(&mut input, hw_accelerator.as_ref());
+        self.hw_accelerator = hw_accelerator;
+        opened?;
         
-        let video_decoder = self.video_decoder.as_mut()
-            .ok_or_else(|| CCPlayerError::decoder_error("No video decoder".to_string()))?;
+        // Open audio stream
+        self.open_audio_stream(&mut input)?;
+        
+        self.input_context = Some(input);
+        self.custom_io = Some(io);
+        self.media_info = Some(media_info.clone());
+        self.live = media_info.is_live.then(LiveState::default);
+        self.audio_trim = self.stream_trim(&media_info);
+        self.eof = false;
+        self.position = Duration::ZERO;
         
-        // Try to get frame from queue first
+        Ok(media_info)
+    }
+    
+    fn decode_frame(&mut self) -> Result<Option<VideoFrame>> {
+        // Try to get frame from queue first; it holds the flushed frames at the end
         {
             let mut queue = self.frame_queue.lock();
             if let Some(frame) = queue.pop_frame() {
@@ -476,115 +1458,226 @@ impl Decoder for FFmpegDecoder {
             }
         }
         
+        if self.eof {
+            return Ok(None);
+        }
+        
+        let input = self.input_context.as_mut()
+            .ok_or_else(|| CCPlayerError::decoder_error("No input context".to_string()))?;
+        
+        let video_decoder = self.video_decoder.as_mut()
+            .ok_or_else(|| CCPlayerError::decoder_error("No video decoder".to_string()))?;
+        
         // Decode new frames
         loop {
-            match input.packets().next() {
-                Some((stream, packet)) => {
-                    if stream.index() == video_decoder.stream_index {
+            match Self::next_packet(input) {
+                Ok(Some(mut packet)) => {
+                    if let Some(interrupt) = &self.interrupt {
+                        interrupt.watchdog().touch();
+                    }
+                    normalize_packet(&mut self.timestamps, input, &mut packet, 0);
+                    
+                    if packet.stream() == video_decoder.stream_index {
+                        if let Some(time) = packet_info(&packet, video_decoder.time_base).time() {
+                            self.video_bitrate.add(time, packet.size());
+                        }
+                        
                         // Send packet to decoder
                         video_decoder.decoder.send_packet(&packet)?;
                         
-                        // Receive frames
+                        // Receive frames; deinterlacing may turn one into two
                         let mut decoded_frame = ffmpeg::frame::Video::empty();
                         while video_decoder.decoder.receive_frame(&mut decoded_frame).is_ok() {
-                            let frame = self.convert_video_frame(&decoded_frame)?;
-                            
+                            for frame in self.filter_video_frame(&decoded_frame)? {
+                                if self.already_delivered(frame.pts) {
+                                    continue;
+                                }
+                                
+                                self.note_live_pts(frame.pts);
+                                self.frame_queue.lock().push_frame(frame)?;
+                            }
+                        }
+                        
+                        if let Some(frame) = self.frame_queue.lock().pop_frame() {
                             // Update position
                             self.position = Duration::from_micros(frame.pts as u64);
-                            
-                            // Add to queue or return directly
-                            let mut queue = self.frame_queue.lock();
-                            if queue.is_empty() {
-                                return Ok(Some(frame));
-                            } else {
-                                queue.push_frame(frame)?;
-                            }
+                            return Ok(Some(frame));
                         }
                     } else if let Some(audio_decoder) = &self.audio_decoder {
-                        if stream.index() == audio_decoder.stream_index {
+                        if packet.stream() == audio_decoder.stream_index {
+                            if let Some(time) = packet_info(&packet, audio_decoder.time_base).time() {
+                                self.audio_bitrate.add(time, packet.size());
+                            }
+                            
                             // Handle audio packet (for now just decode to keep sync)
                             // TODO: Properly handle audio samples
                         }
                     }
                 }
-                None => {
+                Ok(None) => {
+                    // A new init section arrived; continue with a fresh demuxer
+                    if self.adaptive.as_ref().is_some_and(|source| source.main.take_reopen_request()) {
+                        self.reopen_adaptive_input()?;
+                        return self.decode_frame();
+                    }
+                    
                     // End of stream
                     self.eof = true;
                     
-                    // Flush decoder
-                    video_decoder.decoder.send_eof()?;
+                    // Flush decoders; audio from this input is delivered by decode_audio
+                    self.drain_video_decoder()?;
+                    if self.audio_input_context.is_none() {
+                        self.drain_audio_decoder()?;
+                    }
                     
-                    let mut decoded_frame = ffmpeg::frame::Video::empty();
-                    if video_decoder.decoder.receive_frame(&mut decoded_frame).is_ok() {
-                        let frame = self.convert_video_frame(&decoded_frame)?;
+                    let frame = self.frame_queue.lock().pop_frame();
+                    if let Some(frame) = &frame {
                         self.position = Duration::from_micros(frame.pts as u64);
-                        return Ok(Some(frame));
+                    }
+                    return Ok(frame);
+                }
+                Err(e) => {
+                    // Network inputs opened by FFmpeg drop or stall; our own sources reconnect internally
+                    if self.interrupt.is_some() {
+                        self.reconnect(e)?;
+                        return self.decode_frame();
                     }
                     
-                    return Ok(None);
+                    return Err(self.read_error(e));
                 }
             }
         }
     }
     
     fn decode_audio(&mut self) -> Result<Option<AudioSamples>> {
+        if let Some(samples) = self.preroll.pop_front().or_else(|| self.pending_audio.pop_front()) {
+            return Ok(Some(samples));
+        }
+        
         if self.eof {
             return Ok(None);
         }
         
-        let input = self.input_context.as_mut()
-            .ok_or_else(|| CCPlayerError::decoder_error("No input context".to_string()))?;
+        // Adaptive streams may carry audio in a separate rendition
+        let stream_base = if self.audio_input_context.is_some() { AUDIO_INPUT_STREAMS } else { 0 };
+        let input = match self.audio_input_context.as_mut() {
+            Some(input) => input,
+            None => self.input_context.as_mut()
+                .ok_or_else(|| CCPlayerError::decoder_error("No input context".to_string()))?,
+        };
         
         let audio_decoder = self.audio_decoder.as_mut()
             .ok_or_else(|| CCPlayerError::decoder_error("No audio decoder".to_string()))?;
         
         // Decode audio frames
         loop {
-            match input.packets().next() {
-                Some((stream, packet)) => {
-                    if stream.index() == audio_decoder.stream_index {
+            match Self::next_packet(input) {
+                Ok(Some(mut packet)) => {
+                    if let Some(interrupt) = &self.interrupt {
+                        interrupt.watchdog().touch();
+                    }
+                    normalize_packet(&mut self.timestamps, input, &mut packet, stream_base);
+                    
+                    if packet.stream() == audio_decoder.stream_index {
+                        if let Some(time) = packet_info(&packet, audio_decoder.time_base).time() {
+                            self.audio_bitrate.add(time, packet.size());
+                        }
+                        
                         // Send packet to decoder
                         audio_decoder.decoder.send_packet(&packet)?;
                         
                         // Receive frames
                         let mut decoded_frame = ffmpeg::frame::Audio::empty();
                         if audio_decoder.decoder.receive_frame(&mut decoded_frame).is_ok() {
-                            return Ok(Some(self.convert_audio_frame(&decoded_frame)?));
+                            let samples = self.filter_audio_frame(&decoded_frame)?;
+                            self.queue_audio(samples);
+                            if let Some(samples) = self.pending_audio.pop_front() {
+                                return Ok(Some(samples));
+                            }
+                        }
+                    } else if let Some(video_decoder) = &self.video_decoder {
+                        if packet.stream() == video_decoder.stream_index {
+                            if let Some(time) = packet_info(&packet, video_decoder.time_base).time() {
+                                self.video_bitrate.add(time, packet.size());
+                            }
                         }
                     }
                 }
-                None => {
+                Ok(None) => {
+                    if self.adaptive.as_ref()
+                        .and_then(|source| source.audio.as_ref())
+                        .is_some_and(|audio| audio.take_reopen_request())
+                    {
+                        self.reopen_adaptive_audio_input()?;
+                        return self.decode_audio();
+                    }
+                    
                     // End of stream
                     self.eof = true;
                     
-                    // Flush decoder
-                    audio_decoder.decoder.send_eof()?;
+                    // Flush decoders; video from this input is delivered by decode_frame
+                    self.drain_audio_decoder()?;
+                    if stream_base == 0 {
+                        self.drain_video_decoder()?;
+                    }
                     
-                    let mut decoded_frame = ffmpeg::frame::Audio::empty();
-                    if audio_decoder.decoder.receive_frame(&mut decoded_frame).is_ok() {
-                        return Ok(Some(self.convert_audio_frame(&decoded_frame)?));
+                    return Ok(self.pending_audio.pop_front());
+                }
+                Err(e) => {
+                    if self.interrupt.is_some() {
+                        self.reconnect(e)?;
+                        return self.decode_audio();
                     }
                     
-                    return Ok(None);
+                    return Err(self.read_error(e));
                 }
             }
         }
     }
     
     fn seek(&mut self, timestamp: Duration) -> Result<()> {
-        let input = self.input_context.as_mut()
-            .ok_or_else(|| CCPlayerError::decoder_error("No input context".to_string()))?;
+        self.preroll.clear();
+        self.pending_audio.clear();
+        self.video_bitrate.reset();
+        self.audio_bitrate.reset();
         
-        let video_decoder = self.video_decoder.as_ref()
-            .ok_or_else(|| CCPlayerError::decoder_error("No video decoder".to_string()))?;
+        // Adaptive streams restart downloading at the target segment
+        if let Some(source) = &self.adaptive {
+            // Live positions are presentation timestamps; the sessions use their own timeline
+            let target = match self.live.as_mut() {
+                Some(live) => {
+                    let offset = live.offset.take().unwrap_or(0);
+                    Duration::from_micros((timestamp.as_micros() as i64 - offset).max(0) as u64)
+                }
+                None => timestamp,
+            };
+            
+            source.main.seek(target);
+            if let Some(audio) = &source.audio {
+                audio.seek(target);
+            }
+            
+            self.reopen_adaptive_input()?;
+            self.reopen_adaptive_audio_input()?;
+            self.flush()?;
+            self.timestamps.reset();
+            
+            self.position = timestamp;
+            self.eof = false;
+            
+            return Ok(());
+        }
         
-        // Convert timestamp to stream time base
-        let stream_timestamp = (timestamp.as_secs_f64() * 
-            video_decoder.time_base.denominator() as f64 / 
-            video_decoder.time_base.numerator() as f64) as i64;
+        // Live protocols cannot seek; dropping queued output jumps to the edge
+        if self.live.is_some() {
+            self.frame_queue.lock().clear();
+            return Ok(());
+        }
         
-        // Seek to timestamp
-        input.seek(stream_timestamp, stream_timestamp..stream_timestamp)?;
+        // Positions are normalized; the demuxer seeks on the file's own timeline
+        let file_time = self.timestamps.seek(timestamp.as_micros() as i64);
+        self.seek_input(Duration::from_micros(file_time.max(0) as u64))?;
+        self.resume_from = None;
         
         // Flush decoders
         self.flush()?;
@@ -609,10 +1702,16 @@ impl Decoder for FFmpegDecoder {
     fn flush(&mut self) -> Result<()> {
         if let Some(video_decoder) = &mut self.video_decoder {
             video_decoder.decoder.flush();
+            // Field history from before a seek must not blend into new frames
+            video_decoder.deinterlacer = None;
+            video_decoder.filters = None;
+            video_decoder.next_pts = None;
         }
         
         if let Some(audio_decoder) = &mut self.audio_decoder {
             audio_decoder.decoder.flush();
+            audio_decoder.filters = None;
+            audio_decoder.next_pts = None;
         }
         
         self.frame_queue.lock().clear();
@@ -623,6 +1722,13 @@ impl Decoder for FFmpegDecoder {
     fn set_hardware_acceleration(&mut self, enabled: bool) -> Result<()> {
         self.hw_accel_enabled = enabled;
         
+        // Custom sources are consumed when opened and cannot be reopened (URLs can)
+        let from_url = self.media_info.as_ref().is_some_and(|info| info.source.contains("://"));
+        if self.custom_io.is_some() && !from_url {
+            log::info!("Hardware acceleration change applies to the next opened source");
+            return Ok(());
+        }
+        
         // If we have an open file, we need to reopen it with new settings
         if let Some(media_info) = &self.media_info {
             let source = media_info.source.clone();
@@ -633,17 +1739,269 @@ impl Decoder for FFmpegDecoder {
             self.audio_decoder = None;
             self.hw_accelerator = None;
             
+            // The next playlist item stays prepared for the gapless transition
+            let next = self.next.take();
+            let next_opening = self.next_opening.take();
+            
             // Reopen with new settings
-            if source.starts_with("http://") || source.starts_with("https://") || 
+            let reopened = if source.starts_with("http://") || source.starts_with("https://") || 
                source.starts_with("rtmp://") || source.starts_with("rtsp://") {
-                self.open_url(&source)?;
+                self.open_url(&source)
             } else {
-                self.open_file(Path::new(&source))?;
-            }
+                self.open_file(Path::new(&source))
+            };
+            
+            self.next = next;
+            self.next_opening = next_opening;
+            reopened?;
+        }
+        
+        Ok(())
+    }
+    
+    fn set_deinterlace(&mut self, config: DeinterlaceConfig) -> Result<()> {
+        self.deinterlace = config;
+        
+        // Rebuilt with the new settings at the next interlaced frame
+        if let Some(video_decoder) = self.video_decoder.as_mut() {
+            video_decoder.deinterlacer = None;
+        }
+        
+        Ok(())
+    }
+    
+    fn set_audio_track(&mut self, index: Option<usize>) -> Result<()> {
+        self.audio_track = index;
+        Ok(())
+    }
+    
+    fn set_video_filters(&mut self, filters: Option<&str>) -> Result<()> {
+        let filters = filters.map(str::trim).filter(|spec| !spec.is_empty());
+        if let Some(spec) = filters {
+            VideoFilterGraph::check(spec)?;
+        }
+        
+        self.video_filters = filters.map(str::to_string);
+        
+        // Rebuilt with the new chain at the next frame
+        if let Some(video_decoder) = self.video_decoder.as_mut() {
+            video_decoder.filters = None;
+        }
+        
+        Ok(())
+    }
+    
+    fn set_audio_filters(&mut self, filters: Option<&str>) -> Result<()> {
+        let filters = filters.map(str::trim).filter(|spec| !spec.is_empty());
+        if let Some(spec) = filters {
+            AudioFilterGraph::check(spec)?;
+        }
+        
+        self.audio_filters = filters.map(str::to_string);
+        
+        // Rebuilt with the new chain at the next frame
+        if let Some(audio_decoder) = self.audio_decoder.as_mut() {
+            audio_decoder.filters = None;
+        }
+        
+        Ok(())
+    }
+    
+    fn set_quality(&mut self, selection: QualitySelection) -> Result<()> {
+        let source = self.adaptive.as_ref()
+            .ok_or_else(|| CCPlayerError::UnsupportedFormat("Quality selection requires an HLS or DASH stream".to_string()))?;
+        
+        source.main.set_quality(selection)
+    }
+    
+    fn prepare_next(&mut self, source: &str) -> Result<()> {
+        let mut next = Box::new(Self::new()?);
+        next.hw_accel_enabled = self.hw_accel_enabled;
+        next.reconnect_policy = self.reconnect_policy;
+        next.cache_config = self.cache_config;
+        next.thread_count = self.thread_count;
+        next.deinterlace = self.deinterlace;
+        next.audio_track = self.audio_track;
+        next.video_filters = self.video_filters.clone();
+        next.audio_filters = self.audio_filters.clone();
+        
+        // Deliver audio in the current output format so the audio stream carries on
+        next.audio_output = self.audio_output.or_else(|| {
+            self.audio_decoder.as_ref()
+                .map(|audio| (audio.target_rate, audio.target_layout.channels() as u16))
+        });
+        
+        // Opening a network item can take a while; decoding carries on meanwhile
+        let source = source.to_string();
+        let opening = thread::Builder::new()
+            .name("next-item".to_string())
+            .spawn(move || {
+                let media_info = if source.contains("://") {
+                    next.open_url(&source)?
+                } else {
+                    match ArchivePath::parse(Path::new(&source)) {
+                        Some(archive) if archive.entry.is_some() => next.open_source(archive.open_entry()?)?,
+                        _ => next.open_file(Path::new(&source))?,
+                    }
+                };
+                next.preroll_output()?;
+                
+                log::debug!("Prepared {} for gapless playback", source);
+                Ok((next, media_info))
+            })?;
+        
+        self.next = None;
+        self.next_opening = Some(opening);
+        Ok(())
+    }
+    
+    fn next_prepared(&mut self) -> Option<Result<MediaInfo>> {
+        if !self.next_opening.as_ref()?.is_finished() {
+            return None;
+        }
+        
+        let opened = self.next_opening.take()?.join()
+            .unwrap_or_else(|_| Err(CCPlayerError::Internal("Opening the next item panicked".to_string())));
+        Some(opened.map(|(next, media_info)| {
+            self.next = Some(next);
+            media_info
+        }))
+    }
+    
+    fn advance(&mut self) -> Result<Option<MediaInfo>> {
+        let Some(next) = self.next.take() else {
+            return Ok(None);
+        };
+        
+        // The old inputs are closed when the previous decoder drops
+        *self = *next;
+        Ok(self.media_info.clone())
+    }
+    
+    fn decode_next_audio(&mut self) -> Result<Option<AudioSamples>> {
+        self.next.as_mut()
+            .ok_or_else(|| CCPlayerError::decoder_error("No next item prepared".to_string()))?
+            .decode_audio()
+    }
+    
+    fn set_audio_output(&mut self, sample_rate: u32, channels: u16) -> Result<()> {
+        self.audio_output = Some((sample_rate, channels));
+        
+        if let Some(audio_decoder) = self.audio_decoder.as_mut() {
+            let native_layout = source_layout(audio_decoder.decoder.channel_layout(), audio_decoder.decoder.channels());
+            audio_decoder.target_rate = sample_rate;
+            audio_decoder.target_layout = output_layout(native_layout, channels);
+            audio_decoder.resampler = None;
         }
         
         Ok(())
     }
+    
+    fn current_variant(&self) -> Option<usize> {
+        self.adaptive.as_ref().and_then(|source| source.main.current_variant())
+    }
+    
+    fn live_window(&self) -> Option<LiveWindow> {
+        let live = self.live.as_ref()?;
+        let to_pts = |micros: i64| Duration::from_micros(micros.max(0) as u64);
+        
+        match self.adaptive.as_ref().and_then(|source| source.main.live_window()) {
+            Some((start, end)) => {
+                let offset = live.offset?;
+                Some(LiveWindow {
+                    start: to_pts(start.as_micros() as i64 + offset),
+                    end: to_pts(end.as_micros() as i64 + offset),
+                })
+            }
+            // Without a playlist only the newest data is available
+            None => {
+                let edge = to_pts(live.newest_pts);
+                Some(LiveWindow { start: edge, end: edge })
+            }
+        }
+    }
+    
+    fn buffered_ranges(&self) -> Vec<(Duration, Duration)> {
+        let (Some(cache), Some(info)) = (&self.cache, &self.media_info) else {
+            return Vec::new();
+        };
+        
+        // Map bytes to time assuming a constant bitrate
+        let Some(size) = cache.size().filter(|size| *size > 0) else {
+            return Vec::new();
+        };
+        let to_time = |bytes: u64| info.duration.mul_f64(bytes as f64 / size as f64);
+        
+        cache.buffered_ranges()
+            .into_iter()
+            .map(|range| (to_time(range.start), to_time(range.end)))
+            .collect()
+    }
+    
+    fn bitrates(&self) -> (u32, u32) {
+        (self.video_bitrate.bitrate(), self.audio_bitrate.bitrate())
+    }
+}
+
+/// Rewrite a packet's timestamps onto the normalized timeline
+/// 
+/// `stream_base` is added to the stream index so streams of different
+/// inputs are told apart.
+fn normalize_packet(
+    normalizer: &mut TimestampNormalizer,
+    input: &format::context::Input,
+    packet: &mut ffmpeg::Packet,
+    stream_base: usize,
+) {
+    let Some(stream) = input.stream(packet.stream()) else {
+        return;
+    };
+    let time_base = stream.time_base();
+    // SAFETY: the stream belongs to `input`, which outlives this read
+    let wrap_bits = unsafe { (*stream.as_ptr()).pts_wrap_bits };
+    let timing = StreamTiming {
+        numerator: time_base.numerator(),
+        denominator: time_base.denominator(),
+        wrap_bits: wrap_bits.max(0) as u32,
+    };
+    
+    let (pts, dts) = normalizer.normalize(stream_base + packet.stream(), timing, packet.pts(), packet.dts(), packet.duration());
+    packet.set_pts(pts);
+    packet.set_dts(dts);
+}
+
+/// Packet timestamps converted to microseconds
+fn packet_info(packet: &ffmpeg::Packet, time_base: ffmpeg::Rational) -> PacketInfo {
+    let micros = |ts: i64| {
+        (ts as i128 * 1_000_000 * time_base.numerator() as i128 / time_base.denominator().max(1) as i128) as i64
+    };
+    
+    PacketInfo {
+        stream: packet.stream(),
+        size: packet.size(),
+        pts: packet.pts().map(micros),
+        dts: packet.dts().map(micros),
+        duration: micros(packet.duration()),
+        keyframe: packet.is_key(),
+    }
+}
+
+/// Channel layout of a source, falling back to the default for its channel count
+fn source_layout(layout: ffmpeg::channel_layout::ChannelLayout, channels: u16) -> ffmpeg::channel_layout::ChannelLayout {
+    if layout.is_empty() || layout.channels() != channels as i32 {
+        ffmpeg::channel_layout::ChannelLayout::default(channels as i32)
+    } else {
+        layout
+    }
+}
+
+/// Output layout for a channel count, keeping the source layout when it fits
+fn output_layout(native: ffmpeg::channel_layout::ChannelLayout, channels: u16) -> ffmpeg::channel_layout::ChannelLayout {
+    if native.channels() == channels as i32 {
+        native
+    } else {
+        ffmpeg::channel_layout::ChannelLayout::default(channels as i32)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,364 @@
+//! libavfilter graphs for decoded video and audio
+//!
+//! Runs decoded frames through an FFmpeg filtergraph description such as
+//! `crop=1280:720,eq=contrast=1.1` or `highpass=f=200,loudnorm`. Used for
+//! deinterlacing and for user-defined filter chains.
+
+use crate::utils::error::{CCPlayerError, Result};
+use ffmpeg_next as ffmpeg;
+
+/// Filter graph between a buffer source and a buffer sink
+pub struct VideoFilterGraph {
+    /// Graph from the "in" buffer to the "out" sink
+    graph: ffmpeg::filter::Graph,
+
+    /// Format, width, height and time base the graph was built for
+    input: (ffmpeg::format::Pixel, u32, u32, ffmpeg::Rational),
+
+    /// Time base of the filtered frames
+    time_base: ffmpeg::Rational,
+}
+
+impl VideoFilterGraph {
+    /// Build a graph for frames like `frame`
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - FFmpeg filtergraph description
+    /// * `frame` - First frame to filter
+    /// * `time_base` - Time base of the frame's timestamps
+    pub fn new(spec: &str, frame: &ffmpeg::frame::Video, time_base: ffmpeg::Rational) -> Result<Self> {
+        let aspect = match frame.aspect_ratio() {
+            ratio if ratio.numerator() > 0 && ratio.denominator() > 0 => ratio,
+            _ => ffmpeg::Rational::new(1, 1),
+        };
+
+        Self::build(spec, frame.format(), frame.width(), frame.height(), time_base, aspect)
+    }
+
+    /// Check a filtergraph description without any media
+    ///
+    /// The graph is built for a small YUV 4:2:0 input, so unknown filters
+    /// and bad options are reported before playback.
+    pub fn check(spec: &str) -> Result<()> {
+        let time_base = ffmpeg::Rational::new(1, 25);
+        Self::build(spec, ffmpeg::format::Pixel::YUV420P, 64, 64, time_base, ffmpeg::Rational::new(1, 1))
+            .map(|_| ())
+            .map_err(|e| CCPlayerError::InvalidInput(format!("Invalid video filter '{}': {}", spec, e)))
+    }
+
+    /// Build the graph for the given input format
+    fn build(
+        spec: &str,
+        format: ffmpeg::format::Pixel,
+        width: u32,
+        height: u32,
+        time_base: ffmpeg::Rational,
+        aspect: ffmpeg::Rational,
+    ) -> Result<Self> {
+        let args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+            width,
+            height,
+            ffmpeg::ffi::AVPixelFormat::from(format) as i32,
+            time_base.numerator(),
+            time_base.denominator(),
+            aspect.numerator(),
+            aspect.denominator(),
+        );
+
+        let (graph, output_time_base) = build_graph("buffer", &args, "buffersink", spec)?;
+
+        Ok(Self {
+            graph,
+            input: (format, width, height, time_base),
+            time_base: output_time_base,
+        })
+    }
+
+    /// Check whether the graph still fits the frame's format, size and time base
+    pub fn matches(&self, frame: &ffmpeg::frame::Video, time_base: ffmpeg::Rational) -> bool {
+        self.input == (frame.format(), frame.width(), frame.height(), time_base)
+    }
+
+    /// Time base of the filtered frames
+    pub fn time_base(&self) -> ffmpeg::Rational {
+        self.time_base
+    }
+
+    /// Feed a frame
+    pub fn push(&mut self, frame: &ffmpeg::frame::Video) -> Result<()> {
+        input_context(&mut self.graph)?.source().add(frame)?;
+        Ok(())
+    }
+
+    /// Signal the end of the stream so buffered frames are released
+    pub fn flush(&mut self) -> Result<()> {
+        input_context(&mut self.graph)?.source().flush()?;
+        Ok(())
+    }
+
+    /// Take the next filtered frame
+    pub fn pull(&mut self) -> Option<ffmpeg::frame::Video> {
+        let mut frame = ffmpeg::frame::Video::empty();
+        self.graph.get("out")?.sink().frame(&mut frame).ok()?;
+        Some(frame)
+    }
+}
+
+/// Filter graph between an audio buffer source and sink
+pub struct AudioFilterGraph {
+    /// Graph from the "in" buffer to the "out" sink
+    graph: ffmpeg::filter::Graph,
+
+    /// Sample format, channel layout and rate the graph was built for
+    input: (ffmpeg::format::Sample, ffmpeg::channel_layout::ChannelLayout, u32),
+
+    /// Time base of the input timestamps
+    input_time_base: ffmpeg::Rational,
+
+    /// Time base of the filtered frames
+    time_base: ffmpeg::Rational,
+
+    /// Maps output timestamps back onto the media timeline
+    timeline: FilterTimeline,
+}
+
+impl AudioFilterGraph {
+    /// Build a graph for frames like `frame`
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - FFmpeg filtergraph description
+    /// * `frame` - First frame to filter
+    /// * `layout` - Channel layout of the frame
+    /// * `time_base` - Time base of the frame's timestamps
+    pub fn new(
+        spec: &str,
+        frame: &ffmpeg::frame::Audio,
+        layout: ffmpeg::channel_layout::ChannelLayout,
+        time_base: ffmpeg::Rational,
+    ) -> Result<Self> {
+        Self::build(spec, frame.format(), layout, frame.rate(), time_base)
+    }
+
+    /// Check a filtergraph description without any media
+    ///
+    /// The graph is built for 48 kHz stereo, so unknown filters and bad
+    /// options are reported before playback.
+    pub fn check(spec: &str) -> Result<()> {
+        let format = ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Planar);
+        Self::build(spec, format, ffmpeg::channel_layout::ChannelLayout::STEREO, 48000, ffmpeg::Rational::new(1, 48000))
+            .map(|_| ())
+            .map_err(|e| CCPlayerError::InvalidInput(format!("Invalid audio filter '{}': {}", spec, e)))
+    }
+
+    /// Build the graph for the given input format
+    fn build(
+        spec: &str,
+        format: ffmpeg::format::Sample,
+        layout: ffmpeg::channel_layout::ChannelLayout,
+        rate: u32,
+        time_base: ffmpeg::Rational,
+    ) -> Result<Self> {
+        let args = format!(
+            "time_base={}/{}:sample_rate={}:sample_fmt={}:channel_layout=0x{:x}",
+            time_base.numerator(),
+            time_base.denominator(),
+            rate,
+            format.name(),
+            layout.bits(),
+        );
+        let (graph, output_time_base) = build_graph("abuffer", &args, "abuffersink", spec)?;
+
+        Ok(Self {
+            graph,
+            input: (format, layout, rate),
+            input_time_base: time_base,
+            time_base: output_time_base,
+            timeline: FilterTimeline::default(),
+        })
+    }
+
+    /// Check whether the graph still fits the frame's format
+    pub fn matches(&self, frame: &ffmpeg::frame::Audio, layout: ffmpeg::channel_layout::ChannelLayout) -> bool {
+        self.input == (frame.format(), layout, frame.rate())
+    }
+
+    /// Feed a frame
+    pub fn push(&mut self, frame: &ffmpeg::frame::Audio) -> Result<()> {
+        input_context(&mut self.graph)?.source().add(frame)?;
+        self.timeline.input(samples_micros(frame.samples(), frame.rate()));
+        Ok(())
+    }
+
+    /// Signal the end of the stream so buffered audio is released
+    pub fn flush(&mut self) -> Result<()> {
+        input_context(&mut self.graph)?.source().flush()?;
+        Ok(())
+    }
+
+    /// Take the next filtered frame
+    ///
+    /// # Returns
+    ///
+    /// The frame and its position on the media timeline in microseconds
+    pub fn pull(&mut self) -> Option<(ffmpeg::frame::Audio, i64)> {
+        let mut frame = ffmpeg::frame::Audio::empty();
+        self.graph.get("out")?.sink().frame(&mut frame).ok()?;
+
+        // Filters without timestamps continue from the input time base
+        let time_base = if self.time_base.denominator() > 0 { self.time_base } else { self.input_time_base };
+        let filter_pts = frame.timestamp().map_or(0, |pts| {
+            (pts as i128 * 1_000_000 * time_base.numerator() as i128 / time_base.denominator() as i128) as i64
+        });
+        let pts = self.timeline.output(filter_pts, samples_micros(frame.samples(), frame.rate()));
+
+        Some((frame, pts))
+    }
+}
+
+/// Maps audio filter output back onto the media timeline
+///
+/// Filters like atempo stretch or shrink audio, and their output
+/// timestamps count playback time. Media time advances by the ratio of
+/// audio fed to audio produced, so the audio clock stays in step with video.
+#[derive(Debug, Default)]
+pub struct FilterTimeline {
+    /// Media position and duration of the previous output, in microseconds
+    last: Option<(f64, f64)>,
+
+    /// Input fed since the first output, in microseconds
+    input: f64,
+
+    /// Output produced after the first output, in microseconds
+    output: f64,
+}
+
+impl FilterTimeline {
+    /// Record audio fed into the filters
+    ///
+    /// Audio fed before the first output only fills the filters' delay
+    /// and does not count towards the rate.
+    pub fn input(&mut self, duration: f64) {
+        if self.last.is_some() {
+            self.input += duration;
+        }
+    }
+
+    /// Media position of an output buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `filter_pts` - Timestamp the filters gave the buffer, in microseconds
+    /// * `duration` - Playback duration of the buffer, in microseconds
+    pub fn output(&mut self, filter_pts: i64, duration: f64) -> i64 {
+        let pts = match self.last {
+            // The first output keeps its timestamp; filter delay is already in it
+            None => filter_pts as f64,
+            Some((last_pts, last_duration)) => {
+                self.output += duration;
+                last_pts + last_duration * self.rate()
+            }
+        };
+
+        self.last = Some((pts, duration));
+        pts.round() as i64
+    }
+
+    /// Media time covered per unit of playback time (2.0 for atempo=2)
+    pub fn rate(&self) -> f64 {
+        if self.input > 0.0 && self.output > 0.0 {
+            self.input / self.output
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Duration of a number of samples in microseconds
+fn samples_micros(samples: usize, rate: u32) -> f64 {
+    samples as f64 * 1_000_000.0 / rate.max(1) as f64
+}
+
+/// Connect a buffer source through `spec` to a buffer sink
+///
+/// # Returns
+///
+/// The validated graph and the time base of its output
+fn build_graph(
+    source: &str,
+    args: &str,
+    sink: &str,
+    spec: &str,
+) -> Result<(ffmpeg::filter::Graph, ffmpeg::Rational)> {
+    let find = |name: &str| {
+        ffmpeg::filter::find(name)
+            .ok_or_else(|| CCPlayerError::decoder_error(format!("FFmpeg filter {} not available", name)))
+    };
+
+    let mut graph = ffmpeg::filter::Graph::new();
+    graph.add(&find(source)?, "in", args)?;
+    graph.add(&find(sink)?, "out", "")?;
+    graph.output("in", 0)?.input("out", 0)?.parse(spec)?;
+    graph.validate()?;
+
+    let output = graph.get("out")
+        .ok_or_else(|| CCPlayerError::decoder_error("Filter graph has no sink"))?;
+    // SAFETY: the sink context belongs to the validated graph
+    let time_base = unsafe { ffmpeg::ffi::av_buffersink_get_time_base(output.as_ptr()) }.into();
+
+    Ok((graph, time_base))
+}
+
+/// Buffer source context of a graph
+fn input_context(graph: &mut ffmpeg::filter::Graph) -> Result<ffmpeg::filter::Context<'_>> {
+    graph.get("in")
+        .ok_or_else(|| CCPlayerError::decoder_error("Filter graph has no input"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_filter_spec() {
+        assert!(VideoFilterGraph::check("crop=32:32,eq=contrast=1.1,hqdn3d").is_ok());
+
+        let err = VideoFilterGraph::check("crop=32:32,nosuchfilter").unwrap_err();
+        assert!(matches!(err, CCPlayerError::InvalidInput(_)));
+        assert!(err.to_string().contains("nosuchfilter"));
+
+        assert!(AudioFilterGraph::check("highpass=f=200,atempo=1.5").is_ok());
+        assert!(AudioFilterGraph::check("atempo=").is_err());
+    }
+
+    #[test]
+    fn test_timeline_follows_tempo() {
+        // atempo=2: 20 ms in, 10 ms out, after 40 ms of filter delay
+        let mut timeline = FilterTimeline::default();
+        timeline.input(20_000.0);
+        timeline.input(20_000.0);
+        assert_eq!(timeline.output(1_000_000, 10_000.0), 1_000_000);
+
+        for i in 1..=10 {
+            timeline.input(20_000.0);
+            assert_eq!(timeline.output(1_000_000 + i * 10_000, 10_000.0), 1_000_000 + i * 20_000);
+        }
+        assert!((timeline.rate() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_timeline_keeps_delayed_timestamps() {
+        // loudnorm-style: same duration out as in, released late
+        let mut timeline = FilterTimeline::default();
+        for _ in 0..5 {
+            timeline.input(21_333.0);
+        }
+        assert_eq!(timeline.output(0, 21_333.0), 0);
+        timeline.input(21_333.0);
+        assert_eq!(timeline.output(21_333, 21_333.0), 21_333);
+        timeline.input(21_333.0);
+        assert_eq!(timeline.output(42_666, 21_333.0), 42_666);
+    }
+}
@@ -76,7 +76,7 @@ impl FrameQueue {
     }
     
     /// Push a frame to the queue
-    pub fn push_frame(&mut self, frame: VideoFrame) -> Result<()> {
+    pub fn push_frame(&mut self, mut frame: VideoFrame) -> Result<()> {
         // Validate PTS ordering
         if let Some(last_pts) = self.last_pts {
             if frame.pts < last_pts {
@@ -98,6 +98,14 @@ impl FrameQueue {
         let insert_pos = self.frames.iter().position(|f| f.pts > frame.pts)
             .unwrap_or(self.frames.len());
         
+        // Durations follow from the neighbouring timestamps (variable frame rate)
+        if let Some(next) = self.frames.get(insert_pos) {
+            frame.fit_duration(next.pts);
+        }
+        if let Some(previous) = insert_pos.checked_sub(1).and_then(|index| self.frames.get_mut(index)) {
+            previous.fit_duration(frame.pts);
+        }
+        
         self.frames.insert(insert_pos, frame);
         self.total_size += frame_size;
         self.last_pts = Some(self.frames.back().unwrap().pts);
@@ -258,19 +266,20 @@ impl FrameQueue {
 }
 
 /// Frame timing controller for smooth playback
+/// 
+/// Purely PTS-driven: each frame is due when the playback clock reaches its
+/// timestamp and stays on screen for its own duration, so variable frame
+/// rate video keeps its timing.
 pub struct FrameTimingController {
-    /// Target frame rate
-    target_fps: f32,
-    
-    /// Frame duration in microseconds
-    frame_duration: i64,
-    
     /// Last presented frame time
     last_present_time: Option<Instant>,
     
     /// Last presented frame PTS
     last_pts: Option<i64>,
     
+    /// Step between the last two presented frames in microseconds
+    last_step: i64,
+    
     /// Clock offset for synchronization
     clock_offset: i64,
     
@@ -283,14 +292,11 @@ pub struct FrameTimingController {
 
 impl FrameTimingController {
     /// Create a new frame timing controller
-    pub fn new(target_fps: f32) -> Self {
-        let frame_duration = (1_000_000.0 / target_fps) as i64;
-        
+    pub fn new() -> Self {
         Self {
-            target_fps,
-            frame_duration,
             last_present_time: None,
             last_pts: None,
+            last_step: 0,
             clock_offset: 0,
             playback_speed: 1.0,
             drop_threshold: Duration::from_millis(50), // Drop if more than 50ms late
@@ -302,33 +308,37 @@ impl FrameTimingController {
         let now = Instant::now();
         
         // First frame
-        if self.last_present_time.is_none() {
+        let (Some(last_present_time), Some(last_pts)) = (self.last_present_time, self.last_pts) else {
             self.last_present_time = Some(now);
             self.last_pts = Some(frame.pts);
             self.clock_offset = frame.pts;
             return FramePresentation::Present;
-        }
+        };
         
-        let elapsed = now.duration_since(self.last_present_time.unwrap());
+        let elapsed = now.duration_since(last_present_time);
         let elapsed_us = elapsed.as_micros() as i64;
         
         // Calculate expected PTS based on elapsed time and playback speed
-        let expected_pts = self.last_pts.unwrap() + 
-            (elapsed_us as f32 * self.playback_speed) as i64;
+        let expected_pts = last_pts + (elapsed_us as f32 * self.playback_speed) as i64;
         
         // Calculate frame timing difference
         let pts_diff = frame.pts - expected_pts;
         
-        if pts_diff > self.frame_duration {
+        // Frames carry their own duration (fitted to the next frame's PTS);
+        // without one, the previous step between frames is the best guess
+        let frame_duration = if frame.duration > 0 { frame.duration } else { self.last_step };
+        
+        if pts_diff > frame_duration / 2 {
             // Frame is too early
-            let wait_time = Duration::from_micros((pts_diff - self.frame_duration / 2) as u64);
+            let wait_time = Duration::from_micros((pts_diff - frame_duration / 2) as u64);
             FramePresentation::Wait(wait_time)
-        } else if pts_diff < -self.drop_threshold.as_micros() as i64 {
+        } else if pts_diff < -(self.drop_threshold.as_micros() as i64).max(frame_duration) {
             // Frame is too late, should be dropped
             FramePresentation::Drop
         } else {
             // Frame should be presented
             self.last_present_time = Some(now);
+            self.last_step = frame.pts - last_pts;
             self.last_pts = Some(frame.pts);
             FramePresentation::Present
         }
@@ -343,6 +353,7 @@ impl FrameTimingController {
     pub fn reset(&mut self) {
         self.last_present_time = None;
         self.last_pts = None;
+        self.last_step = 0;
         self.clock_offset = 0;
     }
     
@@ -353,6 +364,12 @@ impl FrameTimingController {
     }
 }
 
+impl Default for FrameTimingController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Frame presentation decision
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FramePresentation {
@@ -448,7 +465,7 @@ mod tests {
     
     #[test]
     fn test_frame_timing_controller() {
-        let mut controller = FrameTimingController::new(60.0);
+        let mut controller = FrameTimingController::new();
         
         let frame1 = create_test_frame(0);
         let frame2 = create_test_frame(16667);
@@ -465,6 +482,54 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_frame_timing_double_rate() {
+        // 25 fps interlaced content deinterlaced to 50 frames per second
+        let mut controller = FrameTimingController::new();
+        
+        let mut field1 = create_test_frame(0);
+        field1.duration = 20000;
+        let mut field2 = create_test_frame(30000);
+        field2.duration = 20000;
+        
+        assert_eq!(controller.should_present_frame(&field1), FramePresentation::Present);
+        
+        // 30ms ahead is more than a field early, though less than a frame
+        assert!(matches!(controller.should_present_frame(&field2), FramePresentation::Wait(_)));
+    }
+    
+    #[test]
+    fn test_frame_timing_vfr() {
+        let mut controller = FrameTimingController::new();
+        
+        // A screen recording holding a still frame for two seconds
+        let mut still = create_test_frame(0);
+        still.duration = 2_000_000;
+        let next = create_test_frame(2_000_000);
+        
+        assert_eq!(controller.should_present_frame(&still), FramePresentation::Present);
+        match controller.should_present_frame(&next) {
+            FramePresentation::Wait(duration) => assert!(duration > Duration::from_millis(900)),
+            other => panic!("Expected Wait, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_durations_from_next_pts() {
+        let mut queue = FrameQueue::new(10);
+        
+        // Phone footage alternating between 30 and 24 fps, decoded out of order
+        queue.push_frame(create_test_frame(0)).unwrap();
+        queue.push_frame(create_test_frame(75_000)).unwrap();
+        queue.push_frame(create_test_frame(33_333)).unwrap();
+        
+        assert_eq!(queue.pop_frame().unwrap().duration, 33_333);
+        assert_eq!(queue.pop_frame().unwrap().duration, 41_667);
+        
+        // The last frame keeps the decoder's estimate until a successor shows up
+        assert_eq!(queue.pop_frame().unwrap().duration, 16_667);
+    }
+    
     #[test]
     fn test_pts_range() {
         let mut queue = FrameQueue::new(10);
@@ -4,18 +4,43 @@
 //! rusty_ffmpeg bindings. It supports hardware acceleration and various
 //! video codecs.
 
+mod adaptive;
+mod analysis;
+mod archive;
+mod avio;
+mod cache;
+mod deinterlace;
 mod ffmpeg_decoder;
+mod filter_graph;
 mod frame_queue;
 mod hw_accel;
+mod network;
+mod source;
+mod still_image;
 mod stream_info;
+mod subtitle;
+mod thumbnail;
+mod timestamps;
+mod trim;
 
+pub use adaptive::{AdaptiveConfig, AdaptiveReader, AdaptiveSession, AdaptiveSource, ThroughputEstimator, VariantSelector};
+pub use analysis::{AnalysisReport, Anomaly, AnomalyKind, BitrateMeter, PacketAnalyzer, PacketInfo, StreamAnalysis};
+pub use cache::{CacheConfig, CacheHandle, CachedSource, Readahead};
+pub use deinterlace::{DeinterlaceConfig, DeinterlaceMode};
 pub use ffmpeg_decoder::FFmpegDecoder;
 pub use frame_queue::{FrameQueue, FrameTimingController, FramePresentation};
 pub use hw_accel::{HardwareAccelerator, HwAccelConfig};
+pub use network::{HttpSource, ReconnectPolicy};
+pub use archive::{ArchiveEntry, ArchiveFormat, ArchivePath};
+pub use source::{MediaSource, MemorySource, ReaderSource, StreamSource};
+pub use still_image::{exif_orientation, is_image_format, is_image_path, orientation_rotation, StillImageConfig};
 pub use stream_info::StreamInfoExtractor;
+pub use subtitle::{SubtitleCue, SubtitleTrack};
+pub use thumbnail::{Thumbnail, ThumbnailConfig, ThumbnailGenerator};
+pub use timestamps::{StreamTiming, TimestampNormalizer};
 
 use crate::utils::error::Result;
-use crate::renderer::VideoFrame;
+use crate::renderer::{Rotation, VideoFrame};
 use std::path::Path;
 use std::time::Duration;
 
@@ -50,6 +75,17 @@ pub trait Decoder: Send + Sync {
     /// Returns media information or an error
     fn open_url(&mut self, url: &str) -> Result<MediaInfo>;
     
+    /// Open a custom byte source for decoding
+    /// 
+    /// # Arguments
+    /// 
+    /// * `source` - Source to demux from (memory, archive entry, transport, ...)
+    /// 
+    /// # Returns
+    /// 
+    /// Returns media information or an error
+    fn open_source(&mut self, source: Box<dyn MediaSource>) -> Result<MediaInfo>;
+    
     /// Decode the next video frame
     /// 
     /// # Returns
@@ -90,10 +126,139 @@ pub trait Decoder: Send + Sync {
     /// 
     /// * `enabled` - Whether to enable hardware acceleration
     fn set_hardware_acceleration(&mut self, enabled: bool) -> Result<()>;
+    
+    /// Configure deinterlacing of interlaced video
+    /// 
+    /// # Arguments
+    /// 
+    /// * `config` - Algorithm and output rate; interlacing is detected per frame
+    fn set_deinterlace(&mut self, config: DeinterlaceConfig) -> Result<()>;
+    
+    /// Choose the audio stream played from media opened from now on
+    /// 
+    /// Media without an audio stream at that index falls back to the
+    /// default stream.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - Stream index as listed by `ccplayer probe`, or None for the default stream
+    fn set_audio_track(&mut self, index: Option<usize>) -> Result<()>;
+    
+    /// Set a user-defined FFmpeg filter chain for video
+    /// 
+    /// Takes effect at the next decoded frame. Timestamps keep flowing
+    /// through filters that change the frame rate.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `filters` - Filtergraph description (`crop=1280:720,hqdn3d`), or None to remove
+    /// 
+    /// # Returns
+    /// 
+    /// An error if the description does not parse; the previous chain stays active
+    fn set_video_filters(&mut self, filters: Option<&str>) -> Result<()>;
+    
+    /// Set a user-defined FFmpeg filter chain for audio
+    /// 
+    /// The chain runs before resampling to the output format. Audio
+    /// timestamps stay on the media timeline when filters such as atempo
+    /// change the duration.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `filters` - Filtergraph description (`highpass=f=200,loudnorm`), or None to remove
+    /// 
+    /// # Returns
+    /// 
+    /// An error if the description does not parse; the previous chain stays active
+    fn set_audio_filters(&mut self, filters: Option<&str>) -> Result<()>;
+    
+    /// Select the quality variant for adaptive streams
+    /// 
+    /// # Arguments
+    /// 
+    /// * `selection` - Automatic selection or a pinned variant index
+    fn set_quality(&mut self, selection: QualitySelection) -> Result<()>;
+    
+    /// Start opening the next playlist item in a second decoder and pre-rolling it
+    /// 
+    /// The item is opened on a background thread so decoding carries on;
+    /// `next_prepared` tells when it is ready. Its audio is delivered in the
+    /// current output format so playback can continue in the same audio stream.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `source` - File path, archive entry (`archive.zip!/entry`) or URL of the next item
+    fn prepare_next(&mut self, source: &str) -> Result<()>;
+    
+    /// Check on the item passed to `prepare_next`
+    /// 
+    /// # Returns
+    /// 
+    /// Its media info, or the error opening it failed with, once opening is
+    /// done; None while it is still opening or if nothing is being prepared
+    fn next_prepared(&mut self) -> Option<Result<MediaInfo>>;
+    
+    /// Continue with the prepared item once the current one has ended
+    /// 
+    /// Call it once `decode_frame` and `decode_audio` have returned None for
+    /// every stream the current item has, since its remaining output is dropped.
+    /// 
+    /// # Returns
+    /// 
+    /// Media info of the new item, or None if nothing was prepared
+    fn advance(&mut self) -> Result<Option<MediaInfo>>;
+    
+    /// Decode audio of the prepared item while the current one still plays
+    /// 
+    /// Used to overlap the two items for a crossfade.
+    /// 
+    /// # Returns
+    /// 
+    /// Returns audio samples or None if the prepared item has no more samples
+    fn decode_next_audio(&mut self) -> Result<Option<AudioSamples>>;
+    
+    /// Set the format audio is delivered in
+    /// 
+    /// Until called, audio keeps the source's native rate and layout.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `sample_rate` - Output sample rate in Hz
+    /// * `channels` - Output channel count
+    fn set_audio_output(&mut self, sample_rate: u32, channels: u16) -> Result<()>;
+    
+    /// Get the variant currently being downloaded
+    /// 
+    /// # Returns
+    /// 
+    /// Index into `MediaInfo::variants`, or None for non-adaptive sources
+    fn current_variant(&self) -> Option<usize>;
+    
+    /// Get the parts of the media held in the read-ahead cache
+    /// 
+    /// # Returns
+    /// 
+    /// Sorted, non-overlapping (start, end) time ranges; empty for local files
+    fn buffered_ranges(&self) -> Vec<(Duration, Duration)>;
+    
+    /// Get the seekable window of a live stream
+    /// 
+    /// # Returns
+    /// 
+    /// Window in presentation time, or None for on-demand media
+    fn live_window(&self) -> Option<LiveWindow>;
+    
+    /// Get the bitrates of the packets read over the last second of media
+    /// 
+    /// # Returns
+    /// 
+    /// (video, audio) in bits per second
+    fn bitrates(&self) -> (u32, u32);
 }
 
 /// Media information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct MediaInfo {
     /// File path or URL
     pub source: String,
@@ -121,10 +286,76 @@ pub struct MediaInfo {
     
     /// Metadata tags
     pub metadata: MediaMetadata,
+    
+    /// Quality variants (adaptive streams only)
+    pub variants: Vec<VariantInfo>,
+    
+    /// Index of the variant being played (adaptive streams only)
+    pub current_variant: Option<usize>,
+    
+    /// Live source without a fixed duration (`duration` is zero)
+    pub is_live: bool,
+    
+    /// Single image shown for the configured still image duration
+    pub is_still: bool,
+}
+
+/// Seekable part of a live stream (the DVR window)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiveWindow {
+    /// Oldest position that can still be played
+    pub start: Duration,
+    
+    /// Newest position available (the live edge)
+    pub end: Duration,
+}
+
+impl LiveWindow {
+    /// Length of the window
+    pub fn length(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+    
+    /// Limit a seek target to the window
+    pub fn clamp(&self, position: Duration) -> Duration {
+        position.clamp(self.start, self.end.max(self.start))
+    }
+}
+
+/// Quality variant of an adaptive (HLS/DASH) stream
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VariantInfo {
+    /// Position in the variant list
+    pub index: usize,
+    
+    /// Advertised bandwidth in bits per second
+    pub bandwidth: u64,
+    
+    /// Video resolution (if advertised)
+    pub resolution: Option<(u32, u32)>,
+    
+    /// Frame rate (if advertised)
+    pub frame_rate: Option<f32>,
+    
+    /// RFC 6381 codec string (if advertised)
+    pub codecs: Option<String>,
+    
+    /// Representation ID or rendition name
+    pub name: Option<String>,
+}
+
+/// Quality selection mode for adaptive streams
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualitySelection {
+    /// Pick the variant from measured throughput
+    Auto,
+    
+    /// Always use the variant with this index
+    Pinned(usize),
 }
 
 /// Video stream information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VideoStreamInfo {
     /// Stream index
     pub index: usize,
@@ -152,10 +383,13 @@ pub struct VideoStreamInfo {
     
     /// HDR metadata if available
     pub hdr_metadata: Option<HdrMetadata>,
+    
+    /// Orientation the video should be displayed in
+    pub rotation: Rotation,
 }
 
 /// Audio stream information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AudioStreamInfo {
     /// Stream index
     pub index: usize,
@@ -180,10 +414,16 @@ pub struct AudioStreamInfo {
     
     /// Language tag
     pub language: Option<String>,
+    
+    /// Encoder priming samples the decoder does not remove itself
+    pub encoder_delay: u32,
+    
+    /// Encoder padding samples at the end the decoder does not remove itself
+    pub encoder_padding: u32,
 }
 
 /// Subtitle stream information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SubtitleStreamInfo {
     /// Stream index
     pub index: usize,
@@ -202,7 +442,7 @@ pub struct SubtitleStreamInfo {
 }
 
 /// Media metadata
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct MediaMetadata {
     /// Title
     pub title: Option<String>,
@@ -230,7 +470,7 @@ pub struct MediaMetadata {
 }
 
 /// Color space information
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub enum ColorSpace {
     /// Standard dynamic range BT.709
     Sdr,
@@ -255,7 +495,7 @@ pub enum ColorSpace {
 }
 
 /// HDR metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct HdrMetadata {
     /// Maximum content light level
     pub max_cll: u32,
@@ -268,7 +508,7 @@ pub struct HdrMetadata {
 }
 
 /// Mastering display metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct MasteringDisplay {
     /// Red primary chromaticity
     pub red_x: f32,
@@ -392,4 +632,17 @@ mod tests {
         assert_ne!(HwAccelMethod::None, HwAccelMethod::Nvdec);
         assert_eq!(HwAccelMethod::Nvdec, HwAccelMethod::Nvdec);
     }
+    
+    #[test]
+    fn test_live_window_clamp() {
+        let window = LiveWindow {
+            start: Duration::from_secs(100),
+            end: Duration::from_secs(160),
+        };
+        
+        assert_eq!(window.length(), Duration::from_secs(60));
+        assert_eq!(window.clamp(Duration::from_secs(10)), window.start);
+        assert_eq!(window.clamp(Duration::from_secs(130)), Duration::from_secs(130));
+        assert_eq!(window.clamp(Duration::from_secs(500)), window.end);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,312 @@
+//! Network stream resilience
+//!
+//! Reconnect policy, stall watchdog and an HTTP source that survives
+//! dropped connections by resuming from the last byte it delivered.
+
+use super::source::MediaSource;
+use crate::utils::error::{IntoPlayerError, Result};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How dropped and stalled network streams are recovered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Reconnect attempts before giving up
+    pub max_attempts: u32,
+
+    /// Delay before the first attempt, doubled for every further one
+    pub initial_delay: Duration,
+
+    /// Upper bound for the delay between attempts
+    pub max_delay: Duration,
+
+    /// A stream that delivers no data for this long is considered stalled
+    pub stall_timeout: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            stall_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before the given (zero-based) attempt
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.min(16));
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Detects demuxers that stop receiving packets
+///
+/// Cheap to clone; all clones share the same activity timestamp.
+#[derive(Debug, Clone)]
+pub(crate) struct StallWatchdog {
+    /// Reference point for `last_activity`
+    epoch: Instant,
+
+    /// Milliseconds since `epoch` of the last activity
+    last_activity: Arc<AtomicU64>,
+
+    /// Inactivity that counts as a stall
+    timeout: Duration,
+}
+
+impl StallWatchdog {
+    /// Create a watchdog with the given stall timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            epoch: Instant::now(),
+            last_activity: Arc::new(AtomicU64::new(0)),
+            timeout,
+        }
+    }
+
+    /// Record activity (a packet arrived, a connection was opened)
+    pub fn touch(&self) {
+        let now = self.epoch.elapsed().as_millis() as u64;
+        self.last_activity.store(now, Ordering::Release);
+    }
+
+    /// Time since the last activity
+    pub fn idle_time(&self) -> Duration {
+        let last = Duration::from_millis(self.last_activity.load(Ordering::Acquire));
+        self.epoch.elapsed().saturating_sub(last)
+    }
+
+    /// Whether the stream has been idle for longer than the timeout
+    pub fn is_stalled(&self) -> bool {
+        self.idle_time() > self.timeout
+    }
+}
+
+/// HTTP media source with automatic reconnect
+///
+/// Lost or stalled connections are re-established with exponential
+/// backoff and resume at the current byte position, using a Range request
+/// when the server supports it and skipping ahead otherwise.
+pub struct HttpSource {
+    /// HTTP agent with connect and read timeouts
+    agent: ureq::Agent,
+
+    /// Resource URL
+    url: String,
+
+    /// Reconnect behaviour
+    policy: ReconnectPolicy,
+
+    /// Body of the current response
+    body: Option<Box<dyn Read + Send + Sync>>,
+
+    /// Bytes delivered so far (or seek target)
+    position: u64,
+
+    /// Total size, if the server reported one
+    size: Option<u64>,
+
+    /// Whether the server honours Range requests
+    ranges: bool,
+
+    /// Reconnect attempts since data last arrived
+    failures: u32,
+}
+
+impl HttpSource {
+    /// Connect to `url`
+    pub fn open(url: &str, policy: ReconnectPolicy) -> Result<Self> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(policy.stall_timeout)
+            .timeout_read(policy.stall_timeout)
+            .user_agent(concat!("CCPlayer/", env!("CARGO_PKG_VERSION")))
+            .build();
+
+        let mut source = Self {
+            agent,
+            url: url.to_string(),
+            policy,
+            body: None,
+            position: 0,
+            size: None,
+            ranges: false,
+            failures: 0,
+        };
+
+        source.connect().network_err(&format!("GET {}", url))?;
+        Ok(source)
+    }
+
+    /// Request the resource from the current position on
+    fn connect(&mut self) -> io::Result<()> {
+        let mut request = self.agent.get(&self.url);
+        if self.position > 0 {
+            request = request.set("Range", &format!("bytes={}-", self.position));
+        }
+
+        let response = request.call().map_err(io::Error::other)?;
+
+        let partial = response.status() == 206;
+        let length = response.header("Content-Length").and_then(|v| v.parse::<u64>().ok());
+        let total = response
+            .header("Content-Range")
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse::<u64>().ok());
+        let accepts_ranges = response
+            .header("Accept-Ranges")
+            .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
+
+        let mut body = response.into_reader();
+
+        if partial {
+            self.ranges = true;
+            self.size = total.or(self.size);
+        } else {
+            // Full body: it starts at byte 0 whatever we asked for
+            self.ranges = accepts_ranges;
+            self.size = length.or(self.size);
+
+            if self.position > 0 {
+                log::debug!("{} ignored the Range request, skipping {} bytes", self.url, self.position);
+                let skipped = io::copy(&mut (&mut body).take(self.position), &mut io::sink())?;
+                if skipped < self.position {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "resource shrank on reconnect"));
+                }
+            }
+        }
+
+        self.body = Some(body);
+        Ok(())
+    }
+
+    /// Re-establish the connection with exponential backoff
+    fn reconnect(&mut self, cause: io::Error) -> io::Result<()> {
+        self.body = None;
+        let mut last_error = cause;
+
+        // Attempts only reset once data flows again
+        while self.failures < self.policy.max_attempts {
+            let delay = self.policy.delay(self.failures);
+            self.failures += 1;
+            log::warn!(
+                "Connection to {} lost at byte {} ({}), reconnecting in {:?} ({}/{})",
+                self.url,
+                self.position,
+                last_error,
+                delay,
+                self.failures,
+                self.policy.max_attempts
+            );
+            thread::sleep(delay);
+
+            match self.connect() {
+                Ok(()) => {
+                    log::info!("Reconnected to {} at byte {}", self.url, self.position);
+                    return Ok(());
+                }
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::ConnectionAborted,
+            format!("gave up after {} reconnect attempts: {}", self.policy.max_attempts, last_error),
+        ))
+    }
+}
+
+impl MediaSource for HttpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.size.is_some_and(|size| self.position >= size) {
+            return Ok(0);
+        }
+
+        loop {
+            let Some(body) = self.body.as_mut() else {
+                // After a seek the connection is opened lazily
+                if let Err(e) = self.connect() {
+                    self.reconnect(e)?;
+                }
+                continue;
+            };
+
+            match body.read(buf) {
+                // A body that ends before the reported size was cut off
+                Ok(0) if self.size.is_some_and(|size| self.position < size) => {
+                    self.reconnect(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed early"))?;
+                }
+                Ok(count) => {
+                    self.position += count as u64;
+                    if count > 0 {
+                        self.failures = 0;
+                    }
+                    return Ok(count);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => self.reconnect(e)?,
+            }
+        }
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<u64> {
+        if position == self.position {
+            return Ok(position);
+        }
+
+        if !self.ranges {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "server does not support range requests"));
+        }
+
+        self.body = None;
+        self.position = position;
+        Ok(position)
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.ranges && self.size.is_some()
+    }
+
+    fn name(&self) -> String {
+        self.url.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.delay(0), Duration::from_millis(500));
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+        assert_eq!(policy.delay(10), policy.max_delay);
+        assert_eq!(policy.delay(u32::MAX), policy.max_delay);
+    }
+
+    #[test]
+    fn test_stall_watchdog() {
+        let watchdog = StallWatchdog::new(Duration::from_millis(20));
+        watchdog.touch();
+        assert!(!watchdog.is_stalled());
+
+        thread::sleep(Duration::from_millis(40));
+        assert!(watchdog.is_stalled());
+
+        // Clones share the activity timestamp
+        watchdog.clone().touch();
+        assert!(!watchdog.is_stalled());
+    }
+}
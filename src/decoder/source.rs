@@ -0,0 +1,216 @@
+//! Custom media sources
+//!
+//! Defines the `MediaSource` trait used to feed the decoder from memory
+//! buffers, archives, network transports or any `Read + Seek` type.
+
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+/// Byte source the decoder can demux from
+pub trait MediaSource: Send {
+    /// Read up to `buf.len()` bytes
+    ///
+    /// # Returns
+    ///
+    /// Number of bytes read, 0 at end of stream
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Seek to an absolute byte offset
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Offset from the start of the source
+    ///
+    /// # Returns
+    ///
+    /// The new position
+    fn seek(&mut self, position: u64) -> io::Result<u64>;
+
+    /// Total size in bytes, if known
+    fn size(&self) -> Option<u64>;
+
+    /// Whether `seek` is supported
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    /// Name reported as the media source (file name, URL, ...)
+    fn name(&self) -> String {
+        "custom source".to_string()
+    }
+}
+
+/// Media source over any `Read + Seek` implementation
+pub struct ReaderSource<R> {
+    /// Wrapped reader
+    inner: R,
+
+    /// Size determined when the source was created
+    size: Option<u64>,
+
+    /// Source name
+    name: String,
+}
+
+impl<R: Read + Seek + Send> ReaderSource<R> {
+    /// Wrap a reader, determining its size by seeking to the end
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let current = inner.stream_position()?;
+        let size = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(current))?;
+
+        Ok(Self {
+            inner,
+            size: Some(size),
+            name: "custom source".to_string(),
+        })
+    }
+
+    /// Set the name reported as the media source
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Unwrap the reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + Seek + Send> MediaSource for ReaderSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<u64> {
+        self.inner.seek(SeekFrom::Start(position))
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// Media source over an in-memory buffer
+pub type MemorySource = ReaderSource<Cursor<Vec<u8>>>;
+
+impl MemorySource {
+    /// Play from a byte buffer
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Self {
+        let data = data.into();
+
+        Self {
+            size: Some(data.len() as u64),
+            inner: Cursor::new(data),
+            name: "memory".to_string(),
+        }
+    }
+}
+
+/// Forward-only media source (pipes, sockets, live downloads)
+pub struct StreamSource<R> {
+    /// Wrapped reader
+    inner: R,
+
+    /// Bytes read so far
+    position: u64,
+
+    /// Source name
+    name: String,
+}
+
+impl<R: Read + Send> StreamSource<R> {
+    /// Wrap a forward-only reader
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            position: 0,
+            name: "stream".to_string(),
+        }
+    }
+
+    /// Set the name reported as the media source
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl<R: Read + Send> MediaSource for StreamSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.position += count as u64;
+        Ok(count)
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<u64> {
+        // Seeking to where we already are is harmless
+        if position == self.position {
+            return Ok(position);
+        }
+
+        Err(io::Error::new(io::ErrorKind::Unsupported, "stream source is not seekable"))
+    }
+
+    fn size(&self) -> Option<u64> {
+        None
+    }
+
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_source() {
+        let mut source = MemorySource::from_bytes(b"0123456789".to_vec());
+        assert_eq!(source.size(), Some(10));
+        assert!(source.is_seekable());
+
+        let mut buf = [0u8; 4];
+        assert_eq!(source.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"0123");
+
+        source.seek(8).unwrap();
+        assert_eq!(source.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"89");
+        assert_eq!(source.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reader_source_keeps_position() {
+        let mut cursor = Cursor::new(vec![1u8; 100]);
+        cursor.set_position(40);
+
+        let mut source = ReaderSource::new(cursor).unwrap().with_name("clip.mp4");
+        assert_eq!(source.size(), Some(100));
+        assert_eq!(source.name(), "clip.mp4");
+
+        let mut buf = [0u8; 100];
+        assert_eq!(source.read(&mut buf).unwrap(), 60);
+    }
+
+    #[test]
+    fn test_stream_source_not_seekable() {
+        let mut source = StreamSource::new(&b"abcdef"[..]);
+        assert!(!source.is_seekable());
+        assert_eq!(source.size(), None);
+
+        let mut buf = [0u8; 3];
+        source.read(&mut buf).unwrap();
+        assert_eq!(source.seek(3).unwrap(), 3);
+        assert!(source.seek(0).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Still images and slideshows
+//!
+//! JPEG, PNG and WebP files open through FFmpeg's image demuxers and
+//! decode to a single frame, which is shown for a configured duration.
+//! Cameras store the orientation of a photo in its EXIF data instead of
+//! rotating the pixels, so it is read here and handed to the renderer as
+//! a [`Rotation`].
+
+use crate::renderer::Rotation;
+use std::path::Path;
+use std::time::Duration;
+
+/// File extensions opened as still images
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp", "tif", "tiff"];
+
+/// EXIF tag holding the orientation
+const ORIENTATION_TAG: u16 = 0x0112;
+
+/// Still image and slideshow settings
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct StillImageConfig {
+    /// How long an image is shown before playback ends or moves on
+    pub duration: Duration,
+
+    /// Crossfade between the images of an image-only playlist (None to cut)
+    pub transition: Option<Duration>,
+}
+
+impl Default for StillImageConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(5),
+            transition: None,
+        }
+    }
+}
+
+/// Check whether FFmpeg opened an input with an image demuxer
+///
+/// # Arguments
+///
+/// * `format` - Name of the demuxer (`image2`, `jpeg_pipe`, `png_pipe`, ...)
+pub fn is_image_format(format: &str) -> bool {
+    // GIFs may be animated and play as video
+    format == "image2" || (format.ends_with("_pipe") && format != "gif_pipe")
+}
+
+/// Check whether a playlist entry looks like a still image, by extension
+pub fn is_image_path(path: impl AsRef<Path>) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Read the EXIF orientation of a JPEG, PNG or WebP file
+///
+/// # Arguments
+///
+/// * `data` - Contents of the image file
+///
+/// # Returns
+///
+/// The rotation that displays the image upright, None without EXIF data
+pub fn exif_orientation(data: &[u8]) -> Option<Rotation> {
+    let tiff = if data.starts_with(&[0xFF, 0xD8]) {
+        jpeg_exif(data)?
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_exif(data)?
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        webp_exif(data)?
+    } else {
+        return None;
+    };
+
+    // Some writers keep the JPEG marker's `Exif\0\0` prefix in other containers
+    let tiff = tiff.strip_prefix(b"Exif\0\0").unwrap_or(tiff);
+    orientation_rotation(tiff_orientation(tiff)?)
+}
+
+/// Map an EXIF orientation value (1-8) to a rotation
+///
+/// The values describe how the stored pixels must be transformed; mirrored
+/// ones become a clockwise rotation followed by a horizontal mirror.
+pub fn orientation_rotation(orientation: u16) -> Option<Rotation> {
+    let (degrees, flip) = match orientation {
+        1 => (0.0, false),
+        2 => (0.0, true),
+        3 => (180.0, false),
+        4 => (180.0, true),
+        5 => (90.0, true),
+        6 => (90.0, false),
+        7 => (270.0, true),
+        8 => (270.0, false),
+        _ => return None,
+    };
+    Some(Rotation::new(degrees, flip))
+}
+
+/// TIFF data of the APP1 `Exif` segment of a JPEG
+fn jpeg_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut offset = 2;
+    while offset + 4 <= data.len() {
+        if data[offset] != 0xFF {
+            return None;
+        }
+        let marker = data[offset + 1];
+
+        // Image data follows start of scan; no metadata after it
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+
+        let length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        let segment = data.get(offset + 4..offset + 2 + length)?;
+        if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return Some(segment);
+        }
+        offset += 2 + length;
+    }
+    None
+}
+
+/// Contents of the `eXIf` chunk of a PNG
+fn png_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut offset = 8;
+    while offset + 8 <= data.len() {
+        let length = u32::from_be_bytes(data[offset..offset + 4].try_into().ok()?) as usize;
+        let kind = &data[offset + 4..offset + 8];
+        let body = data.get(offset + 8..offset + 8 + length)?;
+
+        match kind {
+            b"eXIf" => return Some(body),
+            b"IEND" => return None,
+            _ => offset += 12 + length,
+        }
+    }
+    None
+}
+
+/// Contents of the `EXIF` chunk of a WebP
+fn webp_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut offset = 12;
+    while offset + 8 <= data.len() {
+        let length = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let body = data.get(offset + 8..offset + 8 + length)?;
+
+        if &data[offset..offset + 4] == b"EXIF" {
+            return Some(body);
+        }
+        // Chunks are padded to an even size
+        offset += 8 + length + (length & 1);
+    }
+    None
+}
+
+/// Orientation value in the first IFD of TIFF-structured EXIF data
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| -> Option<u16> {
+        let bytes = [*tiff.get(offset)?, *tiff.get(offset + 1)?];
+        Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    };
+    let u32_at = |offset: usize| -> Option<u32> {
+        let bytes: [u8; 4] = tiff.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    };
+
+    if u16_at(2)? != 42 {
+        return None;
+    }
+    let ifd = u32_at(4)? as usize;
+    let entries = u16_at(ifd)? as usize;
+
+    // Entries are 12 bytes: tag, type, count, value (a SHORT is stored inline)
+    (0..entries)
+        .map(|index| ifd + 2 + index * 12)
+        .find(|&entry| u16_at(entry) == Some(ORIENTATION_TAG))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little-endian TIFF header with a single orientation entry
+    fn tiff(orientation: u16) -> Vec<u8> {
+        let mut data = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&ORIENTATION_TAG.to_le_bytes());
+        data.extend_from_slice(&3u16.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&orientation.to_le_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_jpeg_orientation() {
+        let exif = [b"Exif\0\0".as_slice(), &tiff(6)].concat();
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00];
+        jpeg.extend_from_slice(&[0xFF, 0xE1]);
+        jpeg.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+        jpeg.extend_from_slice(&exif);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02]);
+
+        assert_eq!(exif_orientation(&jpeg), Some(Rotation::new(90.0, false)));
+        assert_eq!(exif_orientation(&jpeg[..8]), None);
+    }
+
+    #[test]
+    fn test_png_and_webp_orientation() {
+        let exif = tiff(7);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(&(exif.len() as u32).to_be_bytes());
+        png.extend_from_slice(b"eXIf");
+        png.extend_from_slice(&exif);
+        png.extend_from_slice(&[0; 4]);
+        assert_eq!(exif_orientation(&png), Some(Rotation::new(270.0, true)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBP".to_vec();
+        webp.extend_from_slice(b"VP8X");
+        webp.extend_from_slice(&3u32.to_le_bytes());
+        webp.extend_from_slice(&[0; 4]);
+        webp.extend_from_slice(b"EXIF");
+        webp.extend_from_slice(&(exif.len() as u32).to_le_bytes());
+        webp.extend_from_slice(&exif);
+        assert_eq!(exif_orientation(&webp), Some(Rotation::new(270.0, true)));
+    }
+
+    #[test]
+    fn test_orientation_values() {
+        assert_eq!(orientation_rotation(1), Some(Rotation::default()));
+        assert_eq!(orientation_rotation(4), Some(Rotation::new(180.0, true)));
+        assert_eq!(orientation_rotation(8), Some(Rotation::new(270.0, false)));
+        assert_eq!(orientation_rotation(9), None);
+
+        // Big-endian data as written by most cameras
+        let mut data = b"MM\x00\x2a\x00\x00\x00\x08\x00\x01".to_vec();
+        data.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00]);
+        assert_eq!(tiff_orientation(&data), Some(3));
+    }
+
+    #[test]
+    fn test_image_detection() {
+        assert!(is_image_format("image2"));
+        assert!(is_image_format("webp_pipe"));
+        assert!(!is_image_format("gif_pipe"));
+        assert!(!is_image_format("mov,mp4,m4a,3gp,3g2,mj2"));
+
+        assert!(is_image_path("holiday/IMG_0042.JPG"));
+        assert!(!is_image_path("clip.mp4"));
+        assert!(!is_image_path("https://example.com/live"));
+    }
+}
@@ -4,14 +4,18 @@
 //! including video, audio, and subtitle tracks with metadata.
 
 use crate::decoder::{
-    AudioStreamInfo, ColorSpace, HdrMetadata, MasteringDisplay, MediaInfo, 
+    is_image_format, AudioStreamInfo, ColorSpace, HdrMetadata, MasteringDisplay, MediaInfo, 
     MediaMetadata, SubtitleStreamInfo, VideoStreamInfo,
 };
+use crate::renderer::Rotation;
 use crate::utils::error::{CCPlayerError, Result};
 use ffmpeg_next as ffmpeg;
 use std::collections::HashMap;
 use std::time::Duration;
 
+/// URL schemes of protocols that carry live streams only
+const LIVE_PROTOCOLS: &[&str] = &["rtmp", "rtmps", "rtmpt", "rtsp", "rtsps", "rtp", "srt", "udp", "mms", "mmsh"];
+
 /// Stream information extractor
 pub struct StreamInfoExtractor {
     /// Metadata parser
@@ -34,7 +38,15 @@ impl StreamInfoExtractor {
     ) -> Result<MediaInfo> {
         // Get format info
         let format = input.format().name().to_string();
-        let duration = Duration::from_secs_f64(input.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64);
+        
+        // Unknown durations are reported as AV_NOPTS_VALUE
+        let duration_known = input.duration() > 0;
+        let is_live = Self::detect_live(&source, &format, duration_known);
+        let duration = if duration_known && !is_live {
+            Duration::from_secs_f64(input.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64)
+        } else {
+            Duration::ZERO
+        };
         let bitrate = if input.bit_rate() > 0 {
             Some(input.bit_rate() as u32)
         } else {
@@ -44,6 +56,7 @@ impl StreamInfoExtractor {
         
         // Extract metadata
         let metadata = self.metadata_parser.parse_metadata(input.metadata());
+        let gapless = input.metadata().get("iTunSMPB").and_then(Self::parse_itunes_gapless);
         
         // Extract stream information
         let mut video_streams = Vec::new();
@@ -58,7 +71,12 @@ impl StreamInfoExtractor {
                     }
                 }
                 ffmpeg::media::Type::Audio => {
-                    if let Some(info) = self.extract_audio_stream_info(stream) {
+                    if let Some(mut info) = self.extract_audio_stream_info(stream) {
+                        // iTunes gapless info describes the (only) audio track
+                        if let (Some((delay, padding)), true) = (gapless, audio_streams.is_empty()) {
+                            info.encoder_delay = delay;
+                            info.encoder_padding = padding;
+                        }
                         audio_streams.push(info);
                     }
                 }
@@ -81,9 +99,50 @@ impl StreamInfoExtractor {
             file_size,
             bitrate,
             metadata,
+            variants: Vec::new(),
+            current_variant: None,
+            is_live,
+            is_still: is_image_format(&format),
         })
     }
     
+    /// Check whether a source is a live stream
+    /// 
+    /// # Arguments
+    /// 
+    /// * `source` - File path or URL
+    /// * `format` - Name of the demuxer that opened it
+    /// * `duration_known` - Whether the container reported a duration
+    pub fn detect_live(source: &str, format: &str, duration_known: bool) -> bool {
+        let scheme = source.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+        
+        if scheme.as_deref().is_some_and(|scheme| LIVE_PROTOCOLS.contains(&scheme)) {
+            return true;
+        }
+        
+        if matches!(format, "rtsp" | "rtp" | "sdp") {
+            return true;
+        }
+        
+        // Network streams that never end (Icecast, HTTP MPEG-TS, ...)
+        scheme.is_some() && !duration_known
+    }
+    
+    /// Parse encoder delay and padding from an iTunes `iTunSMPB` tag
+    /// 
+    /// The tag holds hex fields: reserved, delay, padding, sample count, ...
+    /// FFmpeg leaves these samples in the decoded output.
+    /// 
+    /// # Returns
+    /// 
+    /// (delay, padding) in samples
+    pub fn parse_itunes_gapless(value: &str) -> Option<(u32, u32)> {
+        let mut fields = value.split_whitespace().skip(1).map(|field| u32::from_str_radix(field, 16));
+        let delay = fields.next()?.ok()?;
+        let padding = fields.next()?.ok()?;
+        Some((delay, padding))
+    }
+    
     /// Extract video stream information
     fn extract_video_stream_info(&self, stream: ffmpeg::format::stream::Stream) -> Option<VideoStreamInfo> {
         let params = stream.parameters();
@@ -119,6 +178,11 @@ impl StreamInfoExtractor {
         // Detect color space and HDR
         let (color_space, hdr_metadata) = self.detect_color_space_and_hdr(&stream, codec_params);
         
+        let rotation = Self::detect_rotation(&stream);
+        if rotation != Rotation::default() {
+            log::debug!("Stream {} displays rotated {:?}", index, rotation);
+        }
+        
         Some(VideoStreamInfo {
             index,
             codec,
@@ -129,9 +193,66 @@ impl StreamInfoExtractor {
             pixel_format,
             color_space,
             hdr_metadata,
+            rotation,
         })
     }
     
+    /// Detect the display orientation of a video stream
+    /// 
+    /// Prefers the display matrix side data and falls back to the legacy
+    /// `rotate` metadata tag.
+    fn detect_rotation(stream: &ffmpeg::format::stream::Stream) -> Rotation {
+        let matrix = stream
+            .side_data()
+            .find(|side_data| side_data.kind() == ffmpeg::codec::packet::side_data::Type::DisplayMatrix)
+            .and_then(|side_data| Self::parse_display_matrix(side_data.data()));
+        if let Some(rotation) = matrix {
+            return rotation;
+        }
+        
+        stream.metadata()
+            .get("rotate")
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .map(|degrees| Rotation::new(degrees, false))
+            .unwrap_or_default()
+    }
+    
+    /// Parse a display matrix into a rotation
+    /// 
+    /// The matrix is nine native-endian 32-bit integers; the 2x2 part that
+    /// rotates and mirrors the picture is in 16.16 fixed point. A negative
+    /// determinant means the picture is mirrored.
+    /// 
+    /// # Returns
+    /// 
+    /// None when the data is too short or the matrix is degenerate
+    pub fn parse_display_matrix(data: &[u8]) -> Option<Rotation> {
+        if data.len() < 36 {
+            return None;
+        }
+        let value = |i: usize| {
+            let bytes = [data[i * 4], data[i * 4 + 1], data[i * 4 + 2], data[i * 4 + 3]];
+            i32::from_ne_bytes(bytes) as f64 / 65536.0
+        };
+        
+        let (mut a, b, mut c, d) = (value(0), value(1), value(3), value(4));
+        let flip = a * d - b * c < 0.0;
+        if flip {
+            // Undo the horizontal mirror to leave a pure rotation
+            a = -a;
+            c = -c;
+        }
+        
+        let scale_x = a.hypot(c);
+        let scale_y = b.hypot(d);
+        if scale_x == 0.0 || scale_y == 0.0 {
+            return None;
+        }
+        
+        let degrees = (b / scale_y).atan2(a / scale_x).to_degrees();
+        Some(Rotation::new(degrees, flip))
+    }
+    
     /// Extract audio stream information
     fn extract_audio_stream_info(&self, stream: ffmpeg::format::stream::Stream) -> Option<AudioStreamInfo> {
         let params = stream.parameters();
@@ -168,6 +289,8 @@ impl StreamInfoExtractor {
             bitrate,
             sample_format,
             language,
+            encoder_delay: 0,
+            encoder_padding: 0,
         })
     }
     
@@ -403,6 +526,48 @@ mod tests {
         assert!(!parser.known_keys.is_empty());
     }
     
+    #[test]
+    fn test_parse_itunes_gapless() {
+        let tag = " 00000000 00000840 000001CA 00000000003F31F6 00000000 00000000 00000000";
+        assert_eq!(StreamInfoExtractor::parse_itunes_gapless(tag), Some((2112, 458)));
+        assert_eq!(StreamInfoExtractor::parse_itunes_gapless("garbage"), None);
+    }
+    
+    /// Display matrix bytes for the given 2x2 part
+    fn display_matrix(a: i32, b: i32, c: i32, d: i32) -> Vec<u8> {
+        [a, b, 0, c, d, 0, 0, 0, 1 << 30]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect()
+    }
+    
+    #[test]
+    fn test_parse_display_matrix() {
+        let one = 1 << 16;
+        let parse = |data: Vec<u8>| StreamInfoExtractor::parse_display_matrix(&data).unwrap();
+        
+        assert_eq!(parse(display_matrix(one, 0, 0, one)), Rotation::default());
+        // Portrait phone recording
+        assert_eq!(parse(display_matrix(0, one, -one, 0)), Rotation { degrees: 90, flip: false });
+        assert_eq!(parse(display_matrix(-one, 0, 0, -one)), Rotation { degrees: 180, flip: false });
+        assert_eq!(parse(display_matrix(0, -one, one, 0)), Rotation { degrees: 270, flip: false });
+        // Front camera selfie: mirrored
+        assert_eq!(parse(display_matrix(-one, 0, 0, one)), Rotation { degrees: 0, flip: true });
+        
+        assert_eq!(StreamInfoExtractor::parse_display_matrix(&display_matrix(0, 0, 0, 0)), None);
+        assert_eq!(StreamInfoExtractor::parse_display_matrix(&[0; 8]), None);
+    }
+    
+    #[test]
+    fn test_detect_live() {
+        assert!(StreamInfoExtractor::detect_live("rtsp://camera.local/stream", "rtsp", false));
+        assert!(StreamInfoExtractor::detect_live("RTMP://example.com/app/key", "flv", true));
+        assert!(StreamInfoExtractor::detect_live("http://radio.example.com/stream", "mp3", false));
+        
+        assert!(!StreamInfoExtractor::detect_live("http://example.com/movie.mp4", "mov,mp4,m4a,3gp,3g2,mj2", true));
+        assert!(!StreamInfoExtractor::detect_live("/videos/capture.h264", "h264", false));
+    }
+    
     #[test]
     fn test_stream_info_extractor_creation() {
         let extractor = StreamInfoExtractor::new();
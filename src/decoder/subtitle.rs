@@ -0,0 +1,195 @@
+//! Text subtitles
+//!
+//! Cues are read from SubRip (`.srt`) and WebVTT (`.vtt`) files, or taken
+//! from a text subtitle stream of the media by
+//! [`FFmpegDecoder::extract_subtitles`](super::FFmpegDecoder::extract_subtitles).
+//! Markup in the cue text (HTML-like tags, ASS override blocks) is
+//! dropped; the player draws every cue in the configured subtitle style.
+
+use crate::utils::error::{CCPlayerError, Result};
+use std::path::Path;
+use std::time::Duration;
+
+/// A piece of subtitle text and when it is shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    /// When the cue appears
+    pub start: Duration,
+
+    /// When the cue disappears
+    pub end: Duration,
+
+    /// Text without markup, lines separated by `\n`
+    pub text: String,
+}
+
+/// The cues of one subtitle track, sorted by start time
+#[derive(Debug, Clone, Default)]
+pub struct SubtitleTrack {
+    /// Cues sorted by start time
+    cues: Vec<SubtitleCue>,
+}
+
+impl SubtitleTrack {
+    /// Build a track from cues in any order
+    pub fn new(mut cues: Vec<SubtitleCue>) -> Self {
+        cues.sort_by_key(|cue| cue.start);
+        Self { cues }
+    }
+
+    /// Read an SRT or WebVTT file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Subtitle file; the format is recognized by its contents
+    pub fn open(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| CCPlayerError::InvalidInput(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        let track = Self::parse(&contents);
+        if track.cues.is_empty() {
+            return Err(CCPlayerError::InvalidInput(format!("No subtitle cues in {}", path.display())));
+        }
+        Ok(track)
+    }
+
+    /// Parse SRT or WebVTT text
+    ///
+    /// Both formats are blocks separated by blank lines, each with a
+    /// `start --> end` timing line followed by the text. Blocks without a
+    /// timing line (the WebVTT header, `NOTE` and `STYLE` blocks) and
+    /// timing lines that do not parse are skipped.
+    pub fn parse(contents: &str) -> Self {
+        let contents = contents.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+
+        let mut cues = Vec::new();
+        for block in contents.split("\n\n") {
+            let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+            let Some((start, end)) = lines.next().and_then(parse_timing) else {
+                continue;
+            };
+
+            let text = lines.map(strip_markup).collect::<Vec<_>>().join("\n");
+            if !text.trim().is_empty() {
+                cues.push(SubtitleCue { start, end, text });
+            }
+        }
+
+        Self::new(cues)
+    }
+
+    /// Get the text shown at a position, overlapping cues joined line by line
+    pub fn text_at(&self, position: Duration) -> Option<String> {
+        let started = self.cues.partition_point(|cue| cue.start <= position);
+        let texts: Vec<&str> = self.cues[..started]
+            .iter()
+            .filter(|cue| position < cue.end)
+            .map(|cue| cue.text.as_str())
+            .collect();
+
+        (!texts.is_empty()).then(|| texts.join("\n"))
+    }
+
+    /// Get all cues, sorted by start time
+    pub fn cues(&self) -> &[SubtitleCue] {
+        &self.cues
+    }
+}
+
+/// Text of an ASS `Dialogue` event as FFmpeg hands it out
+///
+/// The event is `ReadOrder,Layer,Style,Name,MarginL,MarginR,MarginV,Effect,Text`;
+/// the text may itself contain commas.
+pub fn ass_text(event: &str) -> String {
+    let text = event.splitn(9, ',').nth(8).unwrap_or(event);
+    strip_markup(&text.replace("\\N", "\n").replace("\\n", "\n").replace("\\h", " "))
+}
+
+/// Parse a `start --> end` timing line; WebVTT cue settings after the end are ignored
+fn parse_timing(line: &str) -> Option<(Duration, Duration)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+/// Parse `HH:MM:SS,mmm` (SRT) or `[HH:]MM:SS.mmm` (WebVTT)
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let (clock, millis) = timestamp.split_once([',', '.'])?;
+    let millis: u64 = millis.parse().ok()?;
+
+    let mut seconds = 0u64;
+    for part in clock.split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(Duration::from_secs(seconds) + Duration::from_millis(millis))
+}
+
+/// Remove `<tags>` and `{override}` blocks and decode the common HTML entities
+fn strip_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut closing = None;
+    for c in line.chars() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (None, c) => text.push(c),
+            (Some(end), c) if c == end => closing = None,
+            (Some(_), _) => {}
+        }
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:03,500\r\nHello <i>there</i>\r\n\r\n\
+                   2\r\n00:01:02,250 --> 00:01:04,000\r\n{\\an8}Two\r\nlines\r\n";
+        let track = SubtitleTrack::parse(srt);
+
+        assert_eq!(track.cues(), &[
+            SubtitleCue { start: ms(1000), end: ms(3500), text: "Hello there".to_string() },
+            SubtitleCue { start: ms(62_250), end: ms(64_000), text: "Two\nlines".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_vtt() {
+        let vtt = "WEBVTT\n\nNOTE a comment\n\nintro\n00:01.000 --> 00:02.000 align:start line:0\n\
+                   Fish &amp; chips\n\n01:00:00.000 --> 01:00:01.000\n<v Bob>Hi</v>\n";
+        let track = SubtitleTrack::parse(vtt);
+
+        assert_eq!(track.cues().len(), 2);
+        assert_eq!(track.cues()[0], SubtitleCue { start: ms(1000), end: ms(2000), text: "Fish & chips".to_string() });
+        assert_eq!(track.cues()[1].start, Duration::from_secs(3600));
+        assert_eq!(track.cues()[1].text, "Hi");
+    }
+
+    #[test]
+    fn test_text_at() {
+        let cue = |start, end, text: &str| SubtitleCue { start: ms(start), end: ms(end), text: text.to_string() };
+        let track = SubtitleTrack::new(vec![cue(2000, 3000, "b"), cue(0, 10_000, "a"), cue(5000, 6000, "c")]);
+
+        assert_eq!(track.text_at(ms(1000)).as_deref(), Some("a"));
+        assert_eq!(track.text_at(ms(2500)).as_deref(), Some("a\nb"));
+        assert_eq!(track.text_at(ms(3000)).as_deref(), Some("a"));
+        assert_eq!(track.text_at(ms(10_000)), None);
+    }
+
+    #[test]
+    fn test_ass_text() {
+        assert_eq!(ass_text("0,0,Default,,0,0,0,,{\\i1}Well,{\\i0} yes\\Nno"), "Well, yes\nno");
+        assert_eq!(ass_text("plain"), "plain");
+    }
+}
@@ -0,0 +1,650 @@
+//! Thumbnail extraction for seek-bar previews
+//!
+//! A background thread with its own decoder seeks to the nearest keyframe
+//! before each requested time and keeps the scaled-down frame. Thumbnails
+//! are cached in memory and, optionally, on disk under a hash of the file
+//! contents, so reopening a file (even renamed) reuses them.
+
+use super::{Decoder, FFmpegDecoder};
+use crate::renderer::{FrameData, VideoFrame};
+use crate::utils::error::{CCPlayerError, Result};
+use parking_lot::{Condvar, Mutex};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Bytes hashed at each end of the file
+const HASH_SPAN: u64 = 64 * 1024;
+
+/// Requests kept waiting; older ones are dropped as the cursor moves on
+const MAX_QUEUED: usize = 8;
+
+/// Magic bytes of a cached thumbnail file
+const MAGIC: &[u8; 4] = b"CCTH";
+
+/// Thumbnail settings
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct ThumbnailConfig {
+    /// Thumbnail width in pixels; the height follows the aspect ratio
+    pub width: u32,
+
+    /// Spacing of thumbnails; requests are rounded down to a multiple of it
+    pub interval: Duration,
+
+    /// Thumbnails kept in memory
+    pub memory_entries: usize,
+
+    /// Directory for the on-disk cache (None keeps thumbnails in memory only)
+    pub cache_dir: Option<PathBuf>,
+
+    /// Size the on-disk cache is trimmed to whenever a file is opened; the
+    /// thumbnails of the files cached longest ago are removed first
+    pub max_disk_bytes: u64,
+}
+
+impl Default for ThumbnailConfig {
+    fn default() -> Self {
+        Self {
+            width: 160,
+            interval: Duration::from_secs(5),
+            memory_entries: 256,
+            cache_dir: dirs::cache_dir().map(|dir| dir.join("ccplayer").join("thumbnails")),
+            max_disk_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl ThumbnailConfig {
+    /// Index of the thumbnail covering `time`
+    pub fn slot(&self, time: Duration) -> u64 {
+        let interval = self.interval.as_millis().max(1);
+        (time.as_millis() / interval) as u64
+    }
+
+    /// Time a thumbnail slot starts at
+    pub fn slot_time(&self, slot: u64) -> Duration {
+        Duration::from_millis(slot * self.interval.as_millis().max(1) as u64)
+    }
+}
+
+/// Scaled-down video frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail {
+    /// Timestamp of the frame shown (the keyframe before the requested time)
+    pub pts: Duration,
+
+    /// Width in pixels
+    pub width: u32,
+
+    /// Height in pixels
+    pub height: u32,
+
+    /// RGBA8 pixels, row by row without padding
+    pub data: Vec<u8>,
+}
+
+impl Thumbnail {
+    /// Convert a decoded frame
+    ///
+    /// # Returns
+    ///
+    /// None for formats without a conversion
+    pub fn from_frame(frame: &VideoFrame) -> Option<Self> {
+        Some(Self {
+            pts: Duration::from_micros(frame.pts.max(0) as u64),
+            width: frame.width,
+            height: frame.height,
+            data: to_rgba(frame)?,
+        })
+    }
+
+    /// Serialize for the disk cache
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(20 + self.data.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&(self.pts.as_micros() as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Parse a thumbnail written by [`Thumbnail::encode`]
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 20 || &bytes[..4] != MAGIC {
+            return None;
+        }
+
+        let width = u32::from_le_bytes(bytes[4..8].try_into().ok()?);
+        let height = u32::from_le_bytes(bytes[8..12].try_into().ok()?);
+        let pts = u64::from_le_bytes(bytes[12..20].try_into().ok()?);
+        let data = bytes[20..].to_vec();
+        if data.len() != width as usize * height as usize * 4 {
+            return None;
+        }
+
+        Some(Self {
+            pts: Duration::from_micros(pts),
+            width,
+            height,
+            data,
+        })
+    }
+}
+
+/// Convert a frame to tightly packed RGBA8
+///
+/// YUV formats use the BT.601 limited-range matrix, which is plenty for
+/// previews.
+pub fn to_rgba(frame: &VideoFrame) -> Option<Vec<u8>> {
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let mut rgba = Vec::with_capacity(width * height * 4);
+
+    match &frame.data {
+        FrameData::Yuv420 { y_plane, u_plane, v_plane, y_stride, uv_stride } => {
+            let chroma = planar_chroma(u_plane, v_plane, *uv_stride, 2, 2);
+            push_yuv(&mut rgba, width, height, y_plane, *y_stride, chroma);
+        }
+        FrameData::Yuv422 { y_plane, u_plane, v_plane, y_stride, uv_stride } => {
+            let chroma = planar_chroma(u_plane, v_plane, *uv_stride, 2, 1);
+            push_yuv(&mut rgba, width, height, y_plane, *y_stride, chroma);
+        }
+        FrameData::Yuv444 { y_plane, u_plane, v_plane, stride } => {
+            let chroma = planar_chroma(u_plane, v_plane, *stride, 1, 1);
+            push_yuv(&mut rgba, width, height, y_plane, *stride, chroma);
+        }
+        FrameData::Nv12 { y_plane, uv_plane, y_stride, uv_stride } => {
+            let chroma = |x: usize, y: usize| {
+                let index = (y / 2) * uv_stride + (x / 2) * 2;
+                (
+                    uv_plane.get(index).copied().unwrap_or(128),
+                    uv_plane.get(index + 1).copied().unwrap_or(128),
+                )
+            };
+            push_yuv(&mut rgba, width, height, y_plane, *y_stride, chroma);
+        }
+        FrameData::Rgb { data, stride } => {
+            for y in 0..height {
+                let row = data.get(y * stride..y * stride + width * 3)?;
+                for [r, g, b] in row.as_chunks::<3>().0 {
+                    rgba.extend_from_slice(&[*r, *g, *b, 255]);
+                }
+            }
+        }
+        FrameData::Rgba { data, stride } => {
+            for y in 0..height {
+                rgba.extend_from_slice(data.get(y * stride..y * stride + width * 4)?);
+            }
+        }
+    }
+
+    Some(rgba)
+}
+
+/// Chroma lookup for planes subsampled by (`sx`, `sy`)
+fn planar_chroma<'a>(u: &'a [u8], v: &'a [u8], stride: usize, sx: usize, sy: usize) -> impl Fn(usize, usize) -> (u8, u8) + 'a {
+    move |x, y| {
+        let index = (y / sy) * stride + x / sx;
+        (u.get(index).copied().unwrap_or(128), v.get(index).copied().unwrap_or(128))
+    }
+}
+
+/// Append YUV pixels converted to RGBA
+fn push_yuv(
+    rgba: &mut Vec<u8>,
+    width: usize,
+    height: usize,
+    luma: &[u8],
+    stride: usize,
+    chroma: impl Fn(usize, usize) -> (u8, u8),
+) {
+    for y in 0..height {
+        for x in 0..width {
+            let c = luma.get(y * stride + x).copied().unwrap_or(16) as i32 - 16;
+            let (u, v) = chroma(x, y);
+            let (d, e) = (u as i32 - 128, v as i32 - 128);
+
+            let r = (298 * c + 409 * e + 128) >> 8;
+            let g = (298 * c - 100 * d - 208 * e + 128) >> 8;
+            let b = (298 * c + 516 * d + 128) >> 8;
+            rgba.extend_from_slice(&[r.clamp(0, 255) as u8, g.clamp(0, 255) as u8, b.clamp(0, 255) as u8, 255]);
+        }
+    }
+}
+
+/// Hash identifying a file by its contents
+///
+/// FNV-1a over the size and the first and last 64 KiB, which tells media
+/// files apart without reading them in full.
+pub fn file_hash(path: &Path) -> Result<u64> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    feed(&size.to_le_bytes());
+
+    let mut buffer = Vec::with_capacity(HASH_SPAN as usize);
+    file.by_ref().take(HASH_SPAN).read_to_end(&mut buffer)?;
+    feed(&buffer);
+
+    if size > HASH_SPAN {
+        buffer.clear();
+        file.seek(SeekFrom::Start(size.saturating_sub(HASH_SPAN).max(HASH_SPAN)))?;
+        file.take(HASH_SPAN).read_to_end(&mut buffer)?;
+        feed(&buffer);
+    }
+
+    Ok(hash)
+}
+
+/// State shared with the worker thread
+struct ThumbnailState {
+    /// Thumbnails in memory by slot
+    memory: HashMap<u64, Arc<Thumbnail>>,
+
+    /// Slots in memory, oldest first, for eviction
+    order: VecDeque<u64>,
+
+    /// Requested slots, newest last
+    queue: VecDeque<u64>,
+
+    /// Slots without a thumbnail (past the end, undecodable)
+    failed: HashSet<u64>,
+
+    /// Set when the decoder could not open the file
+    broken: bool,
+
+    /// Set when the generator is dropped
+    closed: bool,
+}
+
+/// Lock and condition variable shared with the worker thread
+struct Shared {
+    /// Generator state
+    state: Mutex<ThumbnailState>,
+
+    /// Signalled on new requests and finished thumbnails
+    changed: Condvar,
+}
+
+/// Background thumbnail extractor for one media file
+pub struct ThumbnailGenerator {
+    /// State shared with the worker thread
+    shared: Arc<Shared>,
+
+    /// File the thumbnails come from
+    path: PathBuf,
+
+    /// Thumbnail settings
+    config: ThumbnailConfig,
+
+    /// Worker thread
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl ThumbnailGenerator {
+    /// Start extracting thumbnails from `path`
+    ///
+    /// The file is opened by a separate decoder on the worker thread, so
+    /// this returns right away and works without a window.
+    pub fn new(path: &Path, config: ThumbnailConfig) -> Result<Self> {
+        if config.width == 0 {
+            return Err(CCPlayerError::InvalidInput("Thumbnail width must be positive".to_string()));
+        }
+
+        // Files that cannot be hashed (archive entries) only use the memory cache
+        let disk_dir = match (&config.cache_dir, file_hash(path)) {
+            (Some(dir), Ok(hash)) => Some(dir.join(format!("{:016x}", hash))),
+            _ => None,
+        };
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(ThumbnailState {
+                memory: HashMap::new(),
+                order: VecDeque::new(),
+                queue: VecDeque::new(),
+                failed: HashSet::new(),
+                broken: false,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let worker = {
+            let shared = Arc::clone(&shared);
+            let path = path.to_path_buf();
+            let config = config.clone();
+            thread::Builder::new()
+                .name("thumbnails".to_string())
+                .spawn(move || extract(&shared, &path, &config, disk_dir))?
+        };
+
+        Ok(Self {
+            shared,
+            path: path.to_path_buf(),
+            config,
+            worker: Some(worker),
+        })
+    }
+
+    /// File the thumbnails come from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Thumbnail settings
+    pub fn config(&self) -> &ThumbnailConfig {
+        &self.config
+    }
+
+    /// Cached thumbnail covering `time`, if it has been extracted
+    pub fn get(&self, time: Duration) -> Option<Arc<Thumbnail>> {
+        let slot = self.config.slot(time);
+        self.shared.state.lock().memory.get(&slot).cloned()
+    }
+
+    /// Ask for the thumbnail covering `time` to be extracted
+    ///
+    /// The newest request is served first; once too many are waiting the
+    /// oldest are dropped.
+    pub fn request(&self, time: Duration) {
+        let slot = self.config.slot(time);
+        let mut state = self.shared.state.lock();
+        if state.broken || state.memory.contains_key(&slot) || state.failed.contains(&slot) {
+            return;
+        }
+
+        state.queue.retain(|queued| *queued != slot);
+        state.queue.push_back(slot);
+        while state.queue.len() > MAX_QUEUED {
+            state.queue.pop_front();
+        }
+        self.shared.changed.notify_all();
+    }
+
+    /// Get the thumbnail covering `time`, waiting up to `timeout` for it
+    ///
+    /// # Returns
+    ///
+    /// None when it could not be extracted in time
+    pub fn thumbnail(&self, time: Duration, timeout: Duration) -> Option<Arc<Thumbnail>> {
+        let slot = self.config.slot(time);
+        let deadline = Instant::now() + timeout;
+        self.request(time);
+
+        let mut state = self.shared.state.lock();
+        loop {
+            if let Some(thumbnail) = state.memory.get(&slot) {
+                return Some(Arc::clone(thumbnail));
+            }
+            if state.broken || state.failed.contains(&slot) {
+                return None;
+            }
+            if self.shared.changed.wait_until(&mut state, deadline).timed_out() {
+                return state.memory.get(&slot).cloned();
+            }
+        }
+    }
+}
+
+impl Drop for ThumbnailGenerator {
+    fn drop(&mut self) {
+        self.shared.state.lock().closed = true;
+        self.shared.changed.notify_all();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Worker thread: extract requested thumbnails until closed
+fn extract(shared: &Shared, path: &Path, config: &ThumbnailConfig, disk_dir: Option<PathBuf>) {
+    let mut decoder = match open_decoder(path, config) {
+        Ok(decoder) => decoder,
+        Err(e) => {
+            log::warn!("Thumbnails unavailable for {:?}: {}", path, e);
+            shared.state.lock().broken = true;
+            shared.changed.notify_all();
+            return;
+        }
+    };
+
+    if let (Some(cache_dir), Some(keep)) = (&config.cache_dir, &disk_dir) {
+        if let Err(e) = prune_disk_cache(cache_dir, keep, config.max_disk_bytes) {
+            log::debug!("Could not trim the thumbnail cache: {}", e);
+        }
+    }
+
+    loop {
+        let slot = {
+            let mut state = shared.state.lock();
+            loop {
+                if state.closed {
+                    return;
+                }
+                if let Some(slot) = state.queue.pop_back() {
+                    break slot;
+                }
+                shared.changed.wait(&mut state);
+            }
+        };
+
+        let file = disk_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}-{}.thumb", config.slot_time(slot).as_millis(), config.width)));
+
+        let cached = file
+            .as_ref()
+            .and_then(|file| fs::read(file).ok())
+            .and_then(|bytes| Thumbnail::decode(&bytes));
+
+        let thumbnail = match cached {
+            Some(thumbnail) => Some(thumbnail),
+            None => match extract_frame(&mut decoder, config.slot_time(slot)) {
+                Ok(thumbnail) => {
+                    if let (Some(thumbnail), Some(file)) = (&thumbnail, &file) {
+                        store(file, thumbnail);
+                    }
+                    thumbnail
+                }
+                Err(e) => {
+                    log::debug!("Thumbnail at {:?} failed: {}", config.slot_time(slot), e);
+                    None
+                }
+            },
+        };
+
+        let mut state = shared.state.lock();
+        match thumbnail {
+            Some(thumbnail) => {
+                state.memory.insert(slot, Arc::new(thumbnail));
+                state.order.push_back(slot);
+                while state.order.len() > config.memory_entries.max(1) {
+                    if let Some(oldest) = state.order.pop_front() {
+                        state.memory.remove(&oldest);
+                    }
+                }
+            }
+            None => {
+                state.failed.insert(slot);
+            }
+        }
+        shared.changed.notify_all();
+    }
+}
+
+/// Open a decoder that outputs thumbnail-sized frames
+fn open_decoder(path: &Path, config: &ThumbnailConfig) -> Result<FFmpegDecoder> {
+    let mut decoder = FFmpegDecoder::new()?;
+    let info = decoder.open_file(path)?;
+    if info.video_streams.is_empty() {
+        return Err(CCPlayerError::UnsupportedFormat("No video stream".to_string()));
+    }
+
+    decoder.set_video_filters(Some(&format!("scale={}:-2", config.width)))?;
+    Ok(decoder)
+}
+
+/// Decode the keyframe at or before `time`
+fn extract_frame(decoder: &mut FFmpegDecoder, time: Duration) -> Result<Option<Thumbnail>> {
+    decoder.seek(time)?;
+    let Some(frame) = decoder.decode_frame()? else {
+        return Ok(None);
+    };
+
+    Ok(Thumbnail::from_frame(&frame))
+}
+
+/// Write a thumbnail to the disk cache, ignoring failures
+fn store(file: &Path, thumbnail: &Thumbnail) {
+    let written = file
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(file, thumbnail.encode()));
+
+    if let Err(e) = written {
+        log::debug!("Could not cache thumbnail {:?}: {}", file, e);
+    }
+}
+
+/// Remove the cached thumbnails of other files, oldest first, until the cache fits in `max_bytes`
+///
+/// Each file has its own directory in the cache; `keep` is the one in use.
+fn prune_disk_cache(cache_dir: &Path, keep: &Path, max_bytes: u64) -> std::io::Result<()> {
+    let mut total = 0;
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(cache_dir)? {
+        let dir = entry?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let (size, modified) = dir_usage(&dir)?;
+        total += size;
+        if dir != keep {
+            candidates.push((modified, size, dir));
+        }
+    }
+
+    candidates.sort();
+    for (_, size, dir) in candidates {
+        if total <= max_bytes {
+            break;
+        }
+        fs::remove_dir_all(&dir)?;
+        total -= size;
+    }
+    Ok(())
+}
+
+/// Total size and newest modification time of the files in a directory
+fn dir_usage(dir: &Path) -> std::io::Result<(u64, SystemTime)> {
+    let mut size = 0;
+    let mut newest = SystemTime::UNIX_EPOCH;
+    for entry in fs::read_dir(dir)? {
+        let metadata = entry?.metadata()?;
+        size += metadata.len();
+        newest = newest.max(metadata.modified()?);
+    }
+    Ok((size, newest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_thumbnail_slots() {
+        let config = ThumbnailConfig::default();
+        assert_eq!(config.slot(Duration::from_millis(4_999)), 0);
+        assert_eq!(config.slot(Duration::from_secs(12)), 2);
+        assert_eq!(config.slot_time(2), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_thumbnail_encoding() {
+        let thumbnail = Thumbnail {
+            pts: Duration::from_millis(41_708),
+            width: 2,
+            height: 1,
+            data: vec![1, 2, 3, 255, 4, 5, 6, 255],
+        };
+
+        assert_eq!(Thumbnail::decode(&thumbnail.encode()), Some(thumbnail.clone()));
+
+        // Truncated files are ignored
+        let bytes = thumbnail.encode();
+        assert_eq!(Thumbnail::decode(&bytes[..bytes.len() - 1]), None);
+    }
+
+    #[test]
+    fn test_to_rgba() {
+        // 2x2 frame: black and white luma, neutral chroma, padded rows
+        let frame = VideoFrame {
+            data: FrameData::Yuv420 {
+                y_plane: vec![16, 235, 0, 0, 235, 16, 0, 0],
+                u_plane: vec![128],
+                v_plane: vec![128],
+                y_stride: 4,
+                uv_stride: 1,
+            },
+            pts: 0,
+            duration: 0,
+            width: 2,
+            height: 2,
+            par: 1.0,
+        };
+
+        let rgba = to_rgba(&frame).unwrap();
+        assert_eq!(rgba.len(), 16);
+        assert_eq!(&rgba[..4], &[0, 0, 0, 255]);
+        assert_eq!(&rgba[4..8], &[255, 255, 255, 255]);
+        assert_eq!(&rgba[12..], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_file_hash() {
+        let mut first = tempfile::NamedTempFile::new().unwrap();
+        first.write_all(&vec![7u8; 200_000]).unwrap();
+        let mut second = tempfile::NamedTempFile::new().unwrap();
+        second.write_all(&vec![7u8; 200_000]).unwrap();
+
+        // Same contents hash the same wherever they live
+        assert_eq!(file_hash(first.path()).unwrap(), file_hash(second.path()).unwrap());
+
+        second.write_all(&[8]).unwrap();
+        assert_ne!(file_hash(first.path()).unwrap(), file_hash(second.path()).unwrap());
+    }
+
+    #[test]
+    fn test_prune_disk_cache() {
+        let cache = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        for (name, age) in [("old", 300), ("newer", 200), ("newest", 100), ("current", 400)] {
+            let dir = cache.path().join(name);
+            fs::create_dir(&dir).unwrap();
+            let file = File::create(dir.join("0-160.thumb")).unwrap();
+            file.set_len(1000).unwrap();
+            file.set_modified(now - Duration::from_secs(age)).unwrap();
+        }
+
+        // The file in use stays even though it is the oldest
+        prune_disk_cache(cache.path(), &cache.path().join("current"), 2500).unwrap();
+        assert!(!cache.path().join("old").exists());
+        assert!(!cache.path().join("newer").exists());
+        assert!(cache.path().join("newest").exists());
+        assert!(cache.path().join("current").exists());
+    }
+}
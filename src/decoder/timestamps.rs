@@ -0,0 +1,368 @@
+//! Timestamp normalization for broadcast streams
+//!
+//! MPEG-TS timestamps are 33-bit and wrap around every ~26.5 hours, and
+//! recordings of IPTV or broadcast channels jump at splice points. The
+//! normalizer unwraps packet timestamps and rebases every stream across a
+//! discontinuity by the same offset, so everything downstream (A/V sync,
+//! the frame queue, the position display) sees one continuous timeline.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Jump in a stream's timestamps treated as a discontinuity
+pub const DEFAULT_DISCONTINUITY_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// Timing parameters of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamTiming {
+    /// Time base numerator
+    pub numerator: i32,
+
+    /// Time base denominator
+    pub denominator: i32,
+
+    /// Width of the container's timestamps in bits (33 for MPEG-TS)
+    pub wrap_bits: u32,
+}
+
+impl StreamTiming {
+    /// Convert a timestamp to microseconds
+    fn ts_to_micros(self, ts: i64) -> i64 {
+        (ts as i128 * 1_000_000 * self.numerator as i128 / self.denominator.max(1) as i128) as i64
+    }
+
+    /// Convert microseconds to a timestamp
+    fn micros_to_ts(self, micros: i64) -> i64 {
+        (micros as i128 * self.denominator as i128 / (self.numerator.max(1) as i128 * 1_000_000)) as i64
+    }
+
+    /// Shift `ts` by whole wrap periods to lie closest to `reference`
+    fn unwrap(self, ts: i64, reference: i64) -> i64 {
+        if self.wrap_bits == 0 || self.wrap_bits >= 63 {
+            return ts;
+        }
+
+        let period = 1i64 << self.wrap_bits;
+        let wraps = (reference - ts + period / 2).div_euclid(period);
+        ts + wraps * period
+    }
+}
+
+/// Stretch of the source timeline with a single offset
+#[derive(Debug, Clone, Copy)]
+struct Epoch {
+    /// Microseconds added to unwrapped timestamps
+    offset: i64,
+
+    /// Normalized time the stretch starts at, in microseconds
+    start: i64,
+}
+
+/// Per-stream state
+#[derive(Debug, Clone, Copy)]
+struct StreamState {
+    /// Timing parameters
+    timing: StreamTiming,
+
+    /// Epoch the stream is in
+    epoch: usize,
+
+    /// Unwrapped timestamp of the previous packet
+    last_ts: i64,
+
+    /// Previous packet time in microseconds, unwrapped but not rebased
+    last_time: i64,
+
+    /// Duration of the previous packet in microseconds
+    last_duration: i64,
+}
+
+impl StreamState {
+    /// Unrebased time the next packet is expected at, in microseconds
+    fn expected(&self) -> i64 {
+        self.last_time + self.last_duration
+    }
+}
+
+/// Unwraps timestamps and rebases all streams across discontinuities
+#[derive(Debug, Clone)]
+pub struct TimestampNormalizer {
+    /// State of each stream seen, by stream id
+    streams: HashMap<usize, StreamState>,
+
+    /// Offsets of the timeline stretches seen so far
+    epochs: Vec<Epoch>,
+
+    /// Jump treated as a discontinuity, in microseconds
+    threshold: i64,
+
+    /// Epoch and unrebased time in microseconds of the last seek target
+    seek_target: Option<(usize, i64)>,
+}
+
+impl TimestampNormalizer {
+    /// Create a normalizer
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - Jump in a stream's timestamps treated as a discontinuity
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            streams: HashMap::new(),
+            epochs: Vec::new(),
+            threshold: threshold.as_micros() as i64,
+            seek_target: None,
+        }
+    }
+
+    /// Normalize the timestamps of one packet
+    ///
+    /// A jump counts as a discontinuity only when no other stream is near
+    /// the new time; then the stream keeps going from where it was
+    /// expected, and the other streams pick up the same offset when they
+    /// reach the jump. Packets without any timestamp get the time the
+    /// stream was expected at as their DTS.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - Stream id, unique across all inputs
+    /// * `timing` - Time base and timestamp width of the stream
+    /// * `pts` - Presentation timestamp in the stream's time base
+    /// * `dts` - Decoding timestamp in the stream's time base
+    /// * `duration` - Packet duration in the stream's time base (0 if unknown)
+    ///
+    /// # Returns
+    ///
+    /// Normalized (pts, dts) in the stream's time base
+    pub fn normalize(
+        &mut self,
+        stream: usize,
+        timing: StreamTiming,
+        pts: Option<i64>,
+        dts: Option<i64>,
+        duration: i64,
+    ) -> (Option<i64>, Option<i64>) {
+        let duration = timing.ts_to_micros(duration.max(0));
+        let previous = self.streams.get(&stream).copied().filter(|state| state.timing == timing);
+
+        let Some(reference) = dts.or(pts) else {
+            // Nothing to go on: continue where the stream left off
+            let Some(mut state) = previous else {
+                return (None, None);
+            };
+            let offset = self.epochs[state.epoch].offset;
+            let time = state.expected();
+            state.last_ts = timing.micros_to_ts(time);
+            state.last_time = time;
+            state.last_duration = duration.max(state.last_duration);
+            self.streams.insert(stream, state);
+            return (None, Some(timing.micros_to_ts(time + offset)));
+        };
+
+        let reference = match (&previous, self.seek_target) {
+            (Some(state), _) => timing.unwrap(reference, state.last_ts),
+            (None, Some((_, target))) => timing.unwrap(reference, timing.micros_to_ts(target)),
+            (None, None) => reference,
+        };
+        let time = timing.ts_to_micros(reference);
+
+        let epoch = match &previous {
+            Some(state) if (time - state.expected()).abs() <= self.threshold => state.epoch,
+            Some(state) => match self.epoch_near(stream, time) {
+                Some(epoch) => epoch,
+                None => {
+                    let start = state.expected() + self.epochs[state.epoch].offset;
+                    log::info!(
+                        "Timestamp discontinuity in stream {}: {:.3}s -> {:.3}s",
+                        stream,
+                        state.expected() as f64 / 1_000_000.0,
+                        time as f64 / 1_000_000.0
+                    );
+                    self.epochs.push(Epoch { offset: start - time, start });
+                    self.epochs.len() - 1
+                }
+            },
+            None => match (self.epoch_near(stream, time), self.seek_target) {
+                (Some(epoch), _) => epoch,
+                // The first packets after a seek continue the stretch the target is in
+                (None, Some((epoch, _))) => epoch,
+                (None, None) if self.epochs.is_empty() => {
+                    self.epochs.push(Epoch { offset: 0, start: i64::MIN });
+                    0
+                }
+                (None, None) => self.epochs.len() - 1,
+            },
+        };
+
+        self.streams.insert(
+            stream,
+            StreamState {
+                timing,
+                epoch,
+                last_ts: reference,
+                last_time: time,
+                last_duration: if duration > 0 {
+                    duration
+                } else {
+                    previous.map_or(0, |state| state.last_duration)
+                },
+            },
+        );
+
+        let offset = timing.micros_to_ts(self.epochs[epoch].offset);
+        let unwrap = |ts: i64| timing.unwrap(ts, reference) + offset;
+        (pts.map(unwrap), dts.map(unwrap))
+    }
+
+    /// Newest epoch of another stream that is near `time`
+    fn epoch_near(&self, stream: usize, time: i64) -> Option<usize> {
+        self.streams
+            .iter()
+            .filter(|(id, state)| **id != stream && (time - state.last_time).abs() <= self.threshold)
+            .map(|(_, state)| state.epoch)
+            .max()
+    }
+
+    /// Prepare for a seek, keeping the offsets found so far
+    ///
+    /// Streams are forgotten; the packets after the seek join the stretch
+    /// of the timeline the target lies in.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Normalized seek target in microseconds
+    ///
+    /// # Returns
+    ///
+    /// The target on the file's own timeline, in microseconds
+    pub fn seek(&mut self, target: i64) -> i64 {
+        self.streams.clear();
+        self.seek_target = self.epoch_at(target).map(|epoch| (epoch, target - self.epochs[epoch].offset));
+        self.seek_target.map_or(target, |(_, time)| time)
+    }
+
+    /// Convert a normalized time in microseconds back to the file's own timeline
+    pub fn file_time(&self, time: i64) -> i64 {
+        self.epoch_at(time).map_or(time, |epoch| time - self.epochs[epoch].offset)
+    }
+
+    /// Epoch a normalized time lies in
+    fn epoch_at(&self, time: i64) -> Option<usize> {
+        self.epochs.iter().rposition(|epoch| epoch.start <= time)
+    }
+
+    /// Forget all streams and offsets (for a new input)
+    pub fn reset(&mut self) {
+        self.streams.clear();
+        self.epochs.clear();
+        self.seek_target = None;
+    }
+}
+
+impl Default for TimestampNormalizer {
+    fn default() -> Self {
+        Self::new(DEFAULT_DISCONTINUITY_THRESHOLD)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// MPEG-TS timing: 90 kHz, 33-bit timestamps
+    const TS: StreamTiming = StreamTiming {
+        numerator: 1,
+        denominator: 90_000,
+        wrap_bits: 33,
+    };
+
+    /// Normalize a packet with equal PTS and DTS, returning the DTS in seconds
+    fn seconds(normalizer: &mut TimestampNormalizer, stream: usize, ts: i64) -> f64 {
+        let (pts, dts) = normalizer.normalize(stream, TS, Some(ts), Some(ts), 3600);
+        assert_eq!(pts, dts);
+        dts.unwrap() as f64 / 90_000.0
+    }
+
+    /// 90 kHz timestamp in microseconds
+    fn timing_micros(ts: i64) -> i64 {
+        TS.ts_to_micros(ts)
+    }
+
+    #[test]
+    fn test_wraparound() {
+        let mut normalizer = TimestampNormalizer::default();
+        let wrap = 1i64 << 33;
+
+        assert_eq!(seconds(&mut normalizer, 0, wrap - 90_000), (wrap - 90_000) as f64 / 90_000.0);
+        assert_eq!(seconds(&mut normalizer, 1, wrap - 45_000), (wrap - 45_000) as f64 / 90_000.0);
+        assert_eq!(seconds(&mut normalizer, 0, 0), wrap as f64 / 90_000.0);
+        assert_eq!(seconds(&mut normalizer, 1, 45_000), (wrap + 45_000) as f64 / 90_000.0);
+
+        // B-frame PTS before the wrap, DTS after it
+        let (pts, dts) = normalizer.normalize(0, TS, Some(wrap - 3600), Some(7200), 3600);
+        assert_eq!(pts, Some(wrap - 3600));
+        assert_eq!(dts, Some(wrap + 7200));
+    }
+
+    #[test]
+    fn test_discontinuity_rebases_all_streams() {
+        let mut normalizer = TimestampNormalizer::default();
+
+        assert_eq!(seconds(&mut normalizer, 0, 900_000), 10.0);
+        assert_eq!(seconds(&mut normalizer, 1, 900_000), 10.0);
+
+        // Splice: video jumps to 500 s and continues after its last packet
+        assert_eq!(seconds(&mut normalizer, 0, 45_000_000), 10.04);
+
+        // Audio still has an old packet, then follows the same jump
+        assert_eq!(seconds(&mut normalizer, 1, 903_600), 10.04);
+        assert_eq!(seconds(&mut normalizer, 1, 45_003_600), 10.08);
+        assert_eq!(seconds(&mut normalizer, 0, 45_003_600), 10.08);
+    }
+
+    #[test]
+    fn test_gap_in_one_stream() {
+        let mut normalizer = TimestampNormalizer::default();
+
+        assert_eq!(seconds(&mut normalizer, 0, 0), 0.0);
+        assert_eq!(seconds(&mut normalizer, 1, 0), 0.0);
+
+        // Audio drops out for 30 s while video keeps going: not a discontinuity
+        for second in (5..=30).step_by(5) {
+            assert_eq!(seconds(&mut normalizer, 0, second * 90_000), second as f64);
+        }
+        assert_eq!(seconds(&mut normalizer, 1, 2_700_000), 30.0);
+    }
+
+    #[test]
+    fn test_missing_timestamps() {
+        let mut normalizer = TimestampNormalizer::default();
+
+        assert_eq!(normalizer.normalize(0, TS, None, None, 3600), (None, None));
+        assert_eq!(seconds(&mut normalizer, 0, 90_000), 1.0);
+        assert_eq!(normalizer.normalize(0, TS, None, None, 3600), (None, Some(93_600)));
+        assert_eq!(seconds(&mut normalizer, 0, 97_200), 1.08);
+    }
+
+    #[test]
+    fn test_seek_keeps_offsets() {
+        let mut normalizer = TimestampNormalizer::default();
+        let wrap = 1i64 << 33;
+
+        // A wrap, then a splice from 5 s after the wrap to 500 s
+        assert_eq!(seconds(&mut normalizer, 0, wrap - 90_000), (wrap - 90_000) as f64 / 90_000.0);
+        assert_eq!(seconds(&mut normalizer, 0, 450_000), (wrap + 450_000) as f64 / 90_000.0);
+        assert_eq!(seconds(&mut normalizer, 0, 45_000_000), (wrap + 453_600) as f64 / 90_000.0);
+        let splice = timing_micros(wrap + 453_600);
+
+        // Back into the first stretch: the file time is unwrapped, and the
+        // wrapped timestamps read there come out as before
+        assert_eq!(normalizer.seek(timing_micros(wrap + 180_000)), timing_micros(wrap + 180_000));
+        assert_eq!(seconds(&mut normalizer, 0, 180_000), (wrap + 180_000) as f64 / 90_000.0);
+
+        // Into the stretch after the splice, 502 s into the unwrapped file
+        let target = splice + 2_000_000;
+        assert_eq!(normalizer.seek(target), timing_micros(wrap) + 502_000_000);
+        assert_eq!(seconds(&mut normalizer, 0, 45_180_000), (wrap + 633_600) as f64 / 90_000.0);
+    }
+}
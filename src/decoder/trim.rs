@@ -0,0 +1,137 @@
+//! Encoder delay and padding removal
+//!
+//! Lossy encoders prepend priming samples and append padding to fill the
+//! last frame. Cutting both off makes consecutive tracks join without a gap.
+
+use crate::decoder::AudioSamples;
+use std::time::Duration;
+
+/// Microseconds per second
+const MICROS: i128 = 1_000_000;
+
+/// Valid part of an audio stream's timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleTrim {
+    /// First valid timestamp (end of the encoder delay), in microseconds
+    start: i64,
+
+    /// End of the valid audio (start of the padding), in microseconds
+    end: Option<i64>,
+}
+
+impl SampleTrim {
+    /// Create a trim from encoder delay and padding
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - Priming samples at the start
+    /// * `padding` - Padding samples at the end
+    /// * `sample_rate` - Rate the delay and padding are counted in
+    /// * `duration` - Stream duration including delay and padding, zero if unknown
+    ///
+    /// # Returns
+    ///
+    /// None when there is nothing to trim
+    pub fn new(delay: u32, padding: u32, sample_rate: u32, duration: Duration) -> Option<Self> {
+        if sample_rate == 0 || (delay == 0 && padding == 0) {
+            return None;
+        }
+
+        let to_micros = |samples: u32| (samples as i128 * MICROS / sample_rate as i128) as i64;
+        let end = (padding > 0 && !duration.is_zero()).then(|| duration.as_micros() as i64 - to_micros(padding));
+
+        Some(Self {
+            start: to_micros(delay),
+            end,
+        })
+    }
+
+    /// Cut delay and padding out of decoded samples
+    ///
+    /// Timestamps are shifted so the first valid sample plays at zero.
+    ///
+    /// # Returns
+    ///
+    /// The valid part of the samples, or None if all of them are cut
+    pub fn apply(&self, samples: AudioSamples) -> Option<AudioSamples> {
+        let rate = samples.sample_rate as i128;
+        let count = samples.sample_count;
+
+        // Index of the first sample at or after a timestamp
+        let index_at = |micros: i64| -> usize {
+            let offset = (micros - samples.pts) as i128 * rate;
+            (offset + MICROS - 1).div_euclid(MICROS).clamp(0, count as i128) as usize
+        };
+
+        let first = index_at(self.start);
+        let last = self.end.map_or(count, index_at);
+        if first >= last {
+            return None;
+        }
+
+        let channels = samples.channels;
+        let pts = samples.pts + (first as i128 * MICROS / rate.max(1)) as i64 - self.start;
+        let data = if first == 0 && last == count {
+            samples.data
+        } else {
+            samples.data[first * channels..last * channels].to_vec()
+        };
+
+        Some(AudioSamples {
+            data,
+            sample_count: last - first,
+            channels,
+            sample_rate: samples.sample_rate,
+            pts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mono samples whose values are their index
+    fn samples(pts: i64, count: usize) -> AudioSamples {
+        AudioSamples {
+            data: (0..count).map(|i| i as f32).collect(),
+            sample_count: count,
+            channels: 1,
+            sample_rate: 1000,
+            pts,
+        }
+    }
+
+    #[test]
+    fn test_trim_delay_and_padding() {
+        // 100 ms priming and 50 ms padding in a 1 s stream
+        let trim = SampleTrim::new(100, 50, 1000, Duration::from_secs(1)).unwrap();
+
+        // First buffer loses its priming and now starts at zero
+        let first = trim.apply(samples(0, 256)).unwrap();
+        assert_eq!(first.sample_count, 156);
+        assert_eq!(first.data[0], 100.0);
+        assert_eq!(first.pts, 0);
+
+        // Middle buffers pass untouched apart from the shifted timestamp
+        let middle = trim.apply(samples(256_000, 256)).unwrap();
+        assert_eq!(middle.sample_count, 256);
+        assert_eq!(middle.pts, 156_000);
+
+        // Last buffer ends where the padding starts
+        let last = trim.apply(samples(768_000, 232)).unwrap();
+        assert_eq!(last.sample_count, 182);
+
+        // Pure padding is dropped
+        assert!(trim.apply(samples(960_000, 40)).is_none());
+    }
+
+    #[test]
+    fn test_nothing_to_trim() {
+        assert!(SampleTrim::new(0, 0, 44100, Duration::from_secs(1)).is_none());
+
+        // Unknown duration: only the delay can be cut
+        let trim = SampleTrim::new(10, 500, 1000, Duration::ZERO).unwrap();
+        assert_eq!(trim.apply(samples(5_000_000, 8)).unwrap().sample_count, 8);
+    }
+}
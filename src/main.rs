@@ -1,84 +1,101 @@
 use anyhow::Result;
 use env_logger::Env;
 use log::{info, error};
-use clap::{Parser, ArgAction};
+use clap::Parser;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 mod audio;
+mod cli;
 mod decoder;
 mod player;
 mod renderer;
 mod utils;
 mod window;
 
-use player::{MediaPlayer, MediaPlayerBuilder, PlayerConfig, PlayerEvent};
-use window::WindowConfig;
+use audio::{AudioOutput, CpalAudioOutput};
+use player::{MediaPlayer, MediaPlayerBuilder, PlayerEvent};
+use utils::Config;
 
 /// CCPlayer - A minimalist, high-performance media player
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
-    /// Media file to play
-    #[arg(value_name = "FILE")]
-    file: Option<PathBuf>,
-    
-    /// Start in fullscreen mode
-    #[arg(short, long)]
-    fullscreen: bool,
-    
-    /// Set initial volume (0-100)
-    #[arg(short, long, value_name = "VOLUME", default_value = "70")]
-    volume: u8,
+    /// Tool to run instead of playing
+    #[command(subcommand)]
+    command: Option<cli::Command>,
     
-    /// Disable hardware acceleration
-    #[arg(long = "no-hw-accel", action = ArgAction::SetFalse)]
-    hardware_accel: bool,
-    
-    /// Enable debug logging
-    #[arg(short, long)]
-    debug: bool,
+    /// Media files or URLs to play; several form a playlist
+    #[arg(value_name = "FILE")]
+    files: Vec<PathBuf>,
     
-    /// Window width
-    #[arg(long, default_value = "1280")]
-    width: u32,
+    /// Options overriding the config files
+    #[command(flatten)]
+    options: cli::options::PlayerOptions,
     
-    /// Window height
-    #[arg(long, default_value = "720")]
-    height: u32,
+    /// Options for this run only
+    #[command(flatten)]
+    playback: cli::options::PlaybackArgs,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Parse command line arguments
-    let args = Args::parse();
+    let args = Arc::new(Args::parse());
+    
+    // `config` subcommands report problems with the configuration themselves
+    let config = match &args.command {
+        Some(cli::Command::Config(_)) => Config::default(),
+        _ => args.options.config()?,
+    };
     
     // Initialize logging
-    let log_level = if args.debug { "debug" } else { "info" };
-    env_logger::Builder::from_env(Env::default().default_filter_or(log_level))
+    env_logger::Builder::from_env(Env::default().default_filter_or(config.general.log_level.as_str()))
         .format_timestamp_millis()
         .init();
     
-    info!("Starting CCPlayer v{}", env!("CARGO_PKG_VERSION"));
+    // Subcommands run without a window
+    if let Some(command) = &args.command {
+        command.run()?;
+        return Ok(());
+    }
+    
+    if config.audio.device.as_deref() == Some("help") {
+        for device in CpalAudioOutput::get_devices()? {
+            println!("{}{}", device.name, if device.is_default { " (default)" } else { "" });
+        }
+        return Ok(());
+    }
     
-    // Create player configuration
-    let mut player_config = PlayerConfig::default();
-    player_config.default_volume = (args.volume as f32) / 100.0;
-    player_config.auto_play = args.file.is_some();
+    // Local files must exist; archive entries (`clips.zip!/clip.mp4`) only exist inside their archive
+    for file in &args.files {
+        let exists = match decoder::ArchivePath::parse(file) {
+            Some(archive) => archive.archive.exists(),
+            None => file.exists(),
+        };
+        if !exists && !file.to_string_lossy().contains("://") {
+            error!("File not found: {:?}", file);
+            return Err(anyhow::anyhow!("File not found"));
+        }
+    }
+    let playlist = args.playback.playlist_items(&args.files)?;
     
-    // Create window configuration
-    let mut window_config = WindowConfig::default();
-    window_config.width = args.width;
-    window_config.height = args.height;
-    window_config.title = "CCPlayer".to_string();
+    info!("Starting CCPlayer v{}", env!("CARGO_PKG_VERSION"));
     
     // Build media player
-    let mut media_player = MediaPlayerBuilder::new()
-        .with_config(player_config)
-        .with_window_config(window_config)
-        .with_hardware_acceleration(args.hardware_accel)
+    let mut media_player = MediaPlayerBuilder::from_config(&config)
+        .with_playback_options(args.playback.playback_options())
         .with_event_handler(Box::new(LoggingEventHandler))
         .build()?;
     
+    if let Some(speed) = args.playback.speed {
+        media_player.set_speed(speed)?;
+    }
+    if args.playback.mute {
+        media_player.set_muted(true)?;
+    }
+    media_player.set_shuffle(args.playback.shuffle);
+    
     // Subscribe to events for UI updates
     let _event_sub = media_player.subscribe_events(|event| {
         match event {
@@ -98,27 +115,26 @@ async fn main() -> Result<()> {
         }
     });
     
+    // Pick up edits to the config files while running
+    let reload_args = Arc::clone(&args);
+    media_player.watch_config(Box::new(move || reload_args.options.config()));
+    
     // Start the player
     media_player.start()?;
     
-    // Load initial file if provided
-    if let Some(file_path) = args.file {
-        if file_path.exists() {
-            info!("Loading file: {:?}", file_path);
-            match media_player.load_file(&file_path) {
-                Ok(_) => {
-                    if args.fullscreen {
-                        media_player.set_fullscreen(true)?;
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to load file: {}", e);
-                    return Err(e.into());
+    // Load the initial playlist if provided
+    if !playlist.is_empty() {
+        info!("Loading {} playlist item(s)", playlist.len());
+        match media_player.load_playlist(playlist) {
+            Ok(()) => {
+                if config.window.fullscreen {
+                    media_player.set_fullscreen(true)?;
                 }
             }
-        } else {
-            error!("File not found: {:?}", file_path);
-            return Err(anyhow::anyhow!("File not found"));
+            Err(e) => {
+                error!("Failed to load file: {}", e);
+                return Err(e.into());
+            }
         }
     }
     
@@ -4,24 +4,50 @@
 //! media playback components including decoder, renderer, audio output,
 //! and window management.
 
+use crate::utils::config::{Config, ConfigLoader, ConfigWatcher};
 use crate::utils::error::{Result, CCPlayerError};
 use crate::window::{Window, WindowEvent};
-use crate::renderer::{Renderer, VideoFrame, Overlay, OverlayPosition, Color};
-use crate::decoder::{Decoder, MediaInfo, AudioSamples};
-use crate::audio::{AudioOutput, AudioFormat, AVSyncController, SyncMode, FrameAction};
+use crate::renderer::{Renderer, RenderStats, Rotation, SeekPreview, VideoFrame, Overlay, OverlayPosition, Color};
+use crate::decoder::{
+    ArchivePath, Decoder, FFmpegDecoder, LiveWindow, MediaInfo, MediaSource, AudioSamples, QualitySelection,
+    SubtitleTrack, ThumbnailGenerator,
+};
+use crate::audio::{
+    AudioOutput, AudioFormat, AVSyncController, ChannelLayout, CrossfadeConfig, Crossfader,
+    SyncMode, FrameAction,
+};
 use crate::player::{
-    Player, PlaybackState, PlayerConfig, PlayerEvent, PlayerEventHandler,
-    PlaybackStats, Playlist, RepeatMode, PlaylistItem,
+    Action, Player, PlaybackOptions, PlaybackState, PlayerConfig, PlayerEvent, PlayerEventHandler,
+    PlaybackStats, Playlist, RepeatMode, PlaylistItem, LiveTracker,
 };
 
 use std::sync::{Arc, Mutex, RwLock, atomic::{AtomicBool, AtomicU64, Ordering}};
-use std::path::Path;
-use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::thread;
 use std::collections::VecDeque;
 use tokio::sync::mpsc;
 use log::{info, warn, error, debug};
 
+/// Decoded audio buffers the decoder keeps queued ahead of the output
+const AUDIO_QUEUE_TARGET: usize = 90;
+
+/// Audio filter placed before the user's chain when loudness normalization is on
+const NORMALIZE_FILTER: &str = "dynaudnorm";
+
+/// Time before the end of an item at which the next playlist item is opened
+const GAPLESS_PRELOAD: Duration = Duration::from_secs(10);
+
+/// How often the downloaded ranges shown on the seek bar are refreshed
+const BUFFERED_RANGES_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Height of the seek bar strip along the bottom of the window, in pixels
+const SEEK_BAR_HEIGHT: f64 = 48.0;
+
+/// Longest sleep of the render thread while a frame stays on screen, so
+/// crossfades animate and stopping does not wait out a still image
+const RENDER_WAIT_SLICE: Duration = Duration::from_millis(16);
+
 /// Internal player command for thread communication
 #[derive(Debug, Clone)]
 enum PlayerCommand {
@@ -65,6 +91,33 @@ struct PlayerState {
     
     /// Last seek position
     last_seek: Option<Duration>,
+    
+    /// Fill level of the playback buffer while buffering (0-100)
+    buffer_percent: f32,
+    
+    /// Time ranges held in the decoder's read-ahead cache
+    buffered_ranges: Vec<(Duration, Duration)>,
+    
+    /// DVR window and latency of a live stream
+    live: LiveTracker,
+    
+    /// Playlist item the decoder was asked to prepare for gapless playback
+    next_item: Option<usize>,
+    
+    /// Media info of the prepared item, once it opened successfully
+    next_info: Option<MediaInfo>,
+    
+    /// Item the decoder continued with gaplessly, until the main loop has set it up
+    joined_item: Option<MediaInfo>,
+    
+    /// Orientation stored in the current video stream
+    stream_rotation: Rotation,
+    
+    /// Extra clockwise rotation chosen by the user, kept across items
+    manual_rotation: i32,
+    
+    /// Seek bar position under the cursor (0.0 to 1.0)
+    seek_hover: Option<f64>,
 }
 
 impl Default for PlayerState {
@@ -82,8 +135,18 @@ impl Default for PlayerState {
                 current_index: None,
                 shuffle: false,
                 repeat_mode: RepeatMode::None,
+                passes_left: None,
             },
             last_seek: None,
+            buffer_percent: 0.0,
+            buffered_ranges: Vec::new(),
+            live: LiveTracker::default(),
+            next_item: None,
+            next_info: None,
+            joined_item: None,
+            stream_rotation: Rotation::default(),
+            manual_rotation: 0,
+            seek_hover: None,
         }
     }
 }
@@ -113,6 +176,7 @@ pub struct PlayerController {
     av_sync: Arc<Mutex<AVSyncController>>,
     running: Arc<AtomicBool>,
     paused: Arc<AtomicBool>,
+    buffering: Arc<AtomicBool>,
     
     // Frame queues
     video_queue: Arc<Mutex<VecDeque<VideoFrame>>>,
@@ -123,6 +187,34 @@ pub struct PlayerController {
     frames_rendered: Arc<AtomicU64>,
     frames_dropped: Arc<AtomicU64>,
     
+    // Live latency catch-up speed applied instead of the user speed, if any
+    live_catchup: Option<f32>,
+    
+    // Seek-bar preview thumbnails of the current file
+    thumbnails: Option<ThumbnailGenerator>,
+    
+    // Last tick of the still image clock
+    still_tick: Instant,
+    
+    // Layered configuration auto-profiles are applied to (None without config files)
+    settings: Option<Config>,
+    
+    // Config file watcher and the loader rebuilding the configuration on changes
+    config_reload: Option<(ConfigWatcher, ConfigLoader)>,
+    
+    // Start, end, tracks and subtitles chosen for this run
+    playback: PlaybackOptions,
+    
+    // Whether the end of the current item has been handled
+    item_done: bool,
+    
+    // Subtitle cues of the current item and the text on screen
+    subtitles: Option<SubtitleTrack>,
+    subtitle_text: Option<String>,
+    
+    // Subtitle stream being read in the background and its cancel flag
+    subtitle_job: Option<(thread::JoinHandle<Result<SubtitleTrack>>, Arc<AtomicBool>)>,
+    
     // Event handling
     event_handlers: Arc<Mutex<Vec<Box<dyn PlayerEventHandler>>>>,
 }
@@ -155,11 +247,22 @@ impl Player for PlayerController {
             av_sync: Arc::new(Mutex::new(av_sync)),
             running: Arc::new(AtomicBool::new(false)),
             paused: Arc::new(AtomicBool::new(false)),
+            buffering: Arc::new(AtomicBool::new(false)),
             video_queue: Arc::new(Mutex::new(VecDeque::with_capacity(30))),
             audio_queue: Arc::new(Mutex::new(VecDeque::with_capacity(100))),
             stats: Arc::new(Mutex::new(PlaybackStats::default())),
             frames_rendered: Arc::new(AtomicU64::new(0)),
             frames_dropped: Arc::new(AtomicU64::new(0)),
+            live_catchup: None,
+            thumbnails: None,
+            still_tick: Instant::now(),
+            settings: None,
+            config_reload: None,
+            playback: PlaybackOptions::default(),
+            item_done: false,
+            subtitles: None,
+            subtitle_text: None,
+            subtitle_job: None,
             event_handlers: Arc::new(Mutex::new(Vec::new())),
         })
     }
@@ -176,49 +279,7 @@ impl Player for PlayerController {
             decoder.open_file(path)?
         };
         
-        // Update state
-        {
-            let mut state = self.state.write().unwrap();
-            state.media_info = Some(media_info.clone());
-            state.state = PlaybackState::Stopped;
-            state.position_us = 0;
-        }
-        
-        // Initialize audio format if audio stream exists
-        if let Some(audio_stream) = media_info.audio_streams.first() {
-            let format = AudioFormat {
-                sample_rate: audio_stream.sample_rate,
-                channels: audio_stream.channels as u16,
-                sample_format: crate::audio::SampleFormat::F32,
-                channel_layout: match audio_stream.channels {
-                    1 => crate::audio::ChannelLayout::Mono,
-                    2 => crate::audio::ChannelLayout::Stereo,
-                    6 => crate::audio::ChannelLayout::Surround51,
-                    8 => crate::audio::ChannelLayout::Surround71,
-                    n => crate::audio::ChannelLayout::Custom(n as u16),
-                },
-            };
-            
-            let mut audio = self.audio.lock().unwrap();
-            audio.initialize(format)?;
-        }
-        
-        // Set video aspect ratio
-        if let Some(video_stream) = media_info.video_streams.first() {
-            let aspect_ratio = video_stream.width as f32 / video_stream.height as f32;
-            let mut renderer = self.renderer.lock().unwrap();
-            renderer.set_aspect_ratio(aspect_ratio)?;
-        }
-        
-        // Send event
-        self.send_event(PlayerEvent::MediaLoaded { info: media_info.clone() });
-        
-        // Auto-play if configured
-        if self.config.auto_play {
-            self.play()?;
-        }
-        
-        Ok(media_info)
+        self.finish_load(media_info)
     }
     
     fn load_url(&mut self, url: &str) -> Result<MediaInfo> {
@@ -233,42 +294,22 @@ impl Player for PlayerController {
             decoder.open_url(url)?
         };
         
-        // Update state
-        {
-            let mut state = self.state.write().unwrap();
-            state.media_info = Some(media_info.clone());
-            state.state = PlaybackState::Stopped;
-            state.position_us = 0;
-        }
-        
-        // Initialize audio format if audio stream exists
-        if let Some(audio_stream) = media_info.audio_streams.first() {
-            let format = AudioFormat {
-                sample_rate: audio_stream.sample_rate,
-                channels: audio_stream.channels as u16,
-                sample_format: crate::audio::SampleFormat::F32,
-                channel_layout: match audio_stream.channels {
-                    1 => crate::audio::ChannelLayout::Mono,
-                    2 => crate::audio::ChannelLayout::Stereo,
-                    6 => crate::audio::ChannelLayout::Surround51,
-                    8 => crate::audio::ChannelLayout::Surround71,
-                    n => crate::audio::ChannelLayout::Custom(n as u16),
-                },
-            };
-            
-            let mut audio = self.audio.lock().unwrap();
-            audio.initialize(format)?;
-        }
+        self.finish_load(media_info)
+    }
+    
+    fn load_source(&mut self, source: Box<dyn MediaSource>) -> Result<MediaInfo> {
+        info!("Loading source: {}", source.name());
         
-        // Send event
-        self.send_event(PlayerEvent::MediaLoaded { info: media_info.clone() });
+        // Stop current playback
+        self.stop()?;
         
-        // Auto-play if configured
-        if self.config.auto_play {
-            self.play()?;
-        }
+        // Open source in decoder
+        let media_info = {
+            let mut decoder = self.decoder.lock().unwrap();
+            decoder.open_source(source)?
+        };
         
-        Ok(media_info)
+        self.finish_load(media_info)
     }
     
     fn play(&mut self) -> Result<()> {
@@ -310,7 +351,7 @@ impl Player for PlayerController {
     }
     
     fn pause(&mut self) -> Result<()> {
-        if self.state.read().unwrap().state != PlaybackState::Playing {
+        if !matches!(self.state.read().unwrap().state, PlaybackState::Playing | PlaybackState::Buffering) {
             return Ok(());
         }
         
@@ -366,6 +407,10 @@ impl Player for PlayerController {
             audio_queue.clear();
         }
         
+        if let Some((_, cancel)) = self.subtitle_job.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        
         // Wait for threads to finish
         if let Some(thread) = self.decoder_thread.take() {
             let _ = thread.join();
@@ -385,20 +430,43 @@ impl Player for PlayerController {
     
     fn toggle_play(&mut self) -> Result<()> {
         match self.state.read().unwrap().state {
-            PlaybackState::Playing => self.pause(),
-            PlaybackState::Paused | PlaybackState::Stopped => self.play(),
-            _ => Ok(()),
+            PlaybackState::Playing => self.pause()?,
+            PlaybackState::Paused | PlaybackState::Stopped => self.play()?,
+            _ => return Ok(()),
         }
+        
+        self.show_controls_overlay()
     }
     
     fn seek(&mut self, position: Duration) -> Result<()> {
+        // Live streams can only seek within their DVR window
+        let position = {
+            let state = self.state.read().unwrap();
+            if state.media_info.as_ref().is_some_and(|info| info.is_live) {
+                state.live.clamp_seek(position)
+            } else {
+                position
+            }
+        };
+        
         info!("Seeking to {:?}", position);
         
-        // Update state
-        {
+        // Update state; seeking away from the end leaves the item paused there
+        let was_ended = {
             let mut state = self.state.write().unwrap();
             state.last_seek = Some(position);
             state.position_us = position.as_micros() as i64;
+            
+            let ended = state.state == PlaybackState::Ended;
+            if ended {
+                state.state = PlaybackState::Paused;
+            }
+            ended
+        };
+        self.item_done = false;
+        if was_ended {
+            self.paused.store(true, Ordering::SeqCst);
+            self.audio.lock().unwrap().pause()?;
         }
         
         // Clear queues
@@ -426,7 +494,7 @@ impl Player for PlayerController {
         // Send event
         self.send_event(PlayerEvent::PositionChanged { position });
         
-        Ok(())
+        self.show_controls_overlay()
     }
     
     fn seek_relative(&mut self, delta: i64) -> Result<()> {
@@ -465,6 +533,7 @@ impl Player for PlayerController {
             let mut state = self.state.write().unwrap();
             state.speed = speed;
         }
+        self.live_catchup = None;
         
         // Update A/V sync
         {
@@ -553,7 +622,7 @@ impl Player for PlayerController {
     }
     
     fn handle_event(&mut self, event: WindowEvent) -> Result<()> {
-        use crate::window::{Key, MouseButton, ControlEvent};
+        use crate::window::{MouseButton, ControlEvent};
         
         match event {
             WindowEvent::CloseRequested => {
@@ -562,40 +631,13 @@ impl Player for PlayerController {
             }
             
             WindowEvent::KeyPressed { key, modifiers } => {
-                match key {
-                    Key::Space => self.toggle_play()?,
-                    Key::Enter if modifiers.alt => self.set_fullscreen(!self.is_fullscreen())?,
-                    Key::F => self.set_fullscreen(!self.is_fullscreen())?,
-                    Key::M => self.toggle_mute()?,
-                    Key::Left => self.seek_relative(-(self.config.seek_step as i64))?,
-                    Key::Right => self.seek_relative(self.config.seek_step as i64)?,
-                    Key::Up => {
-                        let new_volume = self.volume() + self.config.volume_step;
-                        self.set_volume(new_volume)?;
-                    }
-                    Key::Down => {
-                        let new_volume = self.volume() - self.config.volume_step;
-                        self.set_volume(new_volume)?;
-                    }
-                    Key::PageUp => self.seek_relative(self.config.fast_seek_step as i64)?,
-                    Key::PageDown => self.seek_relative(-(self.config.fast_seek_step as i64))?,
-                    Key::Minus => {
-                        let new_speed = (self.speed() - 0.1).max(0.25);
-                        self.set_speed(new_speed)?;
-                    }
-                    Key::Plus => {
-                        let new_speed = (self.speed() + 0.1).min(4.0);
-                        self.set_speed(new_speed)?;
-                    }
-                    Key::Escape if self.is_fullscreen() => self.set_fullscreen(false)?,
-                    Key::Q if modifiers.ctrl => {
-                        self.stop()?;
-                        self.running.store(false, Ordering::SeqCst);
-                    }
-                    _ => {}
+                if let Some(action) = self.config.keybindings.action(&key, modifiers) {
+                    self.perform(action)?;
                 }
             }
             
+            WindowEvent::MouseMove { x, y } => self.hover_seek_bar(x, y)?,
+            
             WindowEvent::MouseWheel { delta } => {
                 let volume_change = delta * self.config.volume_step;
                 let new_volume = self.volume() + volume_change;
@@ -649,6 +691,14 @@ impl Player for PlayerController {
                 self.handle_event(event)?;
             }
             
+            self.update_buffering();
+            self.update_live_latency();
+            self.update_joined_item();
+            self.update_still_image();
+            self.update_item_end();
+            self.update_subtitles();
+            self.update_config_reload();
+            
             // Small sleep to prevent busy waiting
             thread::sleep(Duration::from_millis(16)); // ~60 FPS event handling
         }
@@ -658,9 +708,168 @@ impl Player for PlayerController {
 }
 
 impl PlayerController {
+    /// Set up state, audio and renderer for freshly opened media
+    fn finish_load(&mut self, media_info: MediaInfo) -> Result<MediaInfo> {
+        // Update state
+        {
+            let mut state = self.state.write().unwrap();
+            state.state = PlaybackState::Stopped;
+            state.position_us = 0;
+            state.next_item = None;
+            state.next_info = None;
+            state.joined_item = None;
+        }
+        
+        let media_info = self.start_item(media_info, false)?;
+        
+        // Auto-play if configured
+        if self.config.auto_play {
+            self.play()?;
+        }
+        
+        Ok(media_info)
+    }
+    
+    /// Per-item setup of a loaded item or one joined gaplessly
+    /// 
+    /// A joined item keeps the running audio output, since its decoder
+    /// already delivers the output format.
+    fn start_item(&mut self, mut media_info: MediaInfo, joined: bool) -> Result<MediaInfo> {
+        self.apply_auto_profiles(&media_info)?;
+        
+        // A still image is shown for the configured time
+        if media_info.is_still {
+            media_info.duration = self.config.still_image.duration;
+        }
+        
+        {
+            let mut state = self.state.write().unwrap();
+            state.media_info = Some(media_info.clone());
+            state.live.reset();
+        }
+        self.item_done = false;
+        
+        if !media_info.audio_streams.is_empty() {
+            if !joined {
+                self.open_audio_output(&media_info)?;
+            }
+            
+            // A broken filter chain is reported and the audio plays unfiltered
+            let filters = self.audio_filter_chain();
+            let set = self.decoder.lock().unwrap().set_audio_filters(filters.as_deref());
+            if let Err(e) = set {
+                error!("{}", e);
+                self.send_event(PlayerEvent::Error { message: e.to_string() });
+            }
+        }
+        
+        // Set video aspect ratio and orientation
+        if let Some(video_stream) = media_info.video_streams.first() {
+            let aspect_ratio = video_stream.width as f32 / video_stream.height as f32;
+            self.renderer.lock().unwrap().set_aspect_ratio(aspect_ratio)?;
+            
+            self.state.write().unwrap().stream_rotation = video_stream.rotation;
+            self.apply_rotation()?;
+        }
+        
+        // Image-only playlists crossfade from one image to the next
+        let slideshow = media_info.is_still && self.state.read().unwrap().playlist.is_slideshow();
+        let transition = if slideshow { self.config.still_image.transition } else { None };
+        self.renderer.lock().unwrap().set_transition(transition)?;
+        self.still_tick = Instant::now();
+        
+        // Interlacing is detected per frame while decoding
+        if !media_info.video_streams.is_empty() {
+            let mut decoder = self.decoder.lock().unwrap();
+            decoder.set_deinterlace(self.config.deinterlace)?;
+            
+            // A broken video filter chain is reported and frames are shown unfiltered
+            if let Err(e) = decoder.set_video_filters(self.config.video_filters.as_deref()) {
+                error!("{}", e);
+                drop(decoder);
+                self.send_event(PlayerEvent::Error { message: e.to_string() });
+            }
+        }
+        
+        self.start_thumbnails(&media_info);
+        self.load_subtitles(&media_info);
+        
+        // Send event
+        self.send_event(PlayerEvent::MediaLoaded { info: media_info.clone() });
+        
+        // Items shorter than the start position play from the beginning
+        let start = self.playback.start
+            .filter(|start| !media_info.is_live && !media_info.is_still && *start < media_info.duration);
+        if let Some(start) = start {
+            self.seek(start)?;
+        }
+        
+        Ok(media_info)
+    }
+    
+    /// Negotiate the audio format of an item and set up output and decoder for it
+    fn open_audio_output(&mut self, media_info: &MediaInfo) -> Result<()> {
+        let Some(audio_stream) = media_info.audio_streams.first() else {
+            return Ok(());
+        };
+        
+        let source = AudioFormat {
+            sample_rate: audio_stream.sample_rate,
+            channels: audio_stream.channels as u16,
+            sample_format: crate::audio::SampleFormat::F32,
+            channel_layout: ChannelLayout::from_channel_count(audio_stream.channels as u16),
+        };
+        
+        // Stereo upmix asks the device for 5.1
+        let mix = self.config.audio_mix;
+        let mut requested = if mix.upmix && source.channel_layout == ChannelLayout::Stereo {
+            AudioFormat { channels: 6, channel_layout: ChannelLayout::Surround51, ..source }
+        } else {
+            source
+        };
+        if self.config.output_sample_rate > 0 {
+            requested.sample_rate = self.config.output_sample_rate;
+        }
+        
+        // Keep the native format unless the device cannot play it
+        let negotiated = self.audio.lock().unwrap().negotiate_format(requested);
+        let format = match negotiated {
+            Ok(format) => format,
+            Err(e) => {
+                warn!("Audio format negotiation failed, using the requested format: {}", e);
+                requested
+            }
+        };
+        if format != source {
+            info!(
+                "Audio output {} Hz / {} ch (source {} Hz / {} ch)",
+                format.sample_rate, format.channels, source.sample_rate, source.channels
+            );
+        }
+        
+        {
+            let mut audio = self.audio.lock().unwrap();
+            audio.set_mix_config(mix);
+            audio.initialize(format)?;
+        }
+        
+        // Known layouts are decoded as they are and remixed by the audio
+        // output's channel mixer; anything else is converted by the decoder
+        let channels = match source.channel_layout {
+            ChannelLayout::Custom(_) => format.channels,
+            _ => source.channels,
+        };
+        self.decoder.lock().unwrap().set_audio_output(format.sample_rate, channels)
+    }
+    
     /// Start playback threads
     fn start_playback_threads(&mut self) {
-        // Decoder thread
+        // Decoder thread, restarted if it stopped at the end of the media
+        if self.decoder_thread.as_ref().is_some_and(|thread| thread.is_finished()) {
+            if let Some(thread) = self.decoder_thread.take() {
+                let _ = thread.join();
+            }
+        }
         if self.decoder_thread.is_none() {
             let decoder = Arc::clone(&self.decoder);
             let video_queue = Arc::clone(&self.video_queue);
@@ -668,9 +877,26 @@ impl PlayerController {
             let running = Arc::clone(&self.running);
             let paused = Arc::clone(&self.paused);
             let state = Arc::clone(&self.state);
+            let event_handlers = Arc::clone(&self.event_handlers);
+            // Items cut with --start or --end are loaded one by one
+            let trimmed = self.playback.start.is_some() || self.playback.end.is_some();
+            let gapless = self.config.gapless && !trimmed;
+            let crossfade = self.config.crossfade.filter(|_| !trimmed);
+            let video_target = self.config.frame_buffer.max(1);
             
             self.decoder_thread = Some(thread::spawn(move || {
-                Self::decoder_thread_fn(decoder, video_queue, audio_queue, running, paused, state);
+                Self::decoder_thread_fn(
+                    decoder,
+                    video_queue,
+                    audio_queue,
+                    running,
+                    paused,
+                    state,
+                    event_handlers,
+                    gapless,
+                    crossfade,
+                    video_target,
+                );
             }));
         }
         
@@ -681,10 +907,11 @@ impl PlayerController {
             let av_sync = Arc::clone(&self.av_sync);
             let running = Arc::clone(&self.running);
             let paused = Arc::clone(&self.paused);
+            let buffering = Arc::clone(&self.buffering);
             let state = Arc::clone(&self.state);
             
             self.audio_thread = Some(thread::spawn(move || {
-                Self::audio_thread_fn(audio, audio_queue, av_sync, running, paused, state);
+                Self::audio_thread_fn(audio, audio_queue, av_sync, running, paused, buffering, state);
             }));
         }
         
@@ -695,6 +922,7 @@ impl PlayerController {
             let av_sync = Arc::clone(&self.av_sync);
             let running = Arc::clone(&self.running);
             let paused = Arc::clone(&self.paused);
+            let buffering = Arc::clone(&self.buffering);
             let frames_rendered = Arc::clone(&self.frames_rendered);
             let frames_dropped = Arc::clone(&self.frames_dropped);
             
@@ -705,6 +933,7 @@ impl PlayerController {
                     av_sync,
                     running,
                     paused,
+                    buffering,
                     frames_rendered,
                     frames_dropped,
                 );
@@ -720,33 +949,85 @@ impl PlayerController {
         running: Arc<AtomicBool>,
         paused: Arc<AtomicBool>,
         state: Arc<RwLock<PlayerState>>,
+        event_handlers: Arc<Mutex<Vec<Box<dyn PlayerEventHandler>>>>,
+        gapless: bool,
+        crossfade: Option<CrossfadeConfig>,
+        video_target: usize,
     ) {
+        // The next item is opened early enough to overlap it for the crossfade
+        let preload = GAPLESS_PRELOAD + crossfade.map_or(Duration::ZERO, |config| config.duration);
+        let mut fader: Option<Crossfader> = None;
+        let mut ranges_checked = Instant::now();
+        let mut opening_next = false;
+        
+        // The current item ends once all of its streams have drained
+        let mut video_ended = false;
+        let mut audio_ended = false;
+        
         while running.load(Ordering::SeqCst) {
+            // The cache keeps filling while paused or with full queues
+            if ranges_checked.elapsed() >= BUFFERED_RANGES_INTERVAL {
+                ranges_checked = Instant::now();
+                Self::track_buffered_ranges(&decoder, &state);
+            }
+            Self::track_live_window(&decoder, &state);
+            
             if paused.load(Ordering::SeqCst) {
                 thread::sleep(Duration::from_millis(10));
                 continue;
             }
             
+            if gapless || crossfade.is_some() {
+                Self::prepare_next_item(&decoder, &state, &video_queue, preload, &mut opening_next);
+            }
+            let (has_video, has_audio, still_duration) = state.read().unwrap()
+                .media_info
+                .as_ref()
+                .map_or((false, false, None), |info| {
+                    (!info.video_streams.is_empty(), !info.audio_streams.is_empty(), info.is_still.then_some(info.duration))
+                });
+            
             // Check queue sizes
             let video_queue_size = video_queue.lock().unwrap().len();
             let audio_queue_size = audio_queue.lock().unwrap().len();
             
             // Don't decode too far ahead
-            if video_queue_size > 25 && audio_queue_size > 90 {
+            if video_queue_size > video_target && audio_queue_size > AUDIO_QUEUE_TARGET {
                 thread::sleep(Duration::from_millis(10));
                 continue;
             }
             
             // Decode video frame
-            if video_queue_size < 30 {
+            if has_video && video_queue_size < video_target + 5 {
                 match decoder.lock().unwrap().decode_frame() {
-                    Ok(Some(frame)) => {
-                        video_queue.lock().unwrap().push_back(frame);
+                    Ok(Some(mut frame)) => {
+                        video_ended = false;
+                        if let Some(duration) = still_duration {
+                            frame.duration = duration.as_micros() as i64;
+                        }
+                        {
+                            // The previous frame lasts until this one starts (variable frame rate)
+                            let mut queue = video_queue.lock().unwrap();
+                            if let Some(previous) = queue.back_mut() {
+                                previous.fit_duration(frame.pts);
+                            }
+                            queue.push_back(frame);
+                        }
+                        Self::track_variant(&decoder, &state);
+                    }
+                    Ok(None) if still_duration.is_some() => {
+                        // The image stays up; its clock ends it (update_still_image)
+                        break;
                     }
                     Ok(None) => {
-                        // End of stream
-                        let mut state_guard = state.write().unwrap();
-                        state_guard.state = PlaybackState::Ended;
+                        // End of video; the audio may still be draining
+                        video_ended = true;
+                    }
+                    Err(e @ CCPlayerError::Network(_)) => {
+                        // The decoder already tried to reconnect
+                        error!("Stream lost: {}", e);
+                        state.write().unwrap().state = PlaybackState::Error;
+                        Self::emit(&event_handlers, PlayerEvent::Error { message: e.to_string() });
                         break;
                     }
                     Err(e) => {
@@ -756,93 +1037,315 @@ impl PlayerController {
             }
             
             // Decode audio samples
-            if audio_queue_size < 100 {
+            if has_audio && audio_queue_size < 100 {
                 match decoder.lock().unwrap().decode_audio() {
                     Ok(Some(samples)) => {
+                        audio_ended = false;
+                        let samples = match &crossfade {
+                            Some(config) => Self::crossfade_audio(&decoder, &state, config, gapless, &mut fader, samples),
+                            None => samples,
+                        };
                         audio_queue.lock().unwrap().push_back(samples);
                     }
                     Ok(None) => {
-                        // End of audio stream
+                        // End of audio; the video may still be draining
+                        audio_ended = true;
                     }
                     Err(e) => {
                         error!("Audio decoder error: {}", e);
                     }
                 }
             }
-        }
-    }
-    
-    /// Audio thread function
-    fn audio_thread_fn(
-        audio: Arc<Mutex<dyn AudioOutput>>,
-        audio_queue: Arc<Mutex<VecDeque<AudioSamples>>>,
-        av_sync: Arc<Mutex<AVSyncController>>,
-        running: Arc<AtomicBool>,
-        paused: Arc<AtomicBool>,
-        state: Arc<RwLock<PlayerState>>,
-    ) {
-        while running.load(Ordering::SeqCst) {
-            if paused.load(Ordering::SeqCst) {
-                thread::sleep(Duration::from_millis(10));
-                continue;
-            }
-            
-            // Get next audio samples
-            let samples = {
-                let mut queue = audio_queue.lock().unwrap();
-                queue.pop_front()
-            };
             
-            if let Some(samples) = samples {
-                // Update audio clock
-                {
-                    let mut sync = av_sync.lock().unwrap();
-                    sync.update_audio_clock(samples.pts);
-                }
+            // End of the item, unless the next item is ready to take over: its
+            // audio follows the last sample and its video the last frame
+            let drained = (video_ended || !has_video) && (audio_ended || !has_audio);
+            if drained && (video_ended || audio_ended) {
+                video_ended = false;
+                audio_ended = false;
                 
-                // Update position
-                {
-                    let mut state_guard = state.write().unwrap();
-                    state_guard.position_us = samples.pts;
+                if Self::advance_to_next_item(&decoder, &state) {
+                    // Incoming audio decoded past the end of the outgoing track
+                    if let Some(rest) = fader.as_mut().and_then(|fader| fader.finish()) {
+                        audio_queue.lock().unwrap().push_back(rest);
+                    }
+                    continue;
                 }
                 
-                // Play audio
-                if let Err(e) = audio.lock().unwrap().play(&samples) {
-                    error!("Audio playback error: {}", e);
-                }
-            } else {
-                // No audio samples available
-                thread::sleep(Duration::from_millis(5));
+                state.write().unwrap().state = PlaybackState::Ended;
+                break;
             }
         }
     }
     
-    /// Render thread function
-    fn render_thread_fn(
-        renderer: Arc<Mutex<dyn Renderer>>,
-        video_queue: Arc<Mutex<VecDeque<VideoFrame>>>,
-        av_sync: Arc<Mutex<AVSyncController>>,
-        running: Arc<AtomicBool>,
-        paused: Arc<AtomicBool>,
-        frames_rendered: Arc<AtomicU64>,
-        frames_dropped: Arc<AtomicU64>,
+    /// Open the next playlist item ahead of time when the current one nears its end
+    fn prepare_next_item(
+        decoder: &Arc<Mutex<dyn Decoder>>,
+        state: &Arc<RwLock<PlayerState>>,
+        video_queue: &Mutex<VecDeque<VideoFrame>>,
+        preload: Duration,
+        opening: &mut bool,
     ) {
-        let mut last_frame_time = Instant::now();
-        let target_frame_time = Duration::from_millis(16); // ~60 FPS
-        
-        while running.load(Ordering::SeqCst) {
-            if paused.load(Ordering::SeqCst) {
-                thread::sleep(Duration::from_millis(10));
-                continue;
+        // Pick the item up once the decoder has opened it
+        if *opening {
+            let prepared = decoder.lock().unwrap().next_prepared();
+            match prepared {
+                Some(Ok(info)) => {
+                    debug!("Prepared the next playlist item for gapless playback");
+                    state.write().unwrap().next_info = Some(info);
+                }
+                Some(Err(e)) => warn!("Could not prepare the next playlist item for gapless playback: {}", e),
+                None => return,
             }
+            *opening = false;
+            return;
+        }
+        
+        // Only audio moves the playback position; video-only media goes by the frame on screen
+        let video_pts = video_queue.lock().unwrap().front().map(|frame| frame.pts);
+        let (index, source) = {
+            let state = state.read().unwrap();
+            let Some(info) = state.media_info.as_ref() else {
+                return;
+            };
             
-            // Get next video frame
-            let frame = {
-                let mut queue = video_queue.lock().unwrap();
-                queue.front().cloned()
+            let position_us = match video_pts {
+                Some(pts) if info.audio_streams.is_empty() => pts,
+                _ => state.position_us,
             };
+            let position = Duration::from_micros(position_us.max(0) as u64);
+            let remaining = info.duration.saturating_sub(position);
+            if state.next_item.is_some() || info.is_live || info.is_still || info.duration.is_zero() || remaining > preload {
+                return;
+            }
             
-            if let Some(frame) = frame {
+            let Some(index) = state.playlist.following_index() else {
+                return;
+            };
+            (index, state.playlist.items[index].path.clone())
+        };
+        
+        // Tried once per item; if it fails the item is loaded normally
+        state.write().unwrap().next_item = Some(index);
+        match decoder.lock().unwrap().prepare_next(&source) {
+            Ok(()) => {
+                debug!("Opening playlist item {} for gapless playback", index);
+                *opening = true;
+            }
+            Err(e) => warn!("Could not prepare {} for gapless playback: {}", source, e),
+        }
+    }
+    
+    /// Overlap the end of the current item with the start of the prepared one
+    /// 
+    /// The crossfade starts once the remaining time fits the configured
+    /// duration. After the transition the incoming item keeps fading in
+    /// until it reaches full volume.
+    fn crossfade_audio(
+        decoder: &Arc<Mutex<dyn Decoder>>,
+        state: &Arc<RwLock<PlayerState>>,
+        config: &CrossfadeConfig,
+        gapless: bool,
+        fader: &mut Option<Crossfader>,
+        samples: AudioSamples,
+    ) -> AudioSamples {
+        let (remaining, prepared, applies) = {
+            let state = state.read().unwrap();
+            let Some(info) = state.media_info.as_ref() else {
+                return samples;
+            };
+            
+            let position = Duration::from_micros(samples.pts.max(0) as u64);
+            let applies = state.next_info.as_ref()
+                .is_some_and(|next| config.applies_to(info, next, gapless));
+            (info.duration.saturating_sub(position), state.next_item.is_some(), applies)
+        };
+        
+        // Fading in the item that took over
+        if !prepared {
+            let Some(active) = fader.as_mut() else {
+                return samples;
+            };
+            let samples = active.fade_in(samples);
+            if active.is_complete() {
+                *fader = None;
+            }
+            return samples;
+        }
+        
+        // Seeking away from the end cancels the crossfade
+        if !applies || remaining > config.duration {
+            *fader = None;
+            return samples;
+        }
+        
+        let active = fader.get_or_insert_with(|| {
+            debug!("Starting {:?} crossfade", config.duration);
+            Crossfader::new(config, samples.sample_rate, samples.channels)
+        });
+        
+        let mut decoder = decoder.lock().unwrap();
+        while active.wants_incoming(samples.sample_count) {
+            match decoder.decode_next_audio() {
+                Ok(Some(incoming)) => active.push_incoming(&incoming),
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Could not decode the next item for the crossfade: {}", e);
+                    break;
+                }
+            }
+        }
+        
+        active.mix(samples)
+    }
+    
+    /// Continue with the prepared playlist item without stopping playback
+    /// 
+    /// Its audio joins the same output stream right after the last sample of
+    /// the current item, and its first video frame follows the last one. The
+    /// main loop then sets the item up (see [`Self::update_joined_item`]).
+    /// Still images are loaded normally so they get their display time.
+    fn advance_to_next_item(decoder: &Arc<Mutex<dyn Decoder>>, state: &Arc<RwLock<PlayerState>>) -> bool {
+        let (index, next_info) = {
+            let mut state = state.write().unwrap();
+            (state.next_item.take(), state.next_info.take())
+        };
+        let (Some(index), Some(next_info)) = (index, next_info) else {
+            return false;
+        };
+        if next_info.is_still {
+            return false;
+        }
+        
+        let media_info = match decoder.lock().unwrap().advance() {
+            Ok(Some(info)) => info,
+            Ok(None) => {
+                warn!("Playlist item {} is no longer prepared, loading it normally", index);
+                return false;
+            }
+            Err(e) => {
+                warn!("Gapless transition failed: {}", e);
+                return false;
+            }
+        };
+        
+        info!("Continuing gapless with playlist item {}", index);
+        {
+            let mut state = state.write().unwrap();
+            state.media_info = Some(media_info.clone());
+            state.joined_item = Some(media_info);
+            state.playlist.advance_to(index);
+            state.buffered_ranges.clear();
+        }
+        
+        true
+    }
+    
+    /// Mirror adaptive variant switches into the stored media info
+    fn track_variant(decoder: &Arc<Mutex<dyn Decoder>>, state: &Arc<RwLock<PlayerState>>) {
+        let variant = decoder.lock().unwrap().current_variant();
+        
+        let changed = state.read().unwrap()
+            .media_info
+            .as_ref()
+            .is_some_and(|info| info.current_variant != variant);
+        
+        if changed {
+            if let Some(info) = state.write().unwrap().media_info.as_mut() {
+                debug!("Adaptive stream switched to variant {:?}", variant);
+                info.current_variant = variant;
+            }
+        }
+    }
+    
+    /// Mirror the decoder's cache contents for the seek bar
+    fn track_buffered_ranges(decoder: &Arc<Mutex<dyn Decoder>>, state: &Arc<RwLock<PlayerState>>) {
+        let ranges = decoder.lock().unwrap().buffered_ranges();
+        state.write().unwrap().buffered_ranges = ranges;
+    }
+    
+    /// Mirror the DVR window of a live stream
+    fn track_live_window(decoder: &Arc<Mutex<dyn Decoder>>, state: &Arc<RwLock<PlayerState>>) {
+        if let Some(window) = decoder.lock().unwrap().live_window() {
+            state.write().unwrap().live.update_window(window);
+        }
+    }
+    
+    /// Audio thread function
+    fn audio_thread_fn(
+        audio: Arc<Mutex<dyn AudioOutput>>,
+        audio_queue: Arc<Mutex<VecDeque<AudioSamples>>>,
+        av_sync: Arc<Mutex<AVSyncController>>,
+        running: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        buffering: Arc<AtomicBool>,
+        state: Arc<RwLock<PlayerState>>,
+    ) {
+        while running.load(Ordering::SeqCst) {
+            if paused.load(Ordering::SeqCst) || buffering.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+            
+            // Get next audio samples
+            let samples = {
+                let mut queue = audio_queue.lock().unwrap();
+                queue.pop_front()
+            };
+            
+            if let Some(samples) = samples {
+                // Update audio clock
+                {
+                    let mut sync = av_sync.lock().unwrap();
+                    sync.update_audio_clock(samples.pts);
+                }
+                
+                // Update position
+                {
+                    let mut state_guard = state.write().unwrap();
+                    state_guard.position_us = samples.pts;
+                }
+                
+                // Play audio
+                if let Err(e) = audio.lock().unwrap().play(&samples) {
+                    error!("Audio playback error: {}", e);
+                }
+            } else {
+                // No audio samples available
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+    }
+    
+    /// Render thread function
+    fn render_thread_fn(
+        renderer: Arc<Mutex<dyn Renderer>>,
+        video_queue: Arc<Mutex<VecDeque<VideoFrame>>>,
+        av_sync: Arc<Mutex<AVSyncController>>,
+        running: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        buffering: Arc<AtomicBool>,
+        frames_rendered: Arc<AtomicU64>,
+        frames_dropped: Arc<AtomicU64>,
+    ) {
+        let mut last_frame_time = Instant::now();
+        
+        // How long the frame on screen lasts, from its own duration
+        let mut frame_duration = Duration::ZERO;
+        
+        while running.load(Ordering::SeqCst) {
+            if paused.load(Ordering::SeqCst) || buffering.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+            
+            // Get next video frame
+            let frame = {
+                let mut queue = video_queue.lock().unwrap();
+                queue.front().cloned()
+            };
+            
+            if let Some(frame) = frame {
                 // Check A/V sync
                 let action = {
                     let mut sync = av_sync.lock().unwrap();
@@ -856,6 +1359,7 @@ impl PlayerController {
                             let mut queue = video_queue.lock().unwrap();
                             queue.pop_front();
                         }
+                        frame_duration = Duration::from_micros(frame.duration.max(0) as u64);
                         
                         if let Err(e) = renderer.lock().unwrap().render_frame(frame) {
                             error!("Render error: {}", e);
@@ -897,10 +1401,23 @@ impl PlayerController {
                 continue;
             }
             
-            // Frame timing
-            let elapsed = last_frame_time.elapsed();
-            if elapsed < target_frame_time {
-                thread::sleep(target_frame_time - elapsed);
+            // Keep the frame on screen for its duration (no fixed frame rate),
+            // presenting again while a crossfade animates
+            while running.load(Ordering::SeqCst) {
+                let elapsed = last_frame_time.elapsed();
+                if elapsed >= frame_duration {
+                    break;
+                }
+                
+                {
+                    let mut renderer = renderer.lock().unwrap();
+                    if renderer.is_transitioning() {
+                        if let Err(e) = renderer.present() {
+                            error!("Present error: {}", e);
+                        }
+                    }
+                }
+                thread::sleep((frame_duration - elapsed).min(RENDER_WAIT_SLICE));
             }
             last_frame_time = Instant::now();
         }
@@ -918,24 +1435,786 @@ impl PlayerController {
         Ok(())
     }
     
+    /// Start and length of the time span the seek bar covers
+    fn seek_bar_span(state: &PlayerState) -> (Duration, Duration) {
+        let live = state.media_info.as_ref().is_some_and(|info| info.is_live);
+        
+        // The seek bar of a live stream spans its DVR window
+        match state.live.window() {
+            Some(window) if live => (window.start, window.length()),
+            _ => (Duration::ZERO, state.media_info.as_ref().map(|info| info.duration).unwrap_or_default()),
+        }
+    }
+    
+    /// Show playback controls overlay
+    fn show_controls_overlay(&self) -> Result<()> {
+        let overlay = {
+            let state = self.state.read().unwrap();
+            let position = Duration::from_micros(state.position_us.max(0) as u64);
+            let live = state.media_info.as_ref().is_some_and(|info| info.is_live);
+            let (start, duration) = Self::seek_bar_span(&state);
+            
+            let progress = if duration.is_zero() {
+                if live { 1.0 } else { 0.0 }
+            } else {
+                position.saturating_sub(start).as_secs_f64() / duration.as_secs_f64()
+            };
+            
+            let fraction = |time: Duration| if duration.is_zero() {
+                0.0
+            } else {
+                (time.saturating_sub(start).as_secs_f64() / duration.as_secs_f64()).min(1.0)
+            };
+            let buffered = state.buffered_ranges
+                .iter()
+                .map(|&(from, to)| (fraction(from), fraction(to)))
+                .filter(|(from, to)| to > from)
+                .collect();
+            
+            let preview = state.seek_hover.map(|hover| {
+                let time = start + duration.mul_f64(hover);
+                SeekPreview {
+                    position: hover,
+                    time,
+                    thumbnail: self.thumbnails_for(&state).and_then(|thumbnails| thumbnails.get(time)),
+                }
+            });
+            
+            Overlay::Controls {
+                playing: state.state == PlaybackState::Playing,
+                position: progress.clamp(0.0, 1.0),
+                duration,
+                visible: true,
+                live,
+                buffered,
+                preview,
+            }
+        };
+        
+        self.renderer.lock().unwrap().render_overlay(overlay)?;
+        Ok(())
+    }
+    
+    /// Track the cursor over the seek bar and preview the time under it
+    fn hover_seek_bar(&mut self, x: f64, y: f64) -> Result<()> {
+        let (width, height) = self.window.size();
+        let over_bar = width > 0 && x >= 0.0 && y >= height as f64 - SEEK_BAR_HEIGHT && y <= height as f64;
+        let hover = over_bar.then(|| (x / width as f64).clamp(0.0, 1.0));
+        
+        {
+            let mut state = self.state.write().unwrap();
+            if state.media_info.is_none() || (hover.is_none() && state.seek_hover.is_none()) {
+                return Ok(());
+            }
+            state.seek_hover = hover;
+            
+            // Extraction runs in the background; later moves pick up the result
+            if let (Some(hover), Some(thumbnails)) = (hover, self.thumbnails_for(&state)) {
+                let (start, duration) = Self::seek_bar_span(&state);
+                thumbnails.request(start + duration.mul_f64(hover));
+            }
+        }
+        
+        self.show_controls_overlay()
+    }
+    
+    /// Thumbnail generator, if it belongs to the current item
+    fn thumbnails_for(&self, state: &PlayerState) -> Option<&ThumbnailGenerator> {
+        let source = &state.media_info.as_ref()?.source;
+        self.thumbnails.as_ref().filter(|thumbnails| thumbnails.path() == Path::new(source))
+    }
+    
+    /// Start extracting seek-bar thumbnails for a local video file
+    fn start_thumbnails(&mut self, media_info: &MediaInfo) {
+        self.thumbnails = None;
+        
+        let Some(config) = self.config.thumbnails.clone() else {
+            return;
+        };
+        let path = Path::new(&media_info.source);
+        if media_info.video_streams.is_empty() || media_info.is_live || media_info.is_still || !path.is_file() {
+            return;
+        }
+        
+        match ThumbnailGenerator::new(path, config) {
+            Ok(thumbnails) => self.thumbnails = Some(thumbnails),
+            Err(e) => warn!("Seek-bar thumbnails unavailable: {}", e),
+        }
+    }
+    
+    /// Keep a live stream near its target latency by adjusting the playback speed
+    fn update_live_latency(&mut self) {
+        let (catchup, user_speed) = {
+            let mut state = self.state.write().unwrap();
+            let live = state.media_info.as_ref().is_some_and(|info| info.is_live);
+            if !live && self.live_catchup.is_none() {
+                return;
+            }
+            
+            // A speed chosen by the user wins over catching up
+            let catchup = if live && state.state == PlaybackState::Playing && state.speed == 1.0 {
+                let position = Duration::from_micros(state.position_us.max(0) as u64);
+                Some(state.live.playback_speed(position)).filter(|&speed| speed != 1.0)
+            } else {
+                None
+            };
+            (catchup, state.speed)
+        };
+        
+        if catchup == self.live_catchup {
+            return;
+        }
+        
+        match catchup {
+            Some(speed) => info!("Behind the live edge, catching up at {:.2}x", speed),
+            None => debug!("Caught up with the live edge"),
+        }
+        
+        self.live_catchup = catchup;
+        self.av_sync.lock().unwrap().set_playback_speed(catchup.unwrap_or(user_speed));
+    }
+    
+    /// Enter or leave the buffering state depending on how much decoded media is queued
+    fn update_buffering(&mut self) {
+        let has_video = self.state.read().unwrap()
+            .media_info
+            .as_ref()
+            .is_some_and(|info| !info.video_streams.is_empty());
+        
+        // Video paces playback when there is any, audio otherwise
+        let fill = if has_video {
+            self.video_queue.lock().unwrap().len() as f32 / self.config.frame_buffer.max(1) as f32
+        } else {
+            self.audio_queue.lock().unwrap().len() as f32 / AUDIO_QUEUE_TARGET as f32
+        };
+        let percent = (fill * 100.0).min(100.0).floor();
+        
+        let event = {
+            let mut state = self.state.write().unwrap();
+            match state.state {
+                PlaybackState::Playing if percent == 0.0 => {
+                    info!("Playback buffer ran dry, buffering");
+                    state.state = PlaybackState::Buffering;
+                    state.buffer_percent = 0.0;
+                    self.buffering.store(true, Ordering::SeqCst);
+                    Some(PlayerEvent::BufferingProgress { percent: 0.0 })
+                }
+                PlaybackState::Buffering if percent != state.buffer_percent => {
+                    state.buffer_percent = percent;
+                    if percent >= 100.0 {
+                        debug!("Playback buffer refilled");
+                        state.state = PlaybackState::Playing;
+                        self.buffering.store(false, Ordering::SeqCst);
+                    }
+                    Some(PlayerEvent::BufferingProgress { percent })
+                }
+                PlaybackState::Buffering => None,
+                _ => {
+                    // Paused, stopped or ended while buffering
+                    self.buffering.store(false, Ordering::SeqCst);
+                    None
+                }
+            }
+        };
+        
+        if let Some(event) = event {
+            self.send_event(event);
+        }
+    }
+    
+    /// Set up the item the decoder continued with gaplessly
+    /// 
+    /// Its timestamps start over, so the A/V sync starts over too.
+    fn update_joined_item(&mut self) {
+        let Some(media_info) = self.state.write().unwrap().joined_item.take() else {
+            return;
+        };
+        
+        self.av_sync.lock().unwrap().reset();
+        if let Err(e) = self.start_item(media_info, true) {
+            error!("Could not set up the next playlist item: {}", e);
+            self.send_event(PlayerEvent::Error { message: e.to_string() });
+        }
+    }
+    
+    /// Advance the clock of a still image and end it once it has been shown
+    /// 
+    /// Still images have no audio to drive the position, so it follows the
+    /// wall clock while playing. Slideshows then advance like any playlist
+    /// (see [`Self::update_item_end`]).
+    fn update_still_image(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(std::mem::replace(&mut self.still_tick, now));
+        
+        let mut state = self.state.write().unwrap();
+        let Some(duration) = state.media_info.as_ref().filter(|info| info.is_still).map(|info| info.duration) else {
+            return;
+        };
+        if state.state != PlaybackState::Playing {
+            return;
+        }
+        
+        let end = duration.as_micros() as i64;
+        state.position_us = (state.position_us + elapsed.as_micros() as i64).min(end);
+        if state.position_us >= end {
+            state.state = PlaybackState::Ended;
+        }
+    }
+    
+    /// Move on once the current item has ended or reached the `--end` position
+    /// 
+    /// The following playlist item is loaded, which is how playlists advance
+    /// and repeat. After the last item playback stays ended.
+    fn update_item_end(&mut self) {
+        let (ended, past_end) = {
+            let state = self.state.read().unwrap();
+            let position = Duration::from_micros(state.position_us.max(0) as u64);
+            let playing = matches!(state.state, PlaybackState::Playing | PlaybackState::Buffering);
+            (state.state == PlaybackState::Ended, playing && self.playback.end.is_some_and(|end| position >= end))
+        };
+        
+        // Played again after ending, so the end is handled again too
+        if self.item_done && !ended {
+            self.item_done = false;
+        }
+        if self.item_done || (!ended && !past_end) {
+            return;
+        }
+        self.item_done = true;
+        
+        if past_end {
+            if let Err(e) = self.pause() {
+                warn!("Could not stop at the end position: {}", e);
+            }
+            self.state.write().unwrap().state = PlaybackState::Ended;
+        }
+        self.send_event(PlayerEvent::EndOfMedia);
+        
+        let (index, path) = {
+            let state = self.state.read().unwrap();
+            let Some(index) = state.playlist.following_index() else {
+                return;
+            };
+            (index, state.playlist.items[index].path.clone())
+        };
+        
+        debug!("Continuing with playlist item {}", index);
+        match self.load_item(&path) {
+            Ok(_) => self.state.write().unwrap().playlist.advance_to(index),
+            Err(e) => {
+                error!("Could not load playlist item {}: {}", index, e);
+                self.send_event(PlayerEvent::Error { message: e.to_string() });
+            }
+        }
+    }
+    
+    /// Load a playlist entry: a URL, an archive entry or a file
+    fn load_item(&mut self, path: &str) -> Result<MediaInfo> {
+        if path.contains("://") {
+            return self.load_url(path);
+        }
+        
+        match ArchivePath::parse(Path::new(path)) {
+            Some(archive) if archive.entry.is_some() => self.load_source(archive.open_entry()?),
+            _ => self.load_file(Path::new(path)),
+        }
+    }
+    
+    /// Load the subtitles chosen for this run for a new item
+    /// 
+    /// A subtitle stream is read on a background thread, since that means
+    /// going through the whole file; live streams and network sources are
+    /// skipped. A track that cannot be read is reported and the item plays
+    /// without it.
+    fn load_subtitles(&mut self, media_info: &MediaInfo) {
+        self.subtitles = None;
+        self.subtitle_text = None;
+        if let Some((_, cancel)) = self.subtitle_job.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        
+        if let Some(file) = &self.playback.subtitle_file {
+            let track = SubtitleTrack::open(file);
+            self.use_subtitles(track);
+            return;
+        }
+        let Some(index) = self.playback.subtitle_track else {
+            return;
+        };
+        
+        let path = PathBuf::from(&media_info.source);
+        if media_info.is_live || !path.is_file() {
+            warn!("Subtitle stream #{} can only be read from local files, playing without subtitles", index);
+            return;
+        }
+        
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = cancel.clone();
+        let job = thread::Builder::new()
+            .name("subtitles".to_string())
+            .spawn(move || FFmpegDecoder::extract_subtitles(&path, index, &cancelled));
+        match job {
+            Ok(handle) => self.subtitle_job = Some((handle, cancel)),
+            Err(e) => warn!("Playing without subtitles: {}", e),
+        }
+    }
+    
+    /// Take the cues of a subtitle track read for the current item
+    fn use_subtitles(&mut self, track: Result<SubtitleTrack>) {
+        match track {
+            Ok(track) => {
+                info!("Loaded {} subtitle cues", track.cues().len());
+                self.subtitles = Some(track);
+            }
+            Err(e) => {
+                warn!("Playing without subtitles: {}", e);
+                self.send_event(PlayerEvent::Error { message: e.to_string() });
+            }
+        }
+    }
+    
+    /// Show the subtitle text at the current position in the subtitle style
+    fn update_subtitles(&mut self) {
+        if self.subtitle_job.as_ref().is_some_and(|(handle, _)| handle.is_finished()) {
+            let (handle, _) = self.subtitle_job.take().unwrap();
+            let track = handle.join()
+                .unwrap_or_else(|_| Err(CCPlayerError::Internal("Subtitle reader panicked".to_string())));
+            self.use_subtitles(track);
+        }
+        
+        let Some(track) = &self.subtitles else {
+            return;
+        };
+        
+        let position = Duration::from_micros(self.state.read().unwrap().position_us.max(0) as u64);
+        let text = track.text_at(position).filter(|_| self.config.subtitles.enabled);
+        if text == self.subtitle_text {
+            return;
+        }
+        
+        let style = &self.config.subtitles;
+        let shown = match &text {
+            Some(content) => self.renderer.lock().unwrap().render_overlay(Overlay::Text {
+                content: content.clone(),
+                position: OverlayPosition::Relative { x: 0.5, y: 0.9 },
+                font_size: style.font_size,
+                color: Color::from_hex(&style.color).unwrap_or(Color::WHITE),
+                background: Some(Color::new(0.0, 0.0, 0.0, style.background_opacity)),
+            }),
+            None => self.renderer.lock().unwrap().clear_overlays(),
+        };
+        if let Err(e) = shown {
+            warn!("Failed to show subtitles: {}", e);
+        }
+        self.subtitle_text = text;
+    }
+    
     /// Send event to handlers
     fn send_event(&self, event: PlayerEvent) {
-        let handlers = self.event_handlers.lock().unwrap();
-        for handler in handlers.iter() {
+        Self::emit(&self.event_handlers, event);
+    }
+    
+    /// Send event to handlers from a playback thread
+    fn emit(handlers: &Mutex<Vec<Box<dyn PlayerEventHandler>>>, event: PlayerEvent) {
+        let mut handlers = handlers.lock().unwrap();
+        for handler in handlers.iter_mut() {
             handler.handle_event(event.clone());
         }
     }
     
+    /// Get information about the loaded media
+    pub fn media_info(&self) -> Option<MediaInfo> {
+        self.state.read().unwrap().media_info.clone()
+    }
+    
+    /// Get the time ranges of a network stream that are already downloaded
+    pub fn buffered_ranges(&self) -> Vec<(Duration, Duration)> {
+        self.state.read().unwrap().buffered_ranges.clone()
+    }
+    
+    /// Check whether the loaded media is a live stream
+    pub fn is_live(&self) -> bool {
+        self.state.read().unwrap()
+            .media_info
+            .as_ref()
+            .is_some_and(|info| info.is_live)
+    }
+    
+    /// Get the seekable window of a live stream
+    pub fn live_window(&self) -> Option<LiveWindow> {
+        self.state.read().unwrap().live.window()
+    }
+    
+    /// Get the distance between the playback position and the live edge
+    pub fn live_latency(&self) -> Option<Duration> {
+        let state = self.state.read().unwrap();
+        state.live.latency(Duration::from_micros(state.position_us.max(0) as u64))
+    }
+    
+    /// Jump to the live edge of a live stream
+    pub fn go_to_live_edge(&mut self) -> Result<()> {
+        let edge = self.state.read().unwrap().live.live_edge()
+            .ok_or_else(|| CCPlayerError::InvalidInput("Not playing a live stream".to_string()))?;
+        
+        info!("Jumping to live edge");
+        self.seek(edge)
+    }
+    
+    /// Select the quality of an adaptive stream
+    pub fn set_quality(&mut self, selection: QualitySelection) -> Result<()> {
+        info!("Setting stream quality: {:?}", selection);
+        self.decoder.lock().unwrap().set_quality(selection)
+    }
+    
+    /// Rotate the video on top of its stored orientation
+    /// 
+    /// # Arguments
+    /// 
+    /// * `degrees` - Clockwise rotation to add, in multiples of 90
+    pub fn rotate(&mut self, degrees: i32) -> Result<()> {
+        if degrees % 90 != 0 {
+            return Err(CCPlayerError::InvalidInput(format!(
+                "Rotation must be a multiple of 90 degrees, got {}",
+                degrees
+            )));
+        }
+        
+        info!("Rotating video by {} degrees", degrees);
+        {
+            let mut state = self.state.write().unwrap();
+            state.manual_rotation = (state.manual_rotation + degrees).rem_euclid(360);
+        }
+        self.apply_rotation()
+    }
+    
+    /// Get the orientation the video is displayed in
+    pub fn rotation(&self) -> Rotation {
+        let state = self.state.read().unwrap();
+        state.stream_rotation.rotated(state.manual_rotation)
+    }
+    
+    /// Push the current orientation to the renderer and fit the window to it
+    fn apply_rotation(&mut self) -> Result<()> {
+        let rotation = self.rotation();
+        self.renderer.lock().unwrap().set_rotation(rotation)?;
+        
+        let video_size = {
+            let state = self.state.read().unwrap();
+            if state.fullscreen {
+                return Ok(());
+            }
+            match state.media_info.as_ref().and_then(|info| info.video_streams.first()) {
+                Some(video) => rotation.display_size(video.width, video.height),
+                None => return Ok(()),
+            }
+        };
+        
+        // Keep the window's longer side and give it the video's shape
+        let (window_width, window_height) = self.window.size();
+        let longest = window_width.max(window_height) as f32;
+        let scale = longest / video_size.0.max(video_size.1).max(1) as f32;
+        let width = ((video_size.0 as f32 * scale).round() as u32).max(1);
+        let height = ((video_size.1 as f32 * scale).round() as u32).max(1);
+        
+        if (width, height) != (window_width, window_height) {
+            debug!("Fitting window to {}x{} video: {}x{}", video_size.0, video_size.1, width, height);
+            let mut window = Arc::clone(&self.window);
+            unsafe {
+                let window_ptr = Arc::get_mut_unchecked(&mut window);
+                window_ptr.set_size(width, height)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Apply a player configuration
+    /// 
+    /// Sets the initial volume, heard once unmuted; the other settings take
+    /// effect for media loaded afterwards.
+    pub fn set_config(&mut self, config: PlayerConfig) -> Result<()> {
+        let volume = config.default_volume.clamp(0.0, 1.0);
+        let muted = {
+            let mut state = self.state.write().unwrap();
+            state.volume = volume;
+            state.muted
+        };
+        if !muted {
+            self.audio.lock().unwrap().set_volume(volume)?;
+        }
+        self.config = config;
+        Ok(())
+    }
+    
+    /// Apply the layered configuration, keeping it for auto-profiles
+    pub fn set_settings(&mut self, settings: Config) -> Result<()> {
+        self.set_config(settings.player_config())?;
+        self.settings = Some(settings);
+        Ok(())
+    }
+    
+    /// Mute or unmute the audio, keeping the volume
+    pub fn set_muted(&mut self, muted: bool) -> Result<()> {
+        if muted == self.is_muted() {
+            return Ok(());
+        }
+        self.toggle_mute()
+    }
+    
+    /// Switch to the settings of the auto-profiles a loaded item matches
+    /// 
+    /// Items without a matching profile return to the plain settings. The
+    /// volume only changes when a profile sets it.
+    fn apply_auto_profiles(&mut self, media_info: &MediaInfo) -> Result<()> {
+        let Some(settings) = &self.settings else {
+            return Ok(());
+        };
+        
+        let mut effective = settings.clone();
+        for name in settings.auto_profiles(media_info) {
+            match effective.apply_profile(&name) {
+                Ok(()) => info!("Applying auto-profile '{}'", name),
+                Err(e) => warn!("{}", e),
+            }
+        }
+        
+        let sets_volume = effective.audio.volume != settings.audio.volume;
+        let mut config = effective.player_config();
+        if sets_volume {
+            return self.set_config(config);
+        }
+        config.default_volume = self.config.default_volume;
+        self.config = config;
+        Ok(())
+    }
+    
+    /// Replace the user-defined video filter chain
+    /// 
+    /// Applies from the next decoded frame and to media loaded later. An
+    /// invalid description is rejected and the current chain kept.
+    pub fn set_video_filters(&mut self, filters: Option<&str>) -> Result<()> {
+        info!("Setting video filters: {:?}", filters);
+        self.decoder.lock().unwrap().set_video_filters(filters)?;
+        self.config.video_filters = filters.map(str::to_string);
+        if let Some(settings) = &mut self.settings {
+            settings.decoder.video_filters = self.config.video_filters.clone();
+        }
+        Ok(())
+    }
+    
+    /// Replace the user-defined audio filter chain
+    /// 
+    /// Applies from the next decoded buffer and to media loaded later. An
+    /// invalid description is rejected and the current chain kept.
+    pub fn set_audio_filters(&mut self, filters: Option<&str>) -> Result<()> {
+        info!("Setting audio filters: {:?}", filters);
+        let previous = std::mem::replace(&mut self.config.audio_filters, filters.map(str::to_string));
+        if let Err(e) = self.decoder.lock().unwrap().set_audio_filters(self.audio_filter_chain().as_deref()) {
+            self.config.audio_filters = previous;
+            return Err(e);
+        }
+        if let Some(settings) = &mut self.settings {
+            settings.audio.filters = self.config.audio_filters.clone();
+        }
+        Ok(())
+    }
+    
+    /// Audio filtergraph handed to the decoder: normalization, then the user's chain
+    fn audio_filter_chain(&self) -> Option<String> {
+        match (self.config.normalize, &self.config.audio_filters) {
+            (true, Some(filters)) => Some(format!("{},{}", NORMALIZE_FILTER, filters)),
+            (true, None) => Some(NORMALIZE_FILTER.to_string()),
+            (false, filters) => filters.clone(),
+        }
+    }
+    
+    /// Reload the configuration whenever a config file changes
+    /// 
+    /// # Arguments
+    /// 
+    /// * `loader` - Rebuilds the configuration from all layers, including
+    ///   profiles and command line options
+    pub fn watch_config(&mut self, loader: ConfigLoader) {
+        self.config_reload = Some((ConfigWatcher::new(), loader));
+    }
+    
+    /// Apply edited config files; an invalid edit keeps the current settings
+    fn update_config_reload(&mut self) {
+        let loaded = match &mut self.config_reload {
+            Some((watcher, loader)) if watcher.poll() => loader(),
+            _ => return,
+        };
+        
+        let result = loaded.and_then(|settings| self.apply_live_settings(settings));
+        match result {
+            Ok(()) => {
+                info!("Configuration reloaded");
+                self.show_message("Configuration reloaded", Color::from_rgb(255, 255, 255));
+            }
+            Err(e) => {
+                warn!("Keeping previous configuration: {}", e);
+                self.show_message(&format!("Config error: {}", e), Color::from_rgb(255, 96, 96));
+                self.send_event(PlayerEvent::Error { message: e.to_string() });
+            }
+        }
+    }
+    
+    /// Switch to reloaded settings while playing
+    /// 
+    /// Steps, key bindings, subtitle style and audio processing take effect
+    /// at once; window, decoder and device settings apply on the next start.
+    /// The volume is left as it is.
+    fn apply_live_settings(&mut self, settings: Config) -> Result<()> {
+        let previous = (self.settings.clone(), self.config.clone());
+        let audio_chain = self.audio_filter_chain();
+        
+        let media_info = self.state.read().unwrap().media_info.clone();
+        let volume = self.config.default_volume;
+        self.config = PlayerConfig { default_volume: volume, ..settings.player_config() };
+        self.settings = Some(settings);
+        if let Some(media_info) = &media_info {
+            self.apply_auto_profiles(media_info)?;
+        }
+        
+        // Filter chains are only checked by FFmpeg
+        let chain = self.audio_filter_chain();
+        let applied = {
+            let mut decoder = self.decoder.lock().unwrap();
+            let video_changed = self.config.video_filters != previous.1.video_filters;
+            let mut applied = Ok(());
+            if video_changed {
+                applied = decoder.set_video_filters(self.config.video_filters.as_deref());
+            }
+            if applied.is_ok() && chain != audio_chain {
+                applied = decoder.set_audio_filters(chain.as_deref());
+                if applied.is_err() && video_changed {
+                    let _ = decoder.set_video_filters(previous.1.video_filters.as_deref());
+                }
+            }
+            applied
+        };
+        if let Err(e) = applied {
+            (self.settings, self.config) = previous;
+            return Err(e);
+        }
+        
+        self.audio.lock().unwrap().set_mix_config(self.config.audio_mix);
+        Ok(())
+    }
+    
+    /// Show a short text message on screen
+    fn show_message(&self, text: &str, color: Color) {
+        let overlay = Overlay::Text {
+            content: text.to_string(),
+            position: OverlayPosition::TopLeft { x: 20.0, y: 20.0 },
+            font_size: 20,
+            color,
+            background: Some(Color::new(0.0, 0.0, 0.0, 0.6)),
+        };
+        
+        if let Err(e) = self.renderer.lock().unwrap().render_overlay(overlay) {
+            warn!("Failed to show message: {}", e);
+        }
+    }
+    
+    /// Carry out a key binding's action
+    fn perform(&mut self, action: Action) -> Result<()> {
+        match action {
+            Action::TogglePlay => self.toggle_play()?,
+            Action::ToggleFullscreen => self.set_fullscreen(!self.is_fullscreen())?,
+            Action::ExitFullscreen if self.is_fullscreen() => self.set_fullscreen(false)?,
+            Action::ToggleMute => self.toggle_mute()?,
+            Action::SeekBackward => self.seek_relative(-(self.config.seek_step as i64))?,
+            Action::SeekForward => self.seek_relative(self.config.seek_step as i64)?,
+            Action::FastSeekBackward => self.seek_relative(-(self.config.fast_seek_step as i64))?,
+            Action::FastSeekForward => self.seek_relative(self.config.fast_seek_step as i64)?,
+            Action::VolumeUp => {
+                let new_volume = self.volume() + self.config.volume_step;
+                self.set_volume(new_volume)?;
+            }
+            Action::VolumeDown => {
+                let new_volume = self.volume() - self.config.volume_step;
+                self.set_volume(new_volume)?;
+            }
+            Action::SpeedDown => {
+                let new_speed = (self.speed() - 0.1).max(0.25);
+                self.set_speed(new_speed)?;
+            }
+            Action::SpeedUp => {
+                let new_speed = (self.speed() + 0.1).min(4.0);
+                self.set_speed(new_speed)?;
+            }
+            Action::RotateClockwise => self.rotate(90)?,
+            Action::RotateCounterClockwise => self.rotate(-90)?,
+            Action::GoToLiveEdge if self.is_live() => self.go_to_live_edge()?,
+            Action::Quit => {
+                self.stop()?;
+                self.running.store(false, Ordering::SeqCst);
+            }
+            Action::ExitFullscreen | Action::GoToLiveEdge | Action::Ignore => {}
+        }
+        Ok(())
+    }
+    
+    /// Replace the playlist
+    /// 
+    /// It repeats per the `loop_playback` and `loop_count` settings and is
+    /// put in random order if shuffling is on.
+    pub fn set_playlist(&mut self, items: Vec<PlaylistItem>) {
+        let mut state = self.state.write().unwrap();
+        state.playlist.items = items;
+        state.playlist.current_index = None;
+        state.playlist.repeat_mode = if self.config.loop_playback { RepeatMode::All } else { RepeatMode::None };
+        state.playlist.passes_left = self.config.loop_count;
+        if state.playlist.shuffle {
+            let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+            state.playlist.shuffle_items(seed);
+        }
+        state.next_item = None;
+        state.next_info = None;
+    }
+    
+    /// Play playlists set from now on in random order
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.state.write().unwrap().playlist.shuffle = shuffle;
+    }
+    
+    /// Set where items start and stop, the audio track and subtitles for media loaded from now on
+    pub fn set_playback_options(&mut self, options: PlaybackOptions) -> Result<()> {
+        self.decoder.lock().unwrap().set_audio_track(options.audio_track)?;
+        self.playback = options;
+        Ok(())
+    }
+    
+    /// Get the playlist
+    pub fn playlist(&self) -> Playlist {
+        self.state.read().unwrap().playlist.clone()
+    }
+    
+    /// Mark a playlist item as the current one
+    pub fn set_playlist_index(&mut self, index: usize) {
+        let mut state = self.state.write().unwrap();
+        if index < state.playlist.items.len() {
+            state.playlist.current_index = Some(index);
+        }
+    }
+    
     /// Add event handler
     pub fn add_event_handler(&mut self, handler: Box<dyn PlayerEventHandler>) {
         self.event_handlers.lock().unwrap().push(handler);
     }
     
+    /// Get render statistics, including the measured video frame rate
+    pub fn render_stats(&self) -> RenderStats {
+        self.renderer.lock().unwrap().stats()
+    }
+    
     /// Get playback statistics
     pub fn get_stats(&self) -> PlaybackStats {
         let mut stats = self.stats.lock().unwrap().clone();
         stats.frames_rendered = self.frames_rendered.load(Ordering::SeqCst);
         stats.frames_dropped = self.frames_dropped.load(Ordering::SeqCst);
+        (stats.video_bitrate, stats.audio_bitrate) = self.decoder.lock().unwrap().bitrates();
         stats
     }
 }
@@ -0,0 +1,207 @@
+//! Keyboard bindings
+//!
+//! Maps keys to player actions. The defaults can be changed in the
+//! `[keybindings]` section of the config file, one `"chord" = "action"`
+//! entry per key, e.g. `"ctrl+right" = "fast_seek_forward"`.
+
+use crate::utils::error::{CCPlayerError, Result};
+use crate::window::{Key, KeyModifiers};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Player action a key can trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    TogglePlay,
+    ToggleFullscreen,
+    ExitFullscreen,
+    ToggleMute,
+    SeekBackward,
+    SeekForward,
+    FastSeekBackward,
+    FastSeekForward,
+    VolumeUp,
+    VolumeDown,
+    SpeedDown,
+    SpeedUp,
+    RotateClockwise,
+    RotateCounterClockwise,
+    GoToLiveEdge,
+    Quit,
+
+    /// Do nothing (removes a default binding)
+    Ignore,
+}
+
+/// Key bindings in effect
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    /// Bound keys with the exact modifiers they need
+    bindings: Vec<(Key, KeyModifiers, Action)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        const DEFAULTS: &[(&str, Action)] = &[
+            ("space", Action::TogglePlay),
+            ("alt+enter", Action::ToggleFullscreen),
+            ("f", Action::ToggleFullscreen),
+            ("escape", Action::ExitFullscreen),
+            ("m", Action::ToggleMute),
+            ("left", Action::SeekBackward),
+            ("right", Action::SeekForward),
+            ("shift+left", Action::FastSeekBackward),
+            ("shift+right", Action::FastSeekForward),
+            ("pagedown", Action::FastSeekBackward),
+            ("pageup", Action::FastSeekForward),
+            ("up", Action::VolumeUp),
+            ("down", Action::VolumeDown),
+            ("minus", Action::SpeedDown),
+            ("plus", Action::SpeedUp),
+            ("r", Action::RotateClockwise),
+            ("shift+r", Action::RotateCounterClockwise),
+            ("end", Action::GoToLiveEdge),
+            ("ctrl+q", Action::Quit),
+        ];
+
+        let bindings = DEFAULTS
+            .iter()
+            .map(|&(chord, action)| {
+                let (key, modifiers) = parse_chord(chord).expect("valid default binding");
+                (key, modifiers, action)
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyBindings {
+    /// Default bindings with entries from the config file replacing them
+    ///
+    /// # Arguments
+    ///
+    /// * `overrides` - Key chords (`"ctrl+shift+r"`) and the actions they trigger
+    pub fn with_overrides(overrides: &BTreeMap<String, Action>) -> Result<Self> {
+        let mut bindings = Self::default();
+        for (chord, &action) in overrides {
+            let (key, modifiers) = parse_chord(chord)?;
+            bindings.bindings.retain(|(bound, bound_modifiers, _)| *bound != key || *bound_modifiers != modifiers);
+            bindings.bindings.push((key, modifiers, action));
+        }
+        Ok(bindings)
+    }
+
+    /// Action for a key press
+    ///
+    /// A binding with the exact modifiers wins; otherwise the key's binding
+    /// without modifiers applies, so Shift+Space still toggles playback.
+    pub fn action(&self, key: &Key, modifiers: KeyModifiers) -> Option<Action> {
+        let find = |modifiers: KeyModifiers| {
+            self.bindings
+                .iter()
+                .find(|(bound, bound_modifiers, _)| bound == key && *bound_modifiers == modifiers)
+                .map(|&(_, _, action)| action)
+        };
+
+        find(modifiers)
+            .or_else(|| find(KeyModifiers::default()))
+            .filter(|&action| action != Action::Ignore)
+    }
+}
+
+/// Parse a key chord such as `ctrl+q` or `shift+pageup`
+pub fn parse_chord(chord: &str) -> Result<(Key, KeyModifiers)> {
+    let invalid = || CCPlayerError::Config(format!("Invalid key binding '{}'", chord));
+
+    let mut parts: Vec<String> = chord.split('+').map(|part| part.trim().to_ascii_lowercase()).collect();
+    let key = parts.pop().filter(|key| !key.is_empty()).ok_or_else(invalid)?;
+
+    let mut modifiers = KeyModifiers::default();
+    for modifier in &parts {
+        match modifier.as_str() {
+            "ctrl" | "control" => modifiers.ctrl = true,
+            "shift" => modifiers.shift = true,
+            "alt" => modifiers.alt = true,
+            "meta" | "super" | "cmd" => modifiers.meta = true,
+            _ => return Err(invalid()),
+        }
+    }
+
+    let key = match key.as_str() {
+        "space" => Key::Space,
+        "enter" | "return" => Key::Enter,
+        "escape" | "esc" => Key::Escape,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "volumeup" => Key::VolumeUp,
+        "volumedown" => Key::VolumeDown,
+        "volumemute" => Key::VolumeMute,
+        "minus" => Key::Minus,
+        "plus" => Key::Plus,
+        "f" => Key::F,
+        "m" => Key::M,
+        "o" => Key::O,
+        "q" => Key::Q,
+        "r" => Key::R,
+        "s" => Key::S,
+        "0" => Key::Num0,
+        "1" => Key::Num1,
+        "2" => Key::Num2,
+        "3" => Key::Num3,
+        "4" => Key::Num4,
+        "5" => Key::Num5,
+        "6" => Key::Num6,
+        "7" => Key::Num7,
+        "8" => Key::Num8,
+        "9" => Key::Num9,
+        _ => return Err(invalid()),
+    };
+    Ok((key, modifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with(ctrl: bool, shift: bool) -> KeyModifiers {
+        KeyModifiers { ctrl, shift, ..Default::default() }
+    }
+
+    #[test]
+    fn test_default_bindings() {
+        let bindings = KeyBindings::default();
+        assert_eq!(bindings.action(&Key::Space, KeyModifiers::default()), Some(Action::TogglePlay));
+        assert_eq!(bindings.action(&Key::Space, with(false, true)), Some(Action::TogglePlay));
+        assert_eq!(bindings.action(&Key::R, with(false, true)), Some(Action::RotateCounterClockwise));
+        assert_eq!(bindings.action(&Key::Q, with(true, false)), Some(Action::Quit));
+        assert_eq!(bindings.action(&Key::Q, KeyModifiers::default()), None);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides = BTreeMap::from([
+            ("ctrl+right".to_string(), Action::FastSeekForward),
+            ("M".to_string(), Action::Ignore),
+            ("q".to_string(), Action::Quit),
+        ]);
+        let bindings = KeyBindings::with_overrides(&overrides).unwrap();
+
+        assert_eq!(bindings.action(&Key::Right, with(true, false)), Some(Action::FastSeekForward));
+        assert_eq!(bindings.action(&Key::Right, KeyModifiers::default()), Some(Action::SeekForward));
+        assert_eq!(bindings.action(&Key::M, KeyModifiers::default()), None);
+        assert_eq!(bindings.action(&Key::Q, KeyModifiers::default()), Some(Action::Quit));
+
+        for chord in ["ctrl+", "hyper+q", "ctrl+tab"] {
+            let overrides = BTreeMap::from([(chord.to_string(), Action::Quit)]);
+            assert!(KeyBindings::with_overrides(&overrides).is_err(), "{}", chord);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Live stream playback
+//!
+//! Tracks the seekable (DVR) window of a live stream and keeps playback
+//! near the live edge by speeding up slightly when it falls behind.
+
+use crate::decoder::LiveWindow;
+use std::time::Duration;
+
+/// Live playback settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiveConfig {
+    /// Distance from the live edge playback aims for
+    pub target_latency: Duration,
+
+    /// Latency above the target that is tolerated before catching up
+    pub tolerance: Duration,
+
+    /// Playback speed while catching up
+    pub catchup_speed: f32,
+}
+
+impl Default for LiveConfig {
+    fn default() -> Self {
+        Self {
+            target_latency: Duration::from_secs(3),
+            tolerance: Duration::from_secs(1),
+            catchup_speed: 1.05,
+        }
+    }
+}
+
+/// Latency tracking for a live stream
+#[derive(Debug, Clone)]
+pub struct LiveTracker {
+    /// Settings
+    config: LiveConfig,
+
+    /// Last known DVR window
+    window: Option<LiveWindow>,
+
+    /// Whether playback is currently catching up
+    catching_up: bool,
+}
+
+impl LiveTracker {
+    /// Create a tracker with the given settings
+    pub fn new(config: LiveConfig) -> Self {
+        Self {
+            config,
+            window: None,
+            catching_up: false,
+        }
+    }
+
+    /// Update the DVR window reported by the decoder
+    pub fn update_window(&mut self, window: LiveWindow) {
+        self.window = Some(window);
+    }
+
+    /// Last known DVR window
+    pub fn window(&self) -> Option<LiveWindow> {
+        self.window
+    }
+
+    /// Position to jump to for "go to live edge"
+    ///
+    /// The target latency behind the edge, so playback does not run dry
+    /// straight away.
+    pub fn live_edge(&self) -> Option<Duration> {
+        self.window
+            .map(|window| window.clamp(window.end.saturating_sub(self.config.target_latency)))
+    }
+
+    /// Distance between the playback position and the live edge
+    pub fn latency(&self, position: Duration) -> Option<Duration> {
+        self.window.map(|window| window.end.saturating_sub(position))
+    }
+
+    /// Limit a seek target to the DVR window
+    pub fn clamp_seek(&self, target: Duration) -> Duration {
+        match self.window {
+            Some(window) => window.clamp(target),
+            None => target,
+        }
+    }
+
+    /// Playback speed that steers towards the target latency
+    ///
+    /// Catching up starts once latency exceeds the target by more than the
+    /// tolerance and continues until the target is reached.
+    pub fn playback_speed(&mut self, position: Duration) -> f32 {
+        let Some(latency) = self.latency(position) else {
+            return 1.0;
+        };
+
+        if latency > self.config.target_latency + self.config.tolerance {
+            self.catching_up = true;
+        } else if latency <= self.config.target_latency {
+            self.catching_up = false;
+        }
+
+        if self.catching_up {
+            self.config.catchup_speed
+        } else {
+            1.0
+        }
+    }
+
+    /// Forget the window and catch-up state (new media loaded)
+    pub fn reset(&mut self) {
+        self.window = None;
+        self.catching_up = false;
+    }
+}
+
+impl Default for LiveTracker {
+    fn default() -> Self {
+        Self::new(LiveConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: u64, end: u64) -> LiveWindow {
+        LiveWindow {
+            start: Duration::from_secs(start),
+            end: Duration::from_secs(end),
+        }
+    }
+
+    #[test]
+    fn test_live_edge_and_clamp() {
+        let mut tracker = LiveTracker::default();
+        assert_eq!(tracker.live_edge(), None);
+
+        tracker.update_window(window(100, 160));
+        assert_eq!(tracker.live_edge(), Some(Duration::from_secs(157)));
+        assert_eq!(tracker.clamp_seek(Duration::from_secs(50)), Duration::from_secs(100));
+        assert_eq!(tracker.latency(Duration::from_secs(150)), Some(Duration::from_secs(10)));
+
+        // A window shorter than the target latency starts at its beginning
+        tracker.update_window(window(100, 101));
+        assert_eq!(tracker.live_edge(), Some(Duration::from_secs(100)));
+    }
+
+    #[test]
+    fn test_catchup_hysteresis() {
+        let mut tracker = LiveTracker::default();
+        tracker.update_window(window(0, 60));
+
+        // Within tolerance: normal speed
+        assert_eq!(tracker.playback_speed(Duration::from_millis(56_500)), 1.0);
+
+        // Too far behind: speed up, and keep going until the target is reached
+        assert_eq!(tracker.playback_speed(Duration::from_secs(50)), 1.05);
+        assert_eq!(tracker.playback_speed(Duration::from_millis(56_500)), 1.05);
+        assert_eq!(tracker.playback_speed(Duration::from_secs(57)), 1.0);
+        assert_eq!(tracker.playback_speed(Duration::from_millis(56_500)), 1.0);
+    }
+}
@@ -4,13 +4,14 @@
 //! that wraps the PlayerController with additional features like
 //! automatic error recovery, performance monitoring, and event dispatching.
 
+use crate::utils::config::{AudioConfig, Config, ConfigLoader};
 use crate::utils::error::{Result, CCPlayerError};
 use crate::window::{Window, WindowConfig, WinitWindowImpl};
-use crate::renderer::{Renderer, WgpuRenderer};
-use crate::decoder::{Decoder, FFmpegDecoder, MediaInfo};
+use crate::renderer::{RenderStats, Renderer, Rotation, WgpuRenderer};
+use crate::decoder::{ArchivePath, CacheConfig, Decoder, LiveWindow, FFmpegDecoder, MediaInfo, MediaSource, QualitySelection};
 use crate::audio::{AudioOutput, CpalAudioOutput};
 use crate::player::{
-    Player, PlayerController, PlaybackState, PlayerConfig, PlayerEvent,
+    Player, PlayerController, PlaybackOptions, PlaybackState, PlayerConfig, PlayerEvent,
     PlayerEventHandler, PlaybackStats, Playlist, PlaylistItem, RepeatMode,
 };
 
@@ -24,8 +25,14 @@ use log::{info, warn, error};
 /// Media player builder for customized configuration
 pub struct MediaPlayerBuilder {
     config: PlayerConfig,
+    settings: Option<Config>,
     window_config: WindowConfig,
     enable_hardware_accel: bool,
+    decoder_threads: usize,
+    audio_buffer_frames: u32,
+    audio_device: Option<String>,
+    cache_config: CacheConfig,
+    playback: PlaybackOptions,
     event_handlers: Vec<Box<dyn PlayerEventHandler>>,
 }
 
@@ -34,15 +41,38 @@ impl MediaPlayerBuilder {
     pub fn new() -> Self {
         Self {
             config: PlayerConfig::default(),
+            settings: None,
             window_config: WindowConfig::default(),
             enable_hardware_accel: true,
+            decoder_threads: 0,
+            audio_buffer_frames: AudioConfig::default().buffer_size as u32,
+            audio_device: None,
+            cache_config: CacheConfig::default(),
+            playback: PlaybackOptions::default(),
             event_handlers: Vec::new(),
         }
     }
     
-    /// Set player configuration
+    /// Create a builder from the layered application configuration
+    /// 
+    /// The configuration is kept so its auto-profiles can be applied to
+    /// each loaded item.
+    pub fn from_config(config: &Config) -> Self {
+        let mut builder = Self::new()
+            .with_config(config.player_config())
+            .with_window_config(config.window.clone())
+            .with_hardware_acceleration(config.decoder.hardware_acceleration)
+            .with_decoder_threads(config.decoder.thread_count)
+            .with_audio_buffer_size(config.audio.buffer_size as u32)
+            .with_audio_device(config.audio.device.clone());
+        builder.settings = Some(config.clone());
+        builder
+    }
+    
+    /// Set player configuration (replaces the one from [`Self::from_config`])
     pub fn with_config(mut self, config: PlayerConfig) -> Self {
         self.config = config;
+        self.settings = None;
         self
     }
     
@@ -58,6 +88,36 @@ impl MediaPlayerBuilder {
         self
     }
     
+    /// Set the number of video decoding threads (0 for one per CPU core)
+    pub fn with_decoder_threads(mut self, count: usize) -> Self {
+        self.decoder_threads = count;
+        self
+    }
+    
+    /// Set the audio device buffer size in frames (0 for the device default)
+    pub fn with_audio_buffer_size(mut self, frames: u32) -> Self {
+        self.audio_buffer_frames = frames;
+        self
+    }
+    
+    /// Set the audio output device by name (None for the system default)
+    pub fn with_audio_device(mut self, name: Option<String>) -> Self {
+        self.audio_device = name;
+        self
+    }
+    
+    /// Set start and end positions, tracks and subtitles for every loaded item
+    pub fn with_playback_options(mut self, options: PlaybackOptions) -> Self {
+        self.playback = options;
+        self
+    }
+    
+    /// Set the read-ahead cache used for network streams
+    pub fn with_cache_config(mut self, config: CacheConfig) -> Self {
+        self.cache_config = config;
+        self
+    }
+    
     /// Add an event handler
     pub fn with_event_handler(mut self, handler: Box<dyn PlayerEventHandler>) -> Self {
         self.event_handlers.push(handler);
@@ -118,12 +178,15 @@ impl MediaPlayer {
         // Create decoder
         let mut decoder = FFmpegDecoder::new()?;
         decoder.set_hardware_acceleration(builder.enable_hardware_accel)?;
+        decoder.set_cache_config(builder.cache_config);
+        decoder.set_thread_count(builder.decoder_threads);
         let decoder: Arc<dyn Decoder> = Arc::new(decoder);
         
         // Create audio output
-        let audio: Arc<dyn AudioOutput> = Arc::new(
-            CpalAudioOutput::new()?
-        );
+        let mut audio = CpalAudioOutput::new()?;
+        audio.set_buffer_size(builder.audio_buffer_frames);
+        audio.set_device_name(builder.audio_device);
+        let audio: Arc<dyn AudioOutput> = Arc::new(audio);
         
         // Create player controller
         let mut controller = PlayerController::new(
@@ -132,6 +195,11 @@ impl MediaPlayer {
             decoder,
             audio,
         )?;
+        match builder.settings {
+            Some(settings) => controller.set_settings(settings)?,
+            None => controller.set_config(builder.config)?,
+        }
+        controller.set_playback_options(builder.playback)?;
         
         // Add event handlers
         for handler in builder.event_handlers {
@@ -217,33 +285,61 @@ impl MediaPlayer {
     }
     
     /// Load a media file
+    /// 
+    /// Accepts `archive.zip` (entries become the playlist) and
+    /// `archive.zip!/inner/path.mp4` (plays a single entry).
     pub fn load_file(&self, path: &Path) -> Result<MediaInfo> {
         info!("Loading file: {:?}", path);
         
-        // Attempt to load with error recovery
-        match self.controller.lock().unwrap().load_file(path) {
-            Ok(info) => Ok(info),
-            Err(e) => {
-                error!("Failed to load file: {}", e);
-                
-                // Try recovery strategies
-                let recovery = self.error_recovery.lock().unwrap();
-                if recovery.should_retry(&e) {
-                    warn!("Retrying file load after error");
-                    thread::sleep(Duration::from_millis(500));
-                    self.controller.lock().unwrap().load_file(path)
-                } else {
-                    Err(e)
-                }
-            }
+        if let Some(archive) = ArchivePath::parse(path) {
+            return self.load_archive(&archive);
         }
+        
+        self.load_with_retry("file", |controller| controller.load_file(path))
     }
     
     /// Load a media URL
+    /// 
+    /// Network failures are retried with exponential backoff.
     pub fn load_url(&self, url: &str) -> Result<MediaInfo> {
         info!("Loading URL: {}", url);
         
-        self.controller.lock().unwrap().load_url(url)
+        self.load_with_retry("URL", |controller| controller.load_url(url))
+    }
+    
+    /// Run a load attempt, retrying recoverable errors per the recovery strategy
+    fn load_with_retry<F>(&self, what: &str, mut load: F) -> Result<MediaInfo>
+    where
+        F: FnMut(&mut PlayerController) -> Result<MediaInfo>,
+    {
+        let mut attempt = 0;
+        loop {
+            let error = match load(&mut self.controller.lock().unwrap()) {
+                Ok(info) => return Ok(info),
+                Err(e) => e,
+            };
+            error!("Failed to load {}: {}", what, error);
+            
+            let plan = self.error_recovery.lock().unwrap().retry_plan(&error);
+            match plan {
+                Some((max_attempts, delay)) if attempt < max_attempts => {
+                    let delay = delay * 2u32.pow(attempt);
+                    attempt += 1;
+                    warn!("Retrying {} load in {:?} ({}/{})", what, delay, attempt, max_attempts);
+                    thread::sleep(delay);
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+    
+    /// Load media from a custom byte source
+    /// 
+    /// The source is consumed by the attempt, so no retry is made.
+    pub fn load_source(&self, source: Box<dyn MediaSource>) -> Result<MediaInfo> {
+        info!("Loading source: {}", source.name());
+        
+        self.controller.lock().unwrap().load_source(source)
     }
     
     /// Play the current media
@@ -286,6 +382,11 @@ impl MediaPlayer {
         self.controller.lock().unwrap().toggle_mute()
     }
     
+    /// Mute or unmute
+    pub fn set_muted(&self, muted: bool) -> Result<()> {
+        self.controller.lock().unwrap().set_muted(muted)
+    }
+    
     /// Set playback speed
     pub fn set_speed(&self, speed: f32) -> Result<()> {
         self.controller.lock().unwrap().set_speed(speed)
@@ -321,11 +422,71 @@ impl MediaPlayer {
         self.controller.lock().unwrap().duration()
     }
     
+    /// Get information about the loaded media
+    pub fn media_info(&self) -> Option<MediaInfo> {
+        self.controller.lock().unwrap().media_info()
+    }
+    
+    /// Get the time ranges of a network stream that are already downloaded
+    pub fn buffered_ranges(&self) -> Vec<(Duration, Duration)> {
+        self.controller.lock().unwrap().buffered_ranges()
+    }
+    
+    /// Check whether the loaded media is a live stream
+    pub fn is_live(&self) -> bool {
+        self.controller.lock().unwrap().is_live()
+    }
+    
+    /// Get the seekable (DVR) window of a live stream
+    pub fn live_window(&self) -> Option<LiveWindow> {
+        self.controller.lock().unwrap().live_window()
+    }
+    
+    /// Jump to the live edge of a live stream
+    pub fn go_to_live_edge(&self) -> Result<()> {
+        self.controller.lock().unwrap().go_to_live_edge()
+    }
+    
+    /// Select the quality of an adaptive (HLS/DASH) stream
+    pub fn set_quality(&self, selection: QualitySelection) -> Result<()> {
+        self.controller.lock().unwrap().set_quality(selection)
+    }
+    
+    /// Rotate the video clockwise by a multiple of 90 degrees
+    pub fn rotate(&self, degrees: i32) -> Result<()> {
+        self.controller.lock().unwrap().rotate(degrees)
+    }
+    
+    /// Get the orientation the video is displayed in
+    pub fn rotation(&self) -> Rotation {
+        self.controller.lock().unwrap().rotation()
+    }
+    
+    /// Replace the FFmpeg filter chain applied to video (None to remove it)
+    pub fn set_video_filters(&self, filters: Option<&str>) -> Result<()> {
+        self.controller.lock().unwrap().set_video_filters(filters)
+    }
+    
+    /// Replace the FFmpeg filter chain applied to audio (None to remove it)
+    pub fn set_audio_filters(&self, filters: Option<&str>) -> Result<()> {
+        self.controller.lock().unwrap().set_audio_filters(filters)
+    }
+    
+    /// Reload the configuration whenever a config file is edited
+    pub fn watch_config(&self, loader: ConfigLoader) {
+        self.controller.lock().unwrap().watch_config(loader);
+    }
+    
     /// Get performance statistics
     pub fn get_performance_stats(&self) -> PerformanceStats {
         self.perf_monitor.lock().unwrap().get_stats()
     }
     
+    /// Get render statistics, including the measured video frame rate
+    pub fn render_stats(&self) -> RenderStats {
+        self.controller.lock().unwrap().render_stats()
+    }
+    
     /// Subscribe to events
     pub fn subscribe_events<F>(&self, callback: F) -> EventSubscription
     where
@@ -334,22 +495,99 @@ impl MediaPlayer {
         self.event_dispatcher.subscribe(callback)
     }
     
+    /// Load an archive entry, or list a whole archive into the playlist
+    fn load_archive(&self, archive: &ArchivePath) -> Result<MediaInfo> {
+        if archive.entry.is_some() {
+            return self.load_source(archive.open_entry()?);
+        }
+        
+        self.controller.lock().unwrap().set_playlist(Self::archive_items(archive)?);
+        self.play_item(0)
+    }
+    
+    /// Playlist items for the media entries of an archive
+    fn archive_items(archive: &ArchivePath) -> Result<Vec<PlaylistItem>> {
+        let items: Vec<PlaylistItem> = archive.list_entries()?
+            .into_iter()
+            .map(|entry| PlaylistItem {
+                path: ArchivePath::entry_path(&archive.archive, &entry.name),
+                title: Some(entry.name),
+                duration: None,
+            })
+            .collect();
+        
+        if items.is_empty() {
+            return Err(CCPlayerError::NotFound(format!(
+                "No media files in {}", archive.archive.display()
+            )));
+        }
+        
+        info!("Archive {:?} has {} media entries", archive.archive, items.len());
+        Ok(items)
+    }
+    
+    /// Load the playlist item at `index`
+    fn play_item(&self, index: usize) -> Result<MediaInfo> {
+        let item = self.controller.lock().unwrap().playlist().items.get(index).cloned()
+            .ok_or_else(|| CCPlayerError::InvalidInput(format!("No playlist item {}", index)))?;
+        
+        let info = if item.path.contains("://") {
+            self.load_url(&item.path)?
+        } else {
+            self.load_file(Path::new(&item.path))?
+        };
+        self.controller.lock().unwrap().set_playlist_index(index);
+        Ok(info)
+    }
+    
+    /// Play playlists loaded from now on in random order
+    pub fn set_shuffle(&self, shuffle: bool) {
+        self.controller.lock().unwrap().set_shuffle(shuffle);
+    }
+    
     /// Load playlist
-    pub fn load_playlist(&self, items: Vec<PlaylistItem>) -> Result<()> {
-        // TODO: Implement playlist management
+    /// 
+    /// Whole archives are replaced by their media entries. The playlist
+    /// repeats per the `loop_playback` and `loop_count` settings.
+    pub fn load_playlist(&self, entries: Vec<PlaylistItem>) -> Result<()> {
+        let mut items = Vec::with_capacity(entries.len());
+        for item in entries {
+            match ArchivePath::parse(Path::new(&item.path)) {
+                Some(archive) if archive.entry.is_none() => items.extend(Self::archive_items(&archive)?),
+                _ => items.push(item),
+            }
+        }
+        
+        let empty = items.is_empty();
+        self.controller.lock().unwrap().set_playlist(items);
+        
+        if !empty {
+            self.play_item(0)?;
+        }
+        
         Ok(())
     }
     
     /// Next track
     pub fn next_track(&self) -> Result<()> {
-        // TODO: Implement playlist navigation
-        Ok(())
+        let playlist = self.controller.lock().unwrap().playlist();
+        let Some(next) = playlist.next_index() else {
+            return Ok(());
+        };
+        
+        self.play_item(next).map(|_| ())
     }
     
     /// Previous track
     pub fn previous_track(&self) -> Result<()> {
-        // TODO: Implement playlist navigation
-        Ok(())
+        let playlist = self.controller.lock().unwrap().playlist();
+        let previous = match playlist.current_index {
+            Some(0) if playlist.repeat_mode == RepeatMode::All => playlist.items.len() - 1,
+            Some(index) => index.saturating_sub(1),
+            None => return Ok(()),
+        };
+        
+        self.play_item(previous).map(|_| ())
     }
 }
 
@@ -468,9 +706,24 @@ impl ErrorRecovery {
         matches!(error, 
             CCPlayerError::Decoder(_) | 
             CCPlayerError::Audio(_) |
-            CCPlayerError::FileIO(_)
+            CCPlayerError::FileIO(_) |
+            CCPlayerError::Network(_)
         )
     }
+    
+    /// Attempts and initial delay for retrying an error, if it is retryable
+    fn retry_plan(&self, error: &CCPlayerError) -> Option<(u32, Duration)> {
+        if !self.should_retry(error) {
+            return None;
+        }
+        
+        self.strategies.iter().find_map(|strategy| match strategy {
+            RecoveryStrategy::Retry { max_attempts, delay_ms } => {
+                Some((*max_attempts, Duration::from_millis(*delay_ms)))
+            }
+            _ => None,
+        })
+    }
 }
 
 /// Event dispatcher
@@ -569,5 +822,9 @@ mod tests {
         
         let window_error = CCPlayerError::Window("Test error".to_string());
         assert!(!recovery.should_retry(&window_error));
+        
+        let network_error = CCPlayerError::Network("Connection reset".to_string());
+        assert_eq!(recovery.retry_plan(&network_error), Some((3, Duration::from_millis(500))));
+        assert_eq!(recovery.retry_plan(&window_error), None);
     }
 }
\ No newline at end of file
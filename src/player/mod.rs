@@ -5,20 +5,25 @@
 //! It handles playback state, A/V synchronization, and user interactions.
 
 mod controller;
+mod keybindings;
+mod live;
 mod state;
 mod media_player;
 
 pub use controller::PlayerController;
+pub use keybindings::{parse_chord, Action, KeyBindings};
+pub use live::{LiveConfig, LiveTracker};
 pub use state::{PlayerStateManager, PlayerStateData, StateChangeEvent};
 pub use media_player::{MediaPlayer, MediaPlayerBuilder, PerformanceStats, EventSubscription};
 
+use crate::utils::config::SubtitleConfig;
 use crate::utils::error::Result;
 use crate::window::{Window, WindowEvent};
 use crate::renderer::Renderer;
-use crate::decoder::{Decoder, MediaInfo};
-use crate::audio::AudioOutput;
+use crate::decoder::{is_image_path, Decoder, DeinterlaceConfig, MediaInfo, MediaSource, StillImageConfig, ThumbnailConfig};
+use crate::audio::{AudioOutput, CrossfadeConfig, MixConfig};
 use std::sync::Arc;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 /// Player trait defining the main media player interface
@@ -64,6 +69,17 @@ pub trait Player: Send + Sync {
     /// Returns media information or an error
     fn load_url(&mut self, url: &str) -> Result<MediaInfo>;
     
+    /// Load media from a custom byte source
+    /// 
+    /// # Arguments
+    /// 
+    /// * `source` - Source to play from (memory buffer, archive entry, ...)
+    /// 
+    /// # Returns
+    /// 
+    /// Returns media information or an error
+    fn load_source(&mut self, source: Box<dyn MediaSource>) -> Result<MediaInfo>;
+    
     /// Start or resume playback
     fn play(&mut self) -> Result<()>;
     
@@ -175,18 +191,28 @@ pub enum PlaybackState {
 }
 
 /// Player configuration
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// 
+/// Stored as the `[player]` section of the config file. Fields marked
+/// `#[serde(skip)]` live in other sections and are filled in by
+/// [`crate::utils::Config::player_config`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(default)]
 pub struct PlayerConfig {
-    /// Auto-play when media is loaded
+    /// Auto-play when media is loaded (`[general] auto_play`)
+    #[serde(skip)]
     pub auto_play: bool,
     
     /// Remember playback position
     pub remember_position: bool,
     
-    /// Loop playback
+    /// Repeat the playlist when it ends
     pub loop_playback: bool,
     
-    /// Default volume (0.0 to 1.0)
+    /// Times the playlist plays while looping (None to repeat forever)
+    pub loop_count: Option<u32>,
+    
+    /// Default volume (0.0 to 1.0, `[audio] volume`)
+    #[serde(skip)]
     pub default_volume: f32,
     
     /// Seek step in seconds
@@ -201,15 +227,64 @@ pub struct PlayerConfig {
     /// Enable frame dropping for performance
     pub allow_frame_drop: bool,
     
+    /// Open the next playlist item ahead of time and play it without a gap
+    pub gapless: bool,
+    
+    /// Crossfade between playlist items (None for no crossfade)
+    /// 
+    /// Tracks from the same album are joined gaplessly instead when
+    /// `gapless` is set.
+    pub crossfade: Option<CrossfadeConfig>,
+    
     /// A/V sync threshold in milliseconds
     pub av_sync_threshold: i64,
     
-    /// Subtitle settings
-    pub subtitle_enabled: bool,
+    /// Downmix/upmix settings for multichannel audio
+    pub audio_mix: MixConfig,
+    
+    /// Deinterlacing of interlaced video
+    pub deinterlace: DeinterlaceConfig,
+    
+    /// FFmpeg filtergraph applied to decoded video (`--vf`, `[decoder] video_filters`)
+    #[serde(skip)]
+    pub video_filters: Option<String>,
+    
+    /// FFmpeg filtergraph applied to decoded audio (`--af`, `[audio] filters`)
+    #[serde(skip)]
+    pub audio_filters: Option<String>,
+    
+    /// Normalize loudness before the audio filters (`[audio] normalize`)
+    #[serde(skip)]
+    pub normalize: bool,
+    
+    /// Seek-bar preview thumbnails for local files (None to disable)
+    pub thumbnails: Option<ThumbnailConfig>,
+    
+    /// Display duration of still images and slideshow transitions
+    pub still_image: StillImageConfig,
+    
+    /// Decoded video frames kept queued ahead of the renderer (`[decoder] buffer_size`)
+    #[serde(skip)]
+    pub frame_buffer: usize,
+    
+    /// Preferred audio output rate, 0 for the source rate (`[audio] sample_rate`)
+    #[serde(skip)]
+    pub output_sample_rate: u32,
+    
+    /// Subtitle visibility and style (`[general.subtitles]`)
+    #[serde(skip)]
+    pub subtitles: SubtitleConfig,
+    
+    /// Key bindings (`[keybindings]`)
+    #[serde(skip)]
+    pub keybindings: KeyBindings,
     
     /// Screenshot settings
     pub screenshot_format: ScreenshotFormat,
     pub screenshot_quality: u8,
+    
+    /// Folder screenshots are saved in (None for the current directory)
+    pub screenshot_dir: Option<PathBuf>,
 }
 
 impl Default for PlayerConfig {
@@ -218,21 +293,56 @@ impl Default for PlayerConfig {
             auto_play: true,
             remember_position: false,
             loop_playback: false,
+            loop_count: None,
             default_volume: 0.7,
             seek_step: 10,
             fast_seek_step: 60,
             volume_step: 0.05,
             allow_frame_drop: true,
+            gapless: true,
+            crossfade: None,
             av_sync_threshold: 40, // 40ms
-            subtitle_enabled: true,
+            audio_mix: MixConfig::default(),
+            deinterlace: DeinterlaceConfig::default(),
+            video_filters: None,
+            audio_filters: None,
+            normalize: false,
+            thumbnails: Some(ThumbnailConfig::default()),
+            still_image: StillImageConfig::default(),
+            frame_buffer: 25,
+            output_sample_rate: 0,
+            subtitles: SubtitleConfig::default(),
+            keybindings: KeyBindings::default(),
             screenshot_format: ScreenshotFormat::Png,
             screenshot_quality: 90,
+            screenshot_dir: None,
         }
     }
 }
 
+/// Settings of a single run that are not part of the configuration
+/// 
+/// Given on the command line and applied to every item loaded.
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackOptions {
+    /// Position each item starts playing at (`--start`)
+    pub start: Option<Duration>,
+    
+    /// Position each item stops at, as if it ended there (`--end`)
+    pub end: Option<Duration>,
+    
+    /// Audio stream to play, by stream index (`--aid`, None for the default stream)
+    pub audio_track: Option<usize>,
+    
+    /// Text subtitle stream to show, by stream index (`--sid`)
+    pub subtitle_track: Option<usize>,
+    
+    /// SRT or WebVTT file shown instead of a subtitle stream (`--sub-file`)
+    pub subtitle_file: Option<PathBuf>,
+}
+
 /// Screenshot format
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub enum ScreenshotFormat {
     Png,
     Jpeg,
@@ -325,6 +435,70 @@ pub struct Playlist {
     
     /// Repeat mode
     pub repeat_mode: RepeatMode,
+    
+    /// Passes through the playlist left while repeating it, counting the
+    /// current one (None to repeat forever)
+    pub passes_left: Option<u32>,
+}
+
+impl Playlist {
+    /// Index of the item "next track" moves to
+    pub fn next_index(&self) -> Option<usize> {
+        match self.current_index {
+            Some(index) if index + 1 < self.items.len() => Some(index + 1),
+            Some(_) if self.repeat_mode == RepeatMode::All => Some(0),
+            Some(_) => None,
+            None if self.items.is_empty() => None,
+            None => Some(0),
+        }
+    }
+    
+    /// Index of the item that plays when the current one ends
+    pub fn following_index(&self) -> Option<usize> {
+        match self.current_index {
+            Some(index) if self.repeat_mode == RepeatMode::One => Some(index),
+            // The last pass ends with the last item
+            Some(index) if index + 1 >= self.items.len() && self.passes_left.is_some_and(|passes| passes <= 1) => None,
+            Some(_) => self.next_index(),
+            None => None,
+        }
+    }
+    
+    /// Make the item that followed the current one current
+    /// 
+    /// Starting over counts as a finished pass through a repeated playlist.
+    pub fn advance_to(&mut self, index: usize) {
+        let wrapped = self.current_index.is_some_and(|current| index <= current);
+        if wrapped && self.repeat_mode == RepeatMode::All {
+            if let Some(passes) = self.passes_left.as_mut() {
+                *passes = passes.saturating_sub(1);
+            }
+        }
+        self.current_index = Some(index);
+    }
+    
+    /// Put the items in random order
+    /// 
+    /// # Arguments
+    /// 
+    /// * `seed` - Seed of the order; the same seed gives the same order
+    pub fn shuffle_items(&mut self, seed: u64) {
+        // xorshift64* and Fisher-Yates; the seed must not be zero
+        let mut state = seed | 1;
+        for i in (1..self.items.len()).rev() {
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            let j = (state.wrapping_mul(0x2545_F491_4F6C_DD1D) % (i as u64 + 1)) as usize;
+            self.items.swap(i, j);
+        }
+        self.shuffle = true;
+    }
+    
+    /// Check whether the playlist holds nothing but still images
+    pub fn is_slideshow(&self) -> bool {
+        self.items.len() > 1 && self.items.iter().all(|item| is_image_path(&item.path))
+    }
 }
 
 /// Playlist item
@@ -378,4 +552,103 @@ mod tests {
         assert_ne!(RepeatMode::None, RepeatMode::One);
         assert_ne!(RepeatMode::One, RepeatMode::All);
     }
+    
+    #[test]
+    fn test_playlist_following_index() {
+        let item = |path: &str| PlaylistItem { path: path.to_string(), title: None, duration: None };
+        let mut playlist = Playlist {
+            items: vec![item("a.flac"), item("b.flac")],
+            current_index: Some(0),
+            shuffle: false,
+            repeat_mode: RepeatMode::None,
+            passes_left: None,
+        };
+        assert_eq!(playlist.following_index(), Some(1));
+        
+        playlist.current_index = Some(1);
+        assert_eq!(playlist.following_index(), None);
+        
+        playlist.repeat_mode = RepeatMode::All;
+        assert_eq!(playlist.following_index(), Some(0));
+        
+        // Repeat-one replays the item, "next track" still moves on
+        playlist.repeat_mode = RepeatMode::One;
+        assert_eq!(playlist.following_index(), Some(1));
+        assert_eq!(playlist.next_index(), None);
+    }
+    
+    #[test]
+    fn test_playlist_counted_repeat() {
+        let item = |path: &str| PlaylistItem { path: path.to_string(), title: None, duration: None };
+        let mut playlist = Playlist {
+            items: vec![item("a.flac"), item("b.flac")],
+            current_index: Some(0),
+            shuffle: false,
+            repeat_mode: RepeatMode::All,
+            passes_left: Some(2),
+        };
+        
+        playlist.advance_to(1);
+        assert_eq!(playlist.following_index(), Some(0));
+        playlist.advance_to(0);
+        assert_eq!(playlist.passes_left, Some(1));
+        assert_eq!(playlist.following_index(), Some(1));
+        
+        // The second pass is the last
+        playlist.advance_to(1);
+        assert_eq!(playlist.following_index(), None);
+        
+        // A single item repeats by starting over itself
+        playlist.items.truncate(1);
+        playlist.current_index = Some(0);
+        playlist.passes_left = Some(2);
+        assert_eq!(playlist.following_index(), Some(0));
+        playlist.advance_to(0);
+        assert_eq!(playlist.following_index(), None);
+    }
+    
+    #[test]
+    fn test_playlist_shuffle() {
+        let paths: Vec<String> = (0..20).map(|i| format!("{}.mp4", i)).collect();
+        let playlist = |seed| {
+            let mut playlist = Playlist {
+                items: paths.iter().map(|path| PlaylistItem { path: path.clone(), title: None, duration: None }).collect(),
+                current_index: None,
+                shuffle: false,
+                repeat_mode: RepeatMode::None,
+                passes_left: None,
+            };
+            playlist.shuffle_items(seed);
+            playlist.items.into_iter().map(|item| item.path).collect::<Vec<_>>()
+        };
+        
+        let shuffled = playlist(42);
+        assert_ne!(shuffled, paths);
+        assert_eq!(shuffled, playlist(42));
+        assert_ne!(shuffled, playlist(7));
+        
+        let mut sorted = shuffled.clone();
+        sorted.sort_by_key(|path| path.trim_end_matches(".mp4").parse::<u32>().unwrap());
+        assert_eq!(sorted, paths);
+    }
+    
+    #[test]
+    fn test_playlist_is_slideshow() {
+        let item = |path: &str| PlaylistItem { path: path.to_string(), title: None, duration: None };
+        let mut playlist = Playlist {
+            items: vec![item("a.jpg"), item("b.PNG"), item("c.webp")],
+            current_index: Some(0),
+            shuffle: false,
+            repeat_mode: RepeatMode::None,
+            passes_left: None,
+        };
+        assert!(playlist.is_slideshow());
+        
+        playlist.items.push(item("d.mp4"));
+        assert!(!playlist.is_slideshow());
+        
+        // A single image is a still, not a slideshow
+        playlist.items = vec![item("a.jpg")];
+        assert!(!playlist.is_slideshow());
+    }
 }
\ No newline at end of file
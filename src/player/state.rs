@@ -5,11 +5,12 @@
 
 use crate::decoder::MediaInfo;
 use crate::player::{PlaybackState, PlayerConfig, Playlist, PlaybackStats};
+use crate::utils::Config;
 use std::sync::{Arc, RwLock, Mutex};
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
-use log::{info, debug};
+use log::{info, debug, warn};
 
 /// Player state manager
 pub struct PlayerStateManager {
@@ -83,6 +84,7 @@ impl Default for PlayerStateData {
                 current_index: None,
                 shuffle: false,
                 repeat_mode: crate::player::RepeatMode::None,
+                passes_left: None,
             },
             buffer_percent: 0.0,
             last_error: None,
@@ -419,36 +421,32 @@ impl PlayerStateManager {
     where
         F: FnOnce(&mut PlayerConfig),
     {
-        let mut config = self.config.write().unwrap();
-        updater(&mut *config);
-        self.save_config();
+        let (previous, config) = {
+            let mut config = self.config.write().unwrap();
+            let previous = config.clone();
+            updater(&mut *config);
+            (previous, config.clone())
+        };
+        Self::save_config(&previous, &config);
     }
     
-    /// Load configuration from disk
+    /// Load configuration from the layered config files
     pub fn load_config(&self) {
-        if let Ok(data) = std::fs::read_to_string(Self::config_file_path()) {
-            if let Ok(config) = serde_json::from_str::<PlayerConfig>(&data) {
-                *self.config.write().unwrap() = config;
+        match Config::load() {
+            Ok(config) => {
+                *self.config.write().unwrap() = config.player_config();
                 info!("Loaded configuration from disk");
             }
+            Err(e) => warn!("Keeping current configuration: {}", e),
         }
     }
     
-    /// Save configuration to disk
-    fn save_config(&self) {
-        let config = self.config.read().unwrap();
-        if let Ok(data) = serde_json::to_string_pretty(&*config) {
-            let _ = std::fs::write(Self::config_file_path(), data);
+    /// Save the changed settings to the user config file
+    fn save_config(previous: &PlayerConfig, config: &PlayerConfig) {
+        if let Err(e) = Config::save_player_changes(previous, config) {
+            warn!("Configuration not saved: {}", e);
         }
     }
-    
-    fn config_file_path() -> std::path::PathBuf {
-        let mut path = dirs::config_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        path.push("ccplayer");
-        let _ = std::fs::create_dir_all(&path);
-        path.push("config.json");
-        path
-    }
 }
 
 /// State change event
@@ -8,16 +8,17 @@ use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
 /// Frame timing controller for smooth playback
+/// 
+/// Presentation follows the frames' own timestamps and durations, so
+/// variable frame rate video (phone footage, screen recordings) keeps its
+/// timing instead of being paced at a fixed rate.
 pub struct FrameController {
-    /// Target frame rate (typically 60 FPS)
-    target_fps: f64,
-    
-    /// Frame duration in microseconds
-    frame_duration: Duration,
-    
     /// Last presented frame timestamp
     last_present_time: Instant,
     
+    /// How long the last presented frame stays on screen
+    last_duration: Duration,
+    
     /// Frame presentation history for statistics
     frame_history: VecDeque<FrameInfo>,
     
@@ -26,9 +27,6 @@ pub struct FrameController {
     
     /// Vsync enabled
     vsync_enabled: bool,
-    
-    /// Frame drop threshold (in milliseconds)
-    drop_threshold: f64,
 }
 
 /// Information about a rendered frame
@@ -45,21 +43,20 @@ struct FrameInfo {
     
     /// Frame PTS (presentation timestamp)
     pts: i64,
+    
+    /// Frame duration in microseconds
+    duration: i64,
 }
 
 impl FrameController {
     /// Create a new frame controller
-    pub fn new(target_fps: f64, vsync_enabled: bool) -> Self {
-        let frame_duration = Duration::from_secs_f64(1.0 / target_fps);
-        
+    pub fn new(vsync_enabled: bool) -> Self {
         Self {
-            target_fps,
-            frame_duration,
             last_present_time: Instant::now(),
+            last_duration: Duration::ZERO,
             frame_history: VecDeque::with_capacity(240), // 4 seconds at 60 FPS
             max_history: 240,
             vsync_enabled,
-            drop_threshold: 16.67 * 1.5, // 1.5x frame time for 60 FPS
         }
     }
     
@@ -69,13 +66,13 @@ impl FrameController {
         frame_pts <= current_time
     }
     
-    /// Wait for the appropriate time to present a frame
+    /// Wait until the last presented frame has been on screen for its duration
     pub fn wait_for_present(&mut self) -> Result<()> {
         if !self.vsync_enabled {
-            // Software vsync - wait until next frame time
+            // Software pacing by the frame's own duration
             let elapsed = self.last_present_time.elapsed();
-            if elapsed < self.frame_duration {
-                std::thread::sleep(self.frame_duration - elapsed);
+            if elapsed < self.last_duration {
+                std::thread::sleep(self.last_duration - elapsed);
             }
         }
         // With vsync enabled, the GPU driver handles timing
@@ -84,7 +81,14 @@ impl FrameController {
     }
     
     /// Record frame presentation
-    pub fn record_frame_presented(&mut self, render_start: Instant, pts: i64, dropped: bool) {
+    /// 
+    /// # Arguments
+    /// 
+    /// * `render_start` - When rendering of the frame started
+    /// * `pts` - Frame PTS in microseconds
+    /// * `duration` - Frame duration in microseconds
+    /// * `dropped` - Whether the frame was dropped instead of shown
+    pub fn record_frame_presented(&mut self, render_start: Instant, pts: i64, duration: i64, dropped: bool) {
         let now = Instant::now();
         let render_duration = now.duration_since(render_start);
         
@@ -93,6 +97,7 @@ impl FrameController {
             render_duration,
             dropped,
             pts,
+            duration,
         };
         
         self.frame_history.push_back(frame_info);
@@ -100,7 +105,10 @@ impl FrameController {
             self.frame_history.pop_front();
         }
         
-        self.last_present_time = now;
+        if !dropped {
+            self.last_present_time = now;
+            self.last_duration = Duration::from_micros(duration.max(0) as u64);
+        }
     }
     
     /// Calculate current FPS
@@ -119,6 +127,44 @@ impl FrameController {
         recent_frames as f64
     }
     
+    /// Calculate the video frame rate over the last second of video
+    /// 
+    /// Measured from the timestamps of the presented frames, so it follows
+    /// the content's rate rather than the display's.
+    pub fn average_fps(&self) -> f64 {
+        let mut shown = self.frame_history.iter().rev().filter(|f| !f.dropped);
+        let Some(last) = shown.next() else {
+            return 0.0;
+        };
+        
+        // Frames shown within one second of video before the last one
+        let mut frames = 1;
+        let mut first_pts = last.pts;
+        for frame in shown {
+            if frame.pts >= last.pts || last.pts - frame.pts > 1_000_000 {
+                break;
+            }
+            frames += 1;
+            first_pts = frame.pts;
+        }
+        
+        let span = last.pts - first_pts + last.duration;
+        if span <= 0 {
+            return 0.0;
+        }
+        frames as f64 * 1_000_000.0 / span as f64
+    }
+    
+    /// Calculate the video frame rate at the last presented frame
+    pub fn instant_fps(&self) -> f64 {
+        self.frame_history
+            .iter()
+            .rev()
+            .find(|f| !f.dropped)
+            .filter(|f| f.duration > 0)
+            .map_or(0.0, |f| 1_000_000.0 / f.duration as f64)
+    }
+    
     /// Calculate average frame time
     pub fn calculate_avg_frame_time(&self) -> f64 {
         if self.frame_history.is_empty() {
@@ -151,29 +197,37 @@ impl FrameController {
             .count() as u64
     }
     
-    /// Check if frame rate is stable
+    /// Check if presentation follows the frames' timestamps
+    /// 
+    /// Compares the time between presentations with the time between the
+    /// frames' PTS, so variable frame rate video counts as stable when it
+    /// is shown on time.
     pub fn is_stable(&self) -> bool {
         if self.frame_history.len() < 30 {
             return false;
         }
         
-        // Check variance in recent frame times
-        let recent_times: Vec<f64> = self.frame_history
+        // Deviation of each presentation interval from its PTS interval
+        let deviations: Vec<f64> = self.frame_history
             .iter()
             .rev()
             .take(30)
             .zip(self.frame_history.iter().rev().skip(1).take(30))
-            .map(|(f1, f2)| f1.present_time.duration_since(f2.present_time).as_secs_f64() * 1000.0)
+            .map(|(f1, f2)| {
+                let shown = f1.present_time.duration_since(f2.present_time).as_secs_f64() * 1000.0;
+                let expected = (f1.pts - f2.pts) as f64 / 1000.0;
+                shown - expected
+            })
             .collect();
         
-        if recent_times.is_empty() {
+        if deviations.is_empty() {
             return false;
         }
         
-        let mean = recent_times.iter().sum::<f64>() / recent_times.len() as f64;
-        let variance = recent_times.iter()
+        let mean = deviations.iter().sum::<f64>() / deviations.len() as f64;
+        let variance = deviations.iter()
             .map(|t| (t - mean).powi(2))
-            .sum::<f64>() / recent_times.len() as f64;
+            .sum::<f64>() / deviations.len() as f64;
         
         // Stable if standard deviation is less than 2ms
         variance.sqrt() < 2.0
@@ -183,13 +237,6 @@ impl FrameController {
     pub fn set_vsync(&mut self, enabled: bool) {
         self.vsync_enabled = enabled;
     }
-    
-    /// Set target FPS
-    pub fn set_target_fps(&mut self, fps: f64) {
-        self.target_fps = fps;
-        self.frame_duration = Duration::from_secs_f64(1.0 / fps);
-        self.drop_threshold = (1000.0 / fps) * 1.5;
-    }
 }
 
 /// Frame queue for buffering decoded frames
@@ -260,12 +307,12 @@ mod tests {
     
     #[test]
     fn test_frame_controller_fps() {
-        let mut controller = FrameController::new(60.0, true);
+        let mut controller = FrameController::new(true);
         
         // Simulate frame presentations
         let start = Instant::now();
         for i in 0..60 {
-            controller.record_frame_presented(start, i * 16_667, false);
+            controller.record_frame_presented(start, i * 16_667, 16_667, false);
             std::thread::sleep(Duration::from_millis(16));
         }
         
@@ -274,6 +321,32 @@ mod tests {
         assert!(fps > 50.0 && fps < 70.0);
     }
     
+    #[test]
+    fn test_frame_controller_vfr() {
+        let mut controller = FrameController::new(true);
+        let start = Instant::now();
+        
+        // A second of 30 fps, then the recording slows to 10 fps
+        let mut pts = 0;
+        for _ in 0..30 {
+            controller.record_frame_presented(start, pts, 33_333, false);
+            pts += 33_333;
+        }
+        assert!((controller.average_fps() - 30.0).abs() < 0.1);
+        assert!((controller.instant_fps() - 30.0).abs() < 0.1);
+        
+        for _ in 0..20 {
+            controller.record_frame_presented(start, pts, 100_000, false);
+            pts += 100_000;
+        }
+        assert!((controller.average_fps() - 10.0).abs() < 0.1);
+        assert!((controller.instant_fps() - 10.0).abs() < 0.1);
+        
+        // Dropped frames do not count
+        controller.record_frame_presented(start, pts, 5_000, true);
+        assert!((controller.instant_fps() - 10.0).abs() < 0.1);
+    }
+    
     #[test]
     fn test_frame_queue() {
         let mut queue: FrameQueue<i32> = FrameQueue::new(3);
@@ -6,6 +6,7 @@
 use crate::utils::error::Result;
 use crate::window::Window;
 use std::sync::Arc;
+use std::time::Duration;
 
 // Export submodules
 pub mod frame;
@@ -61,7 +62,8 @@ pub trait Renderer: Send + Sync {
     /// 
     /// # Arguments
     /// 
-    /// * `aspect_ratio` - Video aspect ratio (width / height)
+    /// * `aspect_ratio` - Video aspect ratio (width / height) before rotation,
+    ///   which the renderer applies on top
     fn set_aspect_ratio(&mut self, aspect_ratio: f32) -> Result<()>;
     
     /// Take a screenshot of the current frame
@@ -70,6 +72,90 @@ pub trait Renderer: Send + Sync {
     /// 
     /// Returns the screenshot as RGBA8 data
     fn screenshot(&self) -> Result<Vec<u8>>;
+    
+    /// Set the orientation the video is displayed in
+    /// 
+    /// # Arguments
+    /// 
+    /// * `rotation` - Rotation and mirroring applied to the video
+    fn set_rotation(&mut self, rotation: Rotation) -> Result<()>;
+    
+    /// Crossfade from the frame on screen to each new frame
+    /// 
+    /// # Arguments
+    /// 
+    /// * `duration` - Length of the crossfade (None to cut straight to new frames)
+    fn set_transition(&mut self, duration: Option<Duration>) -> Result<()>;
+    
+    /// Check whether a crossfade is in progress
+    /// 
+    /// # Returns
+    /// 
+    /// True while the frame has to be presented again to animate it
+    fn is_transitioning(&self) -> bool;
+    
+    /// Get render statistics
+    /// 
+    /// # Returns
+    /// 
+    /// Presentation rate, measured video frame rate and timing
+    fn stats(&self) -> RenderStats;
+}
+
+/// Orientation of the displayed video
+/// 
+/// The video is rotated clockwise first and then mirrored horizontally,
+/// matching how display matrices are composed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct Rotation {
+    /// Clockwise rotation in degrees (0, 90, 180 or 270)
+    pub degrees: u32,
+    
+    /// Mirror the video horizontally
+    pub flip: bool,
+}
+
+impl Rotation {
+    /// Create a rotation, snapping the angle to the nearest quarter turn
+    pub fn new(degrees: f64, flip: bool) -> Self {
+        let quarter_turns = (degrees / 90.0).round() as i64;
+        Self {
+            degrees: (quarter_turns.rem_euclid(4) * 90) as u32,
+            flip,
+        }
+    }
+    
+    /// The same orientation turned a further `degrees` clockwise
+    pub fn rotated(self, degrees: i32) -> Self {
+        Self::new(self.degrees as f64 + degrees as f64, self.flip)
+    }
+    
+    /// Check whether width and height swap on screen
+    pub fn is_transposed(&self) -> bool {
+        self.degrees == 90 || self.degrees == 270
+    }
+    
+    /// Aspect ratio of the video on screen
+    /// 
+    /// # Arguments
+    /// 
+    /// * `aspect_ratio` - Aspect ratio of the decoded video (width / height)
+    pub fn display_aspect(&self, aspect_ratio: f32) -> f32 {
+        if self.is_transposed() {
+            1.0 / aspect_ratio
+        } else {
+            aspect_ratio
+        }
+    }
+    
+    /// Size of the video on screen
+    pub fn display_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if self.is_transposed() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
 }
 
 /// Video frame data
@@ -94,6 +180,26 @@ pub struct VideoFrame {
     pub par: f32,
 }
 
+/// Longest duration accepted from the gap to the next frame
+/// 
+/// Screen recordings hold a still frame for seconds; longer gaps are
+/// discontinuities rather than frame durations.
+pub const MAX_FRAME_DURATION: i64 = 10_000_000;
+
+impl VideoFrame {
+    /// Set the duration from the PTS of the frame that follows
+    /// 
+    /// Variable frame rate video only tells how long a frame lasts once
+    /// the next one is known. The decoder's estimate is kept when the gap
+    /// is not a plausible duration.
+    pub fn fit_duration(&mut self, next_pts: i64) {
+        let gap = next_pts - self.pts;
+        if gap > 0 && gap <= MAX_FRAME_DURATION {
+            self.duration = gap;
+        }
+    }
+}
+
 /// Frame data formats
 #[derive(Debug, Clone)]
 pub enum FrameData {
@@ -144,6 +250,19 @@ pub enum FrameData {
     },
 }
 
+/// Preview shown above the seek bar while the cursor hovers it
+#[derive(Debug, Clone)]
+pub struct SeekPreview {
+    /// Hovered position on the seek bar (0.0 to 1.0)
+    pub position: f64,
+    
+    /// Media time at the hovered position
+    pub time: std::time::Duration,
+    
+    /// Frame near that time, None until it has been extracted
+    pub thumbnail: Option<Arc<crate::decoder::Thumbnail>>,
+}
+
 /// Overlay types that can be rendered on top of video
 #[derive(Debug, Clone)]
 pub enum Overlay {
@@ -158,8 +277,11 @@ pub enum Overlay {
     Controls {
         playing: bool,
         position: f64,  // 0.0 to 1.0
-        duration: std::time::Duration,
+        duration: std::time::Duration,  // DVR window length for live streams
         visible: bool,
+        live: bool,
+        buffered: Vec<(f64, f64)>,  // Downloaded ranges, 0.0 to 1.0 like position
+        preview: Option<SeekPreview>,  // While the seek bar is hovered
     },
     
     /// Text overlay (subtitles, info, etc.)
@@ -186,6 +308,20 @@ pub enum Overlay {
     },
 }
 
+impl Overlay {
+    /// Time label shown by the controls overlay
+    /// 
+    /// "LIVE" for live streams (their duration is meaningless), the
+    /// formatted duration otherwise. None for other overlays.
+    pub fn controls_label(&self) -> Option<String> {
+        match self {
+            Overlay::Controls { live: true, .. } => Some("LIVE".to_string()),
+            Overlay::Controls { duration, .. } => Some(crate::utils::format_duration(*duration)),
+            _ => None,
+        }
+    }
+}
+
 /// Overlay positioning
 #[derive(Debug, Clone, Copy)]
 pub enum OverlayPosition {
@@ -288,6 +424,12 @@ pub struct RenderStats {
     /// Frames rendered in the last second
     pub fps: f32,
     
+    /// Video frame rate over the last second, from the frames' timestamps
+    pub average_fps: f32,
+    
+    /// Video frame rate at the current frame, from its duration
+    pub instant_fps: f32,
+    
     /// Average frame render time in milliseconds
     pub frame_time: f32,
     
@@ -330,6 +472,36 @@ mod tests {
         assert!(Color::from_hex("#FF00").is_err());
     }
     
+    #[test]
+    fn test_controls_label() {
+        let controls = |live| Overlay::Controls {
+            playing: true,
+            position: 0.5,
+            duration: std::time::Duration::from_secs(125),
+            visible: true,
+            live,
+            buffered: Vec::new(),
+            preview: None,
+        };
+        
+        assert_eq!(controls(false).controls_label().as_deref(), Some("02:05"));
+        assert_eq!(controls(true).controls_label().as_deref(), Some("LIVE"));
+        assert!(Overlay::Loading { position: OverlayPosition::Center }.controls_label().is_none());
+    }
+    
+    #[test]
+    fn test_rotation() {
+        assert_eq!(Rotation::new(-90.0, false).degrees, 270);
+        assert_eq!(Rotation::new(89.6, false).degrees, 90);
+        assert_eq!(Rotation::new(360.0, true), Rotation { degrees: 0, flip: true });
+        
+        let portrait = Rotation::default().rotated(90);
+        assert!(portrait.is_transposed());
+        assert!((portrait.display_aspect(16.0 / 9.0) - 9.0 / 16.0).abs() < 1e-6);
+        assert_eq!(portrait.display_size(1920, 1080), (1080, 1920));
+        assert_eq!(portrait.rotated(270).degrees, 0);
+    }
+    
     #[test]
     fn test_color_constants() {
         assert_eq!(Color::WHITE.r, 1.0);
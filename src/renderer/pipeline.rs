@@ -8,6 +8,7 @@ use bytemuck::{Pod, Zeroable};
 use wgpu::util::DeviceExt;
 
 use super::texture::TextureManager;
+use super::Rotation;
 
 /// Vertex data for rendering a quad
 #[repr(C)]
@@ -38,14 +39,27 @@ impl Vertex {
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 struct VideoUniforms {
-    /// Transform matrix for aspect ratio correction
+    /// Transform matrix for rotation and aspect ratio correction
     transform: [[f32; 4]; 4],
     /// Color space conversion matrix (for YUV to RGB)
     color_matrix: [[f32; 4]; 4],
-    /// Video properties (width, height, format, padding)
+    /// Video properties (width, height, format, opacity)
     video_props: [f32; 4],
 }
 
+/// Previous frame drawn underneath the current one while crossfading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crossfade {
+    /// Aspect ratio of the previous frame
+    pub aspect_ratio: f32,
+    
+    /// Orientation of the previous frame
+    pub rotation: Rotation,
+    
+    /// Opacity of the current frame over the previous one (0.0 to 1.0)
+    pub progress: f32,
+}
+
 /// Render pipeline for video rendering
 pub struct RenderPipeline {
     /// Main render pipeline
@@ -65,6 +79,12 @@ pub struct RenderPipeline {
     
     /// Current bind group (recreated when textures change)
     bind_group: Option<wgpu::BindGroup>,
+    
+    /// Uniform buffer of the previous frame during a crossfade
+    previous_uniform_buffer: wgpu::Buffer,
+    
+    /// Bind group of the previous frame during a crossfade
+    previous_bind_group: Option<wgpu::BindGroup>,
 }
 
 impl RenderPipeline {
@@ -156,7 +176,8 @@ impl RenderPipeline {
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: surface_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    // The current frame is blended over the previous one while crossfading
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
@@ -211,7 +232,7 @@ impl RenderPipeline {
                 [0.0, 0.0, 0.0, 1.0],
             ],
             color_matrix: Self::get_yuv_to_rgb_matrix(),
-            video_props: [0.0, 0.0, 0.0, 0.0],
+            video_props: [0.0, 0.0, 0.0, 1.0],
         };
         
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -220,6 +241,12 @@ impl RenderPipeline {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
         
+        let previous_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Previous Frame Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniforms]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        
         Ok(Self {
             pipeline,
             vertex_buffer,
@@ -227,10 +254,15 @@ impl RenderPipeline {
             uniform_buffer,
             bind_group_layout,
             bind_group: None,
+            previous_uniform_buffer,
+            previous_bind_group: None,
         })
     }
     
     /// Render video frame
+    /// 
+    /// During a crossfade the previous frame is drawn first, with its own
+    /// geometry, and the current frame blended over it.
     pub fn render_video(
         &mut self,
         device: &wgpu::Device,
@@ -239,13 +271,37 @@ impl RenderPipeline {
         texture_manager: &TextureManager,
         window_size: (u32, u32),
         aspect_ratio: f32,
+        rotation: Rotation,
+        crossfade: Option<Crossfade>,
     ) -> Result<()> {
         // Update uniforms
-        self.update_uniforms(device, encoder, window_size, aspect_ratio)?;
+        let opacity = crossfade.map_or(1.0, |fade| fade.progress.clamp(0.0, 1.0));
+        self.update_uniforms(device, encoder, &self.uniform_buffer, window_size, aspect_ratio, rotation, opacity)?;
+        if let Some(fade) = crossfade {
+            self.update_uniforms(
+                device,
+                encoder,
+                &self.previous_uniform_buffer,
+                window_size,
+                fade.aspect_ratio,
+                fade.rotation,
+                1.0,
+            )?;
+        }
         
-        // Create bind group if needed
+        // Create bind groups if needed
         if self.bind_group.is_none() {
-            self.create_bind_group(device, texture_manager)?;
+            let (y_view, u_view, v_view, sampler) = texture_manager.get_video_views()?;
+            self.bind_group = Some(self.create_bind_group(device, &self.uniform_buffer, [y_view, u_view, v_view], sampler));
+        }
+        if crossfade.is_some() && self.previous_bind_group.is_none() {
+            let (y_view, u_view, v_view, sampler) = texture_manager.get_previous_views()?;
+            self.previous_bind_group = Some(self.create_bind_group(
+                device,
+                &self.previous_uniform_buffer,
+                [&y_view, &u_view, &v_view],
+                sampler,
+            ));
         }
         
         // Begin render pass
@@ -269,17 +325,21 @@ impl RenderPipeline {
             timestamp_writes: None,
         });
         
-        // Set pipeline and bind group
+        // Set pipeline
         render_pass.set_pipeline(&self.pipeline);
-        if let Some(bind_group) = &self.bind_group {
-            render_pass.set_bind_group(0, bind_group, &[]);
-        }
         
         // Set vertex and index buffers
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
         
-        // Draw
+        // Draw the frame fading out, then the current one over it
+        if let (Some(_), Some(bind_group)) = (crossfade, &self.previous_bind_group) {
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw_indexed(0..6, 0, 0..1);
+        }
+        if let Some(bind_group) = &self.bind_group {
+            render_pass.set_bind_group(0, bind_group, &[]);
+        }
         render_pass.draw_indexed(0..6, 0, 0..1);
         
         Ok(())
@@ -290,31 +350,18 @@ impl RenderPipeline {
         &self,
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
+        uniform_buffer: &wgpu::Buffer,
         window_size: (u32, u32),
         aspect_ratio: f32,
+        rotation: Rotation,
+        opacity: f32,
     ) -> Result<()> {
-        // Calculate transform matrix for aspect ratio correction
         let window_aspect = window_size.0 as f32 / window_size.1 as f32;
-        let scale_x = if aspect_ratio > window_aspect {
-            1.0
-        } else {
-            aspect_ratio / window_aspect
-        };
-        let scale_y = if aspect_ratio > window_aspect {
-            window_aspect / aspect_ratio
-        } else {
-            1.0
-        };
         
         let uniforms = VideoUniforms {
-            transform: [
-                [scale_x, 0.0, 0.0, 0.0],
-                [0.0, scale_y, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
-            ],
+            transform: Self::vertex_transform(rotation, aspect_ratio, window_aspect),
             color_matrix: Self::get_yuv_to_rgb_matrix(),
-            video_props: [0.0, 0.0, 0.0, 0.0],
+            video_props: [0.0, 0.0, 0.0, opacity],
         };
         
         // Create staging buffer
@@ -328,7 +375,7 @@ impl RenderPipeline {
         encoder.copy_buffer_to_buffer(
             &staging_buffer,
             0,
-            &self.uniform_buffer,
+            uniform_buffer,
             0,
             std::mem::size_of::<VideoUniforms>() as u64,
         );
@@ -336,17 +383,53 @@ impl RenderPipeline {
         Ok(())
     }
     
-    /// Create bind group for current textures
-    fn create_bind_group(&mut self, device: &wgpu::Device, texture_manager: &TextureManager) -> Result<()> {
-        let (y_view, u_view, v_view, sampler) = texture_manager.get_video_views()?;
+    /// Calculate the vertex transform for the video quad
+    /// 
+    /// Rotates and mirrors the quad, then scales it so the video keeps its
+    /// on-screen aspect ratio inside the window.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `rotation` - Orientation of the video
+    /// * `aspect_ratio` - Aspect ratio of the decoded video (width / height)
+    /// * `window_aspect` - Aspect ratio of the window
+    fn vertex_transform(rotation: Rotation, aspect_ratio: f32, window_aspect: f32) -> [[f32; 4]; 4] {
+        let display_aspect = rotation.display_aspect(aspect_ratio);
+        let (scale_x, scale_y) = if display_aspect > window_aspect {
+            (1.0, window_aspect / display_aspect)
+        } else {
+            (display_aspect / window_aspect, 1.0)
+        };
         
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        // Clockwise rotation in clip space (y points up)
+        let (sin, cos) = (rotation.degrees as f32).to_radians().sin_cos();
+        let mirror = if rotation.flip { -1.0 } else { 1.0 };
+        let (sx, sy) = (scale_x * mirror, scale_y);
+        
+        // Column-major, as WGSL expects
+        [
+            [sx * cos, -sy * sin, 0.0, 0.0],
+            [sx * sin, sy * cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+    
+    /// Create a bind group for one frame's uniforms and plane textures
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        uniform_buffer: &wgpu::Buffer,
+        [y_view, u_view, v_view]: [&wgpu::TextureView; 3],
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Video Bind Group"),
             layout: &self.bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: self.uniform_buffer.as_entire_binding(),
+                    resource: uniform_buffer.as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -365,10 +448,7 @@ impl RenderPipeline {
                     resource: wgpu::BindingResource::Sampler(sampler),
                 },
             ],
-        });
-        
-        self.bind_group = Some(bind_group);
-        Ok(())
+        })
     }
     
     /// Get YUV to RGB color conversion matrix (BT.709)
@@ -382,9 +462,10 @@ impl RenderPipeline {
         ]
     }
     
-    /// Invalidate bind group (call when textures change)
+    /// Invalidate bind groups (call when textures change)
     pub fn invalidate_bind_group(&mut self) {
         self.bind_group = None;
+        self.previous_bind_group = None;
     }
 }
 
@@ -397,4 +478,30 @@ mod tests {
         let desc = Vertex::desc();
         assert_eq!(desc.array_stride, std::mem::size_of::<Vertex>() as u64);
     }
+    
+    /// Apply a column-major transform to a point
+    fn apply(transform: [[f32; 4]; 4], x: f32, y: f32) -> (f32, f32) {
+        (
+            transform[0][0] * x + transform[1][0] * y,
+            transform[0][1] * x + transform[1][1] * y,
+        )
+    }
+    
+    #[test]
+    fn test_vertex_transform_rotation() {
+        // Letterboxed 2:1 video in a square window
+        let plain = RenderPipeline::vertex_transform(Rotation::default(), 2.0, 1.0);
+        let (x, y) = apply(plain, 1.0, 1.0);
+        assert!((x - 1.0).abs() < 1e-6 && (y - 0.5).abs() < 1e-6);
+        
+        // A quarter turn clockwise moves the top edge to the right and
+        // pillarboxes the now portrait video
+        let portrait = RenderPipeline::vertex_transform(Rotation::new(90.0, false), 2.0, 1.0);
+        let (x, y) = apply(portrait, 0.0, 1.0);
+        assert!((x - 0.5).abs() < 1e-6 && y.abs() < 1e-6);
+        
+        let mirrored = RenderPipeline::vertex_transform(Rotation::new(0.0, true), 1.0, 1.0);
+        let (x, y) = apply(mirrored, 1.0, 0.0);
+        assert!((x + 1.0).abs() < 1e-6 && y.abs() < 1e-6);
+    }
 }
\ No newline at end of file
@@ -16,7 +16,7 @@ struct Uniforms {
     transform: mat4x4<f32>,
     // Color space conversion matrix
     color_matrix: mat4x4<f32>,
-    // Video properties: x=width, y=height, z=format, w=reserved
+    // Video properties: x=width, y=height, z=format, w=opacity (below 1 while crossfading)
     video_props: vec4<f32>,
 }
 
@@ -64,14 +64,15 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     // Clamp values to valid range
     let clamped_rgb = clamp(rgb.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
     
-    return vec4<f32>(clamped_rgb, 1.0);
+    return vec4<f32>(clamped_rgb, uniforms.video_props.w);
 }
 
 // Alternative fragment shader for RGB/RGBA formats
 @fragment
 fn fs_main_rgb(input: VertexOutput) -> @location(0) vec4<f32> {
     // For RGB formats, we use the y_texture which contains the RGB data
-    return textureSample(y_texture, video_sampler, input.tex_coords);
+    let color = textureSample(y_texture, video_sampler, input.tex_coords);
+    return vec4<f32>(color.rgb, color.a * uniforms.video_props.w);
 }
 
 // Fragment shader for NV12 format (interleaved UV)
@@ -90,7 +91,7 @@ fn fs_main_nv12(input: VertexOutput) -> @location(0) vec4<f32> {
     // Clamp values to valid range
     let clamped_rgb = clamp(rgb.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
     
-    return vec4<f32>(clamped_rgb, 1.0);
+    return vec4<f32>(clamped_rgb, uniforms.video_props.w);
 }
 
 // Overlay rendering support (for future use)
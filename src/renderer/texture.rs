@@ -29,6 +29,9 @@ pub struct TextureManager {
     
     /// Current pixel format
     current_format: Option<VideoFormat>,
+    
+    /// Y, U and V textures of the previous frame while a crossfade shows it
+    previous: Option<[wgpu::Texture; 3]>,
 }
 
 /// Internal video format representation
@@ -65,21 +68,28 @@ impl TextureManager {
             sampler,
             current_dimensions: None,
             current_format: None,
+            previous: None,
         })
     }
     
     /// Update video texture with new frame data
+    /// 
+    /// # Returns
+    /// 
+    /// Whether the textures were recreated, which invalidates bind groups
+    /// holding the old ones
     pub fn update_video_texture(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         frame: &VideoFrame,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         let format = Self::get_format(&frame.data);
         let dimensions = (frame.width, frame.height);
         
         // Check if we need to recreate textures
-        if self.current_dimensions != Some(dimensions) || self.current_format != Some(format) {
+        let recreate = self.current_dimensions != Some(dimensions) || self.current_format != Some(format);
+        if recreate {
             self.create_textures(device, dimensions, format)?;
             self.current_dimensions = Some(dimensions);
             self.current_format = Some(format);
@@ -107,7 +117,42 @@ impl TextureManager {
             }
         }
         
-        Ok(())
+        Ok(recreate)
+    }
+    
+    /// Keep the current frame's textures for a crossfade
+    /// 
+    /// The next frame is uploaded into new textures.
+    /// 
+    /// # Returns
+    /// 
+    /// False if no frame has been uploaded yet
+    pub fn retain_previous(&mut self) -> bool {
+        let (Some(y), Some(u), Some(v)) = (&self.y_texture, &self.u_texture, &self.v_texture) else {
+            return false;
+        };
+        
+        self.previous = Some([y.clone(), u.clone(), v.clone()]);
+        self.y_texture = None;
+        self.u_texture = None;
+        self.v_texture = None;
+        self.rgb_texture = None;
+        self.current_dimensions = None;
+        true
+    }
+    
+    /// Drop the previous frame's textures once the crossfade is over
+    pub fn release_previous(&mut self) {
+        self.previous = None;
+    }
+    
+    /// Get texture views of the previous frame for a crossfade
+    pub fn get_previous_views(&self) -> Result<(wgpu::TextureView, wgpu::TextureView, wgpu::TextureView, &wgpu::Sampler)> {
+        let [y, u, v] = self.previous.as_ref()
+            .ok_or_else(|| CCPlayerError::Renderer("No previous frame to crossfade from".to_string()))?;
+        let view = |texture: &wgpu::Texture| texture.create_view(&wgpu::TextureViewDescriptor::default());
+        
+        Ok((view(y), view(u), view(v), &self.sampler))
     }
     
     /// Get texture views for rendering
@@ -4,15 +4,16 @@
 //! high-performance GPU-accelerated video rendering.
 
 use crate::renderer::{
-    Color, FrameData, Overlay, OverlayPosition, RenderStats, Renderer, VideoFrame,
+    Color, FrameData, Overlay, OverlayPosition, RenderStats, Renderer, Rotation, VideoFrame,
 };
 use crate::utils::error::{CCPlayerError, Result};
 use crate::window::Window;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use wgpu::util::DeviceExt;
 
-use super::pipeline::RenderPipeline;
+use super::frame::FrameController;
+use super::pipeline::{Crossfade, RenderPipeline};
 use super::texture::TextureManager;
 
 /// WGPU renderer implementation
@@ -41,6 +42,9 @@ pub struct WgpuRenderer {
     /// Video aspect ratio
     aspect_ratio: f32,
     
+    /// Video orientation
+    rotation: Rotation,
+    
     /// Render statistics
     stats: RenderStats,
     
@@ -49,6 +53,37 @@ pub struct WgpuRenderer {
     
     /// Last frame time
     last_frame_time: Instant,
+    
+    /// Presentation history of video frames, for the measured frame rate
+    frame_controller: FrameController,
+    
+    /// PTS and duration of the frame uploaded since the last present
+    pending_frame: Option<(i64, i64)>,
+    
+    /// Length of the crossfade to each new frame (None to cut)
+    transition: Option<Duration>,
+    
+    /// Crossfade in progress
+    fade: Option<Fade>,
+    
+    /// Aspect ratio and orientation of the frame last presented
+    displayed: (f32, Rotation),
+}
+
+/// Crossfade from the previous frame, whose textures are kept meanwhile
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    /// When the new frame was uploaded
+    start: Instant,
+    
+    /// Length of the crossfade
+    duration: Duration,
+    
+    /// Aspect ratio of the previous frame
+    aspect_ratio: f32,
+    
+    /// Orientation of the previous frame
+    rotation: Rotation,
 }
 
 impl Renderer for WgpuRenderer {
@@ -78,19 +113,36 @@ impl Renderer for WgpuRenderer {
             texture_manager,
             window_size,
             aspect_ratio: 16.0 / 9.0, // Default aspect ratio
+            rotation: Rotation::default(),
             stats: RenderStats::default(),
             frame_times: Vec::with_capacity(120), // Track up to 120 frames for FPS
             last_frame_time: Instant::now(),
+            frame_controller: FrameController::new(true),
+            pending_frame: None,
+            transition: None,
+            fade: None,
+            displayed: (16.0 / 9.0, Rotation::default()),
         })
     }
     
     fn render_frame(&mut self, frame: VideoFrame) -> Result<()> {
+        // Keep the frame on screen to fade out under the new one
+        if let Some(duration) = self.transition {
+            if self.texture_manager.retain_previous() {
+                let (aspect_ratio, rotation) = self.displayed;
+                self.fade = Some(Fade { start: Instant::now(), duration, aspect_ratio, rotation });
+            }
+        }
+        
         // Update video texture with new frame data
-        self.texture_manager.update_video_texture(
+        let recreated = self.texture_manager.update_video_texture(
             &self.device,
             &self.queue,
             &frame,
         )?;
+        if recreated {
+            self.pipeline.invalidate_bind_group();
+        }
         
         // Update aspect ratio if needed
         let frame_aspect = (frame.width as f32 * frame.par) / frame.height as f32;
@@ -98,6 +150,8 @@ impl Renderer for WgpuRenderer {
             self.aspect_ratio = frame_aspect;
         }
         
+        self.pending_frame = Some((frame.pts, frame.duration));
+        
         Ok(())
     }
     
@@ -140,7 +194,8 @@ impl Renderer for WgpuRenderer {
             label: Some("Render Encoder"),
         });
         
-        // Render video frame
+        // Render video frame, over the previous one while crossfading
+        let crossfade = self.crossfade();
         self.pipeline.render_video(
             &self.device,
             &mut encoder,
@@ -148,7 +203,10 @@ impl Renderer for WgpuRenderer {
             &self.texture_manager,
             self.window_size,
             self.aspect_ratio,
+            self.rotation,
+            crossfade,
         )?;
+        self.displayed = (self.aspect_ratio, self.rotation);
         
         // Submit commands
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -190,11 +248,32 @@ impl Renderer for WgpuRenderer {
         Ok(())
     }
     
+    fn set_rotation(&mut self, rotation: Rotation) -> Result<()> {
+        self.rotation = rotation;
+        Ok(())
+    }
+    
     fn screenshot(&self) -> Result<Vec<u8>> {
         // TODO: Implement screenshot functionality
         // This would involve reading back the framebuffer
         Err(CCPlayerError::NotImplemented("Screenshot not yet implemented".to_string()))
     }
+    
+    fn set_transition(&mut self, duration: Option<Duration>) -> Result<()> {
+        self.transition = duration.filter(|duration| !duration.is_zero());
+        if self.transition.is_none() {
+            self.end_fade();
+        }
+        Ok(())
+    }
+    
+    fn is_transitioning(&self) -> bool {
+        self.fade.is_some()
+    }
+    
+    fn stats(&self) -> RenderStats {
+        self.stats
+    }
 }
 
 impl WgpuRenderer {
@@ -266,6 +345,30 @@ impl WgpuRenderer {
         Ok((device, queue, surface, surface_config))
     }
     
+    /// Progress of the running crossfade, ending it once complete
+    fn crossfade(&mut self) -> Option<Crossfade> {
+        let fade = self.fade?;
+        let progress = fade.start.elapsed().as_secs_f32() / fade.duration.as_secs_f32();
+        if progress >= 1.0 {
+            self.end_fade();
+            return None;
+        }
+        
+        Some(Crossfade {
+            aspect_ratio: fade.aspect_ratio,
+            rotation: fade.rotation,
+            progress,
+        })
+    }
+    
+    /// Stop crossfading and free the previous frame
+    fn end_fade(&mut self) {
+        if self.fade.take().is_some() {
+            self.texture_manager.release_previous();
+            self.pipeline.invalidate_bind_group();
+        }
+    }
+    
     /// Reconfigure the surface (e.g., after resize or lost surface)
     fn reconfigure_surface(&mut self) -> Result<()> {
         self.surface.configure(&self.device, &self.surface_config);
@@ -295,9 +398,16 @@ impl WgpuRenderer {
             self.stats.frame_time = self.stats.frame_time * 0.9 + frame_time * 0.1;
         }
         
-        // Check for dropped frames (frame time > 16.67ms for 60fps)
-        if frame_time > 16.67 * 1.5 {
-            self.stats.dropped_frames += 1;
+        // A new video frame updates the measured video frame rate
+        if let Some((pts, duration)) = self.pending_frame.take() {
+            self.frame_controller.record_frame_presented(frame_start, pts, duration, false);
+            self.stats.average_fps = self.frame_controller.average_fps() as f32;
+            self.stats.instant_fps = self.frame_controller.instant_fps() as f32;
+            
+            // Late if rendering took longer than the frame is shown for
+            if duration > 0 && frame_time > duration as f32 / 1000.0 * 1.5 {
+                self.stats.dropped_frames += 1;
+            }
         }
         
         self.last_frame_time = now;
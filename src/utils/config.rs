@@ -3,12 +3,20 @@
 //! This module handles loading and managing application configuration
 //! from various sources including config files and environment variables.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use crate::decoder::{ColorSpace, MediaInfo};
+use crate::player::{Action, KeyBindings, PlayerConfig};
+use crate::renderer::Color;
 use crate::utils::error::{CCPlayerError, Result};
 
 /// Main application configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     /// Window configuration
     pub window: WindowConfig,
@@ -21,10 +29,92 @@ pub struct Config {
     
     /// General application settings
     pub general: GeneralConfig,
+    
+    /// Playback settings (`[player]`); see [`Config::player_config`]
+    #[serde(default)]
+    pub player: PlayerConfig,
+    
+    /// Key bindings replacing the defaults (`"ctrl+q" = "quit"`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: BTreeMap<String, Action>,
+    
+    /// Named profiles (`[profile.NAME]`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, Profile>,
+}
+
+/// Named set of settings applied on top of the configuration
+/// 
+/// A profile holds a partial config document, e.g. `[profile.night.audio]`
+/// with `volume = 0.3`. It is applied with `--profile night` or, when it
+/// has an `auto` table, whenever a loaded media item matches it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Profile {
+    /// Conditions for applying the profile automatically (None for `--profile` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto: Option<ProfileCondition>,
+    
+    /// Settings the profile overrides, shaped like the config file
+    #[serde(flatten)]
+    #[schemars(with = "Config")]
+    pub settings: toml::Table,
+}
+
+/// Conditions of an auto-profile; all that are set must match
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ProfileCondition {
+    /// File extensions, without the dot (case-insensitive)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extension: Vec<String>,
+    
+    /// Minimum video height in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<u32>,
+    
+    /// Maximum video height in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u32>,
+    
+    /// Color spaces of the video stream (`Sdr`, `Hdr10`, `DolbyVision`, ...)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub color_space: Vec<ColorSpace>,
+    
+    /// Match network sources (true) or local files (false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<bool>,
+}
+
+impl ProfileCondition {
+    /// Check whether a loaded media item satisfies the conditions
+    pub fn matches(&self, info: &MediaInfo) -> bool {
+        let is_url = info.source.contains("://");
+        if self.url.is_some_and(|url| url != is_url) {
+            return false;
+        }
+        
+        if !self.extension.is_empty() {
+            // Query strings are not part of a URL's file name
+            let path = info.source.split(['?', '#']).next().unwrap_or_default();
+            let extension = Path::new(path).extension().and_then(|extension| extension.to_str());
+            if !extension.is_some_and(|extension| self.extension.iter().any(|wanted| wanted.eq_ignore_ascii_case(extension))) {
+                return false;
+            }
+        }
+        
+        let video = info.video_streams.first();
+        let height = video.map_or(0, |video| video.height);
+        if self.min_height.is_some_and(|min| height < min) || self.max_height.is_some_and(|max| video.is_none() || height > max) {
+            return false;
+        }
+        
+        self.color_space.is_empty()
+            || video.is_some_and(|video| self.color_space.contains(&video.color_space))
+    }
 }
 
 /// Window configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WindowConfig {
     /// Initial window width
     pub width: u32,
@@ -43,10 +133,13 @@ pub struct WindowConfig {
     
     /// Start minimized
     pub start_minimized: bool,
+    
+    /// Initial position of the top-left corner as [x, y] in pixels (None lets the system place it)
+    pub position: Option<[i32; 2]>,
 }
 
 /// Decoder configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DecoderConfig {
     /// Enable hardware acceleration
     pub hardware_acceleration: bool,
@@ -54,31 +147,40 @@ pub struct DecoderConfig {
     /// Number of threads for software decoding
     pub thread_count: usize,
     
-    /// Frame buffer size
+    /// Decoded video frames kept queued ahead of the renderer
     pub buffer_size: usize,
     
     /// Preferred video codec order
     pub preferred_codecs: Vec<String>,
+    
+    /// FFmpeg filtergraph applied to decoded video (e.g. "crop=1280:720,hqdn3d")
+    pub video_filters: Option<String>,
 }
 
 /// Audio configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AudioConfig {
     /// Initial volume (0.0 - 1.0)
     pub volume: f32,
     
-    /// Audio buffer size in frames
+    /// Audio device buffer size in frames (0 for the device default)
     pub buffer_size: usize,
     
-    /// Sample rate (0 for auto-detect)
+    /// Preferred output sample rate (0 to keep the source rate)
     pub sample_rate: u32,
     
     /// Enable audio normalization
     pub normalize: bool,
+    
+    /// FFmpeg filtergraph applied to decoded audio (e.g. "highpass=f=200,loudnorm")
+    pub filters: Option<String>,
+    
+    /// Output device name (None for the system default)
+    pub device: Option<String>,
 }
 
 /// General application configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GeneralConfig {
     /// Remember window position and size
     pub remember_window_state: bool,
@@ -97,7 +199,7 @@ pub struct GeneralConfig {
 }
 
 /// Subtitle configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SubtitleConfig {
     /// Enable subtitles by default
     pub enabled: bool,
@@ -112,17 +214,6 @@ pub struct SubtitleConfig {
     pub background_opacity: f32,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            window: WindowConfig::default(),
-            decoder: DecoderConfig::default(),
-            audio: AudioConfig::default(),
-            general: GeneralConfig::default(),
-        }
-    }
-}
-
 impl Default for WindowConfig {
     fn default() -> Self {
         Self {
@@ -132,6 +223,7 @@ impl Default for WindowConfig {
             title: "CCPlayer".to_string(),
             always_on_top: false,
             start_minimized: false,
+            position: None,
         }
     }
 }
@@ -141,13 +233,14 @@ impl Default for DecoderConfig {
         Self {
             hardware_acceleration: true,
             thread_count: 0, // 0 = auto-detect
-            buffer_size: 3,
+            buffer_size: 25,
             preferred_codecs: vec![
                 "h264".to_string(),
                 "hevc".to_string(),
                 "vp9".to_string(),
                 "av1".to_string(),
             ],
+            video_filters: None,
         }
     }
 }
@@ -156,9 +249,11 @@ impl Default for AudioConfig {
     fn default() -> Self {
         Self {
             volume: 0.7,
-            buffer_size: 512,
+            buffer_size: 256,
             sample_rate: 0, // 0 = auto-detect
             normalize: false,
+            filters: None,
+            device: None,
         }
     }
 }
@@ -194,30 +289,39 @@ impl Config {
     /// 2. System config file (/etc/ccplayer/config.toml on Linux)
     /// 3. User config file (~/.config/ccplayer/config.toml on Linux)
     /// 4. Environment variables (CCPLAYER_* prefix)
+    /// 
+    /// Command line options are applied on top by the caller, which then
+    /// calls [`Config::validate`] again.
     pub fn load() -> Result<Self> {
-        let mut config = Self::default();
+        let config = TracedConfig::load()?.config;
         
-        // Try to load system config
-        if let Some(system_path) = Self::system_config_path() {
-            if system_path.exists() {
-                config.merge_from_file(&system_path)?;
-            }
-        }
+        // Validate configuration
+        config.validate()?;
         
-        // Try to load user config
-        if let Some(user_path) = Self::user_config_path() {
-            if user_path.exists() {
-                config.merge_from_file(&user_path)?;
-            }
+        Ok(config)
+    }
+    
+    /// Read the configuration layers on top of the defaults, in order
+    /// 
+    /// Each layer is a partial config document: the system and user config
+    /// files if they exist, then the environment variable overrides.
+    pub fn layers() -> Result<Vec<(ConfigSource, toml::Table)>> {
+        let mut layers = Vec::new();
+        
+        if let Some(system_path) = Self::system_config_path().filter(|path| path.exists()) {
+            layers.push((ConfigSource::System(system_path.clone()), Self::read_file(&system_path)?));
         }
         
-        // Apply environment variable overrides
-        config.apply_env_overrides()?;
+        if let Some(user_path) = Self::user_config_path().filter(|path| path.exists()) {
+            layers.push((ConfigSource::User(user_path.clone()), Self::read_file(&user_path)?));
+        }
         
-        // Validate configuration
-        config.validate()?;
+        let env = Self::env_overrides()?;
+        if !env.is_empty() {
+            layers.push((ConfigSource::Env, env));
+        }
         
-        Ok(config)
+        Ok(layers)
     }
     
     /// Save configuration to user config file
@@ -240,57 +344,189 @@ impl Config {
         Ok(())
     }
     
+    /// Write changed playback settings to the user config file
+    /// 
+    /// Only the keys whose value differs between `previous` and `config` are
+    /// written, so the rest of the user file, its comments and the values of
+    /// the system file and environment are left alone. Nothing is written if
+    /// the user file cannot be read or parsed.
+    pub fn save_player_changes(previous: &PlayerConfig, config: &PlayerConfig) -> Result<()> {
+        let path = Self::user_config_path()
+            .ok_or_else(|| CCPlayerError::Config("Cannot determine user config path".to_string()))?;
+        
+        Self::write_player_changes(&path, previous, config)
+    }
+    
+    /// Update the keys of a config file that differ between two sets of playback settings
+    fn write_player_changes(path: &Path, previous: &PlayerConfig, config: &PlayerConfig) -> Result<()> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(CCPlayerError::Config(format!("Failed to read config file: {}", e))),
+        };
+        let mut document = contents.parse::<toml_edit::DocumentMut>()
+            .map_err(|e| CCPlayerError::Config(format!("Failed to parse config file {}: {}", path.display(), e)))?;
+        
+        let document_of = |config: &PlayerConfig| {
+            let mut settings = Self::default();
+            settings.set_player_config(config.clone());
+            toml::Table::try_from(&settings)
+                .map_err(|e| CCPlayerError::Config(format!("Failed to serialize config: {}", e)))
+        };
+        write_changes(document.as_table_mut(), &document_of(previous)?, &document_of(config)?)?;
+        
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| CCPlayerError::Config(format!("Failed to create config directory: {}", e)))?;
+        }
+        std::fs::write(path, document.to_string())
+            .map_err(|e| CCPlayerError::Config(format!("Failed to write config file: {}", e)))
+    }
+    
     /// Merge configuration from a TOML file
-    fn merge_from_file(&mut self, path: &Path) -> Result<()> {
+    /// 
+    /// The file may set any subset of keys; everything else keeps its value
+    /// from the earlier layers.
+    pub fn merge_from_file(&mut self, path: &Path) -> Result<()> {
+        let document = Self::read_file(path)?;
+        self.merge_document(document)
+            .map_err(|e| CCPlayerError::Config(format!("Invalid config file {}: {}", path.display(), e)))
+    }
+    
+    /// Parse a TOML file into a partial config document
+    fn read_file(path: &Path) -> Result<toml::Table> {
         let contents = std::fs::read_to_string(path)
             .map_err(|e| CCPlayerError::Config(format!("Failed to read config file: {}", e)))?;
         
-        let file_config: Config = toml::from_str(&contents)
-            .map_err(|e| CCPlayerError::Config(format!("Failed to parse config file: {}", e)))?;
+        toml::from_str(&contents)
+            .map_err(|e| CCPlayerError::Config(format!("Failed to parse config file {}: {}", path.display(), e)))
+    }
+    
+    /// Apply a named `[profile.NAME]` section on top of the current settings
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let settings = self.profile.get(name)
+            .ok_or_else(|| CCPlayerError::Config(format!("Unknown profile '{}'", name)))?
+            .settings
+            .clone();
         
-        // TODO: Implement proper merging logic instead of full replacement
-        *self = file_config;
+        self.merge_document(settings)
+            .map_err(|e| CCPlayerError::Config(format!("Invalid profile '{}': {}", name, e)))
+    }
+    
+    /// Merge a partial config document, key by key
+    /// 
+    /// Tables are merged recursively; any other value replaces the current
+    /// one. The configuration is left unchanged on error.
+    fn merge_document(&mut self, document: toml::Table) -> std::result::Result<(), String> {
+        let mut merged = toml::Table::try_from(&*self).map_err(|e| e.to_string())?;
+        merge_tables(&mut merged, document);
         
+        *self = merged.try_into().map_err(|e: toml::de::Error| e.message().to_string())?;
         Ok(())
     }
     
-    /// Apply environment variable overrides
-    fn apply_env_overrides(&mut self) -> Result<()> {
+    /// Names of the auto-profiles whose conditions a media item matches
+    pub fn auto_profiles(&self, info: &MediaInfo) -> Vec<String> {
+        self.profile.iter()
+            .filter(|(_, profile)| profile.auto.as_ref().is_some_and(|condition| condition.matches(info)))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+    
+    /// Environment variable overrides as a partial config document
+    fn env_overrides() -> Result<toml::Table> {
+        let mut overrides = toml::Table::new();
+        
         // Example: CCPLAYER_WINDOW_WIDTH=1920
         if let Ok(width) = std::env::var("CCPLAYER_WINDOW_WIDTH") {
-            self.window.width = width.parse()
+            let width: u32 = width.parse()
                 .map_err(|_| CCPlayerError::Config("Invalid CCPLAYER_WINDOW_WIDTH".to_string()))?;
+            insert_key(&mut overrides, "window", "width", toml::Value::Integer(width.into()));
         }
         
         if let Ok(height) = std::env::var("CCPLAYER_WINDOW_HEIGHT") {
-            self.window.height = height.parse()
+            let height: u32 = height.parse()
                 .map_err(|_| CCPlayerError::Config("Invalid CCPLAYER_WINDOW_HEIGHT".to_string()))?;
+            insert_key(&mut overrides, "window", "height", toml::Value::Integer(height.into()));
         }
         
         if let Ok(volume) = std::env::var("CCPLAYER_AUDIO_VOLUME") {
-            self.audio.volume = volume.parse()
+            let volume: f64 = volume.parse()
                 .map_err(|_| CCPlayerError::Config("Invalid CCPLAYER_AUDIO_VOLUME".to_string()))?;
+            insert_key(&mut overrides, "audio", "volume", toml::Value::Float(volume));
         }
         
         if let Ok(log_level) = std::env::var("CCPLAYER_LOG_LEVEL") {
-            self.general.log_level = log_level;
+            insert_key(&mut overrides, "general", "log_level", toml::Value::String(log_level));
         }
         
-        Ok(())
+        Ok(overrides)
+    }
+    
+    /// Playback settings with the values shared with other sections filled in
+    /// 
+    /// Volume, auto-play, filters and subtitles are configured in their own
+    /// sections and are not repeated under `[player]`.
+    pub fn player_config(&self) -> PlayerConfig {
+        PlayerConfig {
+            auto_play: self.general.auto_play,
+            default_volume: self.audio.volume,
+            video_filters: self.decoder.video_filters.clone(),
+            audio_filters: self.audio.filters.clone(),
+            normalize: self.audio.normalize,
+            subtitles: self.general.subtitles.clone(),
+            keybindings: KeyBindings::with_overrides(&self.keybindings).unwrap_or_default(),
+            frame_buffer: self.decoder.buffer_size,
+            output_sample_rate: self.audio.sample_rate,
+            ..self.player.clone()
+        }
+    }
+    
+    /// Store playback settings, writing shared values back to their sections
+    /// 
+    /// Key bindings are kept as they are in `[keybindings]`.
+    pub fn set_player_config(&mut self, config: PlayerConfig) {
+        self.general.auto_play = config.auto_play;
+        self.audio.volume = config.default_volume;
+        self.decoder.video_filters = config.video_filters.clone();
+        self.audio.filters = config.audio_filters.clone();
+        self.audio.normalize = config.normalize;
+        self.general.subtitles = config.subtitles.clone();
+        self.decoder.buffer_size = config.frame_buffer;
+        self.audio.sample_rate = config.output_sample_rate;
+        self.player = config;
     }
     
     /// Validate configuration values
-    fn validate(&self) -> Result<()> {
+    pub fn validate(&self) -> Result<()> {
         // Validate window dimensions
         if self.window.width == 0 || self.window.height == 0 {
             return Err(CCPlayerError::Config("Window dimensions must be non-zero".to_string()));
         }
         
+        if self.decoder.buffer_size == 0 {
+            return Err(CCPlayerError::Config("Decoder buffer size must be non-zero".to_string()));
+        }
+        
         // Validate audio volume
         if !(0.0..=1.0).contains(&self.audio.volume) {
             return Err(CCPlayerError::Config("Audio volume must be between 0.0 and 1.0".to_string()));
         }
         
+        if self.player.loop_count == Some(0) {
+            return Err(CCPlayerError::Config("Loop count must be at least 1".to_string()));
+        }
+        
+        // Validate subtitle style
+        let subtitles = &self.general.subtitles;
+        Color::from_hex(&subtitles.color)
+            .map_err(|_| CCPlayerError::Config(format!("Invalid subtitle color '{}'", subtitles.color)))?;
+        if subtitles.font_size == 0 || !(0.0..=1.0).contains(&subtitles.background_opacity) {
+            return Err(CCPlayerError::Config("Subtitle font size must be non-zero and background opacity between 0.0 and 1.0".to_string()));
+        }
+        
+        KeyBindings::with_overrides(&self.keybindings)?;
+        
         // Validate log level
         let valid_log_levels = ["trace", "debug", "info", "warn", "error"];
         if !valid_log_levels.contains(&self.general.log_level.as_str()) {
@@ -304,6 +540,35 @@ impl Config {
         Ok(())
     }
     
+    /// JSON Schema of the config file, for editors and `ccplayer config schema`
+    /// 
+    /// No key is required, since config files are merged key by key.
+    pub fn schema() -> JsonValue {
+        let mut schema = schemars::schema_for!(Config).to_value();
+        remove_required(&mut schema);
+        schema
+    }
+    
+    /// Find the keys of a config file that are not settings
+    /// 
+    /// # Arguments
+    /// 
+    /// * `contents` - Text of the config file
+    /// 
+    /// # Returns
+    /// 
+    /// Line number and dotted path of every unknown key, in file order
+    pub fn unknown_keys(contents: &str) -> Result<Vec<(usize, String)>> {
+        let document = toml_edit::ImDocument::parse(contents)
+            .map_err(|e| CCPlayerError::Config(format!("Failed to parse config file: {}", e)))?;
+        let schema = Self::schema();
+        
+        let mut unknown = Vec::new();
+        find_unknown_keys(&schema, Some(&schema), document.as_table(), "", contents, &mut unknown);
+        unknown.sort();
+        Ok(unknown)
+    }
+    
     /// Get system config file path
     fn system_config_path() -> Option<PathBuf> {
         #[cfg(target_os = "linux")]
@@ -339,9 +604,390 @@ impl Config {
     }
 }
 
+/// Layer of the configuration a value comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Built-in default
+    Default,
+    
+    /// System config file
+    System(PathBuf),
+    
+    /// User config file
+    User(PathBuf),
+    
+    /// `CCPLAYER_*` environment variables
+    Env,
+    
+    /// Profile applied with `--profile`
+    Profile(String),
+    
+    /// Command line options
+    Cli,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::System(path) => write!(f, "system {}", path.display()),
+            Self::User(path) => write!(f, "user {}", path.display()),
+            Self::Env => write!(f, "env"),
+            Self::Profile(name) => write!(f, "profile {}", name),
+            Self::Cli => write!(f, "cli"),
+        }
+    }
+}
+
+/// Configuration that remembers which layer set each value
+/// 
+/// Values are identified by their dotted path in the config file, e.g.
+/// `audio.volume` or `keybindings."ctrl+q"`.
+#[derive(Debug, Clone, Default)]
+pub struct TracedConfig {
+    /// Effective configuration
+    pub config: Config,
+    
+    /// Layer that last set each value (values left at their default are missing)
+    sources: BTreeMap<String, ConfigSource>,
+}
+
+impl TracedConfig {
+    /// Defaults with the config files and environment merged in, not yet validated
+    pub fn load() -> Result<Self> {
+        let mut traced = Self::default();
+        for (source, layer) in Config::layers()? {
+            traced.merge(source, layer)?;
+        }
+        Ok(traced)
+    }
+    
+    /// Merge a partial config document, crediting the keys it sets to `source`
+    pub fn merge(&mut self, source: ConfigSource, layer: toml::Table) -> Result<()> {
+        let mut set = BTreeMap::new();
+        leaf_values(layer.clone(), "", &mut set);
+        
+        self.config.merge_document(layer)
+            .map_err(|e| CCPlayerError::Config(format!("Invalid settings from {}: {}", source, e)))?;
+        
+        for path in set.into_keys() {
+            self.sources.insert(path, source.clone());
+        }
+        Ok(())
+    }
+    
+    /// Apply a named `[profile.NAME]` section
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let settings = self.config.profile.get(name)
+            .ok_or_else(|| CCPlayerError::Config(format!("Unknown profile '{}'", name)))?
+            .settings
+            .clone();
+        
+        self.merge(ConfigSource::Profile(name.to_string()), settings)
+    }
+    
+    /// Change settings in code, crediting every value that changes to `source`
+    pub fn update(&mut self, source: ConfigSource, change: impl FnOnce(&mut Config)) {
+        let before = self.values();
+        change(&mut self.config);
+        
+        for (path, value) in self.values() {
+            if before.get(&path) != Some(&value) {
+                self.sources.insert(path, source.clone());
+            }
+        }
+    }
+    
+    /// Every value of the effective configuration with the layer that set it
+    pub fn entries(&self) -> Vec<(String, toml::Value, &ConfigSource)> {
+        self.values()
+            .into_iter()
+            .map(|(path, value)| {
+                let source = self.sources.get(&path).unwrap_or(&ConfigSource::Default);
+                (path, value, source)
+            })
+            .collect()
+    }
+    
+    /// Values of the configuration by dotted path
+    fn values(&self) -> BTreeMap<String, toml::Value> {
+        let mut values = BTreeMap::new();
+        if let Ok(table) = toml::Table::try_from(&self.config) {
+            leaf_values(table, "", &mut values);
+        }
+        values
+    }
+}
+
+/// Rebuilds the configuration from all of its layers (see [`ConfigWatcher`])
+pub type ConfigLoader = Box<dyn Fn() -> Result<Config> + Send>;
+
+/// How often the config files are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Notices edits to the config files by polling their modification times
+pub struct ConfigWatcher {
+    /// Watched files with the modification time last seen (None while missing)
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    
+    /// Time of the last check
+    last_check: Instant,
+}
+
+impl ConfigWatcher {
+    /// Watch the system and user config files
+    pub fn new() -> Self {
+        Self::with_paths(Config::system_config_path().into_iter().chain(Config::user_config_path()).collect())
+    }
+    
+    /// Watch the given files
+    pub fn with_paths(paths: Vec<PathBuf>) -> Self {
+        Self {
+            files: paths.into_iter().map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            }).collect(),
+            last_check: Instant::now(),
+        }
+    }
+    
+    /// Check whether a file was created, changed or removed since the last change
+    /// 
+    /// Looks at the files at most once per second.
+    pub fn poll(&mut self) -> bool {
+        if self.last_check.elapsed() < WATCH_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        self.check()
+    }
+    
+    /// Compare the modification times with the ones last seen
+    fn check(&mut self) -> bool {
+        let mut changed = false;
+        for (path, seen) in &mut self.files {
+            let modified = modified(path);
+            if modified != *seen {
+                *seen = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+impl Default for ConfigWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Modification time of a file, None if it does not exist
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Insert `key` into the `section` table of a config document
+fn insert_key(document: &mut toml::Table, section: &str, key: &str, value: toml::Value) {
+    if let toml::Value::Table(table) = document
+        .entry(section)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+    {
+        table.insert(key.to_string(), value);
+    }
+}
+
+/// Append a key to a dotted path, quoted unless it is a bare TOML key
+fn join_key(prefix: &str, key: &str) -> String {
+    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    let key = if bare { key.to_string() } else { format!("{:?}", key) };
+    
+    if prefix.is_empty() {
+        key
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// Collect the non-table values of a config document by dotted path
+fn leaf_values(table: toml::Table, prefix: &str, values: &mut BTreeMap<String, toml::Value>) {
+    for (key, value) in table {
+        let path = join_key(prefix, &key);
+        match value {
+            toml::Value::Table(table) => leaf_values(table, &path, values),
+            value => {
+                values.insert(path, value);
+            }
+        }
+    }
+}
+
+/// Write the values of `new` that differ from `old` into a config document
+/// 
+/// Tables are updated key by key; keys missing from `new` are removed.
+fn write_changes(table: &mut dyn toml_edit::TableLike, old: &toml::Table, new: &toml::Table) -> Result<()> {
+    for (key, value) in new {
+        match (old.get(key), value) {
+            (Some(old), value) if old == value => {}
+            (Some(toml::Value::Table(old)), toml::Value::Table(new)) => {
+                let item = table.entry(key).or_insert(toml_edit::table());
+                match item.as_table_like_mut() {
+                    Some(inner) => write_changes(inner, old, new)?,
+                    None => *item = edit_item(value)?,
+                }
+            }
+            (_, value) => {
+                table.insert(key, edit_item(value)?);
+            }
+        }
+    }
+    
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        table.remove(key);
+    }
+    Ok(())
+}
+
+/// Convert a value for a config document
+fn edit_item(value: &toml::Value) -> Result<toml_edit::Item> {
+    value.to_string()
+        .parse::<toml_edit::Value>()
+        .map(toml_edit::Item::Value)
+        .map_err(|e| CCPlayerError::Config(format!("Failed to serialize config: {}", e)))
+}
+
+/// Drop the `required` lists of a schema and of the schemas nested in it
+fn remove_required(schema: &mut JsonValue) {
+    match schema {
+        JsonValue::Object(object) => {
+            object.remove("required");
+            object.values_mut().for_each(remove_required);
+        }
+        JsonValue::Array(items) => items.iter_mut().for_each(remove_required),
+        _ => {}
+    }
+}
+
+/// Collect the keys of a table that its schema does not describe
+/// 
+/// `schema` is None inside values that allow any keys.
+fn find_unknown_keys(
+    root: &JsonValue,
+    schema: Option<&JsonValue>,
+    table: &dyn toml_edit::TableLike,
+    prefix: &str,
+    contents: &str,
+    unknown: &mut Vec<(usize, String)>,
+) {
+    for (key, item) in table.iter() {
+        let path = join_key(prefix, key);
+        let child = match schema.map(|schema| property_schema(root, schema, key)) {
+            Some(None) => {
+                let span = table.get_key_value(key).and_then(|(key, item)| key.span().or_else(|| item.span()));
+                let line = span.map_or(0, |span| contents[..span.start].matches('\n').count() + 1);
+                unknown.push((line, path));
+                continue;
+            }
+            Some(Some(child)) => child,
+            None => None,
+        };
+        
+        if let Some(table) = item.as_table_like() {
+            find_unknown_keys(root, child, table, &path, contents, unknown);
+        }
+    }
+}
+
+/// Schema of a key in a table
+/// 
+/// # Returns
+/// 
+/// None for an unknown key, `Some(None)` if the table allows any keys
+fn property_schema<'a>(root: &'a JsonValue, schema: &'a JsonValue, key: &str) -> Option<Option<&'a JsonValue>> {
+    let tables = table_schemas(root, schema);
+    if tables.is_empty() {
+        return Some(None);
+    }
+    
+    tables.iter()
+        .find_map(|table| {
+            table.get("properties")
+                .and_then(|properties| properties.get(key))
+                .or_else(|| table.get("additionalProperties").filter(|schema| schema.is_object()))
+        })
+        .map(Some)
+}
+
+/// Table schemas a schema is made of, following `$ref`, `allOf`, `anyOf` and `oneOf`
+fn table_schemas<'a>(root: &'a JsonValue, schema: &'a JsonValue) -> Vec<&'a JsonValue> {
+    let schema = match schema.get("$ref").and_then(JsonValue::as_str) {
+        Some(reference) => match reference.strip_prefix('#').and_then(|pointer| root.pointer(pointer)) {
+            Some(schema) => schema,
+            None => return Vec::new(),
+        },
+        None => schema,
+    };
+    
+    let mut tables: Vec<&JsonValue> = ["allOf", "anyOf", "oneOf"]
+        .iter()
+        .filter_map(|combinator| schema.get(combinator)?.as_array())
+        .flatten()
+        .flat_map(|variant| table_schemas(root, variant))
+        .collect();
+    if schema.get("properties").is_some() || schema.get("additionalProperties").is_some() {
+        tables.push(schema);
+    }
+    tables
+}
+
+/// Recursively overlay `overlay` onto `base`
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge_tables(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::decoder::{MediaMetadata, VideoStreamInfo};
+    use crate::renderer::Rotation;
+    use std::time::Duration;
+    
+    /// Media info of a source with one video stream
+    fn video(source: &str, height: u32, color_space: ColorSpace) -> MediaInfo {
+        MediaInfo {
+            source: source.to_string(),
+            duration: Duration::from_secs(60),
+            video_streams: vec![VideoStreamInfo {
+                index: 0,
+                codec: "hevc".to_string(),
+                width: height * 16 / 9,
+                height,
+                fps: 24.0,
+                bitrate: None,
+                pixel_format: "yuv420p10le".to_string(),
+                color_space,
+                hdr_metadata: None,
+                rotation: Rotation::default(),
+            }],
+            audio_streams: Vec::new(),
+            subtitle_streams: Vec::new(),
+            format: "matroska,webm".to_string(),
+            file_size: None,
+            bitrate: None,
+            metadata: MediaMetadata::default(),
+            variants: Vec::new(),
+            current_variant: None,
+            is_live: false,
+            is_still: false,
+        }
+    }
     
     #[test]
     fn test_default_config() {
@@ -365,6 +1011,10 @@ mod tests {
         assert!(config.validate().is_err());
         
         config.audio.volume = 0.5;
+        config.player.loop_count = Some(0);
+        assert!(config.validate().is_err());
+        
+        config.player.loop_count = Some(2);
         config.general.log_level = "invalid".to_string();
         assert!(config.validate().is_err());
     }
@@ -377,5 +1027,229 @@ mod tests {
         
         assert_eq!(config.window.width, deserialized.window.width);
         assert_eq!(config.audio.volume, deserialized.audio.volume);
+        assert_eq!(config.player.seek_step, deserialized.player.seek_step);
+    }
+    
+    #[test]
+    fn test_player_config_shares_sections() {
+        let mut config = Config::default();
+        config.audio.volume = 0.4;
+        config.audio.sample_rate = 48000;
+        config.decoder.buffer_size = 12;
+        config.general.auto_play = false;
+        config.player.seek_step = 5;
+        
+        let player = config.player_config();
+        assert_eq!(player.default_volume, 0.4);
+        assert_eq!(player.keybindings.action(&crate::window::Key::Space, Default::default()), Some(Action::TogglePlay));
+        assert_eq!(player.output_sample_rate, 48000);
+        assert_eq!(player.frame_buffer, 12);
+        assert!(!player.auto_play);
+        assert_eq!(player.seek_step, 5);
+        
+        // Shared values are written back to their own sections
+        let mut player = player;
+        player.default_volume = 0.9;
+        player.audio_filters = Some("loudnorm".to_string());
+        config.set_player_config(player);
+        assert_eq!(config.audio.volume, 0.9);
+        assert_eq!(config.audio.filters.as_deref(), Some("loudnorm"));
+        
+        // ...and are not repeated under [player]
+        let toml = toml::to_string(&config).unwrap();
+        let player_section = toml.split("[player]").nth(1).unwrap();
+        assert!(!player_section.contains("default_volume"));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_partial_files_merge_by_key() {
+        let mut config = Config::default();
+        config.merge_document(toml::from_str("[audio]\nvolume = 0.3\n\n[player]\nseek_step = 5\n").unwrap()).unwrap();
+        config.merge_document(toml::from_str("[window]\nwidth = 1920\n").unwrap()).unwrap();
+        
+        assert_eq!(config.audio.volume, 0.3);
+        assert_eq!(config.audio.buffer_size, 256);
+        assert_eq!(config.player.seek_step, 5);
+        assert_eq!(config.window.width, 1920);
+        assert_eq!(config.window.height, 720);
+        
+        // A wrong type is rejected without touching the current values
+        assert!(config.merge_document(toml::from_str("[window]\nwidth = \"wide\"\n").unwrap()).is_err());
+        assert_eq!(config.window.width, 1920);
+    }
+    
+    #[test]
+    fn test_profiles() {
+        let mut config = Config::default();
+        config.merge_document(toml::from_str(r#"
+            [profile.night.audio]
+            volume = 0.2
+            filters = "dynaudnorm"
+            
+            [profile.hdr.auto]
+            color_space = ["Hdr10", "DolbyVision"]
+            
+            [profile.hdr.decoder]
+            video_filters = "zscale=t=linear,tonemap=hable"
+            
+            [profile.uhd-mkv.auto]
+            extension = ["mkv"]
+            min_height = 2160
+            url = false
+        "#).unwrap()).unwrap();
+        
+        config.apply_profile("night").unwrap();
+        assert_eq!(config.audio.volume, 0.2);
+        assert_eq!(config.audio.filters.as_deref(), Some("dynaudnorm"));
+        assert_eq!(config.window.width, 1280);
+        assert!(config.apply_profile("missing").is_err());
+        
+        assert_eq!(config.auto_profiles(&video("movie.MKV", 2160, ColorSpace::Hdr10)), vec!["hdr", "uhd-mkv"]);
+        assert_eq!(config.auto_profiles(&video("movie.mkv", 1080, ColorSpace::Sdr)), Vec::<String>::new());
+        assert_eq!(config.auto_profiles(&video("https://example.com/movie.mkv?t=1", 2160, ColorSpace::Sdr)), Vec::<String>::new());
+        
+        config.apply_profile("hdr").unwrap();
+        assert!(config.decoder.video_filters.is_some());
+        
+        // Profiles survive a save and load
+        let reloaded: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(reloaded.profile, config.profile);
+    }
+    
+    #[test]
+    fn test_runtime_settings_validation() {
+        let mut config = Config::default();
+        config.merge_document(toml::from_str("[keybindings]\n\"ctrl+right\" = \"fast_seek_forward\"\n").unwrap()).unwrap();
+        assert!(config.validate().is_ok());
+        
+        config.keybindings.insert("hyper+x".to_string(), Action::Quit);
+        assert!(config.validate().is_err());
+        config.keybindings.clear();
+        
+        config.general.subtitles.color = "yellow".to_string();
+        assert!(config.validate().is_err());
+        
+        // Unknown actions fail when the file is read
+        assert!(config.merge_document(toml::from_str("[keybindings]\nq = \"explode\"\n").unwrap()).is_err());
+    }
+    
+    #[test]
+    fn test_traced_sources() {
+        let user = ConfigSource::User(PathBuf::from("config.toml"));
+        let mut traced = TracedConfig::default();
+        traced.merge(user.clone(), toml::from_str(r#"
+            [audio]
+            volume = 0.3
+            
+            [keybindings]
+            "ctrl+q" = "ignore"
+            
+            [profile.night.audio]
+            volume = 0.1
+        "#).unwrap()).unwrap();
+        traced.apply_profile("night").unwrap();
+        traced.update(ConfigSource::Cli, |config| config.window.width = 1920);
+        
+        let entries = traced.entries();
+        let source = |path: &str| entries.iter().find(|(key, _, _)| key == path).map(|(_, _, source)| (*source).clone());
+        assert_eq!(source("audio.volume"), Some(ConfigSource::Profile("night".to_string())));
+        assert_eq!(source("keybindings.\"ctrl+q\""), Some(user.clone()));
+        assert_eq!(source("profile.night.audio.volume"), Some(user));
+        assert_eq!(source("window.width"), Some(ConfigSource::Cli));
+        assert_eq!(source("window.height"), Some(ConfigSource::Default));
+        
+        // A rejected layer changes nothing
+        assert!(traced.merge(ConfigSource::Env, toml::from_str("[window]\nwidth = -1\n").unwrap()).is_err());
+        assert_eq!(traced.config.window.width, 1920);
+    }
+    
+    #[test]
+    fn test_unknown_keys() {
+        let contents = r#"[audio]
+volume = 0.5
+volum = 0.5
+
+[keybindings]
+"ctrl+q" = "quit"
+
+[profile.night]
+window.widht = 800
+audio.volume = 0.2
+
+[profile.night.auto]
+extension = ["mkv"]
+height = 720
+
+[player.still_image]
+duration = { secs = 3, nanos = 0, millis = 1 }
+
+[colours]
+text = "white"
+"#;
+        let unknown: Vec<(usize, String)> = [
+            (3, "audio.volum"),
+            (9, "profile.night.window.widht"),
+            (14, "profile.night.auto.height"),
+            (17, "player.still_image.duration.millis"),
+            (19, "colours"),
+        ].into_iter().map(|(line, key)| (line, key.to_string())).collect();
+        assert_eq!(Config::unknown_keys(contents).unwrap(), unknown);
+        
+        assert!(Config::unknown_keys(&toml::to_string(&Config::default()).unwrap()).unwrap().is_empty());
+        assert!(Config::unknown_keys("[audio\n").is_err());
+    }
+    
+    #[test]
+    fn test_schema() {
+        let schema = Config::schema();
+        assert!(schema["properties"]["audio"].is_object());
+        assert!(schema["properties"]["keybindings"]["additionalProperties"].is_object());
+        assert!(!schema.to_string().contains("\"required\""));
+    }
+    
+    #[test]
+    fn test_config_watcher() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut watcher = ConfigWatcher::with_paths(vec![path.clone()]);
+        assert!(!watcher.check());
+        
+        std::fs::write(&path, "[audio]\nvolume = 0.5\n").unwrap();
+        assert!(watcher.check());
+        assert!(!watcher.check());
+        
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        assert!(watcher.check());
+        
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.check());
+    }
+    
+    #[test]
+    fn test_save_player_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "# My settings\n[audio]\nvolume = 0.5\n\n[window]\nwidth = 800\n").unwrap();
+        
+        let previous = PlayerConfig::default();
+        let mut config = previous.clone();
+        config.default_volume = 0.25;
+        config.auto_play = !previous.auto_play;
+        Config::write_player_changes(&path, &previous, &config).unwrap();
+        
+        // Only the changed keys are written, next to what was there
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# My settings\n[audio]\nvolume = 0.25\n"));
+        let table: toml::Table = toml::from_str(&contents).unwrap();
+        assert_eq!(table["window"]["width"].as_integer(), Some(800));
+        assert_eq!(table["general"]["auto_play"].as_bool(), Some(config.auto_play));
+        assert_eq!(table["general"].as_table().unwrap().len(), 1);
+        assert!(table.get("decoder").is_none());
+        
+        // A file that does not parse is left alone
+        std::fs::write(&path, "[audio\n").unwrap();
+        assert!(Config::write_player_changes(&path, &previous, &config).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[audio\n");
+    }
+}
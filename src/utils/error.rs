@@ -49,6 +49,10 @@ pub enum CCPlayerError {
     #[error("Synchronization error: {0}")]
     Sync(String),
     
+    /// Network errors (HTTP requests, stream downloads)
+    #[error("Network error: {0}")]
+    Network(String),
+    
     /// Generic error for unexpected situations
     #[error("Internal error: {0}")]
     Internal(String),
@@ -60,6 +64,17 @@ impl From<ffmpeg_next::Error> for CCPlayerError {
     }
 }
 
+impl From<zip::result::ZipError> for CCPlayerError {
+    fn from(err: zip::result::ZipError) -> Self {
+        match err {
+            zip::result::ZipError::Io(e) => CCPlayerError::FileIO(e),
+            zip::result::ZipError::FileNotFound => CCPlayerError::NotFound("Archive entry".to_string()),
+            zip::result::ZipError::UnsupportedArchive(msg) => CCPlayerError::UnsupportedFormat(msg.to_string()),
+            other => CCPlayerError::Decoder(format!("ZIP error: {}", other)),
+        }
+    }
+}
+
 impl From<std::ffi::NulError> for CCPlayerError {
     fn from(err: std::ffi::NulError) -> Self {
         CCPlayerError::Decoder(format!("FFI string error: {}", err))
@@ -84,6 +99,7 @@ pub trait IntoPlayerError<T> {
     fn decoder_err(self, context: &str) -> Result<T>;
     fn audio_err(self, context: &str) -> Result<T>;
     fn config_err(self, context: &str) -> Result<T>;
+    fn network_err(self, context: &str) -> Result<T>;
 }
 
 impl<T, E: std::fmt::Display> IntoPlayerError<T> for std::result::Result<T, E> {
@@ -106,6 +122,10 @@ impl<T, E: std::fmt::Display> IntoPlayerError<T> for std::result::Result<T, E> {
     fn config_err(self, context: &str) -> Result<T> {
         self.map_err(|e| CCPlayerError::Config(format!("{}: {}", context, e)))
     }
+    
+    fn network_err(self, context: &str) -> Result<T> {
+        self.map_err(|e| CCPlayerError::Network(format!("{}: {}", context, e)))
+    }
 }
 
 /// Helper macro for creating internal errors with file and line information
@@ -206,6 +206,7 @@ fn convert_key_code(keycode: KeyCode) -> Option<Key> {
         KeyCode::KeyM => Some(Key::M),
         KeyCode::KeyO => Some(Key::O),
         KeyCode::KeyQ => Some(Key::Q),
+        KeyCode::KeyR => Some(Key::R),
         KeyCode::KeyS => Some(Key::S),
         
         // Numbers
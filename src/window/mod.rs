@@ -64,6 +64,14 @@ pub trait Window: Send + Sync {
     
     /// Get the current window size
     fn size(&self) -> (u32, u32);
+    
+    /// Resize the window's client area
+    /// 
+    /// # Arguments
+    /// 
+    /// * `width` - New width in pixels
+    /// * `height` - New height in pixels
+    fn set_size(&mut self, width: u32, height: u32) -> Result<()>;
 }
 
 /// Window events that can occur
@@ -157,6 +165,7 @@ pub enum Key {
     M,  // Mute
     O,  // Open file
     Q,  // Quit
+    R,  // Rotate
     S,  // Subtitles
     
     // Numbers 0-9
@@ -7,7 +7,7 @@ use crate::utils::error::{CCPlayerError, Result, IntoPlayerError};
 use crate::utils::config::WindowConfig;
 use crate::window::{Window, WindowEvent, WindowMetrics};
 use winit::{
-    dpi::{LogicalSize, PhysicalPosition},
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
     event_loop::{EventLoop, EventLoopBuilder},
     window::{Window as WinitWindow, WindowBuilder, CursorIcon},
 };
@@ -59,13 +59,17 @@ impl Window for WinitWindowImpl {
             .window_err("Failed to create event loop")?;
         
         // Create window
-        let window = WindowBuilder::new()
+        let mut builder = WindowBuilder::new()
             .with_title(&config.title)
             .with_decorations(false)  // Borderless window
             .with_resizable(true)
             .with_inner_size(LogicalSize::new(config.width as f64, config.height as f64))
             .with_always_on_top(config.always_on_top)
-            .with_visible(!config.start_minimized)
+            .with_visible(!config.start_minimized);
+        if let Some([x, y]) = config.position {
+            builder = builder.with_position(PhysicalPosition::new(x, y));
+        }
+        let window = builder
             .build(&event_loop)
             .window_err("Failed to create window")?;
         
@@ -115,6 +119,12 @@ impl Window for WinitWindowImpl {
         Ok(())
     }
     
+    fn set_size(&mut self, width: u32, height: u32) -> Result<()> {
+        // The platform may adjust the size; the resize event reports the result
+        let _ = self.window.request_inner_size(PhysicalSize::new(width, height));
+        Ok(())
+    }
+    
     fn set_fullscreen(&mut self, fullscreen: bool) -> Result<()> {
         use winit::window::Fullscreen;
         
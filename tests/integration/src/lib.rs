@@ -261,4 +261,218 @@ pub mod perf_test {
             println!("  Max: {:?}", self.max().unwrap_or(Duration::ZERO));
         }
     }
-}
\ No newline at end of file
+}
+
+/// Local HTTP server for network streaming tests
+pub mod http_server {
+    use std::collections::{HashMap, VecDeque};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+    
+    /// Size of the chunks the body is written in
+    const WRITE_CHUNK: usize = 4096;
+    
+    /// Misbehaviour injected into a response
+    #[derive(Debug, Clone, Copy)]
+    enum Fault {
+        /// Close the connection after this many body bytes
+        Drop(usize),
+        
+        /// Stop sending (but keep the connection open) after this many body bytes
+        Stall(usize),
+    }
+    
+    #[derive(Default)]
+    struct ServerState {
+        routes: HashMap<String, Vec<u8>>,
+        hits: HashMap<String, usize>,
+        ranges: HashMap<String, Vec<Option<String>>>,
+        faults: HashMap<String, VecDeque<Fault>>,
+    }
+    
+    /// Serves in-memory fixtures over HTTP/1.1 with Range support and
+    /// optional bandwidth throttling
+    pub struct TestHttpServer {
+        addr: SocketAddr,
+        state: Arc<Mutex<ServerState>>,
+        bytes_per_second: Arc<AtomicU64>,
+        shutdown: Arc<AtomicBool>,
+        handle: Option<thread::JoinHandle<()>>,
+    }
+    
+    impl TestHttpServer {
+        /// Start listening on a random local port
+        pub fn start() -> std::io::Result<Self> {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            listener.set_nonblocking(true)?;
+            
+            let addr = listener.local_addr()?;
+            let state = Arc::new(Mutex::new(ServerState::default()));
+            let bytes_per_second = Arc::new(AtomicU64::new(0));
+            let shutdown = Arc::new(AtomicBool::new(false));
+            
+            let handle = {
+                let state = state.clone();
+                let bytes_per_second = bytes_per_second.clone();
+                let shutdown = shutdown.clone();
+                
+                thread::spawn(move || {
+                    while !shutdown.load(Ordering::Acquire) {
+                        match listener.accept() {
+                            Ok((stream, _)) => {
+                                let state = state.clone();
+                                let bytes_per_second = bytes_per_second.clone();
+                                thread::spawn(move || {
+                                    let _ = serve(stream, &state, &bytes_per_second);
+                                });
+                            }
+                            Err(_) => thread::sleep(Duration::from_millis(5)),
+                        }
+                    }
+                })
+            };
+            
+            Ok(Self {
+                addr,
+                state,
+                bytes_per_second,
+                shutdown,
+                handle: Some(handle),
+            })
+        }
+        
+        /// Serve `body` at `path`
+        pub fn add_route(&self, path: &str, body: impl Into<Vec<u8>>) {
+            self.state.lock().unwrap().routes.insert(path.to_string(), body.into());
+        }
+        
+        /// Limit response bodies to this many bytes per second (0 = unlimited)
+        pub fn set_throttle(&self, bytes_per_second: u64) {
+            self.bytes_per_second.store(bytes_per_second, Ordering::Release);
+        }
+        
+        /// Full URL of a path on this server
+        pub fn url(&self, path: &str) -> String {
+            format!("http://{}{}", self.addr, path)
+        }
+        
+        /// Number of requests served for a path
+        pub fn hits(&self, path: &str) -> usize {
+            self.state.lock().unwrap().hits.get(path).copied().unwrap_or(0)
+        }
+        
+        /// Range header of every request for a path (`None` if absent)
+        pub fn requested_ranges(&self, path: &str) -> Vec<Option<String>> {
+            self.state.lock().unwrap().ranges.get(path).cloned().unwrap_or_default()
+        }
+        
+        /// Cut off the next `count` responses for `path` after `after_bytes` body bytes
+        pub fn drop_connections(&self, path: &str, after_bytes: usize, count: usize) {
+            self.push_faults(path, Fault::Drop(after_bytes), count);
+        }
+        
+        /// Freeze the next `count` responses for `path` after `after_bytes` body bytes
+        pub fn stall_connections(&self, path: &str, after_bytes: usize, count: usize) {
+            self.push_faults(path, Fault::Stall(after_bytes), count);
+        }
+        
+        fn push_faults(&self, path: &str, fault: Fault, count: usize) {
+            let mut state = self.state.lock().unwrap();
+            state.faults.entry(path.to_string()).or_default().extend(std::iter::repeat_n(fault, count));
+        }
+    }
+    
+    impl Drop for TestHttpServer {
+        fn drop(&mut self) {
+            self.shutdown.store(true, Ordering::Release);
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+    
+    /// Answer a single request
+    fn serve(mut stream: TcpStream, state: &Mutex<ServerState>, bytes_per_second: &AtomicU64) -> std::io::Result<()> {
+        stream.set_nonblocking(false)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+        
+        let mut range = None;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("range") {
+                    range = value.trim().strip_prefix("bytes=").map(str::to_string);
+                }
+            }
+        }
+        
+        let (body, fault) = {
+            let mut state = state.lock().unwrap();
+            *state.hits.entry(path.clone()).or_insert(0) += 1;
+            state.ranges.entry(path.clone()).or_default().push(range.clone());
+            let fault = state.faults.get_mut(&path).and_then(|faults| faults.pop_front());
+            (state.routes.get(&path).cloned(), fault)
+        };
+        
+        let Some(body) = body else {
+            return stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        };
+        
+        let (status, start, end) = match range.as_deref().and_then(|r| r.split_once('-')) {
+            Some((first, last)) => {
+                let start: usize = first.parse().unwrap_or(0);
+                let end = last.parse::<usize>().map(|l| l + 1).unwrap_or(body.len()).min(body.len());
+                ("206 Partial Content", start.min(end), end)
+            }
+            None => ("200 OK", 0, body.len()),
+        };
+        
+        let mut headers = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nContent-Type: application/octet-stream\r\nAccept-Ranges: bytes\r\nConnection: close\r\n",
+            status,
+            end - start
+        );
+        if range.is_some() {
+            headers.push_str(&format!("Content-Range: bytes {}-{}/{}\r\n", start, end.saturating_sub(1), body.len()));
+        }
+        headers.push_str("\r\n");
+        stream.write_all(headers.as_bytes())?;
+        
+        let limit = match fault {
+            Some(Fault::Drop(bytes)) | Some(Fault::Stall(bytes)) => (start + bytes).min(end),
+            None => end,
+        };
+        
+        for chunk in body[start..limit].chunks(WRITE_CHUNK) {
+            stream.write_all(chunk)?;
+            
+            let limit = bytes_per_second.load(Ordering::Acquire);
+            if limit > 0 {
+                thread::sleep(Duration::from_secs_f64(chunk.len() as f64 / limit as f64));
+            }
+        }
+        
+        stream.flush()?;
+        
+        match fault {
+            Some(Fault::Drop(_)) => stream.shutdown(std::net::Shutdown::Both),
+            Some(Fault::Stall(_)) => {
+                thread::sleep(Duration::from_secs(5));
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Adaptive streaming tests
+//!
+//! Serves HLS and DASH fixtures from a local HTTP server and checks the
+//! byte stream the download sessions produce.
+
+use ccplayer::decoder::{AdaptiveConfig, AdaptiveSource, QualitySelection};
+use ccplayer_integration_tests::http_server::TestHttpServer;
+use std::io::Read;
+use std::time::Duration;
+
+/// Size of every fixture segment
+const SEGMENT_SIZE: usize = 32 * 1024;
+
+/// A segment whose payload starts with a readable label
+fn segment(label: &str) -> Vec<u8> {
+    let mut data = format!("{}|", label).into_bytes();
+    data.resize(SEGMENT_SIZE, b'.');
+    data
+}
+
+/// Read a whole stream and return the labels of its segments
+fn read_labels(source: &AdaptiveSource) -> Vec<String> {
+    let mut data = Vec::new();
+    source.main.reader().read_to_end(&mut data).unwrap();
+    
+    data.chunks(SEGMENT_SIZE)
+        .map(|chunk| {
+            let text = String::from_utf8_lossy(chunk);
+            text.split('|').next().unwrap_or_default().to_string()
+        })
+        .collect()
+}
+
+/// Publish a two-variant HLS stream with the given number of 2s segments
+fn serve_hls(server: &TestHttpServer, segments: usize) {
+    server.add_route(
+        "/hls/master.m3u8",
+        "#EXTM3U\n\
+         #EXT-X-STREAM-INF:BANDWIDTH=100000,RESOLUTION=320x180\n\
+         low/index.m3u8\n\
+         #EXT-X-STREAM-INF:BANDWIDTH=2000000,RESOLUTION=1280x720\n\
+         high/index.m3u8\n",
+    );
+    
+    for variant in ["low", "high"] {
+        let mut playlist = String::from("#EXTM3U\n#EXT-X-TARGETDURATION:2\n#EXT-X-MEDIA-SEQUENCE:0\n");
+        for i in 0..segments {
+            playlist.push_str(&format!("#EXTINF:2.0,\nseg{}.ts\n", i));
+            server.add_route(&format!("/hls/{}/seg{}.ts", variant, i), segment(&format!("{}-{}", variant, i)));
+        }
+        playlist.push_str("#EXT-X-ENDLIST\n");
+        server.add_route(&format!("/hls/{}/index.m3u8", variant), playlist);
+    }
+}
+
+#[test]
+fn test_pinned_variant() {
+    let server = TestHttpServer::start().unwrap();
+    serve_hls(&server, 4);
+    
+    let config = AdaptiveConfig {
+        quality: QualitySelection::Pinned(1),
+        ..AdaptiveConfig::default()
+    };
+    let source = AdaptiveSource::open(&server.url("/hls/master.m3u8"), config).unwrap();
+    
+    assert_eq!(source.main.variants().len(), 2);
+    assert_eq!(source.main.variants()[1].resolution, Some((1280, 720)));
+    assert!(source.audio.is_none());
+    
+    let labels = read_labels(&source);
+    assert_eq!(labels, vec!["high-0", "high-1", "high-2", "high-3"]);
+    assert_eq!(source.main.current_variant(), Some(1));
+    assert_eq!(source.main.duration(), Some(Duration::from_secs(8)));
+    assert_eq!(server.hits("/hls/low/seg0.ts"), 0);
+}
+
+#[test]
+fn test_switches_up_on_fast_network() {
+    let server = TestHttpServer::start().unwrap();
+    serve_hls(&server, 6);
+    
+    let source = AdaptiveSource::open(&server.url("/hls/master.m3u8"), AdaptiveConfig::default()).unwrap();
+    let labels = read_labels(&source);
+    
+    // Starts with the first listed variant, then switches at a segment boundary
+    assert_eq!(labels.len(), 6);
+    assert_eq!(labels[0], "low-0");
+    assert_eq!(labels[5], "high-5");
+    
+    for (i, label) in labels.iter().enumerate() {
+        assert!(label.ends_with(&format!("-{}", i)), "segment {} out of order: {}", i, label);
+    }
+    
+    assert_eq!(source.main.current_variant(), Some(1));
+    assert!(source.main.throughput().unwrap() > 2_000_000.0);
+}
+
+#[test]
+fn test_stays_low_on_slow_network() {
+    let server = TestHttpServer::start().unwrap();
+    serve_hls(&server, 3);
+    
+    // 64 KB/s is ~512 kbit/s: enough for the low variant only
+    server.set_throttle(64 * 1024);
+    
+    let source = AdaptiveSource::open(&server.url("/hls/master.m3u8"), AdaptiveConfig::default()).unwrap();
+    let labels = read_labels(&source);
+    
+    assert_eq!(labels, vec!["low-0", "low-1", "low-2"]);
+    assert!(source.main.throughput().unwrap() < 1_000_000.0);
+}
+
+#[test]
+fn test_quality_can_be_pinned_at_runtime() {
+    let server = TestHttpServer::start().unwrap();
+    serve_hls(&server, 4);
+    
+    let config = AdaptiveConfig {
+        prefetch_segments: 1,
+        quality: QualitySelection::Pinned(0),
+        ..AdaptiveConfig::default()
+    };
+    let source = AdaptiveSource::open(&server.url("/hls/master.m3u8"), config).unwrap();
+    
+    assert!(source.main.set_quality(QualitySelection::Pinned(5)).is_err());
+    
+    let mut reader = source.main.reader();
+    let mut first = vec![0u8; SEGMENT_SIZE];
+    reader.read_exact(&mut first).unwrap();
+    assert!(first.starts_with(b"low-0|"));
+    
+    source.main.set_quality(QualitySelection::Pinned(1)).unwrap();
+    
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert!(rest.chunks(SEGMENT_SIZE).last().unwrap().starts_with(b"high-3|"));
+}
+
+#[test]
+fn test_dash_stream() {
+    let server = TestHttpServer::start().unwrap();
+    
+    server.add_route(
+        "/dash/manifest.mpd",
+        r#"<?xml version="1.0"?>
+<MPD xmlns="urn:mpeg:dash:schema:mpd:2011" type="static" mediaPresentationDuration="PT6S">
+  <Period>
+    <AdaptationSet contentType="video">
+      <SegmentTemplate media="$RepresentationID$/$Number$.m4s" initialization="$RepresentationID$/init.mp4" duration="2" startNumber="1"/>
+      <Representation id="v1" bandwidth="300000" width="640" height="360"/>
+      <Representation id="v2" bandwidth="3000000" width="1920" height="1080"/>
+    </AdaptationSet>
+    <AdaptationSet contentType="audio">
+      <SegmentTemplate media="a1/$Number$.m4s" initialization="a1/init.mp4" duration="2" startNumber="1"/>
+      <Representation id="a1" bandwidth="128000"/>
+    </AdaptationSet>
+  </Period>
+</MPD>"#,
+    );
+    
+    for id in ["v1", "v2", "a1"] {
+        server.add_route(&format!("/dash/{}/init.mp4", id), segment(&format!("{}-init", id)));
+        for number in 1..=3 {
+            server.add_route(&format!("/dash/{}/{}.m4s", id, number), segment(&format!("{}-{}", id, number)));
+        }
+    }
+    
+    let config = AdaptiveConfig {
+        quality: QualitySelection::Pinned(0),
+        ..AdaptiveConfig::default()
+    };
+    let source = AdaptiveSource::open(&server.url("/dash/manifest.mpd"), config).unwrap();
+    
+    assert_eq!(source.main.variants().len(), 2);
+    assert_eq!(read_labels(&source), vec!["v1-init", "v1-1", "v1-2", "v1-3"]);
+    
+    let audio = source.audio.as_ref().expect("separate audio rendition");
+    let mut data = Vec::new();
+    audio.reader().read_to_end(&mut data).unwrap();
+    assert_eq!(data.len(), 4 * SEGMENT_SIZE);
+    assert!(data.starts_with(b"a1-init|"));
+}
+
+/// Live media playlist listing segments `first..first + count`
+fn live_playlist(first: usize, count: usize) -> String {
+    let mut playlist = format!("#EXTM3U\n#EXT-X-TARGETDURATION:2\n#EXT-X-MEDIA-SEQUENCE:{}\n", first);
+    for i in first..first + count {
+        playlist.push_str(&format!("#EXTINF:2.0,\nseg{}.ts\n", i));
+    }
+    playlist
+}
+
+/// Read the label of the next segment from a reader
+fn next_label(reader: &mut impl Read) -> String {
+    let mut chunk = vec![0u8; SEGMENT_SIZE];
+    reader.read_exact(&mut chunk).unwrap();
+    String::from_utf8_lossy(&chunk).split('|').next().unwrap_or_default().to_string()
+}
+
+#[test]
+fn test_live_window_follows_playlist() {
+    let server = TestHttpServer::start().unwrap();
+    for i in 100..110 {
+        server.add_route(&format!("/live/seg{}.ts", i), segment(&format!("live-{}", i)));
+    }
+    server.add_route("/live/index.m3u8", live_playlist(100, 6));
+    
+    let source = AdaptiveSource::open(&server.url("/live/index.m3u8"), AdaptiveConfig::default()).unwrap();
+    let mut reader = source.main.reader();
+    
+    // Playback starts three segments from the edge
+    assert_eq!(next_label(&mut reader), "live-103");
+    assert!(source.main.is_live());
+    assert_eq!(source.main.duration(), None);
+    assert_eq!(source.main.live_window(), Some((Duration::ZERO, Duration::from_secs(12))));
+    assert_eq!(source.main.stream_start(), Some(Duration::from_secs(6)));
+    
+    // The playlist slides forward; times of known segments stay put
+    server.add_route("/live/index.m3u8", live_playlist(102, 6));
+    for expected in ["live-104", "live-105", "live-106"] {
+        assert_eq!(next_label(&mut reader), expected);
+    }
+    assert_eq!(source.main.live_window(), Some((Duration::from_secs(4), Duration::from_secs(16))));
+    
+    // Seeking back within the DVR window
+    source.main.seek(Duration::from_secs(5));
+    let mut reader = source.main.reader();
+    assert_eq!(next_label(&mut reader), "live-102");
+    assert_eq!(source.main.stream_start(), Some(Duration::from_secs(4)));
+}
@@ -0,0 +1,101 @@
+//! Network resilience tests
+//!
+//! Serves a file from a local HTTP server that drops or freezes
+//! connections and checks that the HTTP source delivers it intact.
+
+use ccplayer::decoder::{HttpSource, MediaSource, ReconnectPolicy};
+use ccplayer_integration_tests::http_server::TestHttpServer;
+use std::time::{Duration, Instant};
+
+/// Size of the served file
+const FILE_SIZE: usize = 256 * 1024;
+
+/// File content where every byte depends on its offset
+fn file() -> Vec<u8> {
+    (0..FILE_SIZE).map(|i| (i % 251) as u8).collect()
+}
+
+/// Policy with short delays so the tests run quickly
+fn fast_policy() -> ReconnectPolicy {
+    ReconnectPolicy {
+        max_attempts: 4,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(100),
+        stall_timeout: Duration::from_millis(300),
+    }
+}
+
+fn read_all(source: &mut dyn MediaSource) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        match source.read(&mut buf)? {
+            0 => return Ok(data),
+            count => data.extend_from_slice(&buf[..count]),
+        }
+    }
+}
+
+#[test]
+fn test_resumes_after_dropped_connection() {
+    let server = TestHttpServer::start().unwrap();
+    server.add_route("/clip.mp4", file());
+    server.drop_connections("/clip.mp4", 100_000, 2);
+
+    let mut source = HttpSource::open(&server.url("/clip.mp4"), fast_policy()).unwrap();
+    assert_eq!(source.size(), Some(FILE_SIZE as u64));
+    assert!(source.is_seekable());
+
+    assert_eq!(read_all(&mut source).unwrap(), file());
+
+    // Each reconnect continued where the previous connection stopped
+    let ranges = server.requested_ranges("/clip.mp4");
+    assert_eq!(ranges.len(), 3);
+    assert_eq!(ranges[0], None);
+    assert_eq!(ranges[1].as_deref(), Some("100000-"));
+    assert_eq!(ranges[2].as_deref(), Some("200000-"));
+}
+
+#[test]
+fn test_recovers_from_stall() {
+    let server = TestHttpServer::start().unwrap();
+    server.add_route("/live.ts", file());
+    server.stall_connections("/live.ts", 50_000, 1);
+
+    let mut source = HttpSource::open(&server.url("/live.ts"), fast_policy()).unwrap();
+
+    let started = Instant::now();
+    assert_eq!(read_all(&mut source).unwrap(), file());
+
+    // Detected by the read timeout, long before the server gives up
+    assert!(started.elapsed() < Duration::from_secs(3));
+    assert_eq!(server.hits("/live.ts"), 2);
+}
+
+#[test]
+fn test_gives_up_after_max_attempts() {
+    let server = TestHttpServer::start().unwrap();
+    server.add_route("/clip.mp4", file());
+
+    // The first connection breaks after 1000 bytes, every reconnect immediately
+    server.drop_connections("/clip.mp4", 1000, 1);
+    server.drop_connections("/clip.mp4", 0, 100);
+
+    let mut source = HttpSource::open(&server.url("/clip.mp4"), fast_policy()).unwrap();
+
+    let error = read_all(&mut source).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::ConnectionAborted);
+    assert_eq!(server.hits("/clip.mp4"), 1 + fast_policy().max_attempts as usize);
+}
+
+#[test]
+fn test_seek_reconnects_at_offset() {
+    let server = TestHttpServer::start().unwrap();
+    server.add_route("/clip.mp4", file());
+
+    let mut source = HttpSource::open(&server.url("/clip.mp4"), fast_policy()).unwrap();
+    source.seek(200_000).unwrap();
+
+    assert_eq!(read_all(&mut source).unwrap(), &file()[200_000..]);
+    assert_eq!(server.requested_ranges("/clip.mp4").last().unwrap().as_deref(), Some("200000-"));
+}
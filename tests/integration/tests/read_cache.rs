@@ -0,0 +1,123 @@
+//! Read-ahead cache tests
+//!
+//! Streams a file from a throttled local HTTP server through the disk
+//! cache and checks read-ahead, backward seeks and the size limit.
+
+use ccplayer::decoder::{CacheConfig, CachedSource, HttpSource, MediaSource, Readahead, ReconnectPolicy};
+use ccplayer_integration_tests::http_server::TestHttpServer;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Size of the served file
+const FILE_SIZE: usize = 2 * 1024 * 1024;
+
+/// File content where every byte depends on its offset
+fn file() -> Vec<u8> {
+    (0..FILE_SIZE).map(|i| (i % 251) as u8).collect()
+}
+
+fn open(server: &TestHttpServer, config: CacheConfig) -> CachedSource {
+    let http = HttpSource::open(&server.url("/clip.mp4"), ReconnectPolicy::default()).unwrap();
+    CachedSource::new(Box::new(http), config).unwrap()
+}
+
+fn read_exact(source: &mut dyn MediaSource, len: usize) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    let mut filled = 0;
+    while filled < len {
+        let count = source.read(&mut data[filled..]).unwrap();
+        assert!(count > 0, "unexpected end of stream");
+        filled += count;
+    }
+    data
+}
+
+/// Bytes held in the cache
+fn cached_bytes(source: &CachedSource) -> u64 {
+    source.handle().buffered_ranges().iter().map(|range| range.end - range.start).sum()
+}
+
+#[test]
+fn test_backward_seek_served_from_cache() {
+    let server = TestHttpServer::start().unwrap();
+    server.add_route("/clip.mp4", file());
+    server.set_throttle(4 * 1024 * 1024);
+
+    let mut source = open(&server, CacheConfig::default());
+    assert!(source.is_seekable());
+    assert_eq!(read_exact(&mut source, 1_000_000), &file()[..1_000_000]);
+
+    let requests = server.hits("/clip.mp4");
+    source.seek(1000).unwrap();
+    assert_eq!(read_exact(&mut source, 500_000), &file()[1000..501_000]);
+
+    // Everything came from disk
+    assert_eq!(server.hits("/clip.mp4"), requests);
+}
+
+#[test]
+fn test_readahead_stops_at_limit() {
+    let server = TestHttpServer::start().unwrap();
+    server.add_route("/clip.mp4", file());
+
+    let config = CacheConfig {
+        readahead: Readahead::Bytes(512 * 1024),
+        ..CacheConfig::default()
+    };
+    let mut source = open(&server, config);
+    read_exact(&mut source, 1);
+
+    // Let the download thread run until it idles
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while cached_bytes(&source) < 512 * 1024 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    thread::sleep(Duration::from_millis(200));
+
+    let ranges = source.handle().buffered_ranges();
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(ranges[0].start, 0);
+    assert!(ranges[0].end >= 512 * 1024);
+    assert!(ranges[0].end < FILE_SIZE as u64);
+}
+
+#[test]
+fn test_forward_seek_skips_download() {
+    let server = TestHttpServer::start().unwrap();
+    server.add_route("/clip.mp4", file());
+    server.set_throttle(1024 * 1024);
+
+    let config = CacheConfig {
+        readahead: Readahead::Bytes(256 * 1024),
+        ..CacheConfig::default()
+    };
+    let mut source = open(&server, config);
+    source.seek(1_572_864).unwrap();
+    assert_eq!(read_exact(&mut source, 1000), &file()[1_572_864..1_573_864]);
+
+    let ranges = server.requested_ranges("/clip.mp4");
+    assert_eq!(ranges.last().unwrap().as_deref(), Some("1572864-"));
+
+    // The skipped middle of the file was never downloaded
+    let buffered = source.handle().buffered_ranges();
+    assert!(buffered.iter().all(|range| range.end <= 1_048_576 || range.start >= 1_572_864));
+}
+
+#[test]
+fn test_cache_respects_size_limit() {
+    let server = TestHttpServer::start().unwrap();
+    server.add_route("/clip.mp4", file());
+
+    let config = CacheConfig {
+        max_size: 1024 * 1024,
+        readahead: Readahead::Bytes(256 * 1024),
+    };
+    let mut source = open(&server, config);
+    assert_eq!(read_exact(&mut source, FILE_SIZE), file());
+
+    // The start of the file made room for the end
+    let ranges = source.handle().buffered_ranges();
+    assert!(cached_bytes(&source) <= config.max_size);
+    assert!(ranges[0].start > 0);
+    assert_eq!(ranges.last().unwrap().end, FILE_SIZE as u64);
+}
@@ -0,0 +1,58 @@
+//! Still image tests
+//!
+//! Opens the generated JPEG fixture as a still source and checks that
+//! EXIF orientation written into it is picked up.
+
+use ccplayer::decoder::{exif_orientation, is_image_path, Decoder, FFmpegDecoder};
+use ccplayer::renderer::Rotation;
+use ccplayer_integration_tests::TestFixture;
+
+/// Insert an APP1 EXIF segment with the given orientation after the SOI marker
+fn with_orientation(jpeg: &[u8], orientation: u16) -> Vec<u8> {
+    let mut exif = b"Exif\0\0MM\x00\x2a\x00\x00\x00\x08\x00\x01".to_vec();
+    exif.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    exif.extend_from_slice(&orientation.to_be_bytes());
+    exif.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+    let mut out = jpeg[..2].to_vec();
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+    out.extend_from_slice(&exif);
+    out.extend_from_slice(&jpeg[2..]);
+    out
+}
+
+#[test]
+fn test_exif_orientation_of_fixture() {
+    let fixture = TestFixture::new().unwrap();
+    let path = &fixture.media_files.image_jpg;
+    assert!(is_image_path(path));
+
+    let jpeg = std::fs::read(path).unwrap();
+    assert_eq!(exif_orientation(&jpeg), None);
+    assert_eq!(exif_orientation(&with_orientation(&jpeg, 6)), Some(Rotation::new(90.0, false)));
+    assert_eq!(exif_orientation(&with_orientation(&jpeg, 2)), Some(Rotation::new(0.0, true)));
+}
+
+#[test]
+fn test_open_still_image() {
+    let fixture = TestFixture::new().unwrap();
+    let rotated = fixture.path().join("rotated.jpg");
+    let jpeg = std::fs::read(&fixture.media_files.image_jpg).unwrap();
+    std::fs::write(&rotated, with_orientation(&jpeg, 8)).unwrap();
+
+    let mut decoder = FFmpegDecoder::new().unwrap();
+    let info = decoder.open_file(&fixture.media_files.image_jpg).unwrap();
+    assert!(info.is_still);
+    assert!(info.audio_streams.is_empty());
+    assert_eq!(info.video_streams[0].rotation, Rotation::default());
+
+    let frame = decoder.decode_frame().unwrap().expect("image frame");
+    assert_eq!((frame.width, frame.height), (320, 240));
+    assert!(decoder.decode_frame().unwrap().is_none());
+
+    let info = decoder.open_file(&rotated).unwrap();
+    assert!(info.is_still);
+    assert_eq!(info.video_streams[0].rotation, Rotation::new(270.0, false));
+    assert_eq!(info.video_streams[0].rotation.display_size(320, 240), (240, 320));
+}